@@ -2,9 +2,16 @@
 
 pub mod prelude;
 
+pub mod account_deletion;
+pub mod announcement;
+pub mod announcement_delivery;
+pub mod api_key;
+pub mod api_key_scope;
 pub mod asset;
+pub mod audit_log;
 pub mod bundles;
 pub mod bundles_cosmetics;
+pub mod catalog_change;
 pub mod collections;
 pub mod cosmetic;
 pub mod cosmetic_allowed_slot;
@@ -12,13 +19,24 @@ pub mod cosmetic_group;
 pub mod cosmetic_group_allowed_slot;
 pub mod cosmetic_package;
 pub mod daily_playtime;
+pub mod device_code;
+pub mod emote_invite;
+pub mod friend_request;
+pub mod friendship;
 pub mod monthly_active_login;
+pub mod outfit;
+pub mod player_ban;
 pub mod player_equipped_cosmetic;
 pub mod player_owned_cosmetic;
+pub mod player_presence;
+pub mod realtime_instance;
+pub mod role_permission;
 pub mod sea_orm_active_enums;
+pub mod session;
 pub mod tags;
 pub mod tags_cosmetic;
 pub mod tracked_link_hits;
 pub mod tracked_links;
 pub mod transaction;
 pub mod user;
+pub mod user_permission_override;
@@ -1,8 +1,15 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.14
 
+pub use super::account_deletion::Entity as AccountDeletion;
+pub use super::announcement::Entity as Announcement;
+pub use super::announcement_delivery::Entity as AnnouncementDelivery;
+pub use super::api_key::Entity as ApiKey;
+pub use super::api_key_scope::Entity as ApiKeyScope;
 pub use super::asset::Entity as Asset;
+pub use super::audit_log::Entity as AuditLog;
 pub use super::bundles::Entity as Bundles;
 pub use super::bundles_cosmetics::Entity as BundlesCosmetics;
+pub use super::catalog_change::Entity as CatalogChange;
 pub use super::collections::Entity as Collections;
 pub use super::cosmetic::Entity as Cosmetic;
 pub use super::cosmetic_allowed_slot::Entity as CosmeticAllowedSlot;
@@ -10,12 +17,23 @@ pub use super::cosmetic_group::Entity as CosmeticGroup;
 pub use super::cosmetic_group_allowed_slot::Entity as CosmeticGroupAllowedSlot;
 pub use super::cosmetic_package::Entity as CosmeticPackage;
 pub use super::daily_playtime::Entity as DailyPlaytime;
+pub use super::device_code::Entity as DeviceCode;
+pub use super::emote_invite::Entity as EmoteInvite;
+pub use super::friend_request::Entity as FriendRequest;
+pub use super::friendship::Entity as Friendship;
 pub use super::monthly_active_login::Entity as MonthlyActiveLogin;
+pub use super::outfit::Entity as Outfit;
+pub use super::player_ban::Entity as PlayerBan;
 pub use super::player_equipped_cosmetic::Entity as PlayerEquippedCosmetic;
 pub use super::player_owned_cosmetic::Entity as PlayerOwnedCosmetic;
+pub use super::player_presence::Entity as PlayerPresence;
+pub use super::realtime_instance::Entity as RealtimeInstance;
+pub use super::role_permission::Entity as RolePermission;
+pub use super::session::Entity as Session;
 pub use super::tags::Entity as Tags;
 pub use super::tags_cosmetic::Entity as TagsCosmetic;
 pub use super::tracked_link_hits::Entity as TrackedLinkHits;
 pub use super::tracked_links::Entity as TrackedLinks;
 pub use super::transaction::Entity as Transaction;
 pub use super::user::Entity as User;
+pub use super::user_permission_override::Entity as UserPermissionOverride;
@@ -2,6 +2,66 @@
 
 use sea_orm::entity::prelude::*;
 
+#[derive(
+	Debug,
+	Clone,
+	Copy,
+	PartialEq,
+	Eq,
+	EnumIter,
+	DeriveActiveEnum,
+	schemars :: JsonSchema,
+	serde :: Deserialize,
+	serde :: Serialize,
+	Hash,
+)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+#[serde(rename_all = "snake_case")]
+pub enum AnnouncementLevel {
+	#[sea_orm(string_value = "critical")]
+	Critical,
+	#[sea_orm(string_value = "info")]
+	Info,
+	#[sea_orm(string_value = "warning")]
+	Warning,
+}
+#[derive(
+	Debug,
+	Clone,
+	Copy,
+	PartialEq,
+	Eq,
+	EnumIter,
+	DeriveActiveEnum,
+	schemars :: JsonSchema,
+	serde :: Deserialize,
+	serde :: Serialize,
+	Hash,
+)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "api_scope")]
+pub enum ApiScope {
+	#[sea_orm(string_value = "analytics_read")]
+	#[serde(rename = "analytics:read")]
+	AnalyticsRead,
+	#[sea_orm(string_value = "announcements_write")]
+	#[serde(rename = "announcements:write")]
+	AnnouncementsWrite,
+	#[sea_orm(string_value = "audit_read")]
+	#[serde(rename = "audit:read")]
+	AuditRead,
+	#[sea_orm(string_value = "catalog_write")]
+	#[serde(rename = "catalog:write")]
+	CatalogWrite,
+	#[sea_orm(string_value = "grants_write")]
+	#[serde(rename = "grants:write")]
+	GrantsWrite,
+	#[sea_orm(string_value = "links_read")]
+	#[serde(rename = "links:read")]
+	LinksRead,
+	#[sea_orm(string_value = "links_write")]
+	#[serde(rename = "links:write")]
+	LinksWrite,
+}
 #[derive(
 	Debug,
 	Clone,
@@ -94,6 +154,47 @@ pub enum CosmeticType {
 	#[sea_orm(string_value = "shoulder")]
 	Shoulder,
 }
+#[derive(
+	Debug,
+	Clone,
+	Copy,
+	PartialEq,
+	Eq,
+	EnumIter,
+	DeriveActiveEnum,
+	schemars :: JsonSchema,
+	serde :: Deserialize,
+	serde :: Serialize,
+	Hash,
+)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+#[serde(rename_all = "snake_case")]
+pub enum Permission {
+	#[sea_orm(string_value = "ban_player")]
+	BanPlayer,
+	#[sea_orm(string_value = "export_player_data")]
+	ExportPlayerData,
+	#[sea_orm(string_value = "grant_cosmetic")]
+	GrantCosmetic,
+	#[sea_orm(string_value = "manage_announcements")]
+	ManageAnnouncements,
+	#[sea_orm(string_value = "manage_api_keys")]
+	ManageApiKeys,
+	#[sea_orm(string_value = "manage_catalog")]
+	ManageCatalog,
+	#[sea_orm(string_value = "manage_links")]
+	ManageLinks,
+	#[sea_orm(string_value = "manage_roles")]
+	ManageRoles,
+	#[sea_orm(string_value = "manage_sessions")]
+	ManageSessions,
+	#[sea_orm(string_value = "view_analytics")]
+	ViewAnalytics,
+	#[sea_orm(string_value = "view_audit_log")]
+	ViewAuditLog,
+	#[sea_orm(string_value = "view_transactions")]
+	ViewTransactions,
+}
 #[derive(
 	Debug,
 	Clone,
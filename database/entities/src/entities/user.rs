@@ -15,18 +15,41 @@ pub struct Model {
 	pub role: PlayerRole,
 	pub particle_color: Option<i32>,
 	pub refund_count: i32,
+	pub last_known_name: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+	#[sea_orm(has_many = "super::api_key::Entity")]
+	ApiKey,
+	#[sea_orm(has_many = "super::audit_log::Entity")]
+	AuditLog,
 	#[sea_orm(has_many = "super::daily_playtime::Entity")]
 	DailyPlaytime,
+	#[sea_orm(has_many = "super::device_code::Entity")]
+	DeviceCode,
 	#[sea_orm(has_many = "super::monthly_active_login::Entity")]
 	MonthlyActiveLogin,
 	#[sea_orm(has_many = "super::player_equipped_cosmetic::Entity")]
 	PlayerEquippedCosmetic,
 	#[sea_orm(has_many = "super::player_owned_cosmetic::Entity")]
 	PlayerOwnedCosmetic,
+	#[sea_orm(has_many = "super::session::Entity")]
+	Session,
+	#[sea_orm(has_many = "super::user_permission_override::Entity")]
+	UserPermissionOverride,
+}
+
+impl Related<super::api_key::Entity> for Entity {
+	fn to() -> RelationDef {
+		Relation::ApiKey.def()
+	}
+}
+
+impl Related<super::audit_log::Entity> for Entity {
+	fn to() -> RelationDef {
+		Relation::AuditLog.def()
+	}
 }
 
 impl Related<super::daily_playtime::Entity> for Entity {
@@ -35,6 +58,12 @@ impl Related<super::daily_playtime::Entity> for Entity {
 	}
 }
 
+impl Related<super::device_code::Entity> for Entity {
+	fn to() -> RelationDef {
+		Relation::DeviceCode.def()
+	}
+}
+
 impl Related<super::monthly_active_login::Entity> for Entity {
 	fn to() -> RelationDef {
 		Relation::MonthlyActiveLogin.def()
@@ -53,6 +82,18 @@ impl Related<super::player_owned_cosmetic::Entity> for Entity {
 	}
 }
 
+impl Related<super::session::Entity> for Entity {
+	fn to() -> RelationDef {
+		Relation::Session.def()
+	}
+}
+
+impl Related<super::user_permission_override::Entity> for Entity {
+	fn to() -> RelationDef {
+		Relation::UserPermissionOverride.def()
+	}
+}
+
 impl Related<super::cosmetic::Entity> for Entity {
 	fn to() -> RelationDef {
 		super::player_equipped_cosmetic::Relation::Cosmetic.def()
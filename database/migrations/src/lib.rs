@@ -35,6 +35,22 @@ mod m20260710_000000_extend_cosmetics_info;
 mod m20260711_000000_add_cosmetic_cover;
 mod m20260717_000000_add_cosmetic_trigram_search;
 mod m20260720_000000_create_tracked_links;
+mod m20261017_000000_create_session_table;
+mod m20261017_000001_create_api_key_table;
+mod m20261017_000002_create_player_ban_table;
+mod m20261017_000003_create_permission_tables;
+mod m20261017_000004_add_export_permission;
+mod m20261017_000005_create_account_deletion_table;
+mod m20261017_000006_create_device_code_table;
+mod m20261017_000007_create_audit_log_table;
+mod m20261017_000008_add_last_known_name;
+mod m20261017_000009_create_friend_tables;
+mod m20261017_000010_create_announcement_table;
+mod m20261017_000011_create_catalog_change_table;
+mod m20261017_000012_create_outfit_table;
+mod m20261017_000013_create_announcement_delivery_table;
+mod m20261017_000014_create_presence_tables;
+mod m20261017_000015_create_emote_invite_table;
 
 pub struct Migrator;
 
@@ -77,6 +93,22 @@ impl MigratorTrait for Migrator {
 			Box::new(m20260711_000000_add_cosmetic_cover::Migration),
 			Box::new(m20260717_000000_add_cosmetic_trigram_search::Migration),
 			Box::new(m20260720_000000_create_tracked_links::Migration),
+			Box::new(m20261017_000000_create_session_table::Migration),
+			Box::new(m20261017_000001_create_api_key_table::Migration),
+			Box::new(m20261017_000002_create_player_ban_table::Migration),
+			Box::new(m20261017_000003_create_permission_tables::Migration),
+			Box::new(m20261017_000004_add_export_permission::Migration),
+			Box::new(m20261017_000005_create_account_deletion_table::Migration),
+			Box::new(m20261017_000006_create_device_code_table::Migration),
+			Box::new(m20261017_000007_create_audit_log_table::Migration),
+			Box::new(m20261017_000008_add_last_known_name::Migration),
+			Box::new(m20261017_000009_create_friend_tables::Migration),
+			Box::new(m20261017_000010_create_announcement_table::Migration),
+			Box::new(m20261017_000011_create_catalog_change_table::Migration),
+			Box::new(m20261017_000012_create_outfit_table::Migration),
+			Box::new(m20261017_000013_create_announcement_delivery_table::Migration),
+			Box::new(m20261017_000014_create_presence_tables::Migration),
+			Box::new(m20261017_000015_create_emote_invite_table::Migration),
 		]
 	}
 }
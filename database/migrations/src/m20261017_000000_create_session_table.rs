@@ -0,0 +1,85 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20250917_163702_create_users_table::User;
+
+/// A login session. Every access token names the session it was issued for,
+/// and the session holds the (hashed) single-use refresh token used to renew
+/// it.
+#[derive(DeriveIden)]
+pub enum Session {
+	Table,
+	Id,
+	PlayerId,
+	/// SHA-256 of the secret half of the current refresh token. Replaced on
+	/// every refresh, so each refresh token can be used exactly once.
+	RefreshTokenHash,
+	CreatedAt,
+	LastRefreshedAt,
+	ExpiresAt,
+	RevokedAt,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.create_table(
+				Table::create()
+					.table(Session::Table)
+					.if_not_exists()
+					.col(ColumnDef::new(Session::Id).uuid().not_null().primary_key())
+					.col(ColumnDef::new(Session::PlayerId).integer().not_null())
+					.col(ColumnDef::new(Session::RefreshTokenHash).text().not_null())
+					.col(
+						ColumnDef::new(Session::CreatedAt)
+							.timestamp_with_time_zone()
+							.not_null()
+							.default(Expr::current_timestamp()),
+					)
+					.col(
+						ColumnDef::new(Session::LastRefreshedAt)
+							.timestamp_with_time_zone()
+							.not_null()
+							.default(Expr::current_timestamp()),
+					)
+					.col(
+						ColumnDef::new(Session::ExpiresAt)
+							.timestamp_with_time_zone()
+							.not_null(),
+					)
+					.col(
+						ColumnDef::new(Session::RevokedAt)
+							.timestamp_with_time_zone()
+							.null(),
+					)
+					.foreign_key(
+						ForeignKey::create()
+							.from(Session::Table, Session::PlayerId)
+							.to(User::Table, User::Id)
+							.on_delete(ForeignKeyAction::Cascade),
+					)
+					.to_owned(),
+			)
+			.await?;
+
+		manager
+			.create_index(
+				Index::create()
+					.if_not_exists()
+					.name("idx_session_player_id")
+					.table(Session::Table)
+					.col(Session::PlayerId)
+					.to_owned(),
+			)
+			.await
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.drop_table(Table::drop().table(Session::Table).to_owned())
+			.await
+	}
+}
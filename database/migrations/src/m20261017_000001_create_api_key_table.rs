@@ -0,0 +1,141 @@
+use sea_orm_migration::{
+	prelude::{extension::postgres::Type, *},
+	sea_orm::{EnumIter, Iterable as _},
+};
+
+#[derive(DeriveIden)]
+pub struct ApiScope;
+
+#[derive(DeriveIden, EnumIter)]
+pub enum ApiScopeVariants {
+	CatalogWrite,
+	LinksRead,
+	LinksWrite,
+	AnalyticsRead,
+	GrantsWrite,
+}
+
+#[derive(DeriveIden)]
+pub enum ApiKey {
+	Table,
+	Id,
+	/// A human readable description of who or what uses the key
+	Label,
+	/// SHA-256 of the secret half of the key. The key itself is only shown
+	/// once, when it is minted.
+	SecretHash,
+	/// The admin player who minted the key
+	CreatedBy,
+	CreatedAt,
+	ExpiresAt,
+	LastUsedAt,
+	RevokedAt,
+}
+
+#[derive(DeriveIden)]
+pub enum ApiKeyScope {
+	Table,
+	ApiKeyId,
+	Scope,
+}
+
+#[derive(DeriveIden)]
+pub enum User {
+	Table,
+	Id,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.create_type(
+				Type::create()
+					.as_enum(ApiScope)
+					.values(ApiScopeVariants::iter())
+					.to_owned(),
+			)
+			.await?;
+
+		manager
+			.create_table(
+				Table::create()
+					.table(ApiKey::Table)
+					.if_not_exists()
+					.col(ColumnDef::new(ApiKey::Id).uuid().not_null().primary_key())
+					.col(ColumnDef::new(ApiKey::Label).text().not_null())
+					.col(ColumnDef::new(ApiKey::SecretHash).text().not_null())
+					.col(ColumnDef::new(ApiKey::CreatedBy).integer().null())
+					.col(
+						ColumnDef::new(ApiKey::CreatedAt)
+							.timestamp_with_time_zone()
+							.not_null()
+							.default(Expr::current_timestamp()),
+					)
+					.col(
+						ColumnDef::new(ApiKey::ExpiresAt)
+							.timestamp_with_time_zone()
+							.null(),
+					)
+					.col(
+						ColumnDef::new(ApiKey::LastUsedAt)
+							.timestamp_with_time_zone()
+							.null(),
+					)
+					.col(
+						ColumnDef::new(ApiKey::RevokedAt)
+							.timestamp_with_time_zone()
+							.null(),
+					)
+					.foreign_key(
+						ForeignKey::create()
+							.from(ApiKey::Table, ApiKey::CreatedBy)
+							.to(User::Table, User::Id)
+							.on_delete(ForeignKeyAction::SetNull),
+					)
+					.to_owned(),
+			)
+			.await?;
+
+		manager
+			.create_table(
+				Table::create()
+					.table(ApiKeyScope::Table)
+					.if_not_exists()
+					.col(ColumnDef::new(ApiKeyScope::ApiKeyId).uuid().not_null())
+					.col(
+						ColumnDef::new(ApiKeyScope::Scope)
+							.custom(ApiScope)
+							.not_null(),
+					)
+					.primary_key(
+						Index::create()
+							.col(ApiKeyScope::ApiKeyId)
+							.col(ApiKeyScope::Scope),
+					)
+					.foreign_key(
+						ForeignKey::create()
+							.from(ApiKeyScope::Table, ApiKeyScope::ApiKeyId)
+							.to(ApiKey::Table, ApiKey::Id)
+							.on_delete(ForeignKeyAction::Cascade),
+					)
+					.to_owned(),
+			)
+			.await
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.drop_table(Table::drop().table(ApiKeyScope::Table).to_owned())
+			.await?;
+		manager
+			.drop_table(Table::drop().table(ApiKey::Table).to_owned())
+			.await?;
+		manager
+			.drop_type(Type::drop().name(ApiScope).to_owned())
+			.await
+	}
+}
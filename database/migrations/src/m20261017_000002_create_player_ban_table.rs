@@ -0,0 +1,103 @@
+use sea_orm_migration::prelude::*;
+
+/// Every ban ever issued, kept as history after it expires or is lifted. A ban
+/// is active while it is neither lifted nor past its expiry.
+#[derive(DeriveIden)]
+pub enum PlayerBan {
+	Table,
+	Id,
+	PlayerId,
+	Reason,
+	/// The moderator who issued the ban
+	ModeratorId,
+	CreatedAt,
+	/// When the ban lifts by itself. Permanent bans have no expiry.
+	ExpiresAt,
+	/// When a moderator lifted the ban early
+	LiftedAt,
+	LiftedBy,
+}
+
+#[derive(DeriveIden)]
+pub enum User {
+	Table,
+	Id,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.create_table(
+				Table::create()
+					.table(PlayerBan::Table)
+					.if_not_exists()
+					.col(
+						ColumnDef::new(PlayerBan::Id)
+							.integer()
+							.auto_increment()
+							.primary_key(),
+					)
+					.col(ColumnDef::new(PlayerBan::PlayerId).integer().not_null())
+					.col(ColumnDef::new(PlayerBan::Reason).text().not_null())
+					.col(ColumnDef::new(PlayerBan::ModeratorId).integer().null())
+					.col(
+						ColumnDef::new(PlayerBan::CreatedAt)
+							.timestamp_with_time_zone()
+							.not_null()
+							.default(Expr::current_timestamp()),
+					)
+					.col(
+						ColumnDef::new(PlayerBan::ExpiresAt)
+							.timestamp_with_time_zone()
+							.null(),
+					)
+					.col(
+						ColumnDef::new(PlayerBan::LiftedAt)
+							.timestamp_with_time_zone()
+							.null(),
+					)
+					.col(ColumnDef::new(PlayerBan::LiftedBy).integer().null())
+					.foreign_key(
+						ForeignKey::create()
+							.from(PlayerBan::Table, PlayerBan::PlayerId)
+							.to(User::Table, User::Id)
+							.on_delete(ForeignKeyAction::Cascade),
+					)
+					.foreign_key(
+						ForeignKey::create()
+							.from(PlayerBan::Table, PlayerBan::ModeratorId)
+							.to(User::Table, User::Id)
+							.on_delete(ForeignKeyAction::SetNull),
+					)
+					.foreign_key(
+						ForeignKey::create()
+							.from(PlayerBan::Table, PlayerBan::LiftedBy)
+							.to(User::Table, User::Id)
+							.on_delete(ForeignKeyAction::SetNull),
+					)
+					.to_owned(),
+			)
+			.await?;
+
+		manager
+			.create_index(
+				Index::create()
+					.name("idx_player_ban_player_id")
+					.table(PlayerBan::Table)
+					.col(PlayerBan::PlayerId)
+					.if_not_exists()
+					.to_owned(),
+			)
+			.await
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.drop_table(Table::drop().table(PlayerBan::Table).to_owned())
+			.await
+	}
+}
@@ -0,0 +1,136 @@
+use sea_orm_migration::prelude::*;
+
+/// The permissions every player with a role has
+#[derive(DeriveIden)]
+pub enum RolePermission {
+	Table,
+	Role,
+	Permission,
+}
+
+/// Per-player exceptions to the permissions of their role
+#[derive(DeriveIden)]
+pub enum UserPermissionOverride {
+	Table,
+	PlayerId,
+	Permission,
+	/// Whether the permission is granted to, or revoked from, the player
+	Granted,
+}
+
+#[derive(DeriveIden)]
+pub struct PlayerRole;
+
+#[derive(DeriveIden)]
+pub enum User {
+	Table,
+	Id,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// Permissions are stored as text and validated by the backend, rather than as
+/// a Postgres enum: Postgres can't use an enum value in the transaction that
+/// added it, and migrations all run in one transaction, so a new permission
+/// could never be seeded next to its enum value.
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.create_table(
+				Table::create()
+					.table(RolePermission::Table)
+					.if_not_exists()
+					.col(
+						ColumnDef::new(RolePermission::Role)
+							.custom(PlayerRole)
+							.not_null(),
+					)
+					.col(ColumnDef::new(RolePermission::Permission).text().not_null())
+					.primary_key(
+						Index::create()
+							.col(RolePermission::Role)
+							.col(RolePermission::Permission),
+					)
+					.to_owned(),
+			)
+			.await?;
+
+		manager
+			.create_table(
+				Table::create()
+					.table(UserPermissionOverride::Table)
+					.if_not_exists()
+					.col(
+						ColumnDef::new(UserPermissionOverride::PlayerId)
+							.integer()
+							.not_null(),
+					)
+					.col(
+						ColumnDef::new(UserPermissionOverride::Permission)
+							.text()
+							.not_null(),
+					)
+					.col(
+						ColumnDef::new(UserPermissionOverride::Granted)
+							.boolean()
+							.not_null(),
+					)
+					.primary_key(
+						Index::create()
+							.col(UserPermissionOverride::PlayerId)
+							.col(UserPermissionOverride::Permission),
+					)
+					.foreign_key(
+						ForeignKey::create()
+							.from(
+								UserPermissionOverride::Table,
+								UserPermissionOverride::PlayerId,
+							)
+							.to(User::Table, User::Id)
+							.on_delete(ForeignKeyAction::Cascade),
+					)
+					.to_owned(),
+			)
+			.await?;
+
+		// Seed the mappings with what each role could do under the old role
+		// ladder, so nobody gains or loses access
+		manager
+			.get_connection()
+			.execute_unprepared(
+				r#"
+				INSERT INTO role_permission (role, permission) VALUES
+					('moderator', 'ban_player'),
+					('moderator', 'view_transactions'),
+					('admin', 'grant_cosmetic'),
+					('admin', 'manage_catalog'),
+					('admin', 'view_transactions'),
+					('admin', 'manage_links'),
+					('admin', 'view_analytics'),
+					('admin', 'ban_player'),
+					('admin', 'manage_roles'),
+					('admin', 'manage_api_keys'),
+					('admin', 'manage_sessions')
+				ON CONFLICT DO NOTHING;
+				"#,
+			)
+			.await?;
+
+		Ok(())
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.drop_table(
+				Table::drop()
+					.table(UserPermissionOverride::Table)
+					.to_owned(),
+			)
+			.await?;
+		manager
+			.drop_table(Table::drop().table(RolePermission::Table).to_owned())
+			.await
+	}
+}
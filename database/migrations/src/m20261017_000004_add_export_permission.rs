@@ -0,0 +1,37 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.get_connection()
+			.execute_unprepared(
+				r#"
+				INSERT INTO role_permission (role, permission) VALUES
+					('admin', 'export_player_data')
+				ON CONFLICT DO NOTHING;
+				"#,
+			)
+			.await?;
+
+		Ok(())
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.get_connection()
+			.execute_unprepared(
+				r#"
+				DELETE FROM role_permission WHERE permission = 'export_player_data';
+				DELETE FROM user_permission_override
+					WHERE permission = 'export_player_data';
+				"#,
+			)
+			.await?;
+
+		Ok(())
+	}
+}
@@ -0,0 +1,106 @@
+use sea_orm_migration::prelude::*;
+
+/// Requested account deletions. A deletion can be cancelled until it is
+/// scheduled for, and the row is kept afterwards as a record of it.
+#[derive(DeriveIden)]
+pub enum AccountDeletion {
+	Table,
+	Id,
+	/// The player being deleted. Cleared once the deletion is carried out.
+	PlayerId,
+	/// The anonymous user the player's transactions were moved to
+	TombstoneId,
+	RequestedAt,
+	/// When the deletion is carried out, unless it is cancelled first
+	ScheduledFor,
+	CancelledAt,
+	CompletedAt,
+}
+
+#[derive(DeriveIden)]
+pub enum User {
+	Table,
+	Id,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.create_table(
+				Table::create()
+					.table(AccountDeletion::Table)
+					.if_not_exists()
+					.col(
+						ColumnDef::new(AccountDeletion::Id)
+							.integer()
+							.auto_increment()
+							.primary_key(),
+					)
+					.col(ColumnDef::new(AccountDeletion::PlayerId).integer().null())
+					.col(
+						ColumnDef::new(AccountDeletion::TombstoneId)
+							.integer()
+							.null(),
+					)
+					.col(
+						ColumnDef::new(AccountDeletion::RequestedAt)
+							.timestamp_with_time_zone()
+							.not_null()
+							.default(Expr::current_timestamp()),
+					)
+					.col(
+						ColumnDef::new(AccountDeletion::ScheduledFor)
+							.timestamp_with_time_zone()
+							.not_null(),
+					)
+					.col(
+						ColumnDef::new(AccountDeletion::CancelledAt)
+							.timestamp_with_time_zone()
+							.null(),
+					)
+					.col(
+						ColumnDef::new(AccountDeletion::CompletedAt)
+							.timestamp_with_time_zone()
+							.null(),
+					)
+					.foreign_key(
+						ForeignKey::create()
+							.from(AccountDeletion::Table, AccountDeletion::PlayerId)
+							.to(User::Table, User::Id)
+							.on_delete(ForeignKeyAction::SetNull),
+					)
+					.foreign_key(
+						ForeignKey::create()
+							.from(AccountDeletion::Table, AccountDeletion::TombstoneId)
+							.to(User::Table, User::Id)
+							.on_delete(ForeignKeyAction::SetNull),
+					)
+					.to_owned(),
+			)
+			.await?;
+
+		// A player can only have one pending deletion at a time
+		manager
+			.get_connection()
+			.execute_unprepared(
+				r#"
+				CREATE UNIQUE INDEX IF NOT EXISTS idx_account_deletion_pending
+					ON account_deletion (player_id)
+					WHERE cancelled_at IS NULL AND completed_at IS NULL;
+				"#,
+			)
+			.await?;
+
+		Ok(())
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.drop_table(Table::drop().table(AccountDeletion::Table).to_owned())
+			.await
+	}
+}
@@ -0,0 +1,88 @@
+use sea_orm_migration::prelude::*;
+
+/// Pending device code logins. A row lives from when a device asks for a code
+/// until the device exchanges it for a session, or it expires.
+#[derive(DeriveIden)]
+pub enum DeviceCode {
+	Table,
+	Id,
+	/// SHA-256 of the secret code the device polls with
+	SecretHash,
+	/// The short code the player confirms in game
+	UserCode,
+	/// The player who confirmed the code, if anyone has yet
+	PlayerId,
+	CreatedAt,
+	ExpiresAt,
+	ConfirmedAt,
+}
+
+#[derive(DeriveIden)]
+pub enum User {
+	Table,
+	Id,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.create_table(
+				Table::create()
+					.table(DeviceCode::Table)
+					.if_not_exists()
+					.col(
+						ColumnDef::new(DeviceCode::Id)
+							.integer()
+							.auto_increment()
+							.primary_key(),
+					)
+					.col(
+						ColumnDef::new(DeviceCode::SecretHash)
+							.text()
+							.not_null()
+							.unique_key(),
+					)
+					.col(
+						ColumnDef::new(DeviceCode::UserCode)
+							.text()
+							.not_null()
+							.unique_key(),
+					)
+					.col(ColumnDef::new(DeviceCode::PlayerId).integer().null())
+					.col(
+						ColumnDef::new(DeviceCode::CreatedAt)
+							.timestamp_with_time_zone()
+							.not_null()
+							.default(Expr::current_timestamp()),
+					)
+					.col(
+						ColumnDef::new(DeviceCode::ExpiresAt)
+							.timestamp_with_time_zone()
+							.not_null(),
+					)
+					.col(
+						ColumnDef::new(DeviceCode::ConfirmedAt)
+							.timestamp_with_time_zone()
+							.null(),
+					)
+					.foreign_key(
+						ForeignKey::create()
+							.from(DeviceCode::Table, DeviceCode::PlayerId)
+							.to(User::Table, User::Id)
+							.on_delete(ForeignKeyAction::Cascade),
+					)
+					.to_owned(),
+			)
+			.await
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.drop_table(Table::drop().table(DeviceCode::Table).to_owned())
+			.await
+	}
+}
@@ -0,0 +1,137 @@
+use sea_orm_migration::prelude::*;
+
+/// A record of every change made through the admin API
+#[derive(DeriveIden)]
+pub enum AuditLog {
+	Table,
+	Id,
+	/// The API key the change was made with, if any
+	ActorApiKeyId,
+	/// The player the change was made by, if it was made with a bearer token
+	ActorPlayerId,
+	/// What was done, e.g. `cosmetic.update`
+	Action,
+	/// The kind of entity that was changed, e.g. `cosmetic`
+	TargetType,
+	TargetId,
+	/// The changed fields of the target before the change
+	Before,
+	/// The changed fields of the target after the change
+	After,
+	ClientIp,
+	CreatedAt,
+}
+
+#[derive(DeriveIden)]
+pub enum ApiKey {
+	Table,
+	Id,
+}
+
+#[derive(DeriveIden)]
+pub enum User {
+	Table,
+	Id,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.create_table(
+				Table::create()
+					.table(AuditLog::Table)
+					.if_not_exists()
+					.col(
+						ColumnDef::new(AuditLog::Id)
+							.integer()
+							.auto_increment()
+							.primary_key(),
+					)
+					.col(ColumnDef::new(AuditLog::ActorApiKeyId).uuid().null())
+					.col(ColumnDef::new(AuditLog::ActorPlayerId).integer().null())
+					.col(ColumnDef::new(AuditLog::Action).text().not_null())
+					.col(ColumnDef::new(AuditLog::TargetType).text().not_null())
+					.col(ColumnDef::new(AuditLog::TargetId).text().not_null())
+					.col(ColumnDef::new(AuditLog::Before).json_binary().null())
+					.col(ColumnDef::new(AuditLog::After).json_binary().null())
+					.col(ColumnDef::new(AuditLog::ClientIp).text().null())
+					.col(
+						ColumnDef::new(AuditLog::CreatedAt)
+							.timestamp_with_time_zone()
+							.not_null()
+							.default(Expr::current_timestamp()),
+					)
+					.foreign_key(
+						ForeignKey::create()
+							.from(AuditLog::Table, AuditLog::ActorApiKeyId)
+							.to(ApiKey::Table, ApiKey::Id)
+							.on_delete(ForeignKeyAction::SetNull),
+					)
+					.foreign_key(
+						ForeignKey::create()
+							.from(AuditLog::Table, AuditLog::ActorPlayerId)
+							.to(User::Table, User::Id)
+							.on_delete(ForeignKeyAction::SetNull),
+					)
+					.to_owned(),
+			)
+			.await?;
+
+		manager
+			.create_index(
+				Index::create()
+					.name("idx_audit_log_created_at")
+					.table(AuditLog::Table)
+					.col(AuditLog::CreatedAt)
+					.to_owned(),
+			)
+			.await?;
+
+		manager
+			.create_index(
+				Index::create()
+					.name("idx_audit_log_target")
+					.table(AuditLog::Table)
+					.col(AuditLog::TargetType)
+					.col(AuditLog::TargetId)
+					.to_owned(),
+			)
+			.await?;
+
+		manager
+			.get_connection()
+			.execute_unprepared(
+				r#"
+				ALTER TYPE api_scope ADD VALUE IF NOT EXISTS 'audit_read';
+				INSERT INTO role_permission (role, permission) VALUES
+					('admin', 'view_audit_log')
+				ON CONFLICT DO NOTHING;
+				"#,
+			)
+			.await?;
+
+		Ok(())
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		// Postgres can't drop enum values, so `audit_read` stays on `api_scope`
+		manager
+			.get_connection()
+			.execute_unprepared(
+				r#"
+				DELETE FROM api_key_scope WHERE scope = 'audit_read';
+				DELETE FROM role_permission WHERE permission = 'view_audit_log';
+				DELETE FROM user_permission_override WHERE permission = 'view_audit_log';
+				"#,
+			)
+			.await?;
+
+		manager
+			.drop_table(Table::drop().table(AuditLog::Table).to_owned())
+			.await
+	}
+}
@@ -0,0 +1,55 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.alter_table(
+				TableAlterStatement::new()
+					.table(User::Table)
+					.add_column(ColumnDef::new(User::LastKnownName).text().null())
+					.to_owned(),
+			)
+			.await?;
+
+		// Usernames are case insensitive, so they are looked up by their
+		// lowercase form
+		manager
+			.get_connection()
+			.execute_unprepared(
+				r#"CREATE INDEX "idx_user_last_known_name" ON "user" (lower("last_known_name"))"#,
+			)
+			.await?;
+
+		Ok(())
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.drop_index(
+				Index::drop()
+					.name("idx_user_last_known_name")
+					.table(User::Table)
+					.to_owned(),
+			)
+			.await?;
+
+		manager
+			.alter_table(
+				TableAlterStatement::new()
+					.table(User::Table)
+					.drop_column(User::LastKnownName)
+					.to_owned(),
+			)
+			.await
+	}
+}
+
+#[derive(DeriveIden)]
+enum User {
+	Table,
+	LastKnownName,
+}
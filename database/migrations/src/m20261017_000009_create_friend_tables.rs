@@ -0,0 +1,126 @@
+use sea_orm_migration::prelude::*;
+
+/// Accepted friendships. Every friendship is stored once in each direction, so
+/// that a player's friends can be looked up by `player_id` alone.
+#[derive(DeriveIden)]
+pub enum Friendship {
+	Table,
+	PlayerId,
+	FriendId,
+	CreatedAt,
+}
+
+/// Friend requests waiting for the recipient to accept or decline them
+#[derive(DeriveIden)]
+pub enum FriendRequest {
+	Table,
+	SenderId,
+	RecipientId,
+	CreatedAt,
+}
+
+#[derive(DeriveIden)]
+pub enum User {
+	Table,
+	Id,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.create_table(
+				Table::create()
+					.table(Friendship::Table)
+					.if_not_exists()
+					.col(ColumnDef::new(Friendship::PlayerId).integer().not_null())
+					.col(ColumnDef::new(Friendship::FriendId).integer().not_null())
+					.col(
+						ColumnDef::new(Friendship::CreatedAt)
+							.timestamp_with_time_zone()
+							.not_null()
+							.default(Expr::current_timestamp()),
+					)
+					.primary_key(
+						Index::create()
+							.col(Friendship::PlayerId)
+							.col(Friendship::FriendId),
+					)
+					.foreign_key(
+						ForeignKey::create()
+							.from(Friendship::Table, Friendship::PlayerId)
+							.to(User::Table, User::Id)
+							.on_delete(ForeignKeyAction::Cascade),
+					)
+					.foreign_key(
+						ForeignKey::create()
+							.from(Friendship::Table, Friendship::FriendId)
+							.to(User::Table, User::Id)
+							.on_delete(ForeignKeyAction::Cascade),
+					)
+					.to_owned(),
+			)
+			.await?;
+
+		manager
+			.create_table(
+				Table::create()
+					.table(FriendRequest::Table)
+					.if_not_exists()
+					.col(ColumnDef::new(FriendRequest::SenderId).integer().not_null())
+					.col(
+						ColumnDef::new(FriendRequest::RecipientId)
+							.integer()
+							.not_null(),
+					)
+					.col(
+						ColumnDef::new(FriendRequest::CreatedAt)
+							.timestamp_with_time_zone()
+							.not_null()
+							.default(Expr::current_timestamp()),
+					)
+					.primary_key(
+						Index::create()
+							.col(FriendRequest::SenderId)
+							.col(FriendRequest::RecipientId),
+					)
+					.foreign_key(
+						ForeignKey::create()
+							.from(FriendRequest::Table, FriendRequest::SenderId)
+							.to(User::Table, User::Id)
+							.on_delete(ForeignKeyAction::Cascade),
+					)
+					.foreign_key(
+						ForeignKey::create()
+							.from(FriendRequest::Table, FriendRequest::RecipientId)
+							.to(User::Table, User::Id)
+							.on_delete(ForeignKeyAction::Cascade),
+					)
+					.to_owned(),
+			)
+			.await?;
+
+		manager
+			.create_index(
+				Index::create()
+					.name("idx_friend_request_recipient_id")
+					.table(FriendRequest::Table)
+					.col(FriendRequest::RecipientId)
+					.if_not_exists()
+					.to_owned(),
+			)
+			.await
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.drop_table(Table::drop().table(FriendRequest::Table).to_owned())
+			.await?;
+		manager
+			.drop_table(Table::drop().table(Friendship::Table).to_owned())
+			.await
+	}
+}
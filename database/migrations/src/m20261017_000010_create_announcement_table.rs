@@ -0,0 +1,106 @@
+use sea_orm_migration::prelude::*;
+
+/// Notices broadcast to connected mod users, kept so that players who connect
+/// while one is active are sent it too
+#[derive(DeriveIden)]
+pub enum Announcement {
+	Table,
+	Id,
+	/// `info`, `warning` or `critical`
+	Level,
+	Title,
+	Body,
+	Link,
+	/// Who the announcement is for, e.g. `{"type": "all"}`
+	Target,
+	CreatedAt,
+	/// When the announcement stops being sent to players who connect
+	ExpiresAt,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.create_table(
+				Table::create()
+					.table(Announcement::Table)
+					.if_not_exists()
+					.col(
+						ColumnDef::new(Announcement::Id)
+							.integer()
+							.auto_increment()
+							.primary_key(),
+					)
+					.col(ColumnDef::new(Announcement::Level).text().not_null())
+					.col(ColumnDef::new(Announcement::Title).text().not_null())
+					.col(ColumnDef::new(Announcement::Body).text().not_null())
+					.col(ColumnDef::new(Announcement::Link).text().null())
+					.col(
+						ColumnDef::new(Announcement::Target)
+							.json_binary()
+							.not_null(),
+					)
+					.col(
+						ColumnDef::new(Announcement::CreatedAt)
+							.timestamp_with_time_zone()
+							.not_null()
+							.default(Expr::current_timestamp()),
+					)
+					.col(
+						ColumnDef::new(Announcement::ExpiresAt)
+							.timestamp_with_time_zone()
+							.not_null(),
+					)
+					.to_owned(),
+			)
+			.await?;
+
+		manager
+			.create_index(
+				Index::create()
+					.name("idx_announcement_expires_at")
+					.table(Announcement::Table)
+					.col(Announcement::ExpiresAt)
+					.to_owned(),
+			)
+			.await?;
+
+		manager
+			.get_connection()
+			.execute_unprepared(
+				r#"
+				ALTER TYPE api_scope ADD VALUE IF NOT EXISTS 'announcements_write';
+				INSERT INTO role_permission (role, permission) VALUES
+					('admin', 'manage_announcements')
+				ON CONFLICT DO NOTHING;
+				"#,
+			)
+			.await?;
+
+		Ok(())
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		// Postgres can't drop enum values, so `announcements_write` stays on
+		// `api_scope`
+		manager
+			.get_connection()
+			.execute_unprepared(
+				r#"
+				DELETE FROM api_key_scope WHERE scope = 'announcements_write';
+				DELETE FROM role_permission WHERE permission = 'manage_announcements';
+				DELETE FROM user_permission_override
+					WHERE permission = 'manage_announcements';
+				"#,
+			)
+			.await?;
+
+		manager
+			.drop_table(Table::drop().table(Announcement::Table).to_owned())
+			.await
+	}
+}
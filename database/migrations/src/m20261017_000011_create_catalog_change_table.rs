@@ -0,0 +1,64 @@
+use sea_orm_migration::prelude::*;
+
+/// Every change made to the catalog, numbered by an always increasing catalog
+/// version, so that clients can tell which entries they need to re-fetch
+#[derive(DeriveIden)]
+pub enum CatalogChange {
+	Table,
+	Version,
+	/// JSON arrays of the IDs that changed
+	CosmeticIds,
+	GroupIds,
+	BundleIds,
+	CreatedAt,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.create_table(
+				Table::create()
+					.table(CatalogChange::Table)
+					.if_not_exists()
+					.col(
+						ColumnDef::new(CatalogChange::Version)
+							.big_integer()
+							.auto_increment()
+							.primary_key(),
+					)
+					.col(
+						ColumnDef::new(CatalogChange::CosmeticIds)
+							.json_binary()
+							.not_null(),
+					)
+					.col(
+						ColumnDef::new(CatalogChange::GroupIds)
+							.json_binary()
+							.not_null(),
+					)
+					.col(
+						ColumnDef::new(CatalogChange::BundleIds)
+							.json_binary()
+							.not_null(),
+					)
+					.col(
+						ColumnDef::new(CatalogChange::CreatedAt)
+							.timestamp_with_time_zone()
+							.not_null()
+							.default(Expr::current_timestamp()),
+					)
+					.to_owned(),
+			)
+			.await
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.drop_table(Table::drop().table(CatalogChange::Table).to_owned())
+			.await
+	}
+}
@@ -0,0 +1,86 @@
+use sea_orm_migration::prelude::*;
+
+/// Named sets of equipped cosmetics and particle color that players can switch
+/// to at once
+#[derive(DeriveIden)]
+pub enum Outfit {
+	Table,
+	Id,
+	PlayerId,
+	Name,
+	/// JSON object of the cosmetic ID equipped in each slot
+	Equipped,
+	ParticleColor,
+	CreatedAt,
+	UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+pub enum User {
+	Table,
+	Id,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.create_table(
+				Table::create()
+					.table(Outfit::Table)
+					.if_not_exists()
+					.col(
+						ColumnDef::new(Outfit::Id)
+							.integer()
+							.auto_increment()
+							.primary_key(),
+					)
+					.col(ColumnDef::new(Outfit::PlayerId).integer().not_null())
+					.col(ColumnDef::new(Outfit::Name).text().not_null())
+					.col(ColumnDef::new(Outfit::Equipped).json_binary().not_null())
+					.col(ColumnDef::new(Outfit::ParticleColor).integer().null())
+					.col(
+						ColumnDef::new(Outfit::CreatedAt)
+							.timestamp_with_time_zone()
+							.not_null()
+							.default(Expr::current_timestamp()),
+					)
+					.col(
+						ColumnDef::new(Outfit::UpdatedAt)
+							.timestamp_with_time_zone()
+							.not_null()
+							.default(Expr::current_timestamp()),
+					)
+					.foreign_key(
+						ForeignKey::create()
+							.from(Outfit::Table, Outfit::PlayerId)
+							.to(User::Table, User::Id)
+							.on_delete(ForeignKeyAction::Cascade),
+					)
+					.to_owned(),
+			)
+			.await?;
+
+		manager
+			.create_index(
+				Index::create()
+					.name("idx_outfit_player_id_name")
+					.table(Outfit::Table)
+					.col(Outfit::PlayerId)
+					.col(Outfit::Name)
+					.unique()
+					.if_not_exists()
+					.to_owned(),
+			)
+			.await
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.drop_table(Table::drop().table(Outfit::Table).to_owned())
+			.await
+	}
+}
@@ -0,0 +1,97 @@
+use sea_orm_migration::prelude::*;
+
+/// Which announcements each player was sent, so they are only replayed to
+/// players that haven't seen them yet
+#[derive(DeriveIden)]
+pub enum AnnouncementDelivery {
+	Table,
+	AnnouncementId,
+	PlayerId,
+	DeliveredAt,
+}
+
+#[derive(DeriveIden)]
+pub enum Announcement {
+	Table,
+	Id,
+}
+
+#[derive(DeriveIden)]
+pub enum User {
+	Table,
+	Id,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.create_table(
+				Table::create()
+					.table(AnnouncementDelivery::Table)
+					.if_not_exists()
+					.col(
+						ColumnDef::new(AnnouncementDelivery::AnnouncementId)
+							.integer()
+							.not_null(),
+					)
+					.col(
+						ColumnDef::new(AnnouncementDelivery::PlayerId)
+							.integer()
+							.not_null(),
+					)
+					.col(
+						ColumnDef::new(AnnouncementDelivery::DeliveredAt)
+							.timestamp_with_time_zone()
+							.not_null()
+							.default(Expr::current_timestamp()),
+					)
+					.primary_key(
+						Index::create()
+							.col(AnnouncementDelivery::AnnouncementId)
+							.col(AnnouncementDelivery::PlayerId),
+					)
+					.foreign_key(
+						ForeignKey::create()
+							.from(
+								AnnouncementDelivery::Table,
+								AnnouncementDelivery::AnnouncementId,
+							)
+							.to(Announcement::Table, Announcement::Id)
+							.on_delete(ForeignKeyAction::Cascade),
+					)
+					.foreign_key(
+						ForeignKey::create()
+							.from(
+								AnnouncementDelivery::Table,
+								AnnouncementDelivery::PlayerId,
+							)
+							.to(User::Table, User::Id)
+							.on_delete(ForeignKeyAction::Cascade),
+					)
+					.to_owned(),
+			)
+			.await?;
+
+		// Looked up per player on every connection
+		manager
+			.create_index(
+				Index::create()
+					.name("idx_announcement_delivery_player_id")
+					.table(AnnouncementDelivery::Table)
+					.col(AnnouncementDelivery::PlayerId)
+					.if_not_exists()
+					.to_owned(),
+			)
+			.await
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.drop_table(Table::drop().table(AnnouncementDelivery::Table).to_owned())
+			.await
+	}
+}
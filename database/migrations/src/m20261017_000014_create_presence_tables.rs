@@ -0,0 +1,85 @@
+use sea_orm_migration::prelude::*;
+
+/// Backend instances sharing the realtime bus, kept while they send heartbeats
+#[derive(DeriveIden)]
+pub enum RealtimeInstance {
+	Table,
+	Id,
+	StartedAt,
+	HeartbeatAt,
+}
+
+/// The players connected to each instance, so that instances that start later
+/// know who is online, and players of an instance that stopped sending
+/// heartbeats can be taken offline
+#[derive(DeriveIden)]
+pub enum PlayerPresence {
+	Table,
+	InstanceId,
+	/// The Minecraft UUID of the player
+	Player,
+	/// The Minecraft server the player reported being on
+	Server,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.create_table(
+				Table::create()
+					.table(RealtimeInstance::Table)
+					.if_not_exists()
+					.col(ColumnDef::new(RealtimeInstance::Id).uuid().primary_key())
+					.col(
+						ColumnDef::new(RealtimeInstance::StartedAt)
+							.timestamp_with_time_zone()
+							.not_null()
+							.default(Expr::current_timestamp()),
+					)
+					.col(
+						ColumnDef::new(RealtimeInstance::HeartbeatAt)
+							.timestamp_with_time_zone()
+							.not_null()
+							.default(Expr::current_timestamp()),
+					)
+					.to_owned(),
+			)
+			.await?;
+
+		manager
+			.create_table(
+				Table::create()
+					.table(PlayerPresence::Table)
+					.if_not_exists()
+					.col(ColumnDef::new(PlayerPresence::InstanceId).uuid().not_null())
+					.col(ColumnDef::new(PlayerPresence::Player).uuid().not_null())
+					.col(ColumnDef::new(PlayerPresence::Server).text().null())
+					.primary_key(
+						Index::create()
+							.col(PlayerPresence::InstanceId)
+							.col(PlayerPresence::Player),
+					)
+					.foreign_key(
+						ForeignKey::create()
+							.from(PlayerPresence::Table, PlayerPresence::InstanceId)
+							.to(RealtimeInstance::Table, RealtimeInstance::Id)
+							.on_delete(ForeignKeyAction::Cascade),
+					)
+					.to_owned(),
+			)
+			.await
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.drop_table(Table::drop().table(PlayerPresence::Table).to_owned())
+			.await?;
+		manager
+			.drop_table(Table::drop().table(RealtimeInstance::Table).to_owned())
+			.await
+	}
+}
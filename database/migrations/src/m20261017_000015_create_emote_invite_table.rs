@@ -0,0 +1,47 @@
+use sea_orm_migration::prelude::*;
+
+/// Pending paired emote invites, so that instances that start later can still
+/// answer them
+#[derive(DeriveIden)]
+pub enum EmoteInvite {
+	Table,
+	Id,
+	/// The Minecraft UUID of the player who sent the invite
+	Initiator,
+	/// The Minecraft UUID of the player who was invited
+	Target,
+	EmoteId,
+	ExpiresAt,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+	async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.create_table(
+				Table::create()
+					.table(EmoteInvite::Table)
+					.if_not_exists()
+					.col(ColumnDef::new(EmoteInvite::Id).uuid().primary_key())
+					.col(ColumnDef::new(EmoteInvite::Initiator).uuid().not_null())
+					.col(ColumnDef::new(EmoteInvite::Target).uuid().not_null())
+					.col(ColumnDef::new(EmoteInvite::EmoteId).integer().not_null())
+					.col(
+						ColumnDef::new(EmoteInvite::ExpiresAt)
+							.timestamp_with_time_zone()
+							.not_null(),
+					)
+					.to_owned(),
+			)
+			.await
+	}
+
+	async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+		manager
+			.drop_table(Table::drop().table(EmoteInvite::Table).to_owned())
+			.await
+	}
+}
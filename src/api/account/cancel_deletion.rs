@@ -0,0 +1,71 @@
+use aide::{
+	OperationIo,
+	axum::{ApiRouter, routing::delete_with},
+	transform::TransformOperation,
+};
+use axum::{extract::State, http::StatusCode, response::IntoResponse};
+use entities::{account_deletion, prelude::*};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, sea_query::Expr};
+
+use crate::api::{
+	ApiState,
+	account::{AuthenticatedPlayer, deletion::pending_condition},
+};
+
+#[derive(thiserror::Error, Debug, OperationIo)]
+pub enum CancelDeletionError {
+	#[error("No account deletion is pending")]
+	NotPending,
+	#[error("Unable to query database: {0}")]
+	Database(#[from] sea_orm::error::DbErr),
+}
+
+impl IntoResponse for CancelDeletionError {
+	fn into_response(self) -> axum::response::Response {
+		(
+			match self {
+				Self::NotPending => StatusCode::NOT_FOUND,
+				Self::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+			},
+			self.to_string(),
+		)
+			.into_response()
+	}
+}
+
+fn endpoint_doc(op: TransformOperation) -> TransformOperation {
+	op.id("cancelAccountDeletion")
+		.summary("Cancel account deletion")
+		.description(
+			"Cancels the pending deletion of the authenticated player's account. \
+			 Deletions can no longer be cancelled once carried out.",
+		)
+		.tag("account")
+}
+
+pub(super) fn router() -> ApiRouter<ApiState> {
+	ApiRouter::new()
+		.api_route("/deletion", delete_with(self::endpoint, self::endpoint_doc))
+}
+
+#[tracing::instrument(level = "debug", skip(state))]
+async fn endpoint(
+	State(state): State<ApiState>,
+	AuthenticatedPlayer(player): AuthenticatedPlayer,
+) -> Result<StatusCode, CancelDeletionError> {
+	let result = AccountDeletion::update_many()
+		.col_expr(
+			account_deletion::Column::CancelledAt,
+			Expr::current_timestamp().into(),
+		)
+		.filter(account_deletion::Column::PlayerId.eq(player.id))
+		.filter(pending_condition())
+		.exec(&state.database)
+		.await?;
+
+	if result.rows_affected == 0 {
+		return Err(CancelDeletionError::NotPending);
+	}
+
+	Ok(StatusCode::NO_CONTENT)
+}
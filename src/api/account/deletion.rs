@@ -0,0 +1,286 @@
+//! Account deletion. Players ask for their account to be deleted, and it is
+//! carried out once a grace period passes without them cancelling it.
+//!
+//! Transactions have to be kept for accounting, so rather than being deleted
+//! with the player they are moved to a fresh tombstone user with a random
+//! UUID, and scrubbed of anything that could identify the player.
+
+use std::{collections::HashMap, time::Duration};
+
+use chrono::{DateTime, Days, FixedOffset, Utc};
+use entities::{
+	account_deletion, prelude::*, sea_orm_active_enums::TransactionProvider, transaction,
+	user,
+};
+use schemars::JsonSchema;
+use sea_orm::{
+	ColumnTrait, Condition, ConnectionTrait, DbErr, EntityTrait, QueryFilter, QueryOrder,
+	QuerySelect, Set, TransactionTrait,
+	sea_query::{Expr, LockBehavior, LockType},
+};
+use serde::Serialize;
+use stripe_checkout::checkout_session::{RetrieveCheckoutSession, UpdateCheckoutSession};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::api::{
+	ApiState,
+	audit::{self, AuditActor, AuditEntry},
+	websocket::structs::CLOSE_CODE_ACCOUNT_DELETED,
+};
+
+/// How long players have to cancel a deletion before it is carried out
+pub(super) const DELETION_GRACE_PERIOD: Days = Days::new(14);
+const DELETION_POLL_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// A deletion that has been requested, but not carried out yet
+#[derive(Debug, Serialize, JsonSchema)]
+pub(super) struct PendingDeletion {
+	requested_at: DateTime<FixedOffset>,
+	/// When the account will be deleted, unless the deletion is cancelled
+	scheduled_for: DateTime<FixedOffset>,
+}
+
+impl From<account_deletion::Model> for PendingDeletion {
+	fn from(deletion: account_deletion::Model) -> Self {
+		Self {
+			requested_at: deletion.requested_at,
+			scheduled_for: deletion.scheduled_for,
+		}
+	}
+}
+
+/// Matches deletions that are neither cancelled nor carried out yet
+pub(super) fn pending_condition() -> Condition {
+	Condition::all()
+		.add(account_deletion::Column::CancelledAt.is_null())
+		.add(account_deletion::Column::CompletedAt.is_null())
+}
+
+/// A player whose data was deleted by [`anonymize_account`]
+#[derive(Debug)]
+struct DeletedAccount {
+	player: Uuid,
+	tombstone: Uuid,
+	/// Checkout sessions whose metadata still references the player
+	stripe_sessions: Vec<String>,
+}
+
+/// Carries out due account deletions, periodically
+pub(in crate::api) async fn process_deletions_loop(state: ApiState) {
+	let mut interval = tokio::time::interval(DELETION_POLL_INTERVAL);
+
+	loop {
+		interval.tick().await;
+		if let Err(error) = process_due_deletions(&state).await {
+			warn!("Unable to process account deletions: {error}");
+		}
+	}
+}
+
+async fn process_due_deletions(state: &ApiState) -> Result<(), DbErr> {
+	loop {
+		let txn = state.database.begin().await?;
+		// Skip deletions another backend instance is already carrying out
+		let Some(deletion) = AccountDeletion::find()
+			.filter(pending_condition())
+			.filter(account_deletion::Column::ScheduledFor.lte(Utc::now()))
+			.order_by_asc(account_deletion::Column::ScheduledFor)
+			.lock_with_behavior(LockType::Update, LockBehavior::SkipLocked)
+			.one(&txn)
+			.await?
+		else {
+			return Ok(());
+		};
+
+		let deleted = anonymize_account(&txn, &deletion).await?;
+		txn.commit().await?;
+
+		let Some(deleted) = deleted else {
+			continue;
+		};
+		info!(
+			deletion = deletion.id,
+			tombstone = %deleted.tombstone,
+			"Deleted account"
+		);
+
+		state.realtime.forget_player(deleted.player).await;
+		state
+			.realtime
+			.close_connections(
+				deleted.player,
+				CLOSE_CODE_ACCOUNT_DELETED,
+				"Account deleted",
+			)
+			.await;
+		scrub_stripe_metadata(state, &deleted).await;
+	}
+}
+
+/// Deletes everything tied to a player except their transactions, which are
+/// moved to a new tombstone user. Returns [`None`] if the player no longer
+/// exists.
+async fn anonymize_account(
+	db: &impl ConnectionTrait,
+	deletion: &account_deletion::Model,
+) -> Result<Option<DeletedAccount>, DbErr> {
+	let player = match deletion.player_id {
+		Some(player_id) => User::find_by_id(player_id).one(db).await?,
+		None => None,
+	};
+	let Some(player) = player else {
+		AccountDeletion::update_many()
+			.col_expr(
+				account_deletion::Column::CompletedAt,
+				Expr::current_timestamp().into(),
+			)
+			.filter(account_deletion::Column::Id.eq(deletion.id))
+			.exec(db)
+			.await?;
+		return Ok(None);
+	};
+
+	let tombstone = User::insert(user::ActiveModel {
+		minecraft_uuid: Set(Uuid::now_v7()),
+		// Refunds count against the buyer, and stay relevant for accounting
+		refund_count: Set(player.refund_count),
+		..Default::default()
+	})
+	.exec_with_returning(db)
+	.await?;
+
+	let stripe_sessions = Transaction::find()
+		.filter(transaction::Column::Provider.eq(TransactionProvider::Stripe))
+		.filter(
+			Condition::any()
+				.add(transaction::Column::PlayerId.eq(player.id))
+				.add(transaction::Column::Buyer.eq(player.id)),
+		)
+		.all(db)
+		.await?
+		.into_iter()
+		.filter_map(|transaction| transaction.stripe_payment_id)
+		.collect();
+
+	Transaction::update_many()
+		.col_expr(transaction::Column::PlayerId, Expr::value(tombstone.id))
+		.filter(transaction::Column::PlayerId.eq(player.id))
+		.exec(db)
+		.await?;
+	Transaction::update_many()
+		.col_expr(transaction::Column::Buyer, Expr::value(tombstone.id))
+		.filter(transaction::Column::Buyer.eq(player.id))
+		.exec(db)
+		.await?;
+	Transaction::update_many()
+		.col_expr(
+			transaction::Column::RawMetadata,
+			Expr::value(serde_json::json!({})),
+		)
+		.filter(
+			Condition::any()
+				.add(transaction::Column::PlayerId.eq(tombstone.id))
+				.add(transaction::Column::Buyer.eq(tombstone.id)),
+		)
+		.exec(db)
+		.await?;
+
+	AccountDeletion::update_many()
+		.col_expr(
+			account_deletion::Column::CompletedAt,
+			Expr::current_timestamp().into(),
+		)
+		.col_expr(
+			account_deletion::Column::TombstoneId,
+			Expr::value(tombstone.id),
+		)
+		.filter(account_deletion::Column::Id.eq(deletion.id))
+		.exec(db)
+		.await?;
+
+	// Cascades to everything else tied to the player, and clears the player
+	// from the deletion record
+	User::delete_by_id(player.id).exec(db).await?;
+	audit::record(
+		db,
+		&AuditActor::system(),
+		AuditEntry::new("account.delete", "user", player.id).after(serde_json::json!({
+			"deletion_id": deletion.id,
+			"tombstone_id": tombstone.id,
+		})),
+	)
+	.await?;
+
+	Ok(Some(DeletedAccount {
+		player: player.minecraft_uuid,
+		tombstone: tombstone.minecraft_uuid,
+		stripe_sessions,
+	}))
+}
+
+/// Checkout sessions store the UUIDs of the player and buyer as metadata,
+/// which is replaced with the tombstone. Failures are only logged, as the
+/// account is already deleted on our side.
+async fn scrub_stripe_metadata(state: &ApiState, deleted: &DeletedAccount) {
+	let player = deleted.player.to_string();
+	let tombstone = deleted.tombstone.to_string();
+
+	for session_id in &deleted.stripe_sessions {
+		let result = async {
+			let session = RetrieveCheckoutSession::new(session_id.as_str())
+				.send(&state.stripe.client)
+				.await?;
+			let metadata = replace_metadata_values(
+				session.metadata.unwrap_or_default(),
+				&player,
+				&tombstone,
+			);
+			UpdateCheckoutSession::new(session_id.as_str())
+				.metadata(metadata)
+				.send(&state.stripe.client)
+				.await?;
+			Ok::<_, stripe_client::StripeError>(())
+		}
+		.await;
+
+		if let Err(error) = result {
+			warn!("Unable to scrub metadata of checkout session {session_id}: {error}");
+		}
+	}
+}
+
+fn replace_metadata_values(
+	metadata: HashMap<String, String>,
+	from: &str,
+	to: &str,
+) -> HashMap<String, String> {
+	metadata
+		.into_iter()
+		.map(|(key, value)| {
+			let value = if value == from { to.to_owned() } else { value };
+			(key, value)
+		})
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::HashMap;
+
+	use super::replace_metadata_values;
+
+	#[test]
+	fn only_matching_metadata_is_replaced() {
+		let metadata = HashMap::from([
+			("player".to_owned(), "deleted".to_owned()),
+			("buyer".to_owned(), "someone".to_owned()),
+			("prices".to_owned(), "price_1".to_owned()),
+		]);
+
+		let metadata = replace_metadata_values(metadata, "deleted", "tombstone");
+		assert_eq!(metadata["player"], "tombstone");
+		assert_eq!(metadata["buyer"], "someone");
+		assert_eq!(metadata["prices"], "price_1");
+	}
+}
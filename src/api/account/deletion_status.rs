@@ -0,0 +1,65 @@
+use aide::{
+	OperationIo,
+	axum::{ApiRouter, routing::get_with},
+	transform::TransformOperation,
+};
+use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
+use entities::{account_deletion, prelude::*};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+
+use crate::api::{
+	ApiState,
+	account::{
+		AuthenticatedPlayer,
+		deletion::{PendingDeletion, pending_condition},
+	},
+};
+
+#[derive(thiserror::Error, Debug, OperationIo)]
+pub enum DeletionStatusError {
+	#[error("No account deletion is pending")]
+	NotPending,
+	#[error("Unable to query database: {0}")]
+	Database(#[from] sea_orm::error::DbErr),
+}
+
+impl IntoResponse for DeletionStatusError {
+	fn into_response(self) -> axum::response::Response {
+		(
+			match self {
+				Self::NotPending => StatusCode::NOT_FOUND,
+				Self::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+			},
+			self.to_string(),
+		)
+			.into_response()
+	}
+}
+
+fn endpoint_doc(op: TransformOperation) -> TransformOperation {
+	op.id("getAccountDeletion")
+		.summary("Get pending account deletion")
+		.description(
+			"Gets when the authenticated player's account is scheduled to be deleted, \
+			 if a deletion was requested.",
+		)
+		.tag("account")
+}
+
+pub(super) fn router() -> ApiRouter<ApiState> {
+	ApiRouter::new().api_route("/deletion", get_with(self::endpoint, self::endpoint_doc))
+}
+
+#[tracing::instrument(level = "debug", skip(state))]
+async fn endpoint(
+	State(state): State<ApiState>,
+	AuthenticatedPlayer(player): AuthenticatedPlayer,
+) -> Result<Json<PendingDeletion>, DeletionStatusError> {
+	AccountDeletion::find()
+		.filter(account_deletion::Column::PlayerId.eq(player.id))
+		.filter(pending_condition())
+		.one(&state.database)
+		.await?
+		.map(|deletion| Json(deletion.into()))
+		.ok_or(DeletionStatusError::NotPending)
+}
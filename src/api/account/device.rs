@@ -0,0 +1,117 @@
+//! Device code logins, for clients that can't perform the Mojang session
+//! handshake, like the website. The client asks for a short code, the player
+//! confirms it in game, and the client then exchanges its device code for a
+//! normal session.
+
+use chrono::{TimeDelta, Utc};
+use entities::{device_code, prelude::*, user};
+use rand::Rng as _;
+use sea_orm::{
+	ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter, sea_query::Expr,
+};
+
+/// How long a device has to get its code confirmed and exchange it
+pub(super) const DEVICE_CODE_LIFETIME: TimeDelta = TimeDelta::minutes(10);
+/// How many seconds devices should wait between polls for a session
+pub(super) const DEVICE_CODE_POLL_INTERVAL: u64 = 5;
+
+/// Consonants only, so codes are easy to read out and never spell words
+const USER_CODE_ALPHABET: &[u8] = b"BCDFGHJKLMNPQRSTVWXZ";
+const USER_CODE_LENGTH: usize = 8;
+
+#[derive(thiserror::Error, Debug)]
+pub(crate) enum ConfirmDeviceError {
+	#[error("The code is invalid, expired or was already confirmed")]
+	InvalidCode,
+	#[error("Unable to query database: {0}")]
+	Database(#[from] DbErr),
+}
+
+/// Generates a user code, formatted as `XXXX-XXXX`
+pub(super) fn generate_user_code() -> String {
+	let mut rng = rand::rng();
+	let code = (0..USER_CODE_LENGTH)
+		.map(|_| {
+			USER_CODE_ALPHABET[rng.random_range(0..USER_CODE_ALPHABET.len())] as char
+		})
+		.collect::<String>();
+	format_user_code(&code)
+}
+
+fn format_user_code(code: &str) -> String {
+	let (first, second) = code.split_at(USER_CODE_LENGTH / 2);
+	format!("{first}-{second}")
+}
+
+/// Accepts user codes the way players are likely to type them, in any case
+/// and with or without the dash.
+fn normalize_user_code(input: &str) -> Option<String> {
+	let code = input
+		.chars()
+		.filter(|c| *c != '-' && !c.is_whitespace())
+		.map(|c| c.to_ascii_uppercase())
+		.collect::<String>();
+
+	(code.len() == USER_CODE_LENGTH
+		&& code.bytes().all(|c| USER_CODE_ALPHABET.contains(&c)))
+	.then(|| format_user_code(&code))
+}
+
+/// Confirms a pending device code on behalf of a player, letting the device
+/// that requested it log in as them.
+pub(crate) async fn confirm_device_code(
+	db: &impl ConnectionTrait,
+	player: &user::Model,
+	user_code: &str,
+) -> Result<(), ConfirmDeviceError> {
+	let user_code =
+		normalize_user_code(user_code).ok_or(ConfirmDeviceError::InvalidCode)?;
+
+	let result = DeviceCode::update_many()
+		.col_expr(device_code::Column::PlayerId, Expr::value(player.id))
+		.col_expr(
+			device_code::Column::ConfirmedAt,
+			Expr::current_timestamp().into(),
+		)
+		.filter(device_code::Column::UserCode.eq(user_code))
+		.filter(device_code::Column::ConfirmedAt.is_null())
+		.filter(device_code::Column::ExpiresAt.gt(Utc::now()))
+		.exec(db)
+		.await?;
+
+	if result.rows_affected == 0 {
+		return Err(ConfirmDeviceError::InvalidCode);
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{generate_user_code, normalize_user_code};
+
+	#[test]
+	fn generated_codes_are_normalized() {
+		let code = generate_user_code();
+		assert_eq!(code.len(), 9);
+		assert_eq!(normalize_user_code(&code), Some(code));
+	}
+
+	#[test]
+	fn typed_codes_are_normalized() {
+		assert_eq!(
+			normalize_user_code(" bcdf ghjk "),
+			Some("BCDF-GHJK".to_owned())
+		);
+		assert_eq!(
+			normalize_user_code("BCDF-GHJK"),
+			Some("BCDF-GHJK".to_owned())
+		);
+	}
+
+	#[test]
+	fn invalid_codes_are_rejected() {
+		assert_eq!(normalize_user_code("BCDF-GHJ"), None);
+		assert_eq!(normalize_user_code("BCDF-GHJA"), None);
+		assert_eq!(normalize_user_code(""), None);
+	}
+}
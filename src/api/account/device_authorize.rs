@@ -0,0 +1,97 @@
+use aide::{
+	OperationIo,
+	axum::{ApiRouter, routing::post_with},
+	transform::TransformOperation,
+};
+use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
+use chrono::Utc;
+use entities::{device_code, prelude::*};
+use schemars::JsonSchema;
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set};
+use serde::Serialize;
+
+use crate::{
+	api::{
+		ApiState,
+		account::device::{
+			DEVICE_CODE_LIFETIME, DEVICE_CODE_POLL_INTERVAL, generate_user_code,
+		},
+	},
+	secret,
+};
+
+#[derive(thiserror::Error, Debug, OperationIo)]
+pub enum DeviceAuthorizeError {
+	#[error("Unable to query database: {0}")]
+	Database(#[from] sea_orm::error::DbErr),
+}
+
+impl IntoResponse for DeviceAuthorizeError {
+	fn into_response(self) -> axum::response::Response {
+		(
+			match self {
+				Self::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+			},
+			self.to_string(),
+		)
+			.into_response()
+	}
+}
+
+fn endpoint_doc(op: TransformOperation) -> TransformOperation {
+	op.id("authorizeDevice")
+		.summary("Start a device code login")
+		.description(
+			"Starts a login for a client that can't authenticate with Mojang, like \
+			 the website. Show the returned `user_code` to the player, who confirms \
+			 it in game, and meanwhile poll `/account/device/token` with the \
+			 `device_code` every `interval` seconds.",
+		)
+		.tag("account")
+}
+
+#[derive(Serialize, JsonSchema)]
+struct DeviceAuthorizeResponse {
+	/// The secret code to poll for a session with. Never show it to anyone.
+	device_code: String,
+	/// The code for the player to confirm in game
+	user_code: String,
+	/// Seconds until both codes expire
+	expires_in: u64,
+	/// Seconds to wait between polls
+	interval: u64,
+}
+
+pub(super) fn router() -> ApiRouter<ApiState> {
+	ApiRouter::new().api_route("/device", post_with(self::endpoint, self::endpoint_doc))
+}
+
+#[tracing::instrument(level = "debug", skip(state))]
+async fn endpoint(
+	State(state): State<ApiState>,
+) -> Result<Json<DeviceAuthorizeResponse>, DeviceAuthorizeError> {
+	let now = Utc::now();
+	// Clear out codes nobody used, so their user codes can be handed out again
+	DeviceCode::delete_many()
+		.filter(device_code::Column::ExpiresAt.lt(now))
+		.exec(&state.database)
+		.await?;
+
+	let (device_code, hash) = secret::generate();
+	let code = device_code::ActiveModel {
+		secret_hash: Set(hash),
+		user_code: Set(generate_user_code()),
+		created_at: Set(now.fixed_offset()),
+		expires_at: Set((now + DEVICE_CODE_LIFETIME).fixed_offset()),
+		..Default::default()
+	}
+	.insert(&state.database)
+	.await?;
+
+	Ok(Json(DeviceAuthorizeResponse {
+		device_code,
+		user_code: code.user_code,
+		expires_in: DEVICE_CODE_LIFETIME.num_seconds().unsigned_abs(),
+		interval: DEVICE_CODE_POLL_INTERVAL,
+	}))
+}
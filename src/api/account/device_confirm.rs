@@ -0,0 +1,70 @@
+use aide::{
+	OperationIo,
+	axum::{ApiRouter, routing::post_with},
+	transform::TransformOperation,
+};
+use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+use crate::api::{
+	ApiState,
+	account::{
+		AuthenticatedPlayer,
+		device::{ConfirmDeviceError, confirm_device_code},
+	},
+};
+
+#[derive(thiserror::Error, Debug, OperationIo)]
+pub enum DeviceConfirmError {
+	#[error(transparent)]
+	Confirm(#[from] ConfirmDeviceError),
+}
+
+impl IntoResponse for DeviceConfirmError {
+	fn into_response(self) -> axum::response::Response {
+		(
+			match self {
+				Self::Confirm(ConfirmDeviceError::InvalidCode) => StatusCode::NOT_FOUND,
+				Self::Confirm(ConfirmDeviceError::Database(_)) => {
+					StatusCode::INTERNAL_SERVER_ERROR
+				}
+			},
+			self.to_string(),
+		)
+			.into_response()
+	}
+}
+
+fn endpoint_doc(op: TransformOperation) -> TransformOperation {
+	op.id("confirmDevice")
+		.summary("Confirm a device code login")
+		.description(
+			"Logs the device that was shown `user_code` in as the authenticated \
+			 player. The websocket `ConfirmDeviceCode` packet does the same.",
+		)
+		.tag("account")
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct DeviceConfirmRequest {
+	/// The code shown on the device, with or without the dash
+	user_code: String,
+}
+
+pub(super) fn router() -> ApiRouter<ApiState> {
+	ApiRouter::new().api_route(
+		"/device/confirm",
+		post_with(self::endpoint, self::endpoint_doc),
+	)
+}
+
+#[tracing::instrument(level = "debug", skip(state))]
+async fn endpoint(
+	State(state): State<ApiState>,
+	AuthenticatedPlayer(player): AuthenticatedPlayer,
+	Json(body): Json<DeviceConfirmRequest>,
+) -> Result<StatusCode, DeviceConfirmError> {
+	confirm_device_code(&state.database, &player, &body.user_code).await?;
+	Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,124 @@
+use aide::{
+	OperationIo,
+	axum::{ApiRouter, routing::post_with},
+	transform::TransformOperation,
+};
+use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
+use chrono::Utc;
+use entities::{device_code, prelude::*};
+use schemars::JsonSchema;
+use sea_orm::{
+	ColumnTrait, EntityTrait, ModelTrait, QueryFilter, QuerySelect, TransactionTrait,
+};
+use serde::Deserialize;
+
+use crate::{
+	api::{
+		ApiState,
+		account::{login::LoginResponse, session::create_session},
+	},
+	database::{is_banned, record_monthly_active_login},
+	secret,
+};
+
+#[derive(thiserror::Error, Debug, OperationIo)]
+pub enum DeviceTokenError {
+	#[error("The device code is invalid, expired or was already used")]
+	InvalidCode,
+	#[error("The player has not confirmed the code yet")]
+	Pending,
+	#[error("Player is banned")]
+	Banned,
+	#[error("Unable to construct authentication token: {0}")]
+	TokenCreation(#[from] pasetors::errors::Error),
+	#[error("Unable to query database: {0}")]
+	Database(#[from] sea_orm::error::DbErr),
+}
+
+impl IntoResponse for DeviceTokenError {
+	fn into_response(self) -> axum::response::Response {
+		(
+			match self {
+				Self::InvalidCode => StatusCode::BAD_REQUEST,
+				Self::Pending => StatusCode::PRECONDITION_REQUIRED,
+				Self::Banned => StatusCode::FORBIDDEN,
+				Self::TokenCreation(_) => StatusCode::INTERNAL_SERVER_ERROR,
+				Self::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+			},
+			self.to_string(),
+		)
+			.into_response()
+	}
+}
+
+fn endpoint_doc(op: TransformOperation) -> TransformOperation {
+	op.id("exchangeDeviceCode")
+		.summary("Finish a device code login")
+		.description(
+			"Exchanges a confirmed device code for a session, like a normal login. \
+			 Responds 428 while the player has not confirmed the code yet. Each \
+			 device code can only be exchanged once.",
+		)
+		.tag("account")
+		.response_with::<{ StatusCode::PRECONDITION_REQUIRED.as_u16() }, String, _>(
+			|res| res.description("The player has not confirmed the code yet"),
+		)
+}
+
+#[derive(Deserialize, JsonSchema)]
+struct DeviceTokenRequest {
+	/// The device code returned by `/account/device`
+	device_code: String,
+}
+
+// Device codes are secrets, so the request is never logged
+impl std::fmt::Debug for DeviceTokenRequest {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("DeviceTokenRequest").finish_non_exhaustive()
+	}
+}
+
+pub(super) fn router() -> ApiRouter<ApiState> {
+	ApiRouter::new().api_route(
+		"/device/token",
+		post_with(self::endpoint, self::endpoint_doc),
+	)
+}
+
+#[tracing::instrument(level = "debug", skip(state))]
+async fn endpoint(
+	State(state): State<ApiState>,
+	Json(body): Json<DeviceTokenRequest>,
+) -> Result<Json<LoginResponse>, DeviceTokenError> {
+	let txn = state.database.begin().await?;
+	// Lock the code so two concurrent polls can't both get a session
+	let Some(code) = DeviceCode::find()
+		.filter(device_code::Column::SecretHash.eq(secret::hash(&body.device_code)))
+		.lock_exclusive()
+		.one(&txn)
+		.await?
+	else {
+		return Err(DeviceTokenError::InvalidCode);
+	};
+	if code.expires_at < Utc::now() {
+		return Err(DeviceTokenError::InvalidCode);
+	}
+	let Some(player_id) = code.player_id else {
+		return Err(DeviceTokenError::Pending);
+	};
+	let Some(player) = User::find_by_id(player_id).one(&txn).await? else {
+		return Err(DeviceTokenError::InvalidCode);
+	};
+
+	code.delete(&txn).await?;
+	if is_banned(&txn, &player).await? {
+		txn.commit().await?;
+		return Err(DeviceTokenError::Banned);
+	}
+
+	let response = create_session::<DeviceTokenError>(&state, &txn, &player).await?;
+	record_monthly_active_login(&txn, player.id).await?;
+	txn.commit().await?;
+
+	Ok(Json(response))
+}
@@ -0,0 +1,468 @@
+use std::io::{BufWriter, Write};
+
+use aide::{
+	OperationIo, OperationOutput,
+	axum::{ApiRouter, routing::get_with},
+	openapi::{MediaType, Operation},
+	transform::TransformOperation,
+};
+use axum::{
+	body::{Body, Bytes},
+	extract::State,
+	http::{StatusCode, header},
+	response::{IntoResponse, Response},
+};
+use entities::{
+	account_deletion, announcement_delivery, audit_log, daily_playtime, device_code,
+	friend_request, friendship, monthly_active_login, outfit, player_ban,
+	player_equipped_cosmetic, player_owned_cosmetic, player_presence, prelude::*,
+	session, transaction, user, user_permission_override,
+};
+use futures::TryStreamExt as _;
+use sea_orm::{
+	ColumnTrait, Condition, DatabaseConnection, DbErr, EntityTrait, JsonValue,
+	QueryFilter, QuerySelect, SelectModel, Selector,
+};
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use crate::{
+	api::{
+		ApiState,
+		account::{AuthenticationExtractor, zip_stream::ZipStream},
+	},
+	database::DatabaseUserExt,
+};
+
+/// How many rows or archive chunks may wait to be written or sent
+const EXPORT_BUFFER: usize = 16;
+/// How much of the archive is sent to the player at once
+const CHUNK_SIZE: usize = 64 * 1024;
+
+#[derive(thiserror::Error, Debug, OperationIo)]
+pub enum ExportError {
+	#[error("The requested player does not exist")]
+	PlayerMissing,
+	#[error("Unable to write export archive: {0}")]
+	Archive(#[from] std::io::Error),
+	#[error("Unable to serialize exported data: {0}")]
+	Serialization(#[from] serde_json::Error),
+	#[error("Unable to query database: {0}")]
+	Database(#[from] sea_orm::error::DbErr),
+}
+
+impl IntoResponse for ExportError {
+	fn into_response(self) -> axum::response::Response {
+		(
+			match self {
+				Self::PlayerMissing => StatusCode::NOT_FOUND,
+				Self::Archive(_) => StatusCode::INTERNAL_SERVER_ERROR,
+				Self::Serialization(_) => StatusCode::INTERNAL_SERVER_ERROR,
+				Self::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+			},
+			self.to_string(),
+		)
+			.into_response()
+	}
+}
+
+fn endpoint_doc(op: TransformOperation) -> TransformOperation {
+	op.id("exportAccount")
+		.summary("Export account data")
+		.description(
+			"Downloads a ZIP archive of everything stored about the authenticated \
+			 player, with one JSON file per database table. Banned players may still \
+			 export their data.",
+		)
+		.tag("account")
+}
+
+/// A ZIP archive of a player's data, downloaded as an attachment
+pub(super) struct ExportArchive {
+	player: Uuid,
+	body: Body,
+}
+
+impl IntoResponse for ExportArchive {
+	fn into_response(self) -> Response {
+		(
+			[
+				(header::CONTENT_TYPE, "application/zip".to_string()),
+				(
+					header::CONTENT_DISPOSITION,
+					format!(
+						"attachment; filename=\"plus-export-{}.zip\"",
+						self.player.as_hyphenated()
+					),
+				),
+			],
+			self.body,
+		)
+			.into_response()
+	}
+}
+
+impl OperationOutput for ExportArchive {
+	type Inner = Self;
+
+	fn operation_response(
+		_ctx: &mut aide::generate::GenContext,
+		_operation: &mut Operation,
+	) -> Option<aide::openapi::Response> {
+		Some(aide::openapi::Response {
+			description: "A ZIP archive with one JSON file per database table".into(),
+			content: [("application/zip".into(), MediaType::default())]
+				.into_iter()
+				.collect(),
+			..Default::default()
+		})
+	}
+
+	fn inferred_responses(
+		ctx: &mut aide::generate::GenContext,
+		operation: &mut Operation,
+	) -> Vec<(Option<u16>, aide::openapi::Response)> {
+		Self::operation_response(ctx, operation)
+			.map(|res| Vec::from([(Some(200), res)]))
+			.unwrap_or_default()
+	}
+}
+
+/// The queries for every row tied to a player, named after the table they
+/// read. Secrets, like refresh token hashes, are left out.
+fn player_tables(
+	player: &user::Model,
+) -> Vec<(&'static str, Selector<SelectModel<JsonValue>>)> {
+	let player_id = player.id;
+	vec![
+		("user", User::find_by_id(player_id).into_json()),
+		(
+			"player_owned_cosmetic",
+			PlayerOwnedCosmetic::find()
+				.filter(player_owned_cosmetic::Column::PlayerId.eq(player_id))
+				.into_json(),
+		),
+		(
+			"player_equipped_cosmetic",
+			PlayerEquippedCosmetic::find()
+				.filter(player_equipped_cosmetic::Column::PlayerId.eq(player_id))
+				.into_json(),
+		),
+		(
+			"transaction",
+			// Gifts the player bought for others are theirs too
+			Transaction::find()
+				.filter(
+					Condition::any()
+						.add(transaction::Column::PlayerId.eq(player_id))
+						.add(transaction::Column::Buyer.eq(player_id)),
+				)
+				.into_json(),
+		),
+		(
+			"daily_playtime",
+			DailyPlaytime::find()
+				.filter(daily_playtime::Column::PlayerId.eq(player_id))
+				.into_json(),
+		),
+		(
+			"monthly_active_login",
+			MonthlyActiveLogin::find()
+				.filter(monthly_active_login::Column::PlayerId.eq(player_id))
+				.into_json(),
+		),
+		(
+			"session",
+			Session::find()
+				.select_only()
+				.columns([
+					session::Column::Id,
+					session::Column::PlayerId,
+					session::Column::CreatedAt,
+					session::Column::LastRefreshedAt,
+					session::Column::ExpiresAt,
+					session::Column::RevokedAt,
+				])
+				.filter(session::Column::PlayerId.eq(player_id))
+				.into_json(),
+		),
+		(
+			"player_ban",
+			PlayerBan::find()
+				.filter(player_ban::Column::PlayerId.eq(player_id))
+				.into_json(),
+		),
+		(
+			"user_permission_override",
+			UserPermissionOverride::find()
+				.filter(user_permission_override::Column::PlayerId.eq(player_id))
+				.into_json(),
+		),
+		(
+			"friendship",
+			Friendship::find()
+				.filter(friendship::Column::PlayerId.eq(player_id))
+				.into_json(),
+		),
+		(
+			"friend_request",
+			FriendRequest::find()
+				.filter(
+					Condition::any()
+						.add(friend_request::Column::SenderId.eq(player_id))
+						.add(friend_request::Column::RecipientId.eq(player_id)),
+				)
+				.into_json(),
+		),
+		(
+			"account_deletion",
+			AccountDeletion::find()
+				.filter(account_deletion::Column::PlayerId.eq(player_id))
+				.into_json(),
+		),
+		(
+			"device_code",
+			DeviceCode::find()
+				.select_only()
+				.columns([
+					device_code::Column::Id,
+					device_code::Column::UserCode,
+					device_code::Column::PlayerId,
+					device_code::Column::CreatedAt,
+					device_code::Column::ExpiresAt,
+					device_code::Column::ConfirmedAt,
+				])
+				.filter(device_code::Column::PlayerId.eq(player_id))
+				.into_json(),
+		),
+		(
+			"audit_log",
+			// Changes the player made, and changes made to them. The IPs and API
+			// keys of whoever made the changes are left out.
+			AuditLog::find()
+				.select_only()
+				.columns([
+					audit_log::Column::Id,
+					audit_log::Column::ActorPlayerId,
+					audit_log::Column::Action,
+					audit_log::Column::TargetType,
+					audit_log::Column::TargetId,
+					audit_log::Column::Before,
+					audit_log::Column::After,
+					audit_log::Column::CreatedAt,
+				])
+				.filter(
+					Condition::any()
+						.add(audit_log::Column::ActorPlayerId.eq(player_id))
+						.add(
+							Condition::all()
+								.add(audit_log::Column::TargetType.eq("player"))
+								.add(
+									audit_log::Column::TargetId
+										.eq(player.minecraft_uuid.to_string()),
+								),
+						),
+				)
+				.into_json(),
+		),
+		(
+			"outfit",
+			Outfit::find()
+				.filter(outfit::Column::PlayerId.eq(player_id))
+				.into_json(),
+		),
+		(
+			"announcement_delivery",
+			AnnouncementDelivery::find()
+				.filter(announcement_delivery::Column::PlayerId.eq(player_id))
+				.into_json(),
+		),
+		(
+			"player_presence",
+			PlayerPresence::find()
+				.filter(player_presence::Column::Player.eq(player.minecraft_uuid))
+				.into_json(),
+		),
+	]
+}
+
+/// What the database side of an export sends to the archive writer
+#[derive(Debug)]
+enum ExportItem {
+	/// Rows that follow are from this table
+	Table(&'static str),
+	Row(JsonValue),
+}
+
+/// Streams each table's rows to the archive writer, one table at a time
+async fn query_tables(
+	db: DatabaseConnection,
+	tables: Vec<(&'static str, Selector<SelectModel<JsonValue>>)>,
+	items: mpsc::Sender<Result<ExportItem, DbErr>>,
+) {
+	for (table, query) in tables {
+		if items.send(Ok(ExportItem::Table(table))).await.is_err() {
+			// The download was cancelled
+			return;
+		}
+
+		let result = async {
+			let mut rows = query.stream(&db).await?;
+			while let Some(row) = rows.try_next().await? {
+				if items.send(Ok(ExportItem::Row(row))).await.is_err() {
+					break;
+				}
+			}
+			Ok(())
+		}
+		.await;
+		if let Err(error) = result {
+			let _ = items.send(Err(error)).await;
+			return;
+		}
+	}
+}
+
+/// Writes each table to `<table>.json` in a ZIP archive, as its rows come in
+fn write_archive(
+	mut items: mpsc::Receiver<Result<ExportItem, DbErr>>,
+	out: impl Write,
+) -> Result<(), ExportError> {
+	let mut archive = ZipStream::new(out);
+	// Whether a table is open, and whether it has rows
+	let mut open_table = None;
+	while let Some(item) = items.blocking_recv() {
+		match item? {
+			ExportItem::Table(table) => {
+				if let Some(has_rows) = open_table {
+					close_table(&mut archive, has_rows)?;
+				}
+				archive.start_file(&format!("{table}.json"))?;
+				archive.write_all(b"[")?;
+				open_table = Some(false);
+			}
+			ExportItem::Row(row) => {
+				let separator = match open_table {
+					Some(true) => &b",\n"[..],
+					_ => b"\n",
+				};
+				archive.write_all(separator)?;
+				serde_json::to_writer_pretty(&mut archive, &row)?;
+				open_table = Some(true);
+			}
+		}
+	}
+	if let Some(has_rows) = open_table {
+		close_table(&mut archive, has_rows)?;
+	}
+
+	archive.finish()?;
+	Ok(())
+}
+
+fn close_table(archive: &mut impl Write, has_rows: bool) -> std::io::Result<()> {
+	archive.write_all(if has_rows { b"\n]" } else { b"]" })
+}
+
+/// Sends what it is given to a response body, in the chunks it is given
+struct BodyWriter(mpsc::Sender<Result<Bytes, ExportError>>);
+
+impl Write for BodyWriter {
+	fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+		self.0
+			.blocking_send(Ok(Bytes::copy_from_slice(buf)))
+			.map_err(|_| std::io::Error::from(std::io::ErrorKind::BrokenPipe))?;
+		Ok(buf.len())
+	}
+
+	fn flush(&mut self) -> std::io::Result<()> {
+		Ok(())
+	}
+}
+
+/// Exports all data of a player as a ZIP archive. The tables are queried as
+/// the archive is downloaded, so that it is never held in memory as a whole.
+pub(super) fn export_player(
+	db: &DatabaseConnection,
+	player: &user::Model,
+) -> ExportArchive {
+	let (items_tx, items_rx) = mpsc::channel(EXPORT_BUFFER);
+	let (chunks_tx, mut chunks_rx) = mpsc::channel(EXPORT_BUFFER);
+	tokio::spawn(query_tables(db.clone(), player_tables(player), items_tx));
+	tokio::task::spawn_blocking(move || {
+		let out = BufWriter::with_capacity(CHUNK_SIZE, BodyWriter(chunks_tx.clone()));
+		if let Err(error) = write_archive(items_rx, out) {
+			// Cuts the download short, so that it isn't mistaken for a whole
+			// archive
+			let _ = chunks_tx.blocking_send(Err(error));
+		}
+	});
+
+	ExportArchive {
+		player: player.minecraft_uuid,
+		body: Body::from_stream(futures::stream::poll_fn(move |cx| {
+			chunks_rx.poll_recv(cx)
+		})),
+	}
+}
+
+pub(super) fn router() -> ApiRouter<ApiState> {
+	ApiRouter::new().api_route("/export", get_with(self::endpoint, self::endpoint_doc))
+}
+
+#[tracing::instrument(level = "debug", skip(state))]
+async fn endpoint(
+	State(state): State<ApiState>,
+	AuthenticationExtractor(uuid): AuthenticationExtractor,
+) -> Result<ExportArchive, ExportError> {
+	let player = User::get_or_create(&state.database, uuid).await?;
+	Ok(export_player(&state.database, &player))
+}
+
+#[cfg(test)]
+mod tests {
+	use std::io::{Cursor, Read};
+
+	use serde_json::json;
+	use tokio::sync::mpsc;
+
+	use super::{ExportItem, write_archive};
+
+	#[test]
+	fn archive_has_one_json_file_per_table() {
+		let (items_tx, items_rx) = mpsc::channel(8);
+		for item in [
+			ExportItem::Table("user"),
+			ExportItem::Row(json!({ "id": 1 })),
+			ExportItem::Row(json!({ "id": 2 })),
+			ExportItem::Table("session"),
+		] {
+			items_tx.try_send(Ok(item)).expect("item should be queued");
+		}
+		drop(items_tx);
+
+		let mut data = Vec::new();
+		write_archive(items_rx, &mut data).expect("archive should build");
+
+		let mut archive =
+			zip::ZipArchive::new(Cursor::new(data)).expect("archive should be valid");
+		assert_eq!(archive.len(), 2);
+
+		for (table, rows) in [
+			("user.json", json!([{ "id": 1 }, { "id": 2 }])),
+			("session.json", json!([])),
+		] {
+			let mut contents = String::new();
+			archive
+				.by_name(table)
+				.expect("table should be exported")
+				.read_to_string(&mut contents)
+				.expect("table should be readable");
+			assert_eq!(
+				serde_json::from_str::<serde_json::Value>(&contents)
+					.expect("table should be JSON"),
+				rows
+			);
+		}
+	}
+}
@@ -0,0 +1,51 @@
+use aide::{
+	axum::{ApiRouter, routing::get_with},
+	transform::TransformOperation,
+};
+use axum::extract::{Path, State};
+use entities::{prelude::*, user};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+use uuid::Uuid;
+
+use crate::api::{
+	ApiState,
+	account::{
+		export::{ExportArchive, ExportError, export_player},
+		permissions::{ExportPlayerData, RequirePermission},
+	},
+};
+
+fn endpoint_doc(op: TransformOperation) -> TransformOperation {
+	op.id("exportPlayerAccount")
+		.summary("Export a player's account data")
+		.description(
+			"Downloads the same archive as `/account/export` for any player, to \
+			 answer data access requests. Requires the `export_player_data` \
+			 permission.",
+		)
+		.tag("account")
+}
+
+pub(super) fn router() -> ApiRouter<ApiState> {
+	ApiRouter::new().api_route(
+		"/export/{player}",
+		get_with(self::endpoint, self::endpoint_doc),
+	)
+}
+
+#[tracing::instrument(level = "debug", skip(state))]
+async fn endpoint(
+	State(state): State<ApiState>,
+	RequirePermission(_admin, ..): RequirePermission<ExportPlayerData>,
+	Path(player): Path<Uuid>,
+) -> Result<ExportArchive, ExportError> {
+	let Some(player) = User::find()
+		.filter(user::Column::MinecraftUuid.eq(player))
+		.one(&state.database)
+		.await?
+	else {
+		return Err(ExportError::PlayerMissing);
+	};
+
+	Ok(export_player(&state.database, &player))
+}
@@ -1,5 +1,3 @@
-use std::time::Duration;
-
 use aide::{
 	OperationIo,
 	axum::{ApiRouter, routing::post_with},
@@ -11,21 +9,20 @@ use axum::{
 	http::StatusCode,
 	response::IntoResponse,
 };
-use pasetors::{claims::Claims, local};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use tracing::debug;
 use uuid::Uuid;
 
 use crate::{
-	api::{ApiState, account::PASETO_IMPLICIT_ASSERT},
+	api::{
+		ApiState,
+		account::{session::create_session, verifier::VerifyError},
+		players::profile::Profile,
+	},
 	database::{DatabaseUserExt, record_monthly_active_login},
 };
 
-#[derive(Deserialize)]
-pub struct SessionserverLoginSuccess {
-	id: Uuid,
-}
-
 #[derive(thiserror::Error, Debug, OperationIo)]
 pub enum LoginError {
 	#[error("The passed player UUID was not a valid UUID: {0}")]
@@ -43,7 +40,10 @@ pub enum LoginError {
 fn endpoint_doc(op: TransformOperation) -> TransformOperation {
 	op.id("login")
 		.summary("Log in as a minecraft player")
-		.description("Logs in using mojang sessionserver authentication")
+		.description(
+			"Logs in using mojang sessionserver authentication, or any claimed player \
+			 when the backend runs with `--dev-auth`",
+		)
 		.tag("account")
 		.response_with::<{ StatusCode::INTERNAL_SERVER_ERROR.as_u16() }, String, _>(
 			|res| {
@@ -80,14 +80,22 @@ struct LoginQuery {
 	/// This should ideally be randomly generated on the client
 	#[schemars(example = &"FnuhJQCStLeUOIwnrHgBjiTolqWRBBSe")]
 	server_id: String,
+	/// The UUID of the player to login as. This is only used, and required,
+	/// when the backend runs with `--dev-auth`
+	uuid: Option<Uuid>,
 }
 
-/// A response given on successful a payment restore
+/// A response given on a successful login or session refresh
 #[derive(Debug, Default, Serialize, JsonSchema)]
 pub struct LoginResponse {
 	/// The authentication token to use for requests to the plus backend.
 	/// These tokens are valid for 2 hours after their issue date.
-	token: String,
+	pub(super) token: String,
+	/// A single-use token that can be exchanged at `/account/refresh` for a
+	/// new token pair. Refresh tokens expire after 30 days without use.
+	pub(super) refresh_token: String,
+	/// The number of seconds until `token` expires
+	pub(super) expires_in: u64,
 }
 
 pub(super) fn router() -> ApiRouter<ApiState> {
@@ -99,43 +107,32 @@ async fn endpoint(
 	State(state): State<ApiState>,
 	Query(query): Query<LoginQuery>,
 ) -> Result<Json<LoginResponse>, LoginError> {
-	let response = state
-		.client
-		.get("https://sessionserver.mojang.com/session/minecraft/hasJoined")
-		.query(&[("username", query.username), ("serverId", query.server_id)])
-		.send()
+	let verified = state
+		.session_verifier
+		.verify(&query.username, &query.server_id, query.uuid)
 		.await
-		.map_err(LoginError::SessionserverAuthentication)?
-		.error_for_status()
-		.map_err(LoginError::SessionserverAuthentication)?;
+		.map_err(|e| match e {
+			VerifyError::Request(e) => LoginError::SessionserverAuthentication(e),
+			VerifyError::Rejected => LoginError::Unauthorized,
+		})?;
+	debug!(player = %verified.id, name = %verified.name, "Verified login");
 
-	// 200 w/ JSON data is returned on success
-	if response.status() != StatusCode::OK {
-		return Err(LoginError::Unauthorized);
-	}
-	let body = response
-		.text()
-		.await
-		.map_err(LoginError::SessionserverAuthentication)?;
-	let parsed: SessionserverLoginSuccess =
-		serde_json::from_str(&body).map_err(|_| LoginError::Unauthorized)?;
 	let player =
-		entities::prelude::User::get_or_create(&state.database, parsed.id).await?;
+		entities::prelude::User::get_or_create(&state.database, verified.id).await?;
 	record_monthly_active_login(&state.database, player.id).await?;
+	// The session server tells us the player's current name for free
+	if player.last_known_name.as_deref() != Some(verified.name.as_str()) {
+		state
+			.profiles
+			.update(
+				&state.database,
+				Profile {
+					id: verified.id,
+					name: verified.name,
+				},
+			)
+			.await?;
+	}
 
-	let token = local::encrypt(
-		&state.paseto_key,
-		&{
-			let mut claims = Claims::new()?;
-
-			claims.set_expires_in(&Duration::from_secs(60 * 60 * 2 /* 2h */))?;
-			claims.subject(&parsed.id.as_hyphenated().to_string())?;
-
-			claims
-		},
-		None,
-		PASETO_IMPLICIT_ASSERT,
-	)?;
-
-	Ok(Json(LoginResponse { token }))
+	Ok(Json(create_session::<LoginError>(&state, &state.database, &player).await?))
 }
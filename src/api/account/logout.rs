@@ -0,0 +1,61 @@
+use aide::{
+	OperationIo,
+	axum::{ApiRouter, routing::post_with},
+	transform::TransformOperation,
+};
+use axum::{extract::State, http::StatusCode, response::IntoResponse};
+use chrono::Utc;
+use entities::{prelude::*, session};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, sea_query::Expr};
+
+use crate::api::{ApiState, account::AuthenticatedSession};
+
+#[derive(thiserror::Error, Debug, OperationIo)]
+pub enum LogoutError {
+	#[error("Unable to query database: {0}")]
+	Database(#[from] sea_orm::error::DbErr),
+}
+
+impl IntoResponse for LogoutError {
+	fn into_response(self) -> axum::response::Response {
+		(
+			match self {
+				Self::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+			},
+			self.to_string(),
+		)
+			.into_response()
+	}
+}
+
+fn endpoint_doc(op: TransformOperation) -> TransformOperation {
+	op.id("logout")
+		.summary("Log out")
+		.description(
+			"Revokes the current session. Its access and refresh tokens stop working \
+			 immediately.",
+		)
+		.tag("account")
+}
+
+pub(super) fn router() -> ApiRouter<ApiState> {
+	ApiRouter::new().api_route("/logout", post_with(self::endpoint, self::endpoint_doc))
+}
+
+#[tracing::instrument(level = "debug", skip(state))]
+async fn endpoint(
+	State(state): State<ApiState>,
+	session: AuthenticatedSession,
+) -> Result<StatusCode, LogoutError> {
+	Session::update_many()
+		.col_expr(
+			session::Column::RevokedAt,
+			Expr::value(Utc::now().fixed_offset()),
+		)
+		.filter(session::Column::Id.eq(session.session_id))
+		.filter(session::Column::RevokedAt.is_null())
+		.exec(&state.database)
+		.await?;
+
+	Ok(StatusCode::NO_CONTENT)
+}
@@ -1,4 +1,21 @@
+mod cancel_deletion;
+pub(super) mod deletion;
+mod deletion_status;
+pub(super) mod device;
+mod device_authorize;
+mod device_confirm;
+mod device_token;
+mod export;
+mod export_player;
 mod login;
+mod logout;
+pub(super) mod permissions;
+mod refresh;
+mod request_deletion;
+mod revoke;
+mod session;
+pub(super) mod verifier;
+mod zip_stream;
 
 use aide::{OperationInput, axum::ApiRouter, openapi::SecurityRequirement};
 use axum::{
@@ -11,26 +28,33 @@ use pasetors::{
 	Local, claims::ClaimsValidationRules, local, token::UntrustedToken, version4::V4,
 };
 use reqwest::header::AUTHORIZATION;
+use sea_orm::EntityTrait;
 use uuid::Uuid;
 
-use crate::{api::ApiState, database::DatabaseUserExt};
-use entities::{
-	prelude::*, sea_orm_active_enums, sea_orm_active_enums::PlayerRole, user,
+use crate::{
+	api::ApiState,
+	database::{DatabaseUserExt, is_banned},
 };
+use entities::{prelude::*, user};
 
 pub const OPENAPI_SECURITY_NAME: &str = "Bearer Token";
 pub const PASETO_IMPLICIT_ASSERT: Option<&[u8]> = Some(b"plus-backend");
 
+/// An authenticated player along with the session their access token was
+/// issued for
+#[derive(Debug)]
+pub struct AuthenticatedSession {
+	pub player: Uuid,
+	pub session_id: Uuid,
+}
 #[derive(Debug)]
 pub struct AuthenticationExtractor(pub Uuid);
 #[derive(Debug)]
 pub struct OptionalAuthenticationExtractor(pub Option<Uuid>);
 #[derive(Debug)]
 pub struct AuthenticatedPlayer(pub user::Model);
-#[derive(Debug)]
-pub struct AdminPlayer(pub user::Model);
 
-impl OperationInput for AuthenticationExtractor {
+impl OperationInput for AuthenticatedSession {
 	fn operation_input(
 		_ctx: &mut aide::generate::GenContext,
 		operation: &mut aide::openapi::Operation,
@@ -42,6 +66,15 @@ impl OperationInput for AuthenticationExtractor {
 	}
 }
 
+impl OperationInput for AuthenticationExtractor {
+	fn operation_input(
+		ctx: &mut aide::generate::GenContext,
+		operation: &mut aide::openapi::Operation,
+	) {
+		AuthenticatedSession::operation_input(ctx, operation);
+	}
+}
+
 impl OperationInput for OptionalAuthenticationExtractor {
 	fn operation_input(
 		_ctx: &mut aide::generate::GenContext,
@@ -63,25 +96,95 @@ impl OperationInput for AuthenticatedPlayer {
 	}
 }
 
-impl OperationInput for AdminPlayer {
-	fn operation_input(
-		ctx: &mut aide::generate::GenContext,
-		operation: &mut aide::openapi::Operation,
-	) {
-		AuthenticationExtractor::operation_input(ctx, operation);
-	}
-}
-
 const MISSING_AUTHORIZATION_ERR: (StatusCode, &str) =
 	(StatusCode::UNAUTHORIZED, "Authorization header was missing");
 const INVALID_AUTHORIZATION_ERR: (StatusCode, &str) =
 	(StatusCode::UNAUTHORIZED, "Authorization header was invalid");
+const REVOKED_SESSION_ERR: (StatusCode, &str) =
+	(StatusCode::UNAUTHORIZED, "Session has been revoked");
 const BLACKLISTED_ERR: (StatusCode, &str) =
-	(StatusCode::FORBIDDEN, "Authenticated player is blacklisted");
-const INSUFFICIENT_ROLE_ERR: (StatusCode, &str) = (
-	StatusCode::FORBIDDEN,
-	"Authenticated player does not have permission",
-);
+	(StatusCode::FORBIDDEN, "Authenticated player is banned");
+
+/// Decrypts the access token in the `Authorization` header, returning the
+/// player and session it was issued for. Revocation is not checked here.
+pub(super) fn decrypt_access_token(
+	parts: &Parts,
+	state: &ApiState,
+) -> Result<(Uuid, Uuid), (StatusCode, &'static str)> {
+	let header = parts
+		.headers
+		.get(AUTHORIZATION)
+		.ok_or(MISSING_AUTHORIZATION_ERR)?
+		.to_str()
+		.map_err(|_| INVALID_AUTHORIZATION_ERR)?;
+
+	let Some(token) = header.strip_prefix("Bearer ") else {
+		return Err(INVALID_AUTHORIZATION_ERR);
+	};
+
+	let token = UntrustedToken::<Local, V4>::try_from(token)
+		.map_err(|_| INVALID_AUTHORIZATION_ERR)?;
+	// The footer names the key the token was encrypted with. It is only
+	// trusted to pick a key, and is authenticated as part of decryption.
+	let key = state
+		.paseto_keys
+		.verifying_key(token.untrusted_footer())
+		.ok_or(INVALID_AUTHORIZATION_ERR)?;
+	let token = local::decrypt(
+		key,
+		&token,
+		&ClaimsValidationRules::new(),
+		None,
+		PASETO_IMPLICIT_ASSERT,
+	)
+	.map_err(|_| INVALID_AUTHORIZATION_ERR)?;
+	let claims = token.payload_claims().ok_or(MISSING_AUTHORIZATION_ERR)?;
+
+	let sub = claims
+		.get_claim("sub")
+		.ok_or(MISSING_AUTHORIZATION_ERR)?
+		.as_str()
+		.ok_or(MISSING_AUTHORIZATION_ERR)?;
+
+	let player = Uuid::parse_str(sub).map_err(|_| MISSING_AUTHORIZATION_ERR)?;
+	let session_id = claims
+		.get_claim(session::SESSION_CLAIM)
+		.and_then(|sid| sid.as_str())
+		.and_then(|sid| Uuid::parse_str(sid).ok())
+		.ok_or(INVALID_AUTHORIZATION_ERR)?;
+
+	Ok((player, session_id))
+}
+
+impl FromRequestParts<ApiState> for AuthenticatedSession {
+	type Rejection = Response;
+
+	async fn from_request_parts(
+		parts: &mut Parts,
+		state: &ApiState,
+	) -> Result<Self, Self::Rejection> {
+		let (player, session_id) =
+			decrypt_access_token(parts, state).map_err(IntoResponse::into_response)?;
+
+		// Access tokens stop working as soon as their session is revoked,
+		// rather than when they expire
+		let session = Session::find_by_id(session_id)
+			.one(&state.database)
+			.await
+			.map_err(|e| {
+				(
+					StatusCode::INTERNAL_SERVER_ERROR,
+					format!("Unable to load session: {e}"),
+				)
+					.into_response()
+			})?;
+		if session.is_none_or(|session| session.revoked_at.is_some()) {
+			return Err(REVOKED_SESSION_ERR.into_response());
+		}
+
+		Ok(Self { player, session_id })
+	}
+}
 
 impl FromRequestParts<ApiState> for AuthenticationExtractor {
 	type Rejection = Response;
@@ -90,40 +193,9 @@ impl FromRequestParts<ApiState> for AuthenticationExtractor {
 		parts: &mut Parts,
 		state: &ApiState,
 	) -> Result<Self, Self::Rejection> {
-		let header = parts
-			.headers
-			.get(AUTHORIZATION)
-			.ok_or(MISSING_AUTHORIZATION_ERR)
-			.map_err(IntoResponse::into_response)?
-			.to_str()
-			.map_err(|_| INVALID_AUTHORIZATION_ERR.into_response())?;
-
-		let Some(token) = header.strip_prefix("Bearer ") else {
-			return Err(INVALID_AUTHORIZATION_ERR.into_response());
-		};
-
-		let token = local::decrypt(
-			&state.paseto_key,
-			&UntrustedToken::<Local, V4>::try_from(token)
-				.map_err(|_| INVALID_AUTHORIZATION_ERR.into_response())?,
-			&ClaimsValidationRules::new(),
-			None,
-			PASETO_IMPLICIT_ASSERT,
-		)
-		.map_err(|_| INVALID_AUTHORIZATION_ERR.into_response())?;
-		let claims = token
-			.payload_claims()
-			.ok_or(MISSING_AUTHORIZATION_ERR.into_response())?;
-
-		let sub = claims
-			.get_claim("sub")
-			.ok_or(MISSING_AUTHORIZATION_ERR.into_response())?
-			.as_str()
-			.ok_or(MISSING_AUTHORIZATION_ERR.into_response())?;
-
-		Uuid::parse_str(sub)
-			.map(Self)
-			.map_err(|_| MISSING_AUTHORIZATION_ERR.into_response())
+		AuthenticatedSession::from_request_parts(parts, state)
+			.await
+			.map(|session| Self(session.player))
 	}
 }
 
@@ -166,7 +238,14 @@ impl FromRequestParts<ApiState> for AuthenticatedPlayer {
 					.into_response()
 			})?;
 
-		if player.blacklisted {
+		let banned = is_banned(&state.database, &player).await.map_err(|e| {
+			(
+				StatusCode::INTERNAL_SERVER_ERROR,
+				format!("Unable to load authenticated player: {e}"),
+			)
+				.into_response()
+		})?;
+		if banned {
 			return Err(BLACKLISTED_ERR.into_response());
 		}
 
@@ -174,54 +253,18 @@ impl FromRequestParts<ApiState> for AuthenticatedPlayer {
 	}
 }
 
-impl FromRequestParts<ApiState> for AdminPlayer {
-	type Rejection = Response;
-
-	async fn from_request_parts(
-		parts: &mut Parts,
-		state: &ApiState,
-	) -> Result<Self, Self::Rejection> {
-		let player = AuthenticatedPlayer::from_request_parts(parts, state)
-			.await?
-			.0;
-
-		if !role_at_least(&player.role, &PlayerRole::Admin) {
-			return Err(INSUFFICIENT_ROLE_ERR.into_response());
-		}
-
-		Ok(Self(player))
-	}
-}
-
-pub(super) fn role_at_least(
-	actual: &sea_orm_active_enums::PlayerRole,
-	required: &sea_orm_active_enums::PlayerRole,
-) -> bool {
-	role_rank(actual) >= role_rank(required)
-}
-
-fn role_rank(role: &sea_orm_active_enums::PlayerRole) -> u8 {
-	match role {
-		sea_orm_active_enums::PlayerRole::Player => 0,
-		sea_orm_active_enums::PlayerRole::Moderator => 1,
-		sea_orm_active_enums::PlayerRole::Admin => 2,
-	}
-}
-
-#[cfg(test)]
-mod tests {
-	use entities::sea_orm_active_enums::PlayerRole;
-
-	use super::role_at_least;
-
-	#[test]
-	fn role_order_allows_elevated_access() {
-		assert!(role_at_least(&PlayerRole::Admin, &PlayerRole::Moderator));
-		assert!(role_at_least(&PlayerRole::Moderator, &PlayerRole::Player));
-		assert!(!role_at_least(&PlayerRole::Player, &PlayerRole::Moderator));
-	}
-}
-
 pub(super) async fn setup_router() -> ApiRouter<ApiState> {
-	ApiRouter::new().merge(login::router())
+	ApiRouter::new()
+		.merge(login::router())
+		.merge(refresh::router())
+		.merge(logout::router())
+		.merge(revoke::router())
+		.merge(export::router())
+		.merge(export_player::router())
+		.merge(request_deletion::router())
+		.merge(deletion_status::router())
+		.merge(cancel_deletion::router())
+		.merge(device_authorize::router())
+		.merge(device_confirm::router())
+		.merge(device_token::router())
 }
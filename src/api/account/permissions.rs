@@ -0,0 +1,226 @@
+use std::{collections::HashSet, marker::PhantomData};
+
+use aide::{OperationInput, openapi::SecurityRequirement};
+use axum::{
+	extract::FromRequestParts,
+	http::{StatusCode, request::Parts},
+	response::{IntoResponse, Response},
+};
+use entities::{
+	prelude::*, role_permission, sea_orm_active_enums::Permission, user,
+	user_permission_override,
+};
+use sea_orm::{ColumnTrait, ConnectionTrait, DbErr, EntityTrait, Iterable, QueryFilter};
+
+use crate::api::{
+	ApiState,
+	account::{AuthenticatedPlayer, OPENAPI_SECURITY_NAME},
+	audit::AuditActor,
+};
+
+/// A permission a [`RequirePermission`] extractor requires of the player.
+pub trait RequiredPermission {
+	const PERMISSION: Permission;
+}
+
+macro_rules! required_permissions {
+	($($name:ident),* $(,)?) => {
+		$(
+			#[derive(Debug)]
+			pub struct $name;
+
+			impl RequiredPermission for $name {
+				const PERMISSION: Permission = Permission::$name;
+			}
+		)*
+	};
+}
+
+required_permissions! {
+	BanPlayer,
+	ExportPlayerData,
+	ManageApiKeys,
+	ManageRoles,
+	ManageSessions,
+}
+
+/// An authenticated player holding the permission `P`, either through their
+/// role or an override. Also carries who made the request, for the audit log.
+#[derive(Debug)]
+pub struct RequirePermission<P: RequiredPermission>(
+	pub user::Model,
+	pub AuditActor,
+	pub PhantomData<P>,
+);
+
+/// The name a permission is serialized with, e.g. `ban_player`
+pub(crate) fn permission_name(permission: Permission) -> String {
+	serde_json::to_value(permission)
+		.ok()
+		.and_then(|value| value.as_str().map(str::to_owned))
+		.expect("permissions serialize as strings")
+}
+
+impl<P: RequiredPermission> OperationInput for RequirePermission<P> {
+	fn operation_input(
+		_ctx: &mut aide::generate::GenContext,
+		operation: &mut aide::openapi::Operation,
+	) {
+		operation.security.push(SecurityRequirement::from([(
+			OPENAPI_SECURITY_NAME.to_string(),
+			vec![permission_name(P::PERMISSION)],
+		)]));
+	}
+}
+
+const MISSING_PERMISSION_ERR: (StatusCode, &str) = (
+	StatusCode::FORBIDDEN,
+	"Authenticated player does not have permission",
+);
+
+/// Authenticates the player making a request, and checks they hold
+/// `permission`.
+pub(crate) async fn authenticate_with_permission(
+	parts: &mut Parts,
+	state: &ApiState,
+	permission: Permission,
+) -> Result<user::Model, Response> {
+	let player = AuthenticatedPlayer::from_request_parts(parts, state)
+		.await?
+		.0;
+
+	let allowed = has_permission(&state.database, &player, permission)
+		.await
+		.map_err(|e| {
+			(
+				StatusCode::INTERNAL_SERVER_ERROR,
+				format!("Unable to load permissions: {e}"),
+			)
+				.into_response()
+		})?;
+	if !allowed {
+		return Err(MISSING_PERMISSION_ERR.into_response());
+	}
+
+	Ok(player)
+}
+
+impl<P: RequiredPermission> FromRequestParts<ApiState> for RequirePermission<P> {
+	type Rejection = Response;
+
+	async fn from_request_parts(
+		parts: &mut Parts,
+		state: &ApiState,
+	) -> Result<Self, Self::Rejection> {
+		let player = authenticate_with_permission(parts, state, P::PERMISSION).await?;
+		let actor = AuditActor::player(parts, state, &player).await;
+		Ok(Self(player, actor, PhantomData))
+	}
+}
+
+/// Whether a player holds a permission. An override for the player takes
+/// precedence over the mappings of their role.
+pub(crate) async fn has_permission(
+	db: &impl ConnectionTrait,
+	player: &user::Model,
+	permission: Permission,
+) -> Result<bool, DbErr> {
+	if let Some(permission_override) =
+		UserPermissionOverride::find_by_id((player.id, permission))
+			.one(db)
+			.await?
+	{
+		return Ok(permission_override.granted);
+	}
+
+	Ok(
+		RolePermission::find_by_id((player.role.clone(), permission))
+			.one(db)
+			.await?
+			.is_some(),
+	)
+}
+
+/// Every permission a player holds, through their role or overrides
+pub(crate) async fn player_permissions(
+	db: &impl ConnectionTrait,
+	player: &user::Model,
+) -> Result<Vec<Permission>, DbErr> {
+	let role_permissions = RolePermission::find()
+		.filter(role_permission::Column::Role.eq(player.role.clone()))
+		.all(db)
+		.await?
+		.into_iter()
+		.map(|mapping| mapping.permission);
+	let overrides = UserPermissionOverride::find()
+		.filter(user_permission_override::Column::PlayerId.eq(player.id))
+		.all(db)
+		.await?
+		.into_iter()
+		.map(|o| (o.permission, o.granted));
+
+	Ok(effective_permissions(role_permissions, overrides))
+}
+
+/// Applies per-player overrides on top of the permissions of a role, returning
+/// the result in a stable order.
+fn effective_permissions(
+	role_permissions: impl IntoIterator<Item = Permission>,
+	overrides: impl IntoIterator<Item = (Permission, bool)>,
+) -> Vec<Permission> {
+	let mut permissions = role_permissions.into_iter().collect::<HashSet<_>>();
+	for (permission, granted) in overrides {
+		if granted {
+			permissions.insert(permission);
+		} else {
+			permissions.remove(&permission);
+		}
+	}
+
+	Permission::iter()
+		.filter(|permission| permissions.contains(permission))
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use entities::sea_orm_active_enums::Permission;
+
+	use super::{effective_permissions, permission_name};
+
+	#[test]
+	fn overrides_grant_and_revoke_role_permissions() {
+		assert_eq!(
+			effective_permissions(
+				[Permission::BanPlayer, Permission::ViewTransactions],
+				[
+					(Permission::ViewTransactions, false),
+					(Permission::GrantCosmetic, true)
+				],
+			),
+			vec![Permission::BanPlayer, Permission::GrantCosmetic]
+		);
+	}
+
+	#[test]
+	fn roles_only_hold_their_mapped_permissions() {
+		let moderator = [Permission::BanPlayer, Permission::ViewTransactions];
+		let permissions = effective_permissions(moderator, []);
+		assert!(permissions.contains(&Permission::BanPlayer));
+		assert!(!permissions.contains(&Permission::ManageRoles));
+		assert_eq!(effective_permissions([], []), Vec::new());
+	}
+
+	#[test]
+	fn revoking_unheld_permission_is_harmless() {
+		assert_eq!(
+			effective_permissions([], [(Permission::ManageRoles, false)]),
+			Vec::new()
+		);
+	}
+
+	#[test]
+	fn permissions_use_snake_case_names() {
+		assert_eq!(permission_name(Permission::GrantCosmetic), "grant_cosmetic");
+	}
+}
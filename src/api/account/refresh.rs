@@ -0,0 +1,143 @@
+use aide::{
+	OperationIo,
+	axum::{ApiRouter, routing::post_with},
+	transform::TransformOperation,
+};
+use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
+use chrono::Utc;
+use entities::{prelude::*, session};
+use schemars::JsonSchema;
+use sea_orm::{ActiveModelTrait, EntityTrait, QuerySelect, Set, TransactionTrait};
+use serde::Deserialize;
+use tracing::warn;
+
+use crate::{
+	api::{
+		ApiState,
+		account::{
+			login::LoginResponse,
+			session::{parse_refresh_token, rotate_session},
+		},
+	},
+	database::{is_banned, record_monthly_active_login},
+	secret,
+};
+
+#[derive(thiserror::Error, Debug, OperationIo)]
+pub enum RefreshError {
+	#[error("The refresh token is invalid, expired or revoked")]
+	InvalidToken,
+	#[error("Player is banned")]
+	Banned,
+	#[error("Unable to construct authentication token: {0}")]
+	TokenCreation(#[from] pasetors::errors::Error),
+	#[error("Unable to query database: {0}")]
+	Database(#[from] sea_orm::error::DbErr),
+}
+
+impl IntoResponse for RefreshError {
+	fn into_response(self) -> axum::response::Response {
+		(
+			match self {
+				Self::InvalidToken => StatusCode::UNAUTHORIZED,
+				Self::Banned => StatusCode::FORBIDDEN,
+				Self::TokenCreation(_) => StatusCode::INTERNAL_SERVER_ERROR,
+				Self::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+			},
+			self.to_string(),
+		)
+			.into_response()
+	}
+}
+
+fn endpoint_doc(op: TransformOperation) -> TransformOperation {
+	op.id("refreshSession")
+		.summary("Refresh a session")
+		.description(
+			"Exchanges a refresh token for a new access and refresh token pair. Refresh \
+			 tokens are single use: presenting one that was already exchanged revokes \
+			 the whole session.",
+		)
+		.tag("account")
+		.response_with::<{ StatusCode::UNAUTHORIZED.as_u16() }, String, _>(|res| {
+			res.description("The refresh token is invalid, expired or revoked")
+		})
+}
+
+#[derive(Deserialize, JsonSchema)]
+struct RefreshRequest {
+	/// The refresh token returned by the last login or refresh
+	refresh_token: String,
+}
+
+// Refresh tokens are secrets, so the request is never logged
+impl std::fmt::Debug for RefreshRequest {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("RefreshRequest").finish_non_exhaustive()
+	}
+}
+
+pub(super) fn router() -> ApiRouter<ApiState> {
+	ApiRouter::new().api_route("/refresh", post_with(self::endpoint, self::endpoint_doc))
+}
+
+#[tracing::instrument(level = "debug", skip(state))]
+async fn endpoint(
+	State(state): State<ApiState>,
+	Json(body): Json<RefreshRequest>,
+) -> Result<Json<LoginResponse>, RefreshError> {
+	let (session_id, secret) =
+		parse_refresh_token(&body.refresh_token).ok_or(RefreshError::InvalidToken)?;
+
+	let txn = state.database.begin().await?;
+	// Lock the session so two concurrent refreshes can't both succeed with the
+	// same token
+	let Some(session) = Session::find_by_id(session_id)
+		.lock_exclusive()
+		.one(&txn)
+		.await?
+	else {
+		return Err(RefreshError::InvalidToken);
+	};
+	if session.revoked_at.is_some() || session.expires_at < Utc::now() {
+		return Err(RefreshError::InvalidToken);
+	}
+
+	if session.refresh_token_hash != secret::hash(secret) {
+		// A refresh token that was already exchanged is being reused, so it has
+		// probably leaked. Revoke the session to cut off whoever holds it.
+		warn!(
+			session = %session.id,
+			player_id = session.player_id,
+			"Refresh token reused, revoking session"
+		);
+		revoke(&txn, session).await?;
+		txn.commit().await?;
+		return Err(RefreshError::InvalidToken);
+	}
+
+	let Some(player) = User::find_by_id(session.player_id).one(&txn).await? else {
+		return Err(RefreshError::InvalidToken);
+	};
+	if is_banned(&txn, &player).await? {
+		revoke(&txn, session).await?;
+		txn.commit().await?;
+		return Err(RefreshError::Banned);
+	}
+
+	let response = rotate_session::<RefreshError>(&state, &txn, &player, session).await?;
+	record_monthly_active_login(&txn, player.id).await?;
+	txn.commit().await?;
+
+	Ok(Json(response))
+}
+
+async fn revoke(
+	db: &impl sea_orm::ConnectionTrait,
+	session: session::Model,
+) -> Result<(), sea_orm::DbErr> {
+	let mut session: session::ActiveModel = session.into();
+	session.revoked_at = Set(Some(Utc::now().fixed_offset()));
+	session.update(db).await?;
+	Ok(())
+}
@@ -0,0 +1,83 @@
+use aide::{
+	OperationIo,
+	axum::{ApiRouter, routing::post_with},
+	transform::TransformOperation,
+};
+use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
+use chrono::Utc;
+use entities::{account_deletion, prelude::*};
+use sea_orm::{
+	ActiveModelTrait, ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter, Set,
+};
+
+use crate::api::{
+	ApiState,
+	account::{
+		AuthenticatedPlayer,
+		deletion::{DELETION_GRACE_PERIOD, PendingDeletion, pending_condition},
+	},
+};
+
+#[derive(thiserror::Error, Debug, OperationIo)]
+pub enum RequestDeletionError {
+	#[error("Account deletion was already requested")]
+	AlreadyRequested,
+	#[error("Unable to query database: {0}")]
+	Database(#[from] sea_orm::error::DbErr),
+}
+
+impl IntoResponse for RequestDeletionError {
+	fn into_response(self) -> axum::response::Response {
+		(
+			match self {
+				Self::AlreadyRequested => StatusCode::CONFLICT,
+				Self::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+			},
+			self.to_string(),
+		)
+			.into_response()
+	}
+}
+
+fn endpoint_doc(op: TransformOperation) -> TransformOperation {
+	op.id("requestAccountDeletion")
+		.summary("Request account deletion")
+		.description(
+			"Schedules the authenticated player's account for deletion after a 14 day \
+			 grace period, during which the deletion can be cancelled. Deleting an \
+			 account removes its cosmetics, equipment and statistics. Transactions \
+			 are kept for accounting, but no longer reference the player.",
+		)
+		.tag("account")
+}
+
+pub(super) fn router() -> ApiRouter<ApiState> {
+	ApiRouter::new().api_route("/deletion", post_with(self::endpoint, self::endpoint_doc))
+}
+
+#[tracing::instrument(level = "debug", skip(state))]
+async fn endpoint(
+	State(state): State<ApiState>,
+	AuthenticatedPlayer(player): AuthenticatedPlayer,
+) -> Result<(StatusCode, Json<PendingDeletion>), RequestDeletionError> {
+	let pending = AccountDeletion::find()
+		.filter(account_deletion::Column::PlayerId.eq(player.id))
+		.filter(pending_condition())
+		.count(&state.database)
+		.await?;
+	if pending > 0 {
+		return Err(RequestDeletionError::AlreadyRequested);
+	}
+
+	let now = Utc::now();
+	let deletion = account_deletion::ActiveModel {
+		player_id: Set(Some(player.id)),
+		requested_at: Set(now.fixed_offset()),
+		scheduled_for: Set((now + DELETION_GRACE_PERIOD).fixed_offset()),
+		..Default::default()
+	}
+	.insert(&state.database)
+	.await?;
+
+	Ok((StatusCode::ACCEPTED, Json(deletion.into())))
+}
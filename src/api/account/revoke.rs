@@ -0,0 +1,91 @@
+use aide::{
+	OperationIo,
+	axum::{ApiRouter, routing::post_with},
+	transform::TransformOperation,
+};
+use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
+use entities::{prelude::*, user};
+use schemars::JsonSchema;
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, TransactionTrait};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::{
+	api::{
+		ApiState,
+		account::permissions::{ManageSessions, RequirePermission},
+		audit::{self, AuditEntry},
+	},
+	database::revoke_sessions,
+};
+
+#[derive(thiserror::Error, Debug, OperationIo)]
+pub enum RevokeSessionsError {
+	#[error("The requested player does not exist")]
+	MissingPlayer,
+	#[error("Unable to query database: {0}")]
+	Database(#[from] sea_orm::error::DbErr),
+}
+
+impl IntoResponse for RevokeSessionsError {
+	fn into_response(self) -> axum::response::Response {
+		(
+			match self {
+				Self::MissingPlayer => StatusCode::NOT_FOUND,
+				Self::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+			},
+			self.to_string(),
+		)
+			.into_response()
+	}
+}
+
+fn endpoint_doc(op: TransformOperation) -> TransformOperation {
+	op.id("revokePlayerSessions")
+		.summary("Revoke all sessions of a player")
+		.description(
+			"Revokes every active session of a player, logging them out everywhere. \
+			 Requires the `manage_sessions` permission.",
+		)
+		.tag("account")
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct RevokeSessionsRequest {
+	player: Uuid,
+}
+
+pub(super) fn router() -> ApiRouter<ApiState> {
+	ApiRouter::new().api_route(
+		"/sessions/revoke",
+		post_with(self::endpoint, self::endpoint_doc),
+	)
+}
+
+#[tracing::instrument(level = "debug", skip(state, actor))]
+async fn endpoint(
+	State(state): State<ApiState>,
+	RequirePermission(_admin, actor, ..): RequirePermission<ManageSessions>,
+	Json(body): Json<RevokeSessionsRequest>,
+) -> Result<StatusCode, RevokeSessionsError> {
+	let txn = state.database.begin().await?;
+	let Some(player) = User::find()
+		.filter(user::Column::MinecraftUuid.eq(body.player))
+		.one(&txn)
+		.await?
+	else {
+		return Err(RevokeSessionsError::MissingPlayer);
+	};
+
+	let revoked = revoke_sessions(&txn, player.id).await?;
+	audit::record(
+		&txn,
+		&actor,
+		AuditEntry::new("player.revoke_sessions", "player", body.player)
+			.after(serde_json::json!({ "revoked_sessions": revoked })),
+	)
+	.await?;
+	txn.commit().await?;
+
+	Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,148 @@
+use std::time::Duration;
+
+use chrono::{Days, Utc};
+use entities::{session, user};
+use pasetors::{claims::Claims, local};
+use sea_orm::{ActiveModelTrait, ConnectionTrait, DbErr, Set};
+use uuid::Uuid;
+
+use crate::{
+	api::{
+		ApiState,
+		account::{PASETO_IMPLICIT_ASSERT, login::LoginResponse},
+	},
+	secret,
+};
+
+/// How long an access token is valid for after being issued
+pub(super) const ACCESS_TOKEN_LIFETIME: Duration =
+	Duration::from_secs(60 * 60 * 2 /* 2h */);
+/// How long a session may go without being refreshed before it expires
+pub(super) const REFRESH_TOKEN_LIFETIME: Days = Days::new(30);
+
+/// The claim holding the id of the session an access token was issued for
+pub(super) const SESSION_CLAIM: &str = "sid";
+
+/// Refresh tokens are `<session id>.<secret>`, so the session can be looked up
+/// directly and the secret compared against its stored hash.
+fn format_refresh_token(session_id: Uuid, secret: &str) -> String {
+	format!("{}.{secret}", session_id.simple())
+}
+
+pub(super) fn parse_refresh_token(token: &str) -> Option<(Uuid, &str)> {
+	let (session_id, secret) = token.split_once('.')?;
+	if secret.is_empty() {
+		return None;
+	}
+	Some((Uuid::try_parse(session_id).ok()?, secret))
+}
+
+fn access_token(
+	state: &ApiState,
+	player: Uuid,
+	session_id: Uuid,
+) -> Result<String, pasetors::errors::Error> {
+	let (key, footer) = state.paseto_keys.signing_key();
+	local::encrypt(
+		key,
+		&{
+			let mut claims = Claims::new()?;
+
+			claims.set_expires_in(&ACCESS_TOKEN_LIFETIME)?;
+			claims.subject(&player.as_hyphenated().to_string())?;
+			claims
+				.add_additional(SESSION_CLAIM, session_id.as_hyphenated().to_string())?;
+
+			claims
+		},
+		Some(&footer),
+		PASETO_IMPLICIT_ASSERT,
+	)
+}
+
+/// Starts a new session for a player, returning its first token pair.
+pub(super) async fn create_session<E>(
+	state: &ApiState,
+	db: &impl ConnectionTrait,
+	player: &user::Model,
+) -> Result<LoginResponse, E>
+where
+	E: From<DbErr> + From<pasetors::errors::Error>,
+{
+	let (secret, hash) = secret::generate();
+	let now = Utc::now();
+
+	let session = session::ActiveModel {
+		id: Set(Uuid::now_v7()),
+		player_id: Set(player.id),
+		refresh_token_hash: Set(hash),
+		created_at: Set(now.fixed_offset()),
+		last_refreshed_at: Set(now.fixed_offset()),
+		expires_at: Set((now + REFRESH_TOKEN_LIFETIME).fixed_offset()),
+		revoked_at: Set(None),
+	}
+	.insert(db)
+	.await?;
+
+	Ok(LoginResponse {
+		token: access_token(state, player.minecraft_uuid, session.id)?,
+		refresh_token: format_refresh_token(session.id, &secret),
+		expires_in: ACCESS_TOKEN_LIFETIME.as_secs(),
+	})
+}
+
+/// Replaces the refresh token of a session that was just verified, extending
+/// its expiry and returning the new token pair. The previous refresh token is
+/// no longer accepted afterwards.
+pub(super) async fn rotate_session<E>(
+	state: &ApiState,
+	db: &impl ConnectionTrait,
+	player: &user::Model,
+	session: session::Model,
+) -> Result<LoginResponse, E>
+where
+	E: From<DbErr> + From<pasetors::errors::Error>,
+{
+	let (secret, hash) = secret::generate();
+	let now = Utc::now();
+
+	let mut session: session::ActiveModel = session.into();
+	session.refresh_token_hash = Set(hash);
+	session.last_refreshed_at = Set(now.fixed_offset());
+	session.expires_at = Set((now + REFRESH_TOKEN_LIFETIME).fixed_offset());
+	let session = session.update(db).await?;
+
+	Ok(LoginResponse {
+		token: access_token(state, player.minecraft_uuid, session.id)?,
+		refresh_token: format_refresh_token(session.id, &secret),
+		expires_in: ACCESS_TOKEN_LIFETIME.as_secs(),
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use uuid::Uuid;
+
+	use super::{format_refresh_token, parse_refresh_token};
+	use crate::secret;
+
+	#[test]
+	fn refresh_token_round_trips() {
+		let session_id = Uuid::now_v7();
+		let (secret, hash) = secret::generate();
+		let token = format_refresh_token(session_id, &secret);
+
+		let (parsed_id, parsed_secret) =
+			parse_refresh_token(&token).expect("token should parse");
+		assert_eq!(parsed_id, session_id);
+		assert_eq!(secret::hash(parsed_secret), hash);
+	}
+
+	#[test]
+	fn rejects_malformed_refresh_tokens() {
+		assert!(parse_refresh_token("").is_none());
+		assert!(parse_refresh_token("not-a-uuid.secret").is_none());
+		assert!(parse_refresh_token(&format!("{}.", Uuid::nil().simple())).is_none());
+		assert!(parse_refresh_token(&Uuid::nil().simple().to_string()).is_none());
+	}
+}
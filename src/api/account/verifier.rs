@@ -0,0 +1,100 @@
+use std::fmt::Debug;
+
+use futures::future::BoxFuture;
+use reqwest::{Client, StatusCode};
+use serde::Deserialize;
+use uuid::Uuid;
+
+pub const MOJANG_SESSIONSERVER_URL: &str = "https://sessionserver.mojang.com";
+
+/// The player a login was verified for
+#[derive(Debug, Deserialize)]
+pub(crate) struct VerifiedPlayer {
+	pub(crate) id: Uuid,
+	pub(crate) name: String,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub(crate) enum VerifyError {
+	#[error("Unable to reach the session server: {0}")]
+	Request(#[from] reqwest::Error),
+	#[error("The session server did not verify the login")]
+	Rejected,
+}
+
+/// Checks that a player joined a server with the given server id, proving
+/// they own the account they are logging in as.
+pub(crate) trait SessionVerifier: Debug + Send + Sync {
+	/// Verifies a login attempt. `claimed_id` is the UUID the client claims to
+	/// be, which real verifiers ignore.
+	fn verify<'a>(
+		&'a self,
+		username: &'a str,
+		server_id: &'a str,
+		claimed_id: Option<Uuid>,
+	) -> BoxFuture<'a, Result<VerifiedPlayer, VerifyError>>;
+}
+
+/// Verifies logins against Mojang's session server, or a server implementing
+/// the same `hasJoined` API.
+#[derive(Debug)]
+pub(crate) struct MojangVerifier {
+	client: Client,
+	base_url: String,
+}
+
+impl MojangVerifier {
+	pub(crate) fn new(client: Client, base_url: &str) -> Self {
+		Self {
+			client,
+			base_url: base_url.trim_end_matches('/').to_owned(),
+		}
+	}
+}
+
+impl SessionVerifier for MojangVerifier {
+	fn verify<'a>(
+		&'a self,
+		username: &'a str,
+		server_id: &'a str,
+		_claimed_id: Option<Uuid>,
+	) -> BoxFuture<'a, Result<VerifiedPlayer, VerifyError>> {
+		Box::pin(async move {
+			let response = self
+				.client
+				.get(format!("{}/session/minecraft/hasJoined", self.base_url))
+				.query(&[("username", username), ("serverId", server_id)])
+				.send()
+				.await?
+				.error_for_status()?;
+
+			// 200 w/ JSON data is returned on success
+			if response.status() != StatusCode::OK {
+				return Err(VerifyError::Rejected);
+			}
+			let body = response.text().await?;
+			serde_json::from_str(&body).map_err(|_| VerifyError::Rejected)
+		})
+	}
+}
+
+/// Accepts every login as whichever player the client claims to be. Only for
+/// local development and integration tests, never production.
+#[derive(Debug)]
+pub(crate) struct DevVerifier;
+
+impl SessionVerifier for DevVerifier {
+	fn verify<'a>(
+		&'a self,
+		username: &'a str,
+		_server_id: &'a str,
+		claimed_id: Option<Uuid>,
+	) -> BoxFuture<'a, Result<VerifiedPlayer, VerifyError>> {
+		Box::pin(async move {
+			Ok(VerifiedPlayer {
+				id: claimed_id.ok_or(VerifyError::Rejected)?,
+				name: username.to_owned(),
+			})
+		})
+	}
+}
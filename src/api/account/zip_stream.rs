@@ -0,0 +1,233 @@
+//! A ZIP writer for outputs that can't seek, like response bodies. Files are
+//! deflated straight into the output, and their sizes and checksums follow
+//! their data in data descriptors instead of being patched into their headers.
+//! Archives and the files in them are limited to 4 GiB, as ZIP64 isn't
+//! supported.
+
+use std::io::{self, Write};
+
+use flate2::{Compression, write::DeflateEncoder};
+
+const LOCAL_FILE_HEADER: u32 = 0x0403_4b50;
+const DATA_DESCRIPTOR: u32 = 0x0807_4b50;
+const CENTRAL_DIRECTORY_HEADER: u32 = 0x0201_4b50;
+const END_OF_CENTRAL_DIRECTORY: u32 = 0x0605_4b50;
+/// ZIP 2.0, the first version with deflate
+const VERSION: u16 = 20;
+/// The sizes and checksum follow the data, and the name is UTF-8
+const FLAGS: u16 = 1 << 3 | 1 << 11;
+const DEFLATE: u16 = 8;
+/// 1980-01-01 00:00, the earliest date ZIP can store
+const DOS_TIME: u16 = 0;
+const DOS_DATE: u16 = 1 << 5 | 1;
+
+/// A file that was written, kept for the central directory
+struct FileEntry {
+	name: String,
+	offset: u32,
+	crc: u32,
+	compressed_size: u32,
+	size: u32,
+}
+
+/// The file being written
+struct OpenFile {
+	name: String,
+	offset: u32,
+	encoder: DeflateEncoder<Vec<u8>>,
+	hasher: crc32fast::Hasher,
+	compressed_size: u64,
+	size: u64,
+}
+
+pub(super) struct ZipStream<W: Write> {
+	out: W,
+	/// How many bytes were written to `out`
+	offset: u64,
+	files: Vec<FileEntry>,
+	current: Option<OpenFile>,
+}
+
+impl<W: Write> ZipStream<W> {
+	pub(super) fn new(out: W) -> Self {
+		Self {
+			out,
+			offset: 0,
+			files: Vec::new(),
+			current: None,
+		}
+	}
+
+	/// Starts a new file, finishing the previous one. Data written to the
+	/// stream goes to this file.
+	pub(super) fn start_file(&mut self, name: &str) -> io::Result<()> {
+		self.finish_file()?;
+
+		let offset = to_u32(self.offset)?;
+		let mut header = Vec::with_capacity(30 + name.len());
+		header.extend(LOCAL_FILE_HEADER.to_le_bytes());
+		for field in [VERSION, FLAGS, DEFLATE, DOS_TIME, DOS_DATE] {
+			header.extend(field.to_le_bytes());
+		}
+		// The checksum and sizes are in the data descriptor
+		header.extend([0; 12]);
+		header.extend(to_u16(name.len())?.to_le_bytes());
+		header.extend(0u16.to_le_bytes());
+		header.extend(name.as_bytes());
+		self.write_out(&header)?;
+
+		self.current = Some(OpenFile {
+			name: name.to_owned(),
+			offset,
+			encoder: DeflateEncoder::new(Vec::new(), Compression::default()),
+			hasher: crc32fast::Hasher::new(),
+			compressed_size: 0,
+			size: 0,
+		});
+		Ok(())
+	}
+
+	/// Writes the central directory, and returns the output
+	pub(super) fn finish(mut self) -> io::Result<W> {
+		self.finish_file()?;
+
+		let start = self.offset;
+		let mut directory = Vec::new();
+		for file in &self.files {
+			directory.extend(CENTRAL_DIRECTORY_HEADER.to_le_bytes());
+			for field in [VERSION, VERSION, FLAGS, DEFLATE, DOS_TIME, DOS_DATE] {
+				directory.extend(field.to_le_bytes());
+			}
+			for field in [file.crc, file.compressed_size, file.size] {
+				directory.extend(field.to_le_bytes());
+			}
+			directory.extend(to_u16(file.name.len())?.to_le_bytes());
+			// Extra field and comment lengths, disk number and attributes
+			directory.extend([0; 12]);
+			directory.extend(file.offset.to_le_bytes());
+			directory.extend(file.name.as_bytes());
+		}
+		self.write_out(&directory)?;
+
+		let entries = to_u16(self.files.len())?;
+		let mut end = Vec::with_capacity(22);
+		end.extend(END_OF_CENTRAL_DIRECTORY.to_le_bytes());
+		end.extend([0; 4]);
+		end.extend(entries.to_le_bytes());
+		end.extend(entries.to_le_bytes());
+		end.extend(to_u32(self.offset - start)?.to_le_bytes());
+		end.extend(to_u32(start)?.to_le_bytes());
+		end.extend(0u16.to_le_bytes());
+		self.write_out(&end)?;
+
+		self.out.flush()?;
+		Ok(self.out)
+	}
+
+	fn finish_file(&mut self) -> io::Result<()> {
+		let Some(mut file) = self.current.take() else {
+			return Ok(());
+		};
+		file.encoder.try_finish()?;
+		self.drain(&mut file)?;
+
+		let entry = FileEntry {
+			name: file.name,
+			offset: file.offset,
+			crc: file.hasher.finalize(),
+			compressed_size: to_u32(file.compressed_size)?,
+			size: to_u32(file.size)?,
+		};
+		let mut descriptor = Vec::with_capacity(16);
+		for field in [
+			DATA_DESCRIPTOR,
+			entry.crc,
+			entry.compressed_size,
+			entry.size,
+		] {
+			descriptor.extend(field.to_le_bytes());
+		}
+		self.write_out(&descriptor)?;
+		self.files.push(entry);
+		Ok(())
+	}
+
+	/// Moves what the encoder compressed so far to the output
+	fn drain(&mut self, file: &mut OpenFile) -> io::Result<()> {
+		let compressed = std::mem::take(file.encoder.get_mut());
+		file.compressed_size += compressed.len() as u64;
+		self.write_out(&compressed)
+	}
+
+	fn write_out(&mut self, data: &[u8]) -> io::Result<()> {
+		self.out.write_all(data)?;
+		self.offset += data.len() as u64;
+		Ok(())
+	}
+}
+
+impl<W: Write> Write for ZipStream<W> {
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		let Some(mut file) = self.current.take() else {
+			return Err(io::Error::other("No file was started"));
+		};
+		file.hasher.update(buf);
+		file.size += buf.len() as u64;
+		let result = file
+			.encoder
+			.write_all(buf)
+			.and_then(|()| self.drain(&mut file));
+		self.current = Some(file);
+		result.map(|()| buf.len())
+	}
+
+	fn flush(&mut self) -> io::Result<()> {
+		self.out.flush()
+	}
+}
+
+fn to_u32(value: u64) -> io::Result<u32> {
+	u32::try_from(value).map_err(|_| io::Error::other("The archive is too large"))
+}
+
+fn to_u16(value: usize) -> io::Result<u16> {
+	u16::try_from(value).map_err(|_| io::Error::other("The archive has too many files"))
+}
+
+#[cfg(test)]
+mod tests {
+	use std::io::{Cursor, Read, Write};
+
+	use super::ZipStream;
+
+	#[test]
+	fn archives_can_be_read_back() {
+		let mut archive = ZipStream::new(Vec::new());
+		archive.start_file("empty.json").expect("file should start");
+		archive.start_file("large.json").expect("file should start");
+		for line in 0..10_000 {
+			writeln!(archive, "line {line}").expect("file should be written");
+		}
+		let data = archive.finish().expect("archive should finish");
+
+		let mut archive =
+			zip::ZipArchive::new(Cursor::new(data)).expect("archive should be valid");
+		assert_eq!(archive.len(), 2);
+		assert_eq!(
+			archive
+				.by_name("empty.json")
+				.expect("file should exist")
+				.size(),
+			0
+		);
+
+		let mut large = String::new();
+		archive
+			.by_name("large.json")
+			.expect("file should exist")
+			.read_to_string(&mut large)
+			.expect("file should be readable");
+		assert_eq!(large.lines().count(), 10_000);
+		assert_eq!(large.lines().last(), Some("line 9999"));
+	}
+}
@@ -1,27 +1,168 @@
+use std::marker::PhantomData;
+
 use aide::{OperationInput, openapi::SecurityRequirement};
 use axum::{
 	extract::FromRequestParts,
 	http::{StatusCode, request::Parts},
 	response::{IntoResponse, Response},
 };
+use chrono::Utc;
+use entities::{
+	api_key, api_key_scope,
+	prelude::*,
+	sea_orm_active_enums::{ApiScope, Permission},
+};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, sea_query::Expr};
+use uuid::Uuid;
+
+use crate::{
+	api::{
+		ApiState,
+		account::{
+			OPENAPI_SECURITY_NAME,
+			permissions::{authenticate_with_permission, permission_name},
+		},
+		audit::AuditActor,
+	},
+	secret,
+};
 
-use crate::api::ApiState;
+pub const API_KEY_SECURITY_NAME: &str = "API Key";
+/// Prefix of every API key, so leaked keys are easy to recognize
+const API_KEY_PREFIX: &str = "plus_";
 
-pub struct AdminAuthenticationExtractor;
+/// A scope an [`AdminAuthenticationExtractor`] requires of API keys, and the
+/// permission it requires of players instead.
+pub trait RequiredScope {
+	const SCOPE: ApiScope;
+	const PERMISSION: Permission;
+}
+
+macro_rules! required_scopes {
+	($($name:ident => $scope:ident, $permission:ident),* $(,)?) => {
+		$(
+			#[derive(Debug)]
+			pub struct $name;
+
+			impl RequiredScope for $name {
+				const SCOPE: ApiScope = ApiScope::$scope;
+				const PERMISSION: Permission = Permission::$permission;
+			}
+		)*
+	};
+}
 
-impl OperationInput for AdminAuthenticationExtractor {
+required_scopes! {
+	CatalogWrite => CatalogWrite, ManageCatalog,
+	LinksRead => LinksRead, ManageLinks,
+	LinksWrite => LinksWrite, ManageLinks,
+	AnalyticsRead => AnalyticsRead, ViewAnalytics,
+	AuditRead => AuditRead, ViewAuditLog,
+	GrantsWrite => GrantsWrite, GrantCosmetic,
+	AnnouncementsWrite => AnnouncementsWrite, ManageAnnouncements,
+}
+
+/// Authenticates admin operations, using either an API key holding the scope
+/// `S`, or the bearer token of a player holding the matching permission.
+/// Carries who made the request, for the audit log.
+#[derive(Debug)]
+pub struct AdminAuthenticationExtractor<S: RequiredScope>(
+	pub AuditActor,
+	pub PhantomData<S>,
+);
+
+/// The name a scope is serialized with, e.g. `catalog:write`
+pub(super) fn scope_name(scope: ApiScope) -> String {
+	serde_json::to_value(scope)
+		.ok()
+		.and_then(|value| value.as_str().map(str::to_owned))
+		.expect("API scopes serialize as strings")
+}
+
+impl<S: RequiredScope> OperationInput for AdminAuthenticationExtractor<S> {
 	fn operation_input(
 		_ctx: &mut aide::generate::GenContext,
 		operation: &mut aide::openapi::Operation,
 	) {
-		operation.security.push(SecurityRequirement::from([(
-			"Admin Password".to_string(),
-			Vec::new(),
-		)]));
+		operation.security.extend([
+			SecurityRequirement::from([(
+				API_KEY_SECURITY_NAME.to_string(),
+				vec![scope_name(S::SCOPE)],
+			)]),
+			SecurityRequirement::from([(
+				OPENAPI_SECURITY_NAME.to_string(),
+				vec![permission_name(S::PERMISSION)],
+			)]),
+		]);
+	}
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum AdminCredential<'a> {
+	ApiKey { id: Uuid, secret: &'a str },
+	Bearer,
+	MissingOrInvalid,
+}
+
+fn classify_authorization_header(header: Option<&str>) -> AdminCredential<'_> {
+	let Some(header) = header else {
+		return AdminCredential::MissingOrInvalid;
+	};
+	if header.starts_with("Bearer ") {
+		return AdminCredential::Bearer;
+	}
+
+	header
+		.strip_prefix(API_KEY_PREFIX)
+		.and_then(parse_api_key)
+		.map(|(id, secret)| AdminCredential::ApiKey { id, secret })
+		.unwrap_or(AdminCredential::MissingOrInvalid)
+}
+
+/// API keys are `plus_<key id>_<secret>`. The id is hex, so the first
+/// underscore always ends it even if the secret contains more.
+fn parse_api_key(key: &str) -> Option<(Uuid, &str)> {
+	let (id, secret) = key.split_once('_')?;
+	if secret.is_empty() {
+		return None;
 	}
+	Some((Uuid::try_parse(id).ok()?, secret))
 }
 
-impl FromRequestParts<ApiState> for AdminAuthenticationExtractor {
+pub(super) fn format_api_key(id: Uuid, secret: &str) -> String {
+	format!("{API_KEY_PREFIX}{}_{secret}", id.simple())
+}
+
+const INVALID_KEY_ERR: (StatusCode, &str) = (
+	StatusCode::UNAUTHORIZED,
+	"Invalid, expired or missing API key",
+);
+const MISSING_SCOPE_ERR: (StatusCode, &str) = (
+	StatusCode::FORBIDDEN,
+	"API key does not have the scope required for this operation",
+);
+
+/// Checks a stored key against the secret it was presented with, and that it
+/// holds the `required` scope.
+fn authorize_api_key(
+	key: &api_key::Model,
+	secret: &str,
+	scopes: &[ApiScope],
+	required: ApiScope,
+) -> Result<(), (StatusCode, &'static str)> {
+	if key.secret_hash != secret::hash(secret)
+		|| key.revoked_at.is_some()
+		|| key.expires_at.is_some_and(|expiry| expiry < Utc::now())
+	{
+		return Err(INVALID_KEY_ERR);
+	}
+	if !scopes.contains(&required) {
+		return Err(MISSING_SCOPE_ERR);
+	}
+	Ok(())
+}
+
+impl<S: RequiredScope> FromRequestParts<ApiState> for AdminAuthenticationExtractor<S> {
 	type Rejection = Response;
 
 	async fn from_request_parts(
@@ -33,13 +174,192 @@ impl FromRequestParts<ApiState> for AdminAuthenticationExtractor {
 			.get("Authorization")
 			.and_then(|h| h.to_str().ok());
 
-		match auth_header {
-			Some(h) if h == state.admin_password => Ok(Self),
-			_ => Err((
-				StatusCode::UNAUTHORIZED,
-				"Invalid or missing admin password",
+		let (id, secret) = match classify_authorization_header(auth_header) {
+			AdminCredential::ApiKey { id, secret } => (id, secret),
+			AdminCredential::Bearer => {
+				let player =
+					authenticate_with_permission(parts, state, S::PERMISSION).await?;
+				let actor = AuditActor::player(parts, state, &player).await;
+				return Ok(Self(actor, PhantomData));
+			}
+			AdminCredential::MissingOrInvalid => {
+				return Err(INVALID_KEY_ERR.into_response());
+			}
+		};
+
+		let database_error = |e: sea_orm::DbErr| {
+			(
+				StatusCode::INTERNAL_SERVER_ERROR,
+				format!("Unable to load API key: {e}"),
 			)
-				.into_response()),
+				.into_response()
+		};
+
+		let Some(key) = ApiKey::find_by_id(id)
+			.one(&state.database)
+			.await
+			.map_err(database_error)?
+		else {
+			return Err(INVALID_KEY_ERR.into_response());
+		};
+		let scopes = ApiKeyScope::find()
+			.filter(api_key_scope::Column::ApiKeyId.eq(key.id))
+			.all(&state.database)
+			.await
+			.map_err(database_error)?
+			.into_iter()
+			.map(|scope| scope.scope)
+			.collect::<Vec<_>>();
+		authorize_api_key(&key, secret, &scopes, S::SCOPE)
+			.map_err(IntoResponse::into_response)?;
+
+		ApiKey::update_many()
+			.col_expr(
+				api_key::Column::LastUsedAt,
+				Expr::current_timestamp().into(),
+			)
+			.filter(api_key::Column::Id.eq(key.id))
+			.exec(&state.database)
+			.await
+			.map_err(database_error)?;
+
+		let actor = AuditActor::api_key(parts, state, key.id).await;
+		Ok(Self(actor, PhantomData))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use axum::http::StatusCode;
+	use chrono::Utc;
+	use entities::{
+		api_key,
+		sea_orm_active_enums::{ApiScope, Permission},
+	};
+	use uuid::Uuid;
+
+	use super::{
+		AdminCredential, AnalyticsRead, RequiredScope, authorize_api_key,
+		classify_authorization_header, format_api_key, scope_name,
+	};
+	use crate::secret;
+
+	fn stored_key(id: Uuid, secret: &str) -> api_key::Model {
+		api_key::Model {
+			id,
+			label: "test".to_owned(),
+			secret_hash: secret::hash(secret),
+			created_by: None,
+			created_at: Utc::now().fixed_offset(),
+			expires_at: None,
+			last_used_at: None,
+			revoked_at: None,
 		}
 	}
+
+	/// Authorizes an `Authorization` header against a key stored with
+	/// `stored_secret` and `scopes`, requiring `links:read`
+	fn authorize(
+		header: &str,
+		stored_secret: &str,
+		scopes: &[ApiScope],
+	) -> Result<(), StatusCode> {
+		let AdminCredential::ApiKey { id, secret } =
+			classify_authorization_header(Some(header))
+		else {
+			return Err(StatusCode::UNAUTHORIZED);
+		};
+		authorize_api_key(
+			&stored_key(id, stored_secret),
+			secret,
+			scopes,
+			ApiScope::LinksRead,
+		)
+		.map_err(|(status, _)| status)
+	}
+
+	#[test]
+	fn api_key_with_scope_is_authorized() {
+		let key = format_api_key(Uuid::now_v7(), "secret");
+		assert_eq!(authorize(&key, "secret", &[ApiScope::LinksRead]), Ok(()));
+	}
+
+	#[test]
+	fn malformed_api_key_is_rejected() {
+		assert_eq!(
+			authorize("plus_secret", "secret", &[ApiScope::LinksRead]),
+			Err(StatusCode::UNAUTHORIZED)
+		);
+	}
+
+	#[test]
+	fn api_key_with_wrong_secret_is_rejected() {
+		let key = format_api_key(Uuid::now_v7(), "guess");
+		assert_eq!(
+			authorize(&key, "secret", &[ApiScope::LinksRead]),
+			Err(StatusCode::UNAUTHORIZED)
+		);
+	}
+
+	#[test]
+	fn api_key_without_scope_is_forbidden() {
+		let key = format_api_key(Uuid::now_v7(), "secret");
+		assert_eq!(
+			authorize(&key, "secret", &[ApiScope::LinksWrite]),
+			Err(StatusCode::FORBIDDEN)
+		);
+	}
+
+	#[test]
+	fn auth_header_accepts_api_keys() {
+		let id = Uuid::now_v7();
+		let key = format_api_key(id, "se_cr_et");
+		assert_eq!(
+			classify_authorization_header(Some(&key)),
+			AdminCredential::ApiKey {
+				id,
+				secret: "se_cr_et"
+			}
+		);
+	}
+
+	#[test]
+	fn auth_header_accepts_bearer_token() {
+		assert_eq!(
+			classify_authorization_header(Some("Bearer token")),
+			AdminCredential::Bearer
+		);
+	}
+
+	#[test]
+	fn auth_header_rejects_missing_or_invalid_values() {
+		assert_eq!(
+			classify_authorization_header(None),
+			AdminCredential::MissingOrInvalid
+		);
+		assert_eq!(
+			classify_authorization_header(Some("secret")),
+			AdminCredential::MissingOrInvalid
+		);
+		assert_eq!(
+			classify_authorization_header(Some("plus_not-a-uuid_secret")),
+			AdminCredential::MissingOrInvalid
+		);
+		assert_eq!(
+			classify_authorization_header(Some(&format_api_key(Uuid::nil(), ""))),
+			AdminCredential::MissingOrInvalid
+		);
+	}
+
+	#[test]
+	fn scopes_use_colon_names() {
+		assert_eq!(scope_name(ApiScope::CatalogWrite), "catalog:write");
+		assert_eq!(scope_name(ApiScope::AnalyticsRead), "analytics:read");
+	}
+
+	#[test]
+	fn bearer_analytics_auth_requires_analytics_permission() {
+		assert_eq!(AnalyticsRead::SCOPE, ApiScope::AnalyticsRead);
+		assert_eq!(AnalyticsRead::PERMISSION, Permission::ViewAnalytics);
+	}
 }
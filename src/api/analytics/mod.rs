@@ -0,0 +1,15 @@
+mod overview;
+mod servers;
+
+use aide::axum::ApiRouter;
+
+use crate::api::ApiState;
+
+pub(super) async fn setup_router() -> ApiRouter<ApiState> {
+	ApiRouter::new().nest(
+		"/analytics",
+		ApiRouter::new()
+			.merge(overview::router())
+			.merge(servers::router()),
+	)
+}
@@ -1,20 +1,11 @@
 use aide::{
-	OperationInput, OperationIo,
+	OperationIo,
 	axum::{ApiRouter, routing::get_with},
-	openapi::SecurityRequirement,
 	transform::TransformOperation,
 };
-use axum::{
-	Json,
-	extract::{FromRequestParts, State},
-	http::{StatusCode, request::Parts},
-	response::{IntoResponse, Response},
-};
+use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
 use chrono::{Days, Utc};
-use entities::{
-	daily_playtime, monthly_active_login, player_owned_cosmetic, prelude::*,
-	sea_orm_active_enums::PlayerRole,
-};
+use entities::{daily_playtime, monthly_active_login, player_owned_cosmetic, prelude::*};
 use schemars::JsonSchema;
 use sea_orm::{
 	ColumnTrait as _, EntityTrait, FromQueryResult, PaginatorTrait as _, QueryFilter,
@@ -25,88 +16,11 @@ use serde::Serialize;
 use crate::{
 	api::{
 		ApiState,
-		account::{AuthenticatedPlayer, OPENAPI_SECURITY_NAME, role_at_least},
+		admin_auth::{AdminAuthenticationExtractor, AnalyticsRead},
 	},
 	database::current_utc_month,
 };
 
-#[derive(Debug)]
-pub struct PrivateAnalyticsAuth;
-
-#[derive(Debug, PartialEq, Eq)]
-enum PrivateAuthCredential<'a> {
-	AdminPassword,
-	Bearer(&'a str),
-	MissingOrInvalid,
-}
-
-fn classify_authorization_header<'a>(
-	header: Option<&'a str>,
-	admin_password: &str,
-) -> PrivateAuthCredential<'a> {
-	match header {
-		Some(value) if value == admin_password => PrivateAuthCredential::AdminPassword,
-		Some(value) => value
-			.strip_prefix("Bearer ")
-			.map(PrivateAuthCredential::Bearer)
-			.unwrap_or(PrivateAuthCredential::MissingOrInvalid),
-		None => PrivateAuthCredential::MissingOrInvalid,
-	}
-}
-
-fn is_admin_role(role: &PlayerRole) -> bool {
-	role_at_least(role, &PlayerRole::Admin)
-}
-
-impl OperationInput for PrivateAnalyticsAuth {
-	fn operation_input(
-		_ctx: &mut aide::generate::GenContext,
-		operation: &mut aide::openapi::Operation,
-	) {
-		operation.security.extend([
-			SecurityRequirement::from([("Admin Password".to_string(), Vec::new())]),
-			SecurityRequirement::from([(OPENAPI_SECURITY_NAME.to_string(), Vec::new())]),
-		]);
-	}
-}
-
-impl FromRequestParts<ApiState> for PrivateAnalyticsAuth {
-	type Rejection = Response;
-
-	async fn from_request_parts(
-		parts: &mut Parts,
-		state: &ApiState,
-	) -> Result<Self, Self::Rejection> {
-		let auth_header = parts
-			.headers
-			.get("Authorization")
-			.and_then(|h| h.to_str().ok());
-
-		match classify_authorization_header(auth_header, &state.admin_password) {
-			PrivateAuthCredential::AdminPassword => Ok(Self),
-			PrivateAuthCredential::Bearer(_) => {
-				let player = AuthenticatedPlayer::from_request_parts(parts, state)
-					.await?
-					.0;
-				if is_admin_role(&player.role) {
-					Ok(Self)
-				} else {
-					Err((
-						StatusCode::FORBIDDEN,
-						"Authenticated player does not have permission",
-					)
-						.into_response())
-				}
-			}
-			PrivateAuthCredential::MissingOrInvalid => Err((
-				StatusCode::UNAUTHORIZED,
-				"Invalid or missing analytics authorization",
-			)
-				.into_response()),
-		}
-	}
-}
-
 #[derive(thiserror::Error, Debug, OperationIo)]
 pub enum AnalyticsError {
 	#[error("Unable to query analytics data: {0}")]
@@ -123,7 +37,8 @@ fn endpoint_doc(op: TransformOperation) -> TransformOperation {
 	op.id("getAnalyticsOverview")
 		.summary("Get private analytics overview")
 		.description(
-			"Returns private aggregate analytics for users, MAU, owned items, and playtime.",
+			"Returns private aggregate analytics for users, MAU, owned items, and \
+			 playtime. Requires the `analytics:read` scope.",
 		)
 		.tag("analytics")
 }
@@ -231,17 +146,14 @@ impl OwnedItemsCounts {
 	}
 }
 
-pub(super) async fn setup_router() -> ApiRouter<ApiState> {
-	ApiRouter::new().api_route(
-		"/analytics/overview",
-		get_with(self::endpoint, self::endpoint_doc),
-	)
+pub(super) fn router() -> ApiRouter<ApiState> {
+	ApiRouter::new().api_route("/overview", get_with(self::endpoint, self::endpoint_doc))
 }
 
 #[tracing::instrument(level = "debug", skip(state))]
 async fn endpoint(
 	State(state): State<ApiState>,
-	_auth: PrivateAnalyticsAuth,
+	_auth: AdminAuthenticationExtractor<AnalyticsRead>,
 ) -> Result<Json<AnalyticsOverviewResponse>, AnalyticsError> {
 	let user_counts = UserCounts {
 		total_users: User::find().count(&state.database).await? as i64,
@@ -341,45 +253,3 @@ async fn endpoint(
 		},
 	}))
 }
-
-#[cfg(test)]
-mod tests {
-	use entities::sea_orm_active_enums::PlayerRole;
-
-	use super::{PrivateAuthCredential, classify_authorization_header, is_admin_role};
-
-	#[test]
-	fn auth_header_accepts_admin_password() {
-		assert_eq!(
-			classify_authorization_header(Some("secret"), "secret"),
-			PrivateAuthCredential::AdminPassword
-		);
-	}
-
-	#[test]
-	fn auth_header_accepts_bearer_token() {
-		assert_eq!(
-			classify_authorization_header(Some("Bearer token"), "secret"),
-			PrivateAuthCredential::Bearer("token")
-		);
-	}
-
-	#[test]
-	fn auth_header_rejects_missing_or_invalid_values() {
-		assert_eq!(
-			classify_authorization_header(None, "secret"),
-			PrivateAuthCredential::MissingOrInvalid
-		);
-		assert_eq!(
-			classify_authorization_header(Some("token"), "secret"),
-			PrivateAuthCredential::MissingOrInvalid
-		);
-	}
-
-	#[test]
-	fn only_admin_role_can_use_bearer_analytics_auth() {
-		assert!(is_admin_role(&PlayerRole::Admin));
-		assert!(!is_admin_role(&PlayerRole::Moderator));
-		assert!(!is_admin_role(&PlayerRole::Player));
-	}
-}
@@ -0,0 +1,131 @@
+use aide::{
+	axum::{ApiRouter, routing::get_with},
+	transform::TransformOperation,
+};
+use axum::{
+	Json,
+	extract::{Query, State},
+};
+use entities::{player_presence, prelude::*};
+use schemars::JsonSchema;
+use sea_orm::{
+	ColumnTrait, EntityTrait, QueryFilter, QuerySelect,
+	sea_query::{Expr, Func, SimpleExpr},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::api::{
+	ApiState,
+	admin_auth::{AdminAuthenticationExtractor, AnalyticsRead},
+	analytics::overview::AnalyticsError,
+};
+
+/// The maximum number of servers allowed per page.
+const MAX_NB: usize = 100;
+
+fn default_nb() -> usize {
+	50
+}
+
+fn default_page() -> usize {
+	1
+}
+
+fn default_min_users() -> usize {
+	1
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct ServersQuery {
+	/// Only list servers with at least this many PolyPlus users on them
+	#[serde(default = "default_min_users")]
+	min_users: usize,
+	/// The number of servers per page, capped at 100.
+	#[serde(default = "default_nb")]
+	nb: usize,
+	/// The 1-indexed page to return.
+	#[serde(default = "default_page")]
+	page: usize,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+struct ServerUsers {
+	/// The server's address or hash, as reported by clients
+	server: String,
+	/// How many PolyPlus users are on the server right now
+	users: i64,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+struct ServersResponse {
+	servers: Vec<ServerUsers>,
+	/// The total number of servers matching the query across all pages.
+	total_items: usize,
+	/// How many PolyPlus users are on any server right now
+	total_users: i64,
+}
+
+fn endpoint_doc(op: TransformOperation) -> TransformOperation {
+	op.id("getAnalyticsServers")
+		.summary("Count PolyPlus users per Minecraft server")
+		.description(
+			"Lists the Minecraft servers PolyPlus users are on right now, as \
+			 reported by their clients to any backend instance, with the most \
+			 users first. Paginated by \
+			 `nb` per page and 1-indexed `page`. Requires the `analytics:read` \
+			 scope.",
+		)
+		.tag("analytics")
+}
+
+/// Counts each player once, though players connected to several instances have
+/// a presence row for each
+fn distinct_players() -> SimpleExpr {
+	Func::count_distinct(Expr::col(player_presence::Column::Player)).into()
+}
+
+pub(super) fn router() -> ApiRouter<ApiState> {
+	ApiRouter::new().api_route("/servers", get_with(self::endpoint, self::endpoint_doc))
+}
+
+#[tracing::instrument(level = "debug", skip(state, _auth))]
+async fn endpoint(
+	State(state): State<ApiState>,
+	_auth: AdminAuthenticationExtractor<AnalyticsRead>,
+	Query(query): Query<ServersQuery>,
+) -> Result<Json<ServersResponse>, AnalyticsError> {
+	// [nb * (page - 1); nb * page).
+	let nb = query.nb.min(MAX_NB);
+	let offset = nb.saturating_mul(query.page.saturating_sub(1));
+
+	let mut servers = PlayerPresence::find()
+		.select_only()
+		.column(player_presence::Column::Server)
+		.column_as(distinct_players(), "users")
+		.filter(player_presence::Column::Server.is_not_null())
+		.group_by(player_presence::Column::Server)
+		.into_tuple::<(String, i64)>()
+		.all(&state.database)
+		.await?
+		.into_iter()
+		.filter(|(_, users)| *users >= query.min_users as i64)
+		.map(|(server, users)| ServerUsers { server, users })
+		.collect::<Vec<_>>();
+	servers.sort_unstable_by(|a, b| {
+		b.users.cmp(&a.users).then_with(|| a.server.cmp(&b.server))
+	});
+	let total_users = PlayerPresence::find()
+		.select_only()
+		.column_as(distinct_players(), "users")
+		.filter(player_presence::Column::Server.is_not_null())
+		.into_tuple::<i64>()
+		.one(&state.database)
+		.await?
+		.unwrap_or_default();
+
+	Ok(Json(ServersResponse {
+		total_items: servers.len(),
+		servers: servers.into_iter().skip(offset).take(nb).collect(),
+		total_users,
+	}))
+}
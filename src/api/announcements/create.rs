@@ -0,0 +1,238 @@
+use std::collections::HashSet;
+
+use aide::{
+	OperationIo,
+	axum::{ApiRouter, routing::post_with},
+	transform::TransformOperation,
+};
+use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
+use chrono::{DateTime, Utc};
+use entities::{
+	announcement, player_owned_cosmetic, sea_orm_active_enums::AnnouncementLevel, user,
+};
+use schemars::JsonSchema;
+use sea_orm::{
+	ActiveModelTrait, ColumnTrait, DbErr, EntityTrait, QueryFilter, QuerySelect, Set,
+	TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::api::{
+	ApiState,
+	admin_auth::{AdminAuthenticationExtractor, AnnouncementsWrite},
+	announcements::AnnouncementTarget,
+	audit::{self, AuditEntry},
+	links::valid_target,
+	realtime::RealtimeEvent,
+	websocket::structs::AnnouncementInfo,
+};
+
+/// Max characters in an announcement's title
+const MAX_TITLE_LENGTH: usize = 100;
+/// Max characters in an announcement's body
+const MAX_BODY_LENGTH: usize = 1000;
+/// Max players an announcement can list in its target
+const MAX_TARGET_PLAYERS: usize = 1000;
+/// How many recipients are sent per realtime event, which keeps the events
+/// under the size limit of Postgres notifications
+const RECIPIENTS_PER_EVENT: usize = 20;
+
+#[derive(thiserror::Error, Debug, OperationIo)]
+pub enum CreateAnnouncementError {
+	#[error("The title must be between 1 and 100 characters long")]
+	InvalidTitle,
+	#[error("The body must be between 1 and 1000 characters long")]
+	InvalidBody,
+	#[error("The link must be an absolute http(s) url")]
+	InvalidLink,
+	#[error("The expiry must be in the future")]
+	ExpiryInPast,
+	#[error("Too many target players (max 1000)")]
+	TooManyPlayers,
+	#[error("Unable to query database: {0}")]
+	Database(#[from] DbErr),
+}
+
+impl IntoResponse for CreateAnnouncementError {
+	fn into_response(self) -> axum::response::Response {
+		(
+			match self {
+				Self::InvalidTitle
+				| Self::InvalidBody
+				| Self::InvalidLink
+				| Self::ExpiryInPast
+				| Self::TooManyPlayers => StatusCode::BAD_REQUEST,
+				Self::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+			},
+			self.to_string(),
+		)
+			.into_response()
+	}
+}
+
+fn endpoint_doc(op: TransformOperation) -> TransformOperation {
+	op.id("createAnnouncement")
+		.summary("Announce something to mod users")
+		.description(
+			"Sends an announcement to the targeted players that are connected to \
+			 the websocket right now, and to those that connect before it \
+			 expires. Only clients with the `announcements` capability receive \
+			 it, and each player receives it once. Requires the \
+			 `announcements:write` scope.",
+		)
+		.tag("announcements")
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct CreateAnnouncementRequest {
+	level: AnnouncementLevel,
+	title: String,
+	body: String,
+	/// An absolute http(s) url where players can read more
+	link: Option<String>,
+	/// When the announcement stops being sent to players that connect
+	expires_at: DateTime<Utc>,
+	/// Who the announcement is for, everyone if left out
+	#[serde(default)]
+	target: AnnouncementTarget,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+struct CreateAnnouncementResponse {
+	#[serde(flatten)]
+	announcement: AnnouncementInfo,
+	target: AnnouncementTarget,
+	/// How many of the targeted players were online when the announcement was
+	/// created. Only their connections that negotiated the `announcements`
+	/// capability are sent it, the rest get it once they connect with it.
+	recipients: usize,
+}
+
+pub(super) fn router() -> ApiRouter<ApiState> {
+	ApiRouter::new().api_route("/", post_with(self::endpoint, self::endpoint_doc))
+}
+
+#[tracing::instrument(level = "debug", skip(state, actor))]
+async fn endpoint(
+	State(state): State<ApiState>,
+	AdminAuthenticationExtractor(actor, ..): AdminAuthenticationExtractor<
+		AnnouncementsWrite,
+	>,
+	Json(body): Json<CreateAnnouncementRequest>,
+) -> Result<(StatusCode, Json<CreateAnnouncementResponse>), CreateAnnouncementError> {
+	let title = body.title.trim().to_owned();
+	if title.is_empty() || title.chars().count() > MAX_TITLE_LENGTH {
+		return Err(CreateAnnouncementError::InvalidTitle);
+	}
+	let text = body.body.trim().to_owned();
+	if text.is_empty() || text.chars().count() > MAX_BODY_LENGTH {
+		return Err(CreateAnnouncementError::InvalidBody);
+	}
+	if body.link.as_deref().is_some_and(|link| !valid_target(link)) {
+		return Err(CreateAnnouncementError::InvalidLink);
+	}
+	if body.expires_at <= Utc::now() {
+		return Err(CreateAnnouncementError::ExpiryInPast);
+	}
+	if let AnnouncementTarget::Players { players } = &body.target
+		&& players.len() > MAX_TARGET_PLAYERS
+	{
+		return Err(CreateAnnouncementError::TooManyPlayers);
+	}
+
+	let txn = state.database.begin().await?;
+	let announcement = announcement::ActiveModel {
+		level: Set(body.level),
+		title: Set(title),
+		body: Set(text),
+		link: Set(body.link),
+		target: Set(
+			serde_json::to_value(&body.target).expect("announcement targets are JSON")
+		),
+		expires_at: Set(body.expires_at.fixed_offset()),
+		..Default::default()
+	}
+	.insert(&txn)
+	.await?;
+	audit::record(
+		&txn,
+		&actor,
+		AuditEntry::new("announcement.create", "announcement", announcement.id)
+			.after(audit::model_json(&announcement)),
+	)
+	.await?;
+	txn.commit().await?;
+
+	let announcement = AnnouncementInfo::from(announcement);
+	let recipients = match online_recipients(&state, &body.target).await? {
+		None => {
+			state
+				.realtime
+				.publish(RealtimeEvent::Announcement {
+					announcement: announcement.clone(),
+					players: None,
+				})
+				.await;
+			state.realtime.online.read().await.len()
+		}
+		Some(players) => {
+			for players in players.chunks(RECIPIENTS_PER_EVENT) {
+				state
+					.realtime
+					.publish(RealtimeEvent::Announcement {
+						announcement: announcement.clone(),
+						players: Some(players.to_vec()),
+					})
+					.await;
+			}
+			players.len()
+		}
+	};
+
+	Ok((
+		StatusCode::CREATED,
+		Json(CreateAnnouncementResponse {
+			announcement,
+			target: body.target,
+			recipients,
+		}),
+	))
+}
+
+/// The online players an announcement is for, or [None] if it is for everyone
+async fn online_recipients(
+	state: &ApiState,
+	target: &AnnouncementTarget,
+) -> Result<Option<Vec<Uuid>>, DbErr> {
+	use entities::prelude::*;
+
+	let targeted: Vec<Uuid> = match target {
+		AnnouncementTarget::All => return Ok(None),
+		AnnouncementTarget::Players { players } => players.clone(),
+		AnnouncementTarget::CosmeticOwners { cosmetic_ids } => {
+			PlayerOwnedCosmetic::find()
+				.select_only()
+				.column(user::Column::MinecraftUuid)
+				.inner_join(User)
+				.filter(
+					player_owned_cosmetic::Column::CosmeticId
+						.is_in(cosmetic_ids.iter().copied()),
+				)
+				.distinct()
+				.into_tuple()
+				.all(&state.database)
+				.await?
+		}
+	};
+
+	let online = state.realtime.online.read().await;
+	Ok(Some(
+		targeted
+			.into_iter()
+			.filter(|player| online.contains_key(player))
+			.collect::<HashSet<_>>()
+			.into_iter()
+			.collect(),
+	))
+}
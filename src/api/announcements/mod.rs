@@ -0,0 +1,179 @@
+//! Announcements, like maintenance notices, pushed to connected mod users over
+//! the websocket. They are kept until they expire, so that players who connect
+//! in the meantime are sent them after the handshake. Deliveries are recorded
+//! per player, so each player is only sent an announcement once.
+
+mod create;
+
+use std::collections::HashSet;
+
+use aide::axum::ApiRouter;
+use chrono::Utc;
+use entities::{
+	announcement, announcement_delivery, player_owned_cosmetic, prelude::*, user,
+};
+use schemars::JsonSchema;
+use sea_orm::{
+	ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter, QueryOrder,
+	QuerySelect,
+	sea_query::{Expr, OnConflict, Query},
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::api::{ApiState, websocket::structs::AnnouncementInfo};
+
+/// Who an announcement is for
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub(crate) enum AnnouncementTarget {
+	/// Every player
+	#[default]
+	All,
+	/// Players that own any of the cosmetics
+	CosmeticOwners { cosmetic_ids: Vec<i32> },
+	/// The listed players
+	Players { players: Vec<Uuid> },
+}
+
+impl From<announcement::Model> for AnnouncementInfo {
+	fn from(announcement: announcement::Model) -> Self {
+		Self {
+			id: announcement.id,
+			level: announcement.level,
+			title: announcement.title,
+			body: announcement.body,
+			link: announcement.link,
+			expires_at: announcement.expires_at.timestamp_millis(),
+		}
+	}
+}
+
+/// The active announcements meant for a player that they haven't been sent
+/// yet, oldest first
+pub(crate) async fn announcements_for(
+	db: &impl ConnectionTrait,
+	player: &user::Model,
+) -> Result<Vec<AnnouncementInfo>, DbErr> {
+	let announcements = Announcement::find()
+		.filter(announcement::Column::ExpiresAt.gt(Utc::now()))
+		.filter(
+			announcement::Column::Id.not_in_subquery(
+				Query::select()
+					.column(announcement_delivery::Column::AnnouncementId)
+					.from(AnnouncementDelivery)
+					.and_where(announcement_delivery::Column::PlayerId.eq(player.id))
+					.to_owned(),
+			),
+		)
+		.order_by_asc(announcement::Column::Id)
+		.all(db)
+		.await?;
+
+	// Only looked up if an announcement targets cosmetic owners
+	let mut owned: Option<HashSet<i32>> = None;
+	let mut infos = Vec::new();
+	for announcement in announcements {
+		let Ok(target) =
+			serde_json::from_value::<AnnouncementTarget>(announcement.target.clone())
+		else {
+			continue;
+		};
+		let targeted = match target {
+			AnnouncementTarget::All => true,
+			AnnouncementTarget::Players { players } => {
+				players.contains(&player.minecraft_uuid)
+			}
+			AnnouncementTarget::CosmeticOwners { cosmetic_ids } => {
+				if owned.is_none() {
+					owned = Some(
+						PlayerOwnedCosmetic::find()
+							.select_only()
+							.column(player_owned_cosmetic::Column::CosmeticId)
+							.filter(player_owned_cosmetic::Column::PlayerId.eq(player.id))
+							.into_tuple()
+							.all(db)
+							.await?
+							.into_iter()
+							.collect(),
+					);
+				}
+				owned
+					.as_ref()
+					.is_some_and(|owned| cosmetic_ids.iter().any(|id| owned.contains(id)))
+			}
+		};
+		if targeted {
+			infos.push(announcement.into());
+		}
+	}
+
+	Ok(infos)
+}
+
+/// Records that an announcement was sent to the given players
+pub(crate) async fn record_deliveries(
+	db: &impl ConnectionTrait,
+	announcement_id: i32,
+	players: impl IntoIterator<Item = Uuid>,
+) -> Result<(), DbErr> {
+	let mut insert = Query::insert();
+	insert
+		.into_table(AnnouncementDelivery)
+		.columns([
+			announcement_delivery::Column::AnnouncementId,
+			announcement_delivery::Column::PlayerId,
+		])
+		.select_from(
+			Query::select()
+				.expr(Expr::value(announcement_id))
+				.column(user::Column::Id)
+				.from(User)
+				.and_where(user::Column::MinecraftUuid.is_in(players))
+				.to_owned(),
+		)
+		.expect("the select has a value for each column")
+		.on_conflict(
+			OnConflict::columns([
+				announcement_delivery::Column::AnnouncementId,
+				announcement_delivery::Column::PlayerId,
+			])
+			.do_nothing()
+			.to_owned(),
+		);
+
+	db.execute(db.get_database_backend().build(&insert)).await?;
+	Ok(())
+}
+
+pub(super) async fn setup_router() -> ApiRouter<ApiState> {
+	ApiRouter::new().nest(
+		"/admin/announcements",
+		ApiRouter::new().merge(create::router()),
+	)
+}
+
+#[cfg(test)]
+mod tests {
+	use uuid::Uuid;
+
+	use super::AnnouncementTarget;
+
+	#[test]
+	fn targets_are_stored_as_tagged_json() {
+		let target = AnnouncementTarget::Players {
+			players: vec![Uuid::nil()],
+		};
+
+		let json = serde_json::to_value(&target).expect("target should serialize");
+		assert_eq!(
+			json,
+			serde_json::json!({ "type": "players", "players": [Uuid::nil()] })
+		);
+		assert_eq!(
+			serde_json::from_value::<AnnouncementTarget>(json)
+				.expect("target should parse"),
+			target
+		);
+	}
+}
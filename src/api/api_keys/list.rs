@@ -0,0 +1,89 @@
+use std::collections::HashMap;
+
+use aide::{
+	OperationIo,
+	axum::{ApiRouter, routing::get_with},
+	transform::TransformOperation,
+};
+use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
+use entities::{api_key, prelude::*};
+use schemars::JsonSchema;
+use sea_orm::{EntityTrait, QueryOrder};
+use serde::Serialize;
+
+use crate::api::{
+	ApiState,
+	account::permissions::{ManageApiKeys, RequirePermission},
+	api_keys::ApiKeyInfo,
+};
+
+#[derive(thiserror::Error, Debug, OperationIo)]
+pub enum ListError {
+	#[error("Unable to query database: {0}")]
+	Database(#[from] sea_orm::error::DbErr),
+}
+
+impl IntoResponse for ListError {
+	fn into_response(self) -> axum::response::Response {
+		(
+			match self {
+				Self::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+			},
+			self.to_string(),
+		)
+			.into_response()
+	}
+}
+
+fn endpoint_doc(op: TransformOperation) -> TransformOperation {
+	op.id("listApiKeys")
+		.summary("List API keys")
+		.description(
+			"Lists every admin API key, including revoked and expired ones, newest \
+			 first. Requires the `manage_api_keys` permission.",
+		)
+		.tag("api keys")
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+struct ListResponse {
+	keys: Vec<ApiKeyInfo>,
+}
+
+pub(super) fn router() -> ApiRouter<ApiState> {
+	ApiRouter::new().api_route("/", get_with(self::endpoint, self::endpoint_doc))
+}
+
+#[tracing::instrument(level = "debug", skip(state))]
+async fn endpoint(
+	State(state): State<ApiState>,
+	RequirePermission(_admin, ..): RequirePermission<ManageApiKeys>,
+) -> Result<Json<ListResponse>, ListError> {
+	let keys = ApiKey::find()
+		.find_also_related(User)
+		.order_by_desc(api_key::Column::CreatedAt)
+		.all(&state.database)
+		.await?;
+
+	let mut scopes: HashMap<_, Vec<_>> = HashMap::new();
+	for scope in ApiKeyScope::find().all(&state.database).await? {
+		scopes
+			.entry(scope.api_key_id)
+			.or_default()
+			.push(scope.scope);
+	}
+
+	Ok(Json(ListResponse {
+		keys: keys
+			.into_iter()
+			.map(|(key, creator)| {
+				let key_scopes = scopes.remove(&key.id).unwrap_or_default();
+				ApiKeyInfo::from_model(
+					key,
+					key_scopes,
+					creator.map(|creator| creator.minecraft_uuid),
+				)
+			})
+			.collect(),
+	}))
+}
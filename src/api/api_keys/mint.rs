@@ -0,0 +1,143 @@
+use aide::{
+	OperationIo,
+	axum::{ApiRouter, routing::post_with},
+	transform::TransformOperation,
+};
+use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
+use chrono::{DateTime, Utc};
+use entities::{api_key, api_key_scope, prelude::*, sea_orm_active_enums::ApiScope};
+use schemars::JsonSchema;
+use sea_orm::{ActiveModelTrait, EntityTrait, Set, TransactionTrait};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+	api::{
+		ApiState,
+		account::permissions::{ManageApiKeys, RequirePermission},
+		admin_auth::format_api_key,
+		api_keys::ApiKeyInfo,
+		audit::{self, AuditEntry},
+	},
+	secret,
+};
+
+#[derive(thiserror::Error, Debug, OperationIo)]
+pub enum MintError {
+	#[error("The label must not be empty")]
+	EmptyLabel,
+	#[error("At least one scope is required")]
+	NoScopes,
+	#[error("The expiry must be in the future")]
+	ExpiryInPast,
+	#[error("Unable to query database: {0}")]
+	Database(#[from] sea_orm::error::DbErr),
+}
+
+impl IntoResponse for MintError {
+	fn into_response(self) -> axum::response::Response {
+		(
+			match self {
+				Self::EmptyLabel => StatusCode::BAD_REQUEST,
+				Self::NoScopes => StatusCode::BAD_REQUEST,
+				Self::ExpiryInPast => StatusCode::BAD_REQUEST,
+				Self::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+			},
+			self.to_string(),
+		)
+			.into_response()
+	}
+}
+
+fn endpoint_doc(op: TransformOperation) -> TransformOperation {
+	op.id("mintApiKey")
+		.summary("Mint an API key")
+		.description(
+			"Creates a new admin API key limited to the given scopes. The key is only \
+			 returned once, and cannot be recovered afterwards. Requires the \
+			 `manage_api_keys` permission.",
+		)
+		.tag("api keys")
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct MintRequest {
+	/// What the key is for, e.g. `admin panel` or `release bot`
+	label: String,
+	scopes: Vec<ApiScope>,
+	/// When the key stops working. Keys without an expiry last until revoked.
+	expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+struct MintResponse {
+	/// The API key, to be sent as the `Authorization` header. This is the only
+	/// time it is shown.
+	key: String,
+	info: ApiKeyInfo,
+}
+
+pub(super) fn router() -> ApiRouter<ApiState> {
+	ApiRouter::new().api_route("/", post_with(self::endpoint, self::endpoint_doc))
+}
+
+#[tracing::instrument(level = "debug", skip(state, actor))]
+async fn endpoint(
+	State(state): State<ApiState>,
+	RequirePermission(admin, actor, ..): RequirePermission<ManageApiKeys>,
+	Json(mut body): Json<MintRequest>,
+) -> Result<(StatusCode, Json<MintResponse>), MintError> {
+	let label = body.label.trim().to_owned();
+	if label.is_empty() {
+		return Err(MintError::EmptyLabel);
+	}
+	body.scopes.sort_by_key(|scope| *scope as u8);
+	body.scopes.dedup();
+	if body.scopes.is_empty() {
+		return Err(MintError::NoScopes);
+	}
+	if body.expires_at.is_some_and(|expiry| expiry <= Utc::now()) {
+		return Err(MintError::ExpiryInPast);
+	}
+
+	let (secret, secret_hash) = secret::generate();
+	let txn = state.database.begin().await?;
+	let key = api_key::ActiveModel {
+		id: Set(Uuid::now_v7()),
+		label: Set(label),
+		secret_hash: Set(secret_hash),
+		created_by: Set(Some(admin.id)),
+		expires_at: Set(body.expires_at.map(|expiry| expiry.fixed_offset())),
+		..Default::default()
+	}
+	.insert(&txn)
+	.await?;
+
+	ApiKeyScope::insert_many(body.scopes.iter().map(|&scope| {
+		api_key_scope::ActiveModel {
+			api_key_id: Set(key.id),
+			scope: Set(scope),
+		}
+	}))
+	.exec(&txn)
+	.await?;
+
+	let key_id = key.id;
+	let info = ApiKeyInfo::from_model(key, body.scopes, Some(admin.minecraft_uuid));
+	// The info leaves out the secret's hash, which has no place in the log
+	audit::record(
+		&txn,
+		&actor,
+		AuditEntry::new("api_key.mint", "api_key", key_id).after(serde_json::json!(info)),
+	)
+	.await?;
+	txn.commit().await?;
+
+	Ok((
+		StatusCode::CREATED,
+		Json(MintResponse {
+			key: format_api_key(key_id, &secret),
+			info,
+		}),
+	))
+}
@@ -0,0 +1,55 @@
+mod list;
+mod mint;
+mod revoke;
+
+use aide::axum::ApiRouter;
+use chrono::{DateTime, FixedOffset};
+use entities::{api_key, sea_orm_active_enums::ApiScope};
+use schemars::JsonSchema;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::api::ApiState;
+
+/// An admin API key, without its secret
+#[derive(Debug, Serialize, JsonSchema)]
+struct ApiKeyInfo {
+	id: Uuid,
+	label: String,
+	scopes: Vec<ApiScope>,
+	/// The admin player who minted this key, if they still exist
+	created_by: Option<Uuid>,
+	created_at: DateTime<FixedOffset>,
+	expires_at: Option<DateTime<FixedOffset>>,
+	last_used_at: Option<DateTime<FixedOffset>>,
+	revoked_at: Option<DateTime<FixedOffset>>,
+}
+
+impl ApiKeyInfo {
+	fn from_model(
+		key: api_key::Model,
+		scopes: Vec<ApiScope>,
+		created_by: Option<Uuid>,
+	) -> Self {
+		Self {
+			id: key.id,
+			label: key.label,
+			scopes,
+			created_by,
+			created_at: key.created_at,
+			expires_at: key.expires_at,
+			last_used_at: key.last_used_at,
+			revoked_at: key.revoked_at,
+		}
+	}
+}
+
+pub(super) async fn setup_router() -> ApiRouter<ApiState> {
+	ApiRouter::new().nest(
+		"/admin/api-keys",
+		ApiRouter::new()
+			.merge(list::router())
+			.merge(mint::router())
+			.merge(revoke::router()),
+	)
+}
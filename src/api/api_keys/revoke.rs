@@ -0,0 +1,85 @@
+use aide::{
+	OperationIo,
+	axum::{ApiRouter, routing::delete_with},
+	transform::TransformOperation,
+};
+use axum::{
+	extract::{Path, State},
+	http::StatusCode,
+	response::IntoResponse,
+};
+use entities::{api_key, prelude::*};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, TransactionTrait, sea_query::Expr};
+use uuid::Uuid;
+
+use crate::api::{
+	ApiState,
+	account::permissions::{ManageApiKeys, RequirePermission},
+	audit::{self, AuditEntry},
+};
+
+#[derive(thiserror::Error, Debug, OperationIo)]
+pub enum RevokeError {
+	#[error("The requested API key does not exist or was already revoked")]
+	NotFound,
+	#[error("Unable to query database: {0}")]
+	Database(#[from] sea_orm::error::DbErr),
+}
+
+impl IntoResponse for RevokeError {
+	fn into_response(self) -> axum::response::Response {
+		(
+			match self {
+				Self::NotFound => StatusCode::NOT_FOUND,
+				Self::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+			},
+			self.to_string(),
+		)
+			.into_response()
+	}
+}
+
+fn endpoint_doc(op: TransformOperation) -> TransformOperation {
+	op.id("revokeApiKey")
+		.summary("Revoke an API key")
+		.description(
+			"Revokes an admin API key. It stops working immediately, but stays listed \
+			 for auditing. Requires the `manage_api_keys` permission.",
+		)
+		.tag("api keys")
+}
+
+pub(super) fn router() -> ApiRouter<ApiState> {
+	ApiRouter::new().api_route("/{id}", delete_with(self::endpoint, self::endpoint_doc))
+}
+
+#[tracing::instrument(level = "debug", skip(state, actor))]
+async fn endpoint(
+	State(state): State<ApiState>,
+	RequirePermission(_admin, actor, ..): RequirePermission<ManageApiKeys>,
+	Path(id): Path<Uuid>,
+) -> Result<StatusCode, RevokeError> {
+	let txn = state.database.begin().await?;
+	let Some(key) = ApiKey::update_many()
+		.col_expr(api_key::Column::RevokedAt, Expr::current_timestamp().into())
+		.filter(api_key::Column::Id.eq(id))
+		.filter(api_key::Column::RevokedAt.is_null())
+		.exec_with_returning(&txn)
+		.await?
+		.pop()
+	else {
+		return Err(RevokeError::NotFound);
+	};
+
+	audit::record(
+		&txn,
+		&actor,
+		AuditEntry::new("api_key.revoke", "api_key", id)
+			.before(serde_json::json!({ "revoked_at": null }))
+			.after(serde_json::json!({ "revoked_at": key.revoked_at })),
+	)
+	.await?;
+	txn.commit().await?;
+
+	Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,231 @@
+use std::collections::{HashMap, HashSet};
+
+use aide::{
+	OperationIo,
+	axum::{ApiRouter, routing::get_with},
+	transform::TransformOperation,
+};
+use axum::{
+	Json,
+	extract::{Query, State},
+	http::StatusCode,
+	response::IntoResponse,
+};
+use chrono::{DateTime, FixedOffset, Utc};
+use entities::{audit_log, prelude::*, user};
+use schemars::JsonSchema;
+use sea_orm::{
+	ColumnTrait, EntityTrait, Order, PaginatorTrait, QueryFilter, QueryOrder, QuerySelect,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::api::{
+	ApiState,
+	admin_auth::{AdminAuthenticationExtractor, AuditRead},
+	players::profile::known_names,
+};
+
+#[derive(thiserror::Error, Debug, OperationIo)]
+pub enum ListError {
+	#[error("Unable to query database: {0}")]
+	Database(#[from] sea_orm::error::DbErr),
+}
+
+impl IntoResponse for ListError {
+	fn into_response(self) -> axum::response::Response {
+		(
+			match self {
+				Self::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+			},
+			self.to_string(),
+		)
+			.into_response()
+	}
+}
+
+/// The maximum number of entries allowed per page.
+const MAX_NB: u64 = 100;
+
+fn default_nb() -> u64 {
+	50
+}
+
+fn default_page() -> u64 {
+	1
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct ListQuery {
+	/// Only list entries with this action, e.g. `cosmetic.update`
+	action: Option<String>,
+	/// Only list entries targeting this kind of entity, e.g. `cosmetic`
+	target_type: Option<String>,
+	/// Only list entries targeting the entity with this id. Usually combined
+	/// with `target_type`.
+	target_id: Option<String>,
+	/// Only list changes made by this player
+	actor_player: Option<Uuid>,
+	/// Only list changes made with this API key
+	actor_api_key: Option<Uuid>,
+	/// Only list changes made at or after this time
+	since: Option<DateTime<Utc>>,
+	/// Only list changes made before this time
+	until: Option<DateTime<Utc>>,
+	/// Also return the last known usernames of the acting and targeted players
+	#[serde(default)]
+	include_names: bool,
+	/// The number of entries per page, capped at 100.
+	#[serde(default = "default_nb")]
+	nb: u64,
+	/// The 1-indexed page to return.
+	#[serde(default = "default_page")]
+	page: u64,
+}
+
+/// A single change made through the admin API
+#[derive(Debug, Serialize, JsonSchema)]
+struct AuditEntryInfo {
+	id: i32,
+	action: String,
+	target_type: String,
+	target_id: String,
+	/// The player who made the change, if it was made with a bearer token and
+	/// the player still exists
+	actor_player: Option<Uuid>,
+	/// The API key the change was made with, if it still exists
+	actor_api_key: Option<Uuid>,
+	/// The fields of the target that changed, as they were before. Missing for
+	/// creations.
+	before: Option<serde_json::Value>,
+	/// The fields of the target that changed, as they are after. Missing for
+	/// deletions.
+	after: Option<serde_json::Value>,
+	client_ip: Option<String>,
+	created_at: DateTime<FixedOffset>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+struct ListResponse {
+	entries: Vec<AuditEntryInfo>,
+	/// The total number of entries matching the query across all pages.
+	total_items: u64,
+	/// The last known usernames of the acting and targeted players, when
+	/// requested with `include_names`
+	#[serde(skip_serializing_if = "Option::is_none")]
+	names: Option<HashMap<Uuid, String>>,
+}
+
+fn endpoint_doc(op: TransformOperation) -> TransformOperation {
+	op.id("listAuditLog")
+		.summary("List audit log entries")
+		.description(
+			"Lists changes made through the admin API newest first, paginated by \
+			 `nb` per page and 1-indexed `page`. Requires the `audit:read` scope.",
+		)
+		.tag("audit")
+}
+
+pub(super) fn router() -> ApiRouter<ApiState> {
+	ApiRouter::new().api_route("/", get_with(self::endpoint, self::endpoint_doc))
+}
+
+#[tracing::instrument(level = "debug", skip(state, _auth))]
+async fn endpoint(
+	State(state): State<ApiState>,
+	_auth: AdminAuthenticationExtractor<AuditRead>,
+	Query(query): Query<ListQuery>,
+) -> Result<Json<ListResponse>, ListError> {
+	// [nb * (page - 1); nb * page).
+	let nb = query.nb.min(MAX_NB);
+	let offset = nb.saturating_mul(query.page.saturating_sub(1));
+
+	let mut find = AuditLog::find();
+	if let Some(action) = query.action {
+		find = find.filter(audit_log::Column::Action.eq(action));
+	}
+	if let Some(target_type) = query.target_type {
+		find = find.filter(audit_log::Column::TargetType.eq(target_type));
+	}
+	if let Some(target_id) = query.target_id {
+		find = find.filter(audit_log::Column::TargetId.eq(target_id));
+	}
+	if let Some(api_key) = query.actor_api_key {
+		find = find.filter(audit_log::Column::ActorApiKeyId.eq(api_key));
+	}
+	if let Some(since) = query.since {
+		find = find.filter(audit_log::Column::CreatedAt.gte(since));
+	}
+	if let Some(until) = query.until {
+		find = find.filter(audit_log::Column::CreatedAt.lt(until));
+	}
+	if let Some(player) = query.actor_player {
+		let Some(player) = User::find()
+			.filter(user::Column::MinecraftUuid.eq(player))
+			.one(&state.database)
+			.await?
+		else {
+			return Ok(Json(ListResponse {
+				entries: Vec::new(),
+				total_items: 0,
+				names: query.include_names.then(HashMap::new),
+			}));
+		};
+		find = find.filter(audit_log::Column::ActorPlayerId.eq(player.id));
+	}
+
+	let total_items = find.clone().count(&state.database).await?;
+	let entries = find
+		.order_by(audit_log::Column::CreatedAt, Order::Desc)
+		.order_by(audit_log::Column::Id, Order::Desc)
+		.offset(offset)
+		.limit(nb)
+		.all(&state.database)
+		.await?;
+
+	let player_ids = entries
+		.iter()
+		.filter_map(|entry| entry.actor_player_id)
+		.collect::<HashSet<_>>();
+	let uuids = User::find()
+		.filter(user::Column::Id.is_in(player_ids))
+		.all(&state.database)
+		.await?
+		.into_iter()
+		.map(|user| (user.id, user.minecraft_uuid))
+		.collect::<HashMap<_, _>>();
+
+	let entries = entries
+		.into_iter()
+		.map(|entry| AuditEntryInfo {
+			id: entry.id,
+			actor_player: entry.actor_player_id.and_then(|id| uuids.get(&id).copied()),
+			actor_api_key: entry.actor_api_key_id,
+			action: entry.action,
+			target_type: entry.target_type,
+			target_id: entry.target_id,
+			before: entry.before,
+			after: entry.after,
+			client_ip: entry.client_ip,
+			created_at: entry.created_at,
+		})
+		.collect::<Vec<_>>();
+	let names = if query.include_names {
+		let players = entries.iter().flat_map(|entry| {
+			let target = (entry.target_type == "player")
+				.then(|| entry.target_id.parse().ok())
+				.flatten();
+			[entry.actor_player, target]
+		});
+		let players = players.flatten().collect::<HashSet<_>>();
+		Some(known_names(&state.database, players).await?)
+	} else {
+		None
+	};
+
+	Ok(Json(ListResponse {
+		entries,
+		total_items,
+		names,
+	}))
+}
@@ -0,0 +1,201 @@
+//! A record of every change made through the admin API. Handlers describe
+//! what they changed with an [`AuditEntry`], and write it with [`record`] in
+//! the same transaction as the change itself.
+
+mod list;
+
+use std::net::IpAddr;
+
+use aide::axum::ApiRouter;
+use axum::{extract::FromRequestParts, http::request::Parts};
+use axum_client_ip::ClientIp;
+use entities::{audit_log, user};
+use sea_orm::{
+	ActiveModelTrait, ConnectionTrait, DbErr, EntityTrait, IdenStatic, Iterable,
+	ModelTrait, Set, sea_query::sea_value_to_json_value,
+};
+use serde_json::{Map, Value};
+use uuid::Uuid;
+
+use crate::api::ApiState;
+
+/// Who made a change, and from where
+#[derive(Debug, Clone)]
+pub struct AuditActor {
+	api_key_id: Option<Uuid>,
+	player_id: Option<i32>,
+	client_ip: Option<IpAddr>,
+}
+
+impl AuditActor {
+	pub(super) async fn api_key(parts: &mut Parts, state: &ApiState, id: Uuid) -> Self {
+		Self {
+			api_key_id: Some(id),
+			player_id: None,
+			client_ip: client_ip(parts, state).await,
+		}
+	}
+
+	/// The backend itself, for changes nobody asked for right then, like
+	/// scheduled account deletions
+	pub(super) fn system() -> Self {
+		Self {
+			api_key_id: None,
+			player_id: None,
+			client_ip: None,
+		}
+	}
+
+	pub(super) async fn player(
+		parts: &mut Parts,
+		state: &ApiState,
+		player: &user::Model,
+	) -> Self {
+		Self {
+			api_key_id: None,
+			player_id: Some(player.id),
+			client_ip: client_ip(parts, state).await,
+		}
+	}
+}
+
+async fn client_ip(parts: &mut Parts, state: &ApiState) -> Option<IpAddr> {
+	ClientIp::from_request_parts(parts, state)
+		.await
+		.ok()
+		.map(|ClientIp(ip)| ip)
+}
+
+/// A change to write to the audit log
+#[derive(Debug)]
+pub(super) struct AuditEntry {
+	/// What was done, e.g. `cosmetic.update`
+	action: &'static str,
+	target_type: &'static str,
+	target_id: String,
+	before: Option<Value>,
+	after: Option<Value>,
+}
+
+impl AuditEntry {
+	pub(super) fn new(
+		action: &'static str,
+		target_type: &'static str,
+		target_id: impl ToString,
+	) -> Self {
+		Self {
+			action,
+			target_type,
+			target_id: target_id.to_string(),
+			before: None,
+			after: None,
+		}
+	}
+
+	/// The target before the change, left out for creations
+	pub(super) fn before(self, value: Value) -> Self {
+		Self {
+			before: Some(value),
+			..self
+		}
+	}
+
+	/// The target after the change, left out for deletions
+	pub(super) fn after(self, value: Value) -> Self {
+		Self {
+			after: Some(value),
+			..self
+		}
+	}
+}
+
+/// Converts a database row to JSON, keyed by column name
+pub(super) fn model_json<M: ModelTrait>(model: &M) -> Value {
+	<M::Entity as EntityTrait>::Column::iter()
+		.map(|column| {
+			(
+				column.as_str().to_owned(),
+				sea_value_to_json_value(&model.get(column)),
+			)
+		})
+		.collect::<Map<_, _>>()
+		.into()
+}
+
+/// Drops every field that is the same before and after a change, so entries
+/// only show what changed. Anything but two objects is kept as is.
+fn diff(before: Option<Value>, after: Option<Value>) -> (Option<Value>, Option<Value>) {
+	let (Some(Value::Object(mut before)), Some(Value::Object(mut after))) =
+		(before.clone(), after.clone())
+	else {
+		return (before, after);
+	};
+
+	let unchanged = before
+		.iter()
+		.filter(|(key, value)| after.get(*key) == Some(*value))
+		.map(|(key, _)| key.clone())
+		.collect::<Vec<_>>();
+	for key in unchanged {
+		before.remove(&key);
+		after.remove(&key);
+	}
+
+	(Some(Value::Object(before)), Some(Value::Object(after)))
+}
+
+/// Writes a change to the audit log
+pub(super) async fn record(
+	db: &impl ConnectionTrait,
+	actor: &AuditActor,
+	entry: AuditEntry,
+) -> Result<(), DbErr> {
+	let (before, after) = diff(entry.before, entry.after);
+
+	audit_log::ActiveModel {
+		actor_api_key_id: Set(actor.api_key_id),
+		actor_player_id: Set(actor.player_id),
+		action: Set(entry.action.to_owned()),
+		target_type: Set(entry.target_type.to_owned()),
+		target_id: Set(entry.target_id),
+		before: Set(before),
+		after: Set(after),
+		client_ip: Set(actor.client_ip.map(|ip| ip.to_string())),
+		..Default::default()
+	}
+	.insert(db)
+	.await?;
+
+	Ok(())
+}
+
+pub(super) async fn setup_router() -> ApiRouter<ApiState> {
+	ApiRouter::new().nest("/admin/audit", ApiRouter::new().merge(list::router()))
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json::json;
+
+	use super::diff;
+
+	#[test]
+	fn diff_keeps_only_changed_fields() {
+		let (before, after) = diff(
+			Some(json!({"id": 1, "name": "Cape", "enabled": false})),
+			Some(json!({"id": 1, "name": "Cape", "enabled": true, "added": 2})),
+		);
+		assert_eq!(before, Some(json!({"enabled": false})));
+		assert_eq!(after, Some(json!({"enabled": true, "added": 2})));
+	}
+
+	#[test]
+	fn diff_keeps_creations_and_deletions_whole() {
+		let created = json!({"id": 1, "name": "Cape"});
+		assert_eq!(
+			diff(None, Some(created.clone())),
+			(None, Some(created.clone()))
+		);
+		assert_eq!(diff(Some(created.clone()), None), (Some(created), None));
+	}
+}
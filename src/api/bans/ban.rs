@@ -0,0 +1,132 @@
+use aide::{
+	OperationIo,
+	axum::{ApiRouter, routing::post_with},
+	transform::TransformOperation,
+};
+use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
+use chrono::{DateTime, Utc};
+use entities::{player_ban, sea_orm_active_enums::Permission, user};
+use schemars::JsonSchema;
+use sea_orm::{ActiveModelTrait, Set, TransactionTrait};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::{
+	api::{
+		ApiState,
+		account::permissions::{BanPlayer, RequirePermission, has_permission},
+		audit::{self, AuditEntry, model_json},
+		bans::{BanInfo, ban_infos},
+		websocket::structs::CLOSE_CODE_BANNED,
+	},
+	database::{DatabaseUserExt, revoke_sessions},
+};
+
+#[derive(thiserror::Error, Debug, OperationIo)]
+pub enum BanError {
+	#[error("A ban reason is required")]
+	MissingReason,
+	#[error("The expiry must be in the future")]
+	ExpiryInPast,
+	#[error("Players who can ban others can't be banned")]
+	TargetIsModerator,
+	#[error("Unable to query database: {0}")]
+	Database(#[from] sea_orm::error::DbErr),
+}
+
+impl IntoResponse for BanError {
+	fn into_response(self) -> axum::response::Response {
+		(
+			match self {
+				Self::MissingReason => StatusCode::BAD_REQUEST,
+				Self::ExpiryInPast => StatusCode::BAD_REQUEST,
+				Self::TargetIsModerator => StatusCode::FORBIDDEN,
+				Self::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+			},
+			self.to_string(),
+		)
+			.into_response()
+	}
+}
+
+fn endpoint_doc(op: TransformOperation) -> TransformOperation {
+	op.id("banPlayer")
+		.summary("Ban a player")
+		.description(
+			"Bans a player, either permanently or until `expires_at`. The player is \
+			 logged out everywhere and their websocket connections are closed. \
+			 Requires the `ban_player` permission. Players who hold it themselves \
+			 cannot be banned.",
+		)
+		.tag("bans")
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct BanRequest {
+	player: Uuid,
+	/// Why the player is banned. Only visible to staff.
+	reason: String,
+	/// When the ban lifts by itself. Omit for a permanent ban.
+	expires_at: Option<DateTime<Utc>>,
+}
+
+pub(super) fn router() -> ApiRouter<ApiState> {
+	ApiRouter::new().api_route("/", post_with(self::endpoint, self::endpoint_doc))
+}
+
+#[tracing::instrument(level = "debug", skip(state, actor))]
+async fn endpoint(
+	State(state): State<ApiState>,
+	RequirePermission(moderator, actor, ..): RequirePermission<BanPlayer>,
+	Json(body): Json<BanRequest>,
+) -> Result<(StatusCode, Json<BanInfo>), BanError> {
+	use entities::prelude::*;
+
+	let reason = body.reason.trim().to_owned();
+	if reason.is_empty() {
+		return Err(BanError::MissingReason);
+	}
+	if body.expires_at.is_some_and(|expiry| expiry <= Utc::now()) {
+		return Err(BanError::ExpiryInPast);
+	}
+
+	let txn = state.database.begin().await?;
+	let player = User::get_or_create(&txn, body.player).await?;
+	if has_permission(&txn, &player, Permission::BanPlayer).await? {
+		return Err(BanError::TargetIsModerator);
+	}
+
+	let ban = player_ban::ActiveModel {
+		player_id: Set(player.id),
+		reason: Set(reason),
+		moderator_id: Set(Some(moderator.id)),
+		expires_at: Set(body.expires_at.map(|expiry| expiry.fixed_offset())),
+		..Default::default()
+	}
+	.insert(&txn)
+	.await?;
+
+	let player_id = player.id;
+	let mut player: user::ActiveModel = player.into();
+	player.blacklisted = Set(true);
+	player.update(&txn).await?;
+	revoke_sessions(&txn, player_id).await?;
+	audit::record(
+		&txn,
+		&actor,
+		AuditEntry::new("player.ban", "player", body.player).after(model_json(&ban)),
+	)
+	.await?;
+	txn.commit().await?;
+
+	state
+		.realtime
+		.close_connections(body.player, CLOSE_CODE_BANNED, "Banned")
+		.await;
+
+	let info = ban_infos(&state.database, vec![ban])
+		.await?
+		.pop()
+		.expect("the banned player exists");
+	Ok((StatusCode::CREATED, Json(info)))
+}
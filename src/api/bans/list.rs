@@ -0,0 +1,160 @@
+use std::collections::{HashMap, HashSet};
+
+use aide::{
+	OperationIo,
+	axum::{ApiRouter, routing::get_with},
+	transform::TransformOperation,
+};
+use axum::{
+	Json,
+	extract::{Query, State},
+	http::StatusCode,
+	response::IntoResponse,
+};
+use entities::{player_ban, prelude::*, user};
+use schemars::JsonSchema;
+use sea_orm::{
+	ColumnTrait, EntityTrait, Order, PaginatorTrait, QueryFilter, QueryOrder, QuerySelect,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+	api::{
+		ApiState,
+		account::permissions::{BanPlayer, RequirePermission},
+		bans::{BanInfo, ban_infos},
+		players::profile::known_names,
+	},
+	database::active_ban_condition,
+};
+
+#[derive(thiserror::Error, Debug, OperationIo)]
+pub enum ListError {
+	#[error("Unable to query database: {0}")]
+	Database(#[from] sea_orm::error::DbErr),
+}
+
+impl IntoResponse for ListError {
+	fn into_response(self) -> axum::response::Response {
+		(
+			match self {
+				Self::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+			},
+			self.to_string(),
+		)
+			.into_response()
+	}
+}
+
+/// The maximum number of bans allowed per page.
+const MAX_NB: u64 = 100;
+
+fn default_nb() -> u64 {
+	50
+}
+
+fn default_page() -> u64 {
+	1
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct ListQuery {
+	/// Only list the bans of this player
+	player: Option<Uuid>,
+	/// Also list bans that expired or were lifted
+	#[serde(default)]
+	include_inactive: bool,
+	/// Also return the last known usernames of the players involved
+	#[serde(default)]
+	include_names: bool,
+	/// The number of bans per page, capped at 100.
+	#[serde(default = "default_nb")]
+	nb: u64,
+	/// The 1-indexed page to return.
+	#[serde(default = "default_page")]
+	page: u64,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+struct ListResponse {
+	bans: Vec<BanInfo>,
+	/// The total number of bans matching the query across all pages.
+	total_items: u64,
+	/// The last known usernames of the banned players and moderators, when
+	/// requested with `include_names`
+	#[serde(skip_serializing_if = "Option::is_none")]
+	names: Option<HashMap<Uuid, String>>,
+}
+
+fn endpoint_doc(op: TransformOperation) -> TransformOperation {
+	op.id("listBans")
+		.summary("List bans")
+		.description(
+			"Lists bans newest first, paginated by `nb` per page and 1-indexed \
+			 `page`. Only active bans are listed unless `include_inactive` is set. \
+			 Requires the `ban_player` permission.",
+		)
+		.tag("bans")
+}
+
+pub(super) fn router() -> ApiRouter<ApiState> {
+	ApiRouter::new().api_route("/", get_with(self::endpoint, self::endpoint_doc))
+}
+
+#[tracing::instrument(level = "debug", skip(state))]
+async fn endpoint(
+	State(state): State<ApiState>,
+	RequirePermission(_moderator, ..): RequirePermission<BanPlayer>,
+	Query(query): Query<ListQuery>,
+) -> Result<Json<ListResponse>, ListError> {
+	// [nb * (page - 1); nb * page).
+	let nb = query.nb.min(MAX_NB);
+	let offset = nb.saturating_mul(query.page.saturating_sub(1));
+
+	let mut find = PlayerBan::find();
+	if !query.include_inactive {
+		find = find.filter(active_ban_condition());
+	}
+	if let Some(player) = query.player {
+		let Some(player) = User::find()
+			.filter(user::Column::MinecraftUuid.eq(player))
+			.one(&state.database)
+			.await?
+		else {
+			return Ok(Json(ListResponse {
+				bans: Vec::new(),
+				total_items: 0,
+				names: query.include_names.then(HashMap::new),
+			}));
+		};
+		find = find.filter(player_ban::Column::PlayerId.eq(player.id));
+	}
+
+	let total_items = find.clone().count(&state.database).await?;
+	let bans = find
+		.order_by(player_ban::Column::CreatedAt, Order::Desc)
+		.order_by(player_ban::Column::Id, Order::Desc)
+		.offset(offset)
+		.limit(nb)
+		.all(&state.database)
+		.await?;
+
+	let bans = ban_infos(&state.database, bans).await?;
+	let names = if query.include_names {
+		let players = bans
+			.iter()
+			.flat_map(|ban| [Some(ban.player), ban.moderator, ban.lifted_by])
+			.flatten()
+			.collect::<HashSet<_>>();
+		Some(known_names(&state.database, players).await?)
+	} else {
+		None
+	};
+
+	Ok(Json(ListResponse {
+		bans,
+		total_items,
+		names,
+	}))
+}
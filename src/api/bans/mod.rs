@@ -0,0 +1,82 @@
+mod ban;
+mod list;
+mod unban;
+
+use std::collections::{HashMap, HashSet};
+
+use aide::axum::ApiRouter;
+use chrono::{DateTime, FixedOffset, Utc};
+use entities::{player_ban, prelude::*, user};
+use schemars::JsonSchema;
+use sea_orm::{ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::api::ApiState;
+
+/// A ban issued to a player, active or not
+#[derive(Debug, Serialize, JsonSchema)]
+struct BanInfo {
+	id: i32,
+	player: Uuid,
+	reason: String,
+	/// The moderator who issued the ban, if they still exist
+	moderator: Option<Uuid>,
+	created_at: DateTime<FixedOffset>,
+	/// When the ban lifts by itself. Permanent bans have no expiry.
+	expires_at: Option<DateTime<FixedOffset>>,
+	/// When a moderator lifted the ban early
+	lifted_at: Option<DateTime<FixedOffset>>,
+	lifted_by: Option<Uuid>,
+	/// Whether the ban is currently in effect
+	active: bool,
+}
+
+/// Resolves the players referenced by each ban, and converts them to
+/// [`BanInfo`]s in the same order.
+async fn ban_infos(
+	db: &impl ConnectionTrait,
+	bans: Vec<player_ban::Model>,
+) -> Result<Vec<BanInfo>, DbErr> {
+	let player_ids = bans
+		.iter()
+		.flat_map(|ban| [Some(ban.player_id), ban.moderator_id, ban.lifted_by])
+		.flatten()
+		.collect::<HashSet<_>>();
+	let uuids = User::find()
+		.filter(user::Column::Id.is_in(player_ids))
+		.all(db)
+		.await?
+		.into_iter()
+		.map(|user| (user.id, user.minecraft_uuid))
+		.collect::<HashMap<_, _>>();
+
+	let now = Utc::now();
+	Ok(bans
+		.into_iter()
+		.filter_map(|ban| {
+			Some(BanInfo {
+				id: ban.id,
+				player: *uuids.get(&ban.player_id)?,
+				moderator: ban.moderator_id.and_then(|id| uuids.get(&id).copied()),
+				lifted_by: ban.lifted_by.and_then(|id| uuids.get(&id).copied()),
+				active: ban.lifted_at.is_none()
+					&& ban.expires_at.is_none_or(|expiry| expiry > now),
+				reason: ban.reason,
+				created_at: ban.created_at,
+				expires_at: ban.expires_at,
+				lifted_at: ban.lifted_at,
+			})
+		})
+		.collect())
+}
+
+pub(super) async fn setup_router() -> ApiRouter<ApiState> {
+	ApiRouter::new().nest(
+		"/bans",
+		ApiRouter::new()
+			.merge(list::router())
+			.merge(ban::router())
+			.merge(unban::router()),
+	)
+}
@@ -0,0 +1,107 @@
+use aide::{
+	OperationIo,
+	axum::{ApiRouter, routing::delete_with},
+	transform::TransformOperation,
+};
+use axum::{
+	extract::{Path, State},
+	http::StatusCode,
+	response::IntoResponse,
+};
+use chrono::Utc;
+use entities::{player_ban, prelude::*, user};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, TransactionTrait, sea_query::Expr};
+use uuid::Uuid;
+
+use crate::{
+	api::{
+		ApiState,
+		account::permissions::{BanPlayer, RequirePermission},
+		audit::{self, AuditEntry},
+	},
+	database::active_ban_condition,
+};
+
+#[derive(thiserror::Error, Debug, OperationIo)]
+pub enum UnbanError {
+	#[error("The player is not banned")]
+	NotBanned,
+	#[error("Unable to query database: {0}")]
+	Database(#[from] sea_orm::error::DbErr),
+}
+
+impl IntoResponse for UnbanError {
+	fn into_response(self) -> axum::response::Response {
+		(
+			match self {
+				Self::NotBanned => StatusCode::NOT_FOUND,
+				Self::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+			},
+			self.to_string(),
+		)
+			.into_response()
+	}
+}
+
+fn endpoint_doc(op: TransformOperation) -> TransformOperation {
+	op.id("unbanPlayer")
+		.summary("Unban a player")
+		.description(
+			"Lifts every active ban of a player. The bans stay on record. Requires \
+			 the `ban_player` permission.",
+		)
+		.tag("bans")
+}
+
+pub(super) fn router() -> ApiRouter<ApiState> {
+	ApiRouter::new()
+		.api_route("/{player}", delete_with(self::endpoint, self::endpoint_doc))
+}
+
+#[tracing::instrument(level = "debug", skip(state, actor))]
+async fn endpoint(
+	State(state): State<ApiState>,
+	RequirePermission(moderator, actor, ..): RequirePermission<BanPlayer>,
+	Path(player): Path<Uuid>,
+) -> Result<StatusCode, UnbanError> {
+	let txn = state.database.begin().await?;
+	let Some(player) = User::find()
+		.filter(user::Column::MinecraftUuid.eq(player))
+		.one(&txn)
+		.await?
+	else {
+		return Err(UnbanError::NotBanned);
+	};
+
+	let lifted = PlayerBan::update_many()
+		.col_expr(
+			player_ban::Column::LiftedAt,
+			Expr::value(Utc::now().fixed_offset()),
+		)
+		.col_expr(player_ban::Column::LiftedBy, Expr::value(moderator.id))
+		.filter(player_ban::Column::PlayerId.eq(player.id))
+		.filter(active_ban_condition())
+		.exec_with_returning(&txn)
+		.await?;
+	// Players may also be flagged without a ban on record
+	if lifted.is_empty() && !player.blacklisted {
+		return Err(UnbanError::NotBanned);
+	}
+
+	User::update_many()
+		.col_expr(user::Column::Blacklisted, Expr::value(false))
+		.filter(user::Column::Id.eq(player.id))
+		.exec(&txn)
+		.await?;
+	let lifted_ban_ids = lifted.iter().map(|ban| ban.id).collect::<Vec<_>>();
+	audit::record(
+		&txn,
+		&actor,
+		AuditEntry::new("player.unban", "player", player.minecraft_uuid)
+			.after(serde_json::json!({ "lifted_ban_ids": lifted_ban_ids })),
+	)
+	.await?;
+	txn.commit().await?;
+
+	Ok(StatusCode::NO_CONTENT)
+}
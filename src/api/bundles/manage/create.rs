@@ -12,12 +12,16 @@ use axum::{
 
 use entities::sea_orm_active_enums::AssetKind;
 use schemars::JsonSchema;
-use sea_orm::{ActiveModelTrait, EntityTrait, Set};
+use sea_orm::{ActiveModelTrait, EntityTrait, Set, TransactionTrait};
 use sha2::{Digest, Sha256};
 use uuid::Uuid;
 
 use crate::api::{
-	ApiState, admin_auth::AdminAuthenticationExtractor, bundles::BundleInfo,
+	ApiState,
+	admin_auth::{AdminAuthenticationExtractor, CatalogWrite},
+	audit::{self, AuditEntry, model_json},
+	bundles::BundleInfo,
+	cosmetics::ChangedEntries,
 	stripe::products,
 };
 
@@ -63,14 +67,14 @@ fn endpoint_doc(op: TransformOperation) -> TransformOperation {
 		.description(
 			"Uploads a bundle's cover image to S3 (optional), registers the bundle \
 			 in the database with its contained cosmetics, then provisions a Stripe \
-			 product and price for it. Admin password required.",
+			 product and price for it. Requires the `catalog:write` scope.",
 		)
 		.tag("bundles")
 		.response_with::<{ StatusCode::OK.as_u16() }, Json<BundleInfo>, _>(|res| {
 			res.description("The created bundle info")
 		})
 		.response_with::<{ StatusCode::UNAUTHORIZED.as_u16() }, String, _>(|res| {
-			res.description("Invalid or missing admin password")
+			res.description("Invalid or missing API key")
 		})
 }
 
@@ -154,7 +158,7 @@ fn sha256_hex(data: &[u8]) -> String {
 
 async fn endpoint(
 	State(state): State<ApiState>,
-	_auth: AdminAuthenticationExtractor,
+	AdminAuthenticationExtractor(actor, ..): AdminAuthenticationExtractor<CatalogWrite>,
 	FileUpload(mut multipart): FileUpload,
 ) -> Result<Json<BundleInfo>, CreateError> {
 	let mut file_data = None;
@@ -258,6 +262,7 @@ async fn endpoint(
 
 	use entities::{bundles, bundles_cosmetics, prelude::*};
 
+	let txn = state.database.begin().await?;
 	let bundle = bundles::ActiveModel {
 		name: Set(name),
 		description: Set(description),
@@ -270,7 +275,7 @@ async fn endpoint(
 		discount_rate: Set(None),
 		..Default::default()
 	}
-	.insert(&state.database)
+	.insert(&txn)
 	.await?;
 
 	if !cosmetic_ids.is_empty() {
@@ -281,9 +286,26 @@ async fn endpoint(
 			}
 		}))
 		.on_conflict_do_nothing()
-		.exec(&state.database)
+		.exec(&txn)
 		.await?;
 	}
 
+	let mut after = model_json(&bundle);
+	after["cosmetic_ids"] = serde_json::json!(cosmetic_ids);
+	audit::record(
+		&txn,
+		&actor,
+		AuditEntry::new("bundle.create", "bundle", bundle.id).after(after),
+	)
+	.await?;
+	let changed = ChangedEntries {
+		bundle_ids: vec![bundle.id],
+		..Default::default()
+	}
+	.record(&txn)
+	.await?;
+	txn.commit().await?;
+	state.realtime.publish(changed).await;
+
 	Ok(Json(bundle.into()))
 }
@@ -5,10 +5,15 @@ use aide::{
 };
 use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
 use schemars::JsonSchema;
-use sea_orm::{ActiveModelTrait, EntityTrait, Set};
+use sea_orm::{ActiveModelTrait, EntityTrait, Set, TransactionTrait};
 use serde::Deserialize;
 
-use crate::api::{ApiState, admin_auth::AdminAuthenticationExtractor};
+use crate::api::{
+	ApiState,
+	admin_auth::{AdminAuthenticationExtractor, CatalogWrite},
+	audit::{self, AuditEntry, model_json},
+	cosmetics::ChangedEntries,
+};
 
 #[derive(thiserror::Error, Debug, OperationIo)]
 pub enum DeleteError {
@@ -42,7 +47,7 @@ fn endpoint_doc(op: TransformOperation) -> TransformOperation {
 		.summary("Delete a bundle")
 		.description(
 			"Soft-deletes a bundle by disabling it. Rows, assets, and Stripe products \
-			 are left intact so the change is reversible. Admin password required.",
+			 are left intact so the change is reversible. Requires the `catalog:write` scope.",
 		)
 		.tag("bundles")
 		.response_with::<{ StatusCode::NO_CONTENT.as_u16() }, (), _>(|res| {
@@ -52,7 +57,7 @@ fn endpoint_doc(op: TransformOperation) -> TransformOperation {
 			res.description("No bundle exists with the given id")
 		})
 		.response_with::<{ StatusCode::UNAUTHORIZED.as_u16() }, String, _>(|res| {
-			res.description("Invalid or missing admin password")
+			res.description("Invalid or missing API key")
 		})
 }
 
@@ -60,25 +65,41 @@ pub(super) fn router() -> ApiRouter<ApiState> {
 	ApiRouter::new().api_route("/delete", post_with(self::endpoint, self::endpoint_doc))
 }
 
-#[tracing::instrument(level = "debug", skip(state, _auth))]
+#[tracing::instrument(level = "debug", skip(state, actor))]
 async fn endpoint(
 	State(state): State<ApiState>,
-	_auth: AdminAuthenticationExtractor,
+	AdminAuthenticationExtractor(actor, ..): AdminAuthenticationExtractor<CatalogWrite>,
 	Json(body): Json<DeleteRequest>,
 ) -> Result<StatusCode, DeleteError> {
 	use entities::{bundles, prelude::*};
 
-	let Some(bundle) = Bundles::find_by_id(body.bundle_id)
-		.one(&state.database)
-		.await?
-	else {
+	let txn = state.database.begin().await?;
+	let Some(bundle) = Bundles::find_by_id(body.bundle_id).one(&txn).await? else {
 		return Err(DeleteError::MissingBundle);
 	};
 
 	if bundle.enabled {
+		let before = model_json(&bundle);
 		let mut active: bundles::ActiveModel = bundle.into();
 		active.enabled = Set(false);
-		active.update(&state.database).await?;
+		let bundle = active.update(&txn).await?;
+
+		audit::record(
+			&txn,
+			&actor,
+			AuditEntry::new("bundle.delete", "bundle", bundle.id)
+				.before(before)
+				.after(model_json(&bundle)),
+		)
+		.await?;
+		let changed = ChangedEntries {
+			bundle_ids: vec![bundle.id],
+			..Default::default()
+		}
+		.record(&txn)
+		.await?;
+		txn.commit().await?;
+		state.realtime.publish(changed).await;
 	}
 
 	Ok(StatusCode::NO_CONTENT)
@@ -10,7 +10,13 @@ use sea_orm::{
 };
 use serde::Deserialize;
 
-use crate::api::{ApiState, admin_auth::AdminAuthenticationExtractor, stripe::products};
+use crate::api::{
+	ApiState,
+	admin_auth::{AdminAuthenticationExtractor, CatalogWrite},
+	audit::{self, AuditEntry, model_json},
+	cosmetics::ChangedEntries,
+	stripe::products,
+};
 
 #[derive(thiserror::Error, Debug, OperationIo)]
 pub enum UpdateError {
@@ -89,7 +95,7 @@ fn endpoint_doc(op: TransformOperation) -> TransformOperation {
 			"Updates a bundle's metadata (enabled, name, collection, description), \
 			 optionally replaces its contained cosmetics, and drives its Stripe \
 			 pricing. A silent price increase creates a new default price; a discount \
-			 creates a non-default price and records the rate. Admin password required.",
+			 creates a non-default price and records the rate. Requires the `catalog:write` scope.",
 		)
 		.tag("bundles")
 		.response_with::<{ StatusCode::NO_CONTENT.as_u16() }, (), _>(|res| {
@@ -99,7 +105,7 @@ fn endpoint_doc(op: TransformOperation) -> TransformOperation {
 			res.description("No bundle exists with the given id")
 		})
 		.response_with::<{ StatusCode::UNAUTHORIZED.as_u16() }, String, _>(|res| {
-			res.description("Invalid or missing admin password")
+			res.description("Invalid or missing API key")
 		})
 }
 
@@ -107,10 +113,10 @@ pub(super) fn router() -> ApiRouter<ApiState> {
 	ApiRouter::new().api_route("/update", post_with(self::endpoint, self::endpoint_doc))
 }
 
-#[tracing::instrument(level = "debug", skip(state, _auth))]
+#[tracing::instrument(level = "debug", skip(state, actor))]
 async fn endpoint(
 	State(state): State<ApiState>,
-	_auth: AdminAuthenticationExtractor,
+	AdminAuthenticationExtractor(actor, ..): AdminAuthenticationExtractor<CatalogWrite>,
 	Json(body): Json<UpdateRequest>,
 ) -> Result<StatusCode, UpdateError> {
 	use entities::{bundles, bundles_cosmetics, prelude::*};
@@ -176,6 +182,8 @@ async fn endpoint(
 
 	let txn = state.database.begin().await?;
 
+	let mut before = model_json(&bundle);
+	let mut after = before.clone();
 	let mut active: bundles::ActiveModel = bundle.into();
 	let mut changed = false;
 
@@ -205,11 +213,18 @@ async fn endpoint(
 	}
 
 	if changed {
-		active.update(&txn).await?;
+		after = model_json(&active.update(&txn).await?);
 	}
 
 	// Replace the bundle's contents when a new set was provided.
 	if let Some(cosmetic_ids) = &body.cosmetic_ids {
+		let previous_cosmetic_ids = BundlesCosmetics::find()
+			.filter(bundles_cosmetics::Column::BundleId.eq(body.bundle_id))
+			.all(&txn)
+			.await?
+			.into_iter()
+			.map(|row| row.cosmetic_id)
+			.collect::<Vec<_>>();
 		BundlesCosmetics::delete_many()
 			.filter(bundles_cosmetics::Column::BundleId.eq(body.bundle_id))
 			.exec(&txn)
@@ -226,9 +241,27 @@ async fn endpoint(
 			.exec(&txn)
 			.await?;
 		}
+
+		before["cosmetic_ids"] = serde_json::json!(previous_cosmetic_ids);
+		after["cosmetic_ids"] = serde_json::json!(cosmetic_ids);
 	}
 
+	audit::record(
+		&txn,
+		&actor,
+		AuditEntry::new("bundle.update", "bundle", body.bundle_id)
+			.before(before)
+			.after(after),
+	)
+	.await?;
+	let changed = ChangedEntries {
+		bundle_ids: vec![body.bundle_id],
+		..Default::default()
+	}
+	.record(&txn)
+	.await?;
 	txn.commit().await?;
+	state.realtime.publish(changed).await;
 
 	Ok(StatusCode::NO_CONTENT)
 }
@@ -11,12 +11,13 @@ use axum::{
 };
 use chrono::{DateTime, FixedOffset};
 use schemars::JsonSchema;
-use sea_orm::{ActiveModelTrait, Set};
+use sea_orm::{ActiveModelTrait, Set, TransactionTrait};
 use serde::Serialize;
 
 use crate::api::{
 	ApiState,
-	admin_auth::AdminAuthenticationExtractor,
+	admin_auth::{AdminAuthenticationExtractor, CatalogWrite},
+	audit::{self, AuditEntry, model_json},
 	collections::{StoreAssetError, store_asset},
 };
 
@@ -64,7 +65,7 @@ fn endpoint_doc(op: TransformOperation) -> TransformOperation {
 		.summary("Create a collection")
 		.description(
 			"Creates a new collection. When a file is provided it is uploaded to S3 \
-			 and referenced as the collection's asset. Admin role required.",
+			 and referenced as the collection's asset. Requires the `catalog:write` scope.",
 		)
 		.tag("collections")
 }
@@ -134,7 +135,7 @@ impl OperationInput for FileUpload {
 
 async fn endpoint(
 	State(state): State<ApiState>,
-	_auth: AdminAuthenticationExtractor,
+	AdminAuthenticationExtractor(actor, ..): AdminAuthenticationExtractor<CatalogWrite>,
 	FileUpload(mut multipart): FileUpload,
 ) -> Result<(StatusCode, Json<CreateResponse>), CreateError> {
 	use entities::collections;
@@ -183,15 +184,25 @@ async fn endpoint(
 		None => None,
 	};
 
+	let txn = state.database.begin().await?;
 	let collection = collections::ActiveModel {
 		name: Set(name),
 		description: Set(description),
 		asset_id: Set(asset_id),
 		..Default::default()
 	}
-	.insert(&state.database)
+	.insert(&txn)
 	.await?;
 
+	audit::record(
+		&txn,
+		&actor,
+		AuditEntry::new("collection.create", "collection", collection.id)
+			.after(model_json(&collection)),
+	)
+	.await?;
+	txn.commit().await?;
+
 	Ok((
 		StatusCode::CREATED,
 		Json(CreateResponse {
@@ -8,9 +8,13 @@ use axum::{
 	http::StatusCode,
 	response::IntoResponse,
 };
-use sea_orm::EntityTrait;
+use sea_orm::{EntityTrait, TransactionTrait};
 
-use crate::api::{ApiState, admin_auth::AdminAuthenticationExtractor};
+use crate::api::{
+	ApiState,
+	admin_auth::{AdminAuthenticationExtractor, CatalogWrite},
+	audit::{self, AuditEntry, model_json},
+};
 
 #[derive(thiserror::Error, Debug, OperationIo)]
 pub enum DeleteError {
@@ -49,19 +53,29 @@ pub(super) fn router() -> ApiRouter<ApiState> {
 		.api_route("/delete/{id}", delete_with(self::endpoint, self::endpoint_doc))
 }
 
-#[tracing::instrument(level = "debug", skip(state, _auth))]
+#[tracing::instrument(level = "debug", skip(state, actor))]
 async fn endpoint(
 	State(state): State<ApiState>,
-	_auth: AdminAuthenticationExtractor,
+	AdminAuthenticationExtractor(actor, ..): AdminAuthenticationExtractor<CatalogWrite>,
 	Path(id): Path<i32>,
 ) -> Result<StatusCode, DeleteError> {
 	use entities::prelude::*;
 
-	let result = Collections::delete_by_id(id).exec(&state.database).await?;
+	let txn = state.database.begin().await?;
+	let collection = Collections::find_by_id(id)
+		.one(&txn)
+		.await?
+		.ok_or(DeleteError::NotFound)?;
+	Collections::delete_by_id(id).exec(&txn).await?;
 
-	if result.rows_affected == 0 {
-		return Err(DeleteError::NotFound);
-	}
+	audit::record(
+		&txn,
+		&actor,
+		AuditEntry::new("collection.delete", "collection", id)
+			.before(model_json(&collection)),
+	)
+	.await?;
+	txn.commit().await?;
 
 	Ok(StatusCode::NO_CONTENT)
 }
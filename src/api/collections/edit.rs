@@ -13,12 +13,13 @@ use axum::{
 };
 use chrono::{DateTime, FixedOffset};
 use schemars::JsonSchema;
-use sea_orm::{ActiveModelTrait, EntityTrait, Set};
+use sea_orm::{ActiveModelTrait, EntityTrait, Set, TransactionTrait};
 use serde::Serialize;
 
 use crate::api::{
 	ApiState,
-	admin_auth::AdminAuthenticationExtractor,
+	admin_auth::{AdminAuthenticationExtractor, CatalogWrite},
+	audit::{self, AuditEntry, model_json},
 	collections::{StoreAssetError, store_asset},
 };
 
@@ -68,7 +69,7 @@ fn endpoint_doc(op: TransformOperation) -> TransformOperation {
 		.description(
 			"Updates a collection's name, description, and/or asset. Only the fields \
 			 present in the request are changed. When a file is provided it is \
-			 uploaded to S3 and set as the collection's asset. Admin role required.",
+			 uploaded to S3 and set as the collection's asset. Requires the `catalog:write` scope.",
 		)
 		.tag("collections")
 }
@@ -139,7 +140,7 @@ impl OperationInput for FileUpload {
 
 async fn endpoint(
 	State(state): State<ApiState>,
-	_auth: AdminAuthenticationExtractor,
+	AdminAuthenticationExtractor(actor, ..): AdminAuthenticationExtractor<CatalogWrite>,
 	Path(id): Path<i32>,
 	FileUpload(mut multipart): FileUpload,
 ) -> Result<Json<EditResponse>, EditError> {
@@ -192,6 +193,7 @@ async fn endpoint(
 		None => None,
 	};
 
+	let before = model_json(&existing);
 	let mut active: collections::ActiveModel = existing.into();
 	if let Some(name) = name {
 		active.name = Set(name);
@@ -203,7 +205,17 @@ async fn endpoint(
 		active.asset_id = Set(Some(asset_id));
 	}
 
-	let collection = active.update(&state.database).await?;
+	let txn = state.database.begin().await?;
+	let collection = active.update(&txn).await?;
+	audit::record(
+		&txn,
+		&actor,
+		AuditEntry::new("collection.update", "collection", collection.id)
+			.before(before)
+			.after(model_json(&collection)),
+	)
+	.await?;
+	txn.commit().await?;
 
 	Ok(Json(EditResponse {
 		id: collection.id,
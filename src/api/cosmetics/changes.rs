@@ -0,0 +1,105 @@
+use std::collections::BTreeSet;
+
+use aide::{
+	OperationIo,
+	axum::{ApiRouter, routing::get_with},
+	transform::TransformOperation,
+};
+use axum::{
+	Json,
+	extract::{Query, State},
+	http::StatusCode,
+	response::IntoResponse,
+};
+use entities::{catalog_change, prelude::*};
+use schemars::JsonSchema;
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QueryOrder};
+use serde::{Deserialize, Serialize};
+
+use crate::api::ApiState;
+
+#[derive(thiserror::Error, Debug, OperationIo)]
+pub enum ChangesError {
+	#[error("Unable to query database: {0}")]
+	Database(#[from] sea_orm::error::DbErr),
+}
+
+impl IntoResponse for ChangesError {
+	fn into_response(self) -> axum::response::Response {
+		(
+			match self {
+				Self::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+			},
+			self.to_string(),
+		)
+			.into_response()
+	}
+}
+
+fn endpoint_doc(op: TransformOperation) -> TransformOperation {
+	op.id("listCatalogChanges")
+		.summary("List catalog changes since a version")
+		.description(
+			"Returns the current catalog version, and every cosmetic, cosmetic \
+			 group and bundle that changed after `since`. Clients can cache the \
+			 catalog along with its version, and re-fetch only these entries when \
+			 they start. While connected, the same changes are pushed to \
+			 websocket sessions with the `catalog_changes` capability.",
+		)
+		.tag("cosmetics")
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct ChangesQuery {
+	/// The catalog version the client's cache is from
+	#[serde(default)]
+	since: i64,
+}
+
+#[derive(Debug, Default, Serialize, JsonSchema)]
+struct ChangesResponse {
+	/// The current catalog version, 0 if the catalog never changed
+	catalog_version: i64,
+	cosmetic_ids: BTreeSet<i32>,
+	group_ids: BTreeSet<i32>,
+	bundle_ids: BTreeSet<i32>,
+}
+
+pub(super) fn router() -> ApiRouter<ApiState> {
+	ApiRouter::new().api_route("/changes", get_with(self::endpoint, self::endpoint_doc))
+}
+
+#[tracing::instrument(level = "debug", skip(state))]
+async fn endpoint(
+	State(state): State<ApiState>,
+	Query(query): Query<ChangesQuery>,
+) -> Result<Json<ChangesResponse>, ChangesError> {
+	let changes = CatalogChange::find()
+		.filter(catalog_change::Column::Version.gt(query.since))
+		.order_by_asc(catalog_change::Column::Version)
+		.all(&state.database)
+		.await?;
+
+	let mut response = ChangesResponse {
+		catalog_version: match changes.last() {
+			Some(change) => change.version,
+			None => CatalogChange::find()
+				.order_by_desc(catalog_change::Column::Version)
+				.one(&state.database)
+				.await?
+				.map_or(0, |change| change.version),
+		},
+		..Default::default()
+	};
+	for change in changes {
+		for (ids, changed) in [
+			(&mut response.cosmetic_ids, change.cosmetic_ids),
+			(&mut response.group_ids, change.group_ids),
+			(&mut response.bundle_ids, change.bundle_ids),
+		] {
+			ids.extend(serde_json::from_value::<Vec<i32>>(changed).unwrap_or_default());
+		}
+	}
+
+	Ok(Json(response))
+}
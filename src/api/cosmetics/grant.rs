@@ -11,10 +11,15 @@ use sea_orm::{
 	TransactionTrait,
 };
 use serde::Deserialize;
-use uuid::Uuid;
 
 use crate::{
-	api::{ApiState, account::AdminPlayer, websocket::structs::ClientBoundPacket},
+	api::{
+		ApiState,
+		admin_auth::{AdminAuthenticationExtractor, GrantsWrite},
+		audit::{self, AuditEntry},
+		players::profile::{PlayerRef, ProfileError},
+		realtime::RealtimeEvent,
+	},
 	database::DatabaseUserExt,
 };
 
@@ -22,6 +27,10 @@ use crate::{
 pub enum GrantError {
 	#[error("The requested cosmetic does not exist")]
 	MissingCosmetic,
+	#[error("The requested player does not exist")]
+	MissingPlayer,
+	#[error("Unable to resolve the player: {0}")]
+	Profile(#[from] ProfileError),
 	#[error("Unable to query database: {0}")]
 	Database(#[from] sea_orm::error::DbErr),
 }
@@ -30,8 +39,11 @@ impl IntoResponse for GrantError {
 	fn into_response(self) -> axum::response::Response {
 		(
 			match self {
-				Self::MissingCosmetic => StatusCode::NOT_FOUND,
-				Self::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+				Self::MissingCosmetic | Self::MissingPlayer => StatusCode::NOT_FOUND,
+				Self::Profile(ProfileError::Request(_)) => StatusCode::BAD_GATEWAY,
+				Self::Profile(ProfileError::Database(_)) | Self::Database(_) => {
+					StatusCode::INTERNAL_SERVER_ERROR
+				}
 			},
 			self.to_string(),
 		)
@@ -42,13 +54,17 @@ impl IntoResponse for GrantError {
 fn endpoint_doc(op: TransformOperation) -> TransformOperation {
 	op.id("grantCosmetic")
 		.summary("Grant a cosmetic to a player")
-		.description("Grants cosmetic ownership to a player. Admin role required.")
+		.description(
+			"Grants cosmetic ownership to a player, given by UUID or by current \
+			 username. Requires the `grants:write` scope.",
+		)
 		.tag("cosmetics")
 }
 
 #[derive(Debug, Deserialize, JsonSchema)]
 struct GrantRequest {
-	player: Uuid,
+	/// The player's UUID or current username
+	player: PlayerRef,
 	cosmetic_id: i32,
 }
 
@@ -56,14 +72,19 @@ pub(super) fn router() -> ApiRouter<ApiState> {
 	ApiRouter::new().api_route("/grant", post_with(self::endpoint, self::endpoint_doc))
 }
 
-#[tracing::instrument(level = "debug", skip(state))]
+#[tracing::instrument(level = "debug", skip(state, actor))]
 async fn endpoint(
 	State(state): State<ApiState>,
-	AdminPlayer(_admin): AdminPlayer,
+	AdminAuthenticationExtractor(actor, ..): AdminAuthenticationExtractor<GrantsWrite>,
 	Json(body): Json<GrantRequest>,
 ) -> Result<StatusCode, GrantError> {
 	use entities::{cosmetic, player_owned_cosmetic, prelude::*, transaction};
 
+	let Some(player_uuid) = state.profiles.resolve(&state.database, &body.player).await?
+	else {
+		return Err(GrantError::MissingPlayer);
+	};
+
 	let txn = state.database.begin().await?;
 	let Some(cosmetic) = Cosmetic::find_by_id(body.cosmetic_id).one(&txn).await? else {
 		return Err(GrantError::MissingCosmetic);
@@ -83,7 +104,7 @@ async fn endpoint(
 		None => vec![cosmetic.id],
 	};
 
-	let player = User::get_or_create(&txn, body.player).await?;
+	let player = User::get_or_create(&txn, player_uuid).await?;
 	let transaction = transaction::ActiveModel {
 		player_id: Set(player.id),
 		provider: Set(TransactionProvider::AdminGrant),
@@ -107,29 +128,23 @@ async fn endpoint(
 	.on_conflict_do_nothing()
 	.exec(&txn)
 	.await?;
+	audit::record(
+		&txn,
+		&actor,
+		AuditEntry::new("cosmetic.grant", "player", player_uuid).after(
+			serde_json::json!({
+				"cosmetic_ids": cosmetic_ids,
+				"transaction_id": transaction.id,
+			}),
+		),
+	)
+	.await?;
 	txn.commit().await?;
 
-	let connection_ids = state
-		.realtime
-		.connections_by_owner
-		.read()
-		.await
-		.get(&body.player)
-		.cloned()
-		.unwrap_or_default();
-	if !connection_ids.is_empty() {
-		let connections = state.realtime.connections.read().await;
-		for connection_id in connection_ids {
-			let Some(connection) = connections.get(&connection_id) else {
-				continue;
-			};
-			let _ = connection.tx.send(ClientBoundPacket::OwnershipUpdated {
-				player: body.player,
-				cosmetic_ids: cosmetic_ids.clone(),
-				emote_ids: Vec::new(),
-				revoked: false,
-			});
-		}
+	let events =
+		RealtimeEvent::ownership_updated(player_uuid, cosmetic_ids, Vec::new(), false);
+	for event in events {
+		state.realtime.publish(event).await;
 	}
 
 	Ok(StatusCode::NO_CONTENT)
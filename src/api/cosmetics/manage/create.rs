@@ -13,14 +13,17 @@ use std::collections::HashMap;
 
 use entities::sea_orm_active_enums::{AssetKind, BodySlot, CosmeticType};
 use schemars::JsonSchema;
-use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set};
+use sea_orm::{
+	ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set, TransactionTrait,
+};
 use sha2::{Digest, Sha256};
 use uuid::Uuid;
 
 use crate::api::{
 	ApiState,
-	admin_auth::AdminAuthenticationExtractor,
-	cosmetics::{CosmeticInfo, group_cosmetics},
+	admin_auth::{AdminAuthenticationExtractor, CatalogWrite},
+	audit::{self, AuditEntry, model_json},
+	cosmetics::{ChangedEntries, CosmeticInfo, group_cosmetics},
 	stripe::products,
 };
 
@@ -148,7 +151,7 @@ fn endpoint_doc(op: TransformOperation) -> TransformOperation {
 			res.description("The created cosmetic info")
 		})
 		.response_with::<{ StatusCode::UNAUTHORIZED.as_u16() }, String, _>(|res| {
-			res.description("Invalid or missing admin password")
+			res.description("Invalid or missing API key")
 		})
 }
 
@@ -245,7 +248,7 @@ fn sha256_hex(data: &[u8]) -> String {
 
 async fn endpoint(
 	State(state): State<ApiState>,
-	_auth: AdminAuthenticationExtractor,
+	AdminAuthenticationExtractor(actor, ..): AdminAuthenticationExtractor<CatalogWrite>,
 	FileUpload(mut multipart): FileUpload,
 ) -> Result<Json<CosmeticInfo>, UploadError> {
 	let mut file_data = None;
@@ -523,6 +526,7 @@ async fn endpoint(
 		}
 	};
 
+	let txn = state.database.begin().await?;
 	let model = cosmetic::ActiveModel {
 		asset_id: Set(Some(asset.id)),
 		cover_asset_id: Set(cover_asset_id),
@@ -541,7 +545,7 @@ async fn endpoint(
 		description: Set(description),
 		..Default::default()
 	}
-	.insert(&state.database)
+	.insert(&txn)
 	.await?;
 
 	if !slots.is_empty() {
@@ -551,10 +555,27 @@ async fn endpoint(
 				slot: Set(slot.clone()),
 			}
 		}))
-		.exec(&state.database)
+		.exec(&txn)
 		.await?;
 	}
 
+	audit::record(
+		&txn,
+		&actor,
+		AuditEntry::new("cosmetic.create", "cosmetic", model.id)
+			.after(model_json(&model)),
+	)
+	.await?;
+	let changed = ChangedEntries {
+		cosmetic_ids: vec![model.id],
+		group_ids: group.iter().map(|group| group.id).collect(),
+		..Default::default()
+	}
+	.record(&txn)
+	.await?;
+	txn.commit().await?;
+	state.realtime.publish(changed).await;
+
 	let info = crate::api::cosmetics::CachedAssetInfo::from_db_model(
 		&asset,
 		state.s3_bucket.clone(),
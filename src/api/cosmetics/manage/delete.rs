@@ -5,10 +5,15 @@ use aide::{
 };
 use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
 use schemars::JsonSchema;
-use sea_orm::{ActiveModelTrait, EntityTrait, Set};
+use sea_orm::{ActiveModelTrait, EntityTrait, Set, TransactionTrait};
 use serde::Deserialize;
 
-use crate::api::{ApiState, admin_auth::AdminAuthenticationExtractor};
+use crate::api::{
+	ApiState,
+	admin_auth::{AdminAuthenticationExtractor, CatalogWrite},
+	audit::{self, AuditEntry, model_json},
+	cosmetics::ChangedEntries,
+};
 
 #[derive(thiserror::Error, Debug, OperationIo)]
 pub enum DeleteError {
@@ -43,7 +48,7 @@ fn endpoint_doc(op: TransformOperation) -> TransformOperation {
 		.description(
 			"Soft-deletes a cosmetic by disabling it. For a grouped cosmetic the \
 			 whole group is disabled. Rows, assets, and Stripe products are left \
-			 intact so the change is reversible. Admin password required.",
+			 intact so the change is reversible. Requires the `catalog:write` scope.",
 		)
 		.tag("cosmetics")
 		.response_with::<{ StatusCode::NO_CONTENT.as_u16() }, (), _>(|res| {
@@ -53,7 +58,7 @@ fn endpoint_doc(op: TransformOperation) -> TransformOperation {
 			res.description("No cosmetic exists with the given id")
 		})
 		.response_with::<{ StatusCode::UNAUTHORIZED.as_u16() }, String, _>(|res| {
-			res.description("Invalid or missing admin password")
+			res.description("Invalid or missing API key")
 		})
 }
 
@@ -61,40 +66,56 @@ pub(super) fn router() -> ApiRouter<ApiState> {
 	ApiRouter::new().api_route("/delete", post_with(self::endpoint, self::endpoint_doc))
 }
 
-#[tracing::instrument(level = "debug", skip(state, _auth))]
+#[tracing::instrument(level = "debug", skip(state, actor))]
 async fn endpoint(
 	State(state): State<ApiState>,
-	_auth: AdminAuthenticationExtractor,
+	AdminAuthenticationExtractor(actor, ..): AdminAuthenticationExtractor<CatalogWrite>,
 	Json(body): Json<DeleteRequest>,
 ) -> Result<StatusCode, DeleteError> {
 	use entities::{cosmetic_group, prelude::*};
 
-	let Some(cosmetic) = Cosmetic::find_by_id(body.cosmetic_id)
-		.one(&state.database)
-		.await?
-	else {
+	let txn = state.database.begin().await?;
+	let Some(cosmetic) = Cosmetic::find_by_id(body.cosmetic_id).one(&txn).await? else {
 		return Err(DeleteError::MissingCosmetic);
 	};
 
 	// Grouped cosmetics disable at the group level; ungrouped ones on the row.
-	match cosmetic.group_id {
-		Some(group_id) => {
-			if let Some(group) = CosmeticGroup::find_by_id(group_id)
-				.one(&state.database)
-				.await?
-			{
+	let entry = AuditEntry::new("cosmetic.delete", "cosmetic", body.cosmetic_id);
+	let group_id = cosmetic.group_id;
+	let entry = match group_id {
+		Some(group_id) => match CosmeticGroup::find_by_id(group_id).one(&txn).await? {
+			Some(group) => {
+				let before = model_json(&group);
 				let mut active: cosmetic_group::ActiveModel = group.into();
 				active.enabled = Set(false);
-				active.update(&state.database).await?;
+				let group = active.update(&txn).await?;
+				Some(entry.before(before).after(model_json(&group)))
 			}
-		}
+			None => None,
+		},
 		None => {
 			if cosmetic.enabled {
+				let before = model_json(&cosmetic);
 				let mut active: entities::cosmetic::ActiveModel = cosmetic.into();
 				active.enabled = Set(false);
-				active.update(&state.database).await?;
+				let cosmetic = active.update(&txn).await?;
+				Some(entry.before(before).after(model_json(&cosmetic)))
+			} else {
+				None
 			}
 		}
+	};
+	if let Some(entry) = entry {
+		audit::record(&state.database, &actor, entry).await?;
+		let changed = ChangedEntries {
+			cosmetic_ids: vec![body.cosmetic_id],
+			group_ids: group_id.into_iter().collect(),
+			..Default::default()
+		}
+		.record(&txn)
+		.await?;
+		txn.commit().await?;
+		state.realtime.publish(changed).await;
 	}
 
 	Ok(StatusCode::NO_CONTENT)
@@ -6,10 +6,17 @@ use aide::{
 use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
 use entities::sea_orm_active_enums::{AssetKind, BodySlot};
 use schemars::JsonSchema;
-use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set};
+use sea_orm::{
+	ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set, TransactionTrait,
+};
 use serde::{Deserialize, Serialize};
 
-use crate::api::{ApiState, admin_auth::AdminAuthenticationExtractor};
+use crate::api::{
+	ApiState,
+	admin_auth::{AdminAuthenticationExtractor, CatalogWrite},
+	audit::{self, AuditEntry, model_json},
+	cosmetics::ChangedEntries,
+};
 
 #[derive(thiserror::Error, Debug, OperationIo)]
 pub enum RenderCoverError {
@@ -69,7 +76,7 @@ fn endpoint_doc(op: TransformOperation) -> TransformOperation {
 			"Generates a cover image for an existing cosmetic by re-rendering its \
 			 source asset through the render service, stores it, and points the \
 			 cosmetic at the new cover. Skips cosmetics that already have a cover \
-			 unless `force` is set. Admin password required.",
+			 unless `force` is set. Requires the `catalog:write` scope.",
 		)
 		.tag("cosmetics")
 		.response_with::<{ StatusCode::OK.as_u16() }, Json<RenderCoverResponse>, _>(
@@ -79,7 +86,7 @@ fn endpoint_doc(op: TransformOperation) -> TransformOperation {
 			res.description("No cosmetic exists with the given id")
 		})
 		.response_with::<{ StatusCode::UNAUTHORIZED.as_u16() }, String, _>(|res| {
-			res.description("Invalid or missing admin password")
+			res.description("Invalid or missing API key")
 		})
 }
 
@@ -90,10 +97,10 @@ pub(super) fn router() -> ApiRouter<ApiState> {
 	)
 }
 
-#[tracing::instrument(level = "debug", skip(state, _auth))]
+#[tracing::instrument(level = "debug", skip(state, actor))]
 async fn endpoint(
 	State(state): State<ApiState>,
-	_auth: AdminAuthenticationExtractor,
+	AdminAuthenticationExtractor(actor, ..): AdminAuthenticationExtractor<CatalogWrite>,
 	Json(body): Json<RenderCoverRequest>,
 ) -> Result<Json<RenderCoverResponse>, RenderCoverError> {
 	use entities::{
@@ -169,9 +176,27 @@ async fn endpoint(
 	.await
 	.map_err(|error| RenderCoverError::Store(error.to_string()))?;
 
+	let before = model_json(&cosmetic);
 	let mut active: cosmetic::ActiveModel = cosmetic.into();
 	active.cover_asset_id = Set(Some(cover_asset_id));
-	let updated = active.update(&state.database).await?;
+	let txn = state.database.begin().await?;
+	let updated = active.update(&txn).await?;
+	audit::record(
+		&txn,
+		&actor,
+		AuditEntry::new("cosmetic.render_cover", "cosmetic", updated.id)
+			.before(before)
+			.after(model_json(&updated)),
+	)
+	.await?;
+	let changed = ChangedEntries {
+		cosmetic_ids: vec![updated.id],
+		..Default::default()
+	}
+	.record(&txn)
+	.await?;
+	txn.commit().await?;
+	state.realtime.publish(changed).await;
 
 	Ok(Json(RenderCoverResponse {
 		cosmetic_id: updated.id,
@@ -10,7 +10,13 @@ use sea_orm::{
 };
 use serde::Deserialize;
 
-use crate::api::{ApiState, admin_auth::AdminAuthenticationExtractor, stripe::products};
+use crate::api::{
+	ApiState,
+	admin_auth::{AdminAuthenticationExtractor, CatalogWrite},
+	audit::{self, AuditEntry, model_json},
+	cosmetics::ChangedEntries,
+	stripe::products,
+};
 
 #[derive(thiserror::Error, Debug, OperationIo)]
 pub enum UpdateError {
@@ -91,8 +97,8 @@ fn endpoint_doc(op: TransformOperation) -> TransformOperation {
 			 when the cosmetic was uploaded without a price; a discount creates a \
 			 non-default price and records the rate, and requires an already \
 			 priced cosmetic. For a grouped cosmetic, name/enabled apply to the \
-			 group and price changes propagate to every variant. Admin password \
-			 required.",
+			 group and price changes propagate to every variant. Requires the \
+			 `catalog:write` scope.",
 		)
 		.tag("cosmetics")
 		.response_with::<{ StatusCode::NO_CONTENT.as_u16() }, (), _>(|res| {
@@ -102,7 +108,7 @@ fn endpoint_doc(op: TransformOperation) -> TransformOperation {
 			res.description("No cosmetic exists with the given id")
 		})
 		.response_with::<{ StatusCode::UNAUTHORIZED.as_u16() }, String, _>(|res| {
-			res.description("Invalid or missing admin password")
+			res.description("Invalid or missing API key")
 		})
 }
 
@@ -110,10 +116,10 @@ pub(super) fn router() -> ApiRouter<ApiState> {
 	ApiRouter::new().api_route("/update", post_with(self::endpoint, self::endpoint_doc))
 }
 
-#[tracing::instrument(level = "debug", skip(state, _auth))]
+#[tracing::instrument(level = "debug", skip(state, actor))]
 async fn endpoint(
 	State(state): State<ApiState>,
-	_auth: AdminAuthenticationExtractor,
+	AdminAuthenticationExtractor(actor, ..): AdminAuthenticationExtractor<CatalogWrite>,
 	Json(body): Json<UpdateRequest>,
 ) -> Result<StatusCode, UpdateError> {
 	use entities::{cosmetic, cosmetic_group, prelude::*};
@@ -228,6 +234,7 @@ async fn endpoint(
 		&& (body.name.is_some() || body.enabled.is_some())
 		&& let Some(group) = CosmeticGroup::find_by_id(group_id).one(&txn).await?
 	{
+		let before = model_json(&group);
 		let mut active: cosmetic_group::ActiveModel = group.into();
 		if let Some(name) = &body.name {
 			active.name = Set(name.clone());
@@ -235,7 +242,15 @@ async fn endpoint(
 		if let Some(enabled) = body.enabled {
 			active.enabled = Set(enabled);
 		}
-		active.update(&txn).await?;
+		let group = active.update(&txn).await?;
+		audit::record(
+			&txn,
+			&actor,
+			AuditEntry::new("cosmetic_group.update", "cosmetic_group", group.id)
+				.before(before)
+				.after(model_json(&group)),
+		)
+		.await?;
 	}
 
 	// Apply collection/description/price to every affected row, plus name/enabled
@@ -249,6 +264,7 @@ async fn endpoint(
 		}
 		None => vec![cosmetic.clone()],
 	};
+	let cosmetic_ids = rows.iter().map(|row| row.id).collect();
 
 	for row in rows {
 		let is_grouped = row.group_id.is_some();
@@ -288,7 +304,26 @@ async fn endpoint(
 		}
 	}
 
+	if let Some(updated) = Cosmetic::find_by_id(cosmetic.id).one(&txn).await? {
+		audit::record(
+			&txn,
+			&actor,
+			AuditEntry::new("cosmetic.update", "cosmetic", cosmetic.id)
+				.before(model_json(&cosmetic))
+				.after(model_json(&updated)),
+		)
+		.await?;
+	}
+
+	let changed = ChangedEntries {
+		cosmetic_ids,
+		group_ids: cosmetic.group_id.into_iter().collect(),
+		..Default::default()
+	}
+	.record(&txn)
+	.await?;
 	txn.commit().await?;
+	state.realtime.publish(changed).await;
 
 	Ok(StatusCode::NO_CONTENT)
 }
@@ -1,3 +1,4 @@
+mod changes;
 mod cover;
 mod get_player;
 mod grant;
@@ -15,15 +16,16 @@ use std::{
 
 use aide::axum::ApiRouter;
 use entities::{
-	asset, cosmetic, cosmetic_group,
+	asset, catalog_change, cosmetic, cosmetic_group,
 	sea_orm_active_enums::{BodySlot, CosmeticType},
 };
 use moka::future::Cache;
 use s3::{Bucket, error::S3Error};
 use schemars::JsonSchema;
+use sea_orm::{ActiveModelTrait, ConnectionTrait, DbErr, Set};
 use serde::{Deserialize, Serialize};
 
-use crate::api::ApiState;
+use crate::api::{ApiState, realtime::RealtimeEvent};
 
 pub(super) fn is_zip(data: &[u8]) -> bool {
 	data.len() >= 4 && &data[0..4] == b"PK\x03\x04"
@@ -375,11 +377,43 @@ pub(super) struct PartialEquippedCosmetics {
 	pub equipped: HashMap<BodySlot, Option<i32>>,
 }
 
+/// Catalog entries that were created, changed or disabled, so that clients can
+/// re-fetch just those
+#[derive(Debug, Default)]
+pub(super) struct ChangedEntries {
+	pub(super) cosmetic_ids: Vec<i32>,
+	pub(super) group_ids: Vec<i32>,
+	pub(super) bundle_ids: Vec<i32>,
+}
+
+impl ChangedEntries {
+	/// Records the change under a new catalog version, returning the event that
+	/// tells clients about it. Publish it once the change is committed.
+	pub(super) async fn record(
+		self,
+		db: &impl ConnectionTrait,
+	) -> Result<RealtimeEvent, DbErr> {
+		let change = catalog_change::ActiveModel {
+			cosmetic_ids: Set(serde_json::json!(self.cosmetic_ids)),
+			group_ids: Set(serde_json::json!(self.group_ids)),
+			bundle_ids: Set(serde_json::json!(self.bundle_ids)),
+			..Default::default()
+		}
+		.insert(db)
+		.await?;
+
+		Ok(RealtimeEvent::CatalogChanged {
+			catalog_version: change.version,
+		})
+	}
+}
+
 pub(super) async fn setup_router() -> ApiRouter<ApiState> {
 	ApiRouter::new()
 		.nest(
 			"/cosmetics",
 			ApiRouter::new()
+				.merge(changes::router())
 				.merge(get_player::router())
 				.merge(put_player::router())
 				.merge(manage::router())
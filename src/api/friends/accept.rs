@@ -0,0 +1,98 @@
+use aide::{
+	OperationIo,
+	axum::{ApiRouter, routing::post_with},
+	transform::TransformOperation,
+};
+use axum::{
+	extract::{Path, State},
+	http::StatusCode,
+	response::IntoResponse,
+};
+use entities::{prelude::*, user};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, TransactionTrait};
+use uuid::Uuid;
+
+use crate::api::{
+	ApiState,
+	account::AuthenticatedPlayer,
+	friends::{MAX_FRIENDS, befriend},
+	realtime::RealtimeEvent,
+};
+
+#[derive(thiserror::Error, Debug, OperationIo)]
+pub enum AcceptFriendRequestError {
+	#[error("The player has not sent a friend request")]
+	NotRequested,
+	#[error("One of the players already has the maximum of {0} friends")]
+	TooManyFriends(u64),
+	#[error("Unable to query database: {0}")]
+	Database(#[from] sea_orm::error::DbErr),
+}
+
+impl IntoResponse for AcceptFriendRequestError {
+	fn into_response(self) -> axum::response::Response {
+		(
+			match self {
+				Self::NotRequested => StatusCode::NOT_FOUND,
+				Self::TooManyFriends(_) => StatusCode::CONFLICT,
+				Self::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+			},
+			self.to_string(),
+		)
+			.into_response()
+	}
+}
+
+fn endpoint_doc(op: TransformOperation) -> TransformOperation {
+	op.id("acceptFriendRequest")
+		.summary("Accept a friend request")
+		.description(
+			"Accepts the friend request a player sent the authenticated player, \
+			 making them friends. Both players are told in game.",
+		)
+		.tag("friends")
+}
+
+pub(super) fn router() -> ApiRouter<ApiState> {
+	ApiRouter::new().api_route(
+		"/requests/{player}/accept",
+		post_with(self::endpoint, self::endpoint_doc),
+	)
+}
+
+#[tracing::instrument(level = "debug", skip(state))]
+async fn endpoint(
+	State(state): State<ApiState>,
+	AuthenticatedPlayer(player): AuthenticatedPlayer,
+	Path(sender): Path<Uuid>,
+) -> Result<StatusCode, AcceptFriendRequestError> {
+	let txn = state.database.begin().await?;
+	let Some(sender) = User::find()
+		.filter(user::Column::MinecraftUuid.eq(sender))
+		.one(&txn)
+		.await?
+	else {
+		return Err(AcceptFriendRequestError::NotRequested);
+	};
+	let request = FriendRequest::find_by_id((sender.id, player.id))
+		.one(&txn)
+		.await?;
+	if request.is_none() {
+		return Err(AcceptFriendRequestError::NotRequested);
+	}
+
+	if !befriend(&txn, player.id, sender.id).await? {
+		return Err(AcceptFriendRequestError::TooManyFriends(MAX_FRIENDS));
+	}
+	txn.commit().await?;
+
+	state
+		.realtime
+		.publish(RealtimeEvent::FriendshipChanged {
+			players: [player.minecraft_uuid, sender.minecraft_uuid],
+			friends: true,
+		})
+		.await;
+
+	Ok(StatusCode::NO_CONTENT)
+}
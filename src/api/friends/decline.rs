@@ -0,0 +1,91 @@
+use aide::{
+	OperationIo,
+	axum::{ApiRouter, routing::delete_with},
+	transform::TransformOperation,
+};
+use axum::{
+	extract::{Path, State},
+	http::StatusCode,
+	response::IntoResponse,
+};
+use entities::{friend_request, prelude::*, user};
+use sea_orm::{ColumnTrait, Condition, EntityTrait, QueryFilter};
+use uuid::Uuid;
+
+use crate::api::{ApiState, account::AuthenticatedPlayer};
+
+#[derive(thiserror::Error, Debug, OperationIo)]
+pub enum DeclineFriendRequestError {
+	#[error("No friend request is pending between the players")]
+	NotRequested,
+	#[error("Unable to query database: {0}")]
+	Database(#[from] sea_orm::error::DbErr),
+}
+
+impl IntoResponse for DeclineFriendRequestError {
+	fn into_response(self) -> axum::response::Response {
+		(
+			match self {
+				Self::NotRequested => StatusCode::NOT_FOUND,
+				Self::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+			},
+			self.to_string(),
+		)
+			.into_response()
+	}
+}
+
+fn endpoint_doc(op: TransformOperation) -> TransformOperation {
+	op.id("declineFriendRequest")
+		.summary("Decline or cancel a friend request")
+		.description(
+			"Declines the friend request a player sent the authenticated player, or \
+			 cancels the one the authenticated player sent them. The other player \
+			 is not told.",
+		)
+		.tag("friends")
+}
+
+pub(super) fn router() -> ApiRouter<ApiState> {
+	ApiRouter::new().api_route(
+		"/requests/{player}",
+		delete_with(self::endpoint, self::endpoint_doc),
+	)
+}
+
+#[tracing::instrument(level = "debug", skip(state))]
+async fn endpoint(
+	State(state): State<ApiState>,
+	AuthenticatedPlayer(player): AuthenticatedPlayer,
+	Path(other): Path<Uuid>,
+) -> Result<StatusCode, DeclineFriendRequestError> {
+	let Some(other) = User::find()
+		.filter(user::Column::MinecraftUuid.eq(other))
+		.one(&state.database)
+		.await?
+	else {
+		return Err(DeclineFriendRequestError::NotRequested);
+	};
+
+	let result = FriendRequest::delete_many()
+		.filter(
+			Condition::any()
+				.add(
+					Condition::all()
+						.add(friend_request::Column::SenderId.eq(other.id))
+						.add(friend_request::Column::RecipientId.eq(player.id)),
+				)
+				.add(
+					Condition::all()
+						.add(friend_request::Column::SenderId.eq(player.id))
+						.add(friend_request::Column::RecipientId.eq(other.id)),
+				),
+		)
+		.exec(&state.database)
+		.await?;
+	if result.rows_affected == 0 {
+		return Err(DeclineFriendRequestError::NotRequested);
+	}
+
+	Ok(StatusCode::NO_CONTENT)
+}
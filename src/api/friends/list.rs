@@ -0,0 +1,96 @@
+use aide::{
+	OperationIo,
+	axum::{ApiRouter, routing::get_with},
+	transform::TransformOperation,
+};
+use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
+use schemars::JsonSchema;
+use sea_orm::prelude::DateTimeWithTimeZone;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::api::{ApiState, account::AuthenticatedPlayer, friends::load_friends};
+
+#[derive(thiserror::Error, Debug, OperationIo)]
+pub enum ListFriendsError {
+	#[error("Unable to query database: {0}")]
+	Database(#[from] sea_orm::error::DbErr),
+}
+
+impl IntoResponse for ListFriendsError {
+	fn into_response(self) -> axum::response::Response {
+		(
+			match self {
+				Self::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+			},
+			self.to_string(),
+		)
+			.into_response()
+	}
+}
+
+fn endpoint_doc(op: TransformOperation) -> TransformOperation {
+	op.id("listFriends")
+		.summary("List friends and friend requests")
+		.description(
+			"Returns the authenticated player's friends, whether each of them is \
+			 online, and the friend requests waiting on or sent by the player.",
+		)
+		.tag("friends")
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+struct FriendInfo {
+	player: Uuid,
+	/// Whether the friend has a live PolyPlus session connected
+	online: bool,
+	since: DateTimeWithTimeZone,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+struct FriendRequestInfo {
+	player: Uuid,
+	sent_at: DateTimeWithTimeZone,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+struct ListFriendsResponse {
+	friends: Vec<FriendInfo>,
+	/// Requests other players sent the authenticated player
+	incoming_requests: Vec<FriendRequestInfo>,
+	/// Requests the authenticated player sent others
+	outgoing_requests: Vec<FriendRequestInfo>,
+}
+
+pub(super) fn router() -> ApiRouter<ApiState> {
+	ApiRouter::new().api_route("/", get_with(self::endpoint, self::endpoint_doc))
+}
+
+#[tracing::instrument(level = "debug", skip(state))]
+async fn endpoint(
+	State(state): State<ApiState>,
+	AuthenticatedPlayer(player): AuthenticatedPlayer,
+) -> Result<Json<ListFriendsResponse>, ListFriendsError> {
+	let list = load_friends(&state.database, player.id).await?;
+	let online = state.realtime.online.read().await;
+
+	let requests = |requests: Vec<(Uuid, DateTimeWithTimeZone)>| {
+		requests
+			.into_iter()
+			.map(|(player, sent_at)| FriendRequestInfo { player, sent_at })
+			.collect()
+	};
+	Ok(Json(ListFriendsResponse {
+		friends: list
+			.friends
+			.into_iter()
+			.map(|(player, since)| FriendInfo {
+				player,
+				online: online.contains_key(&player),
+				since,
+			})
+			.collect(),
+		incoming_requests: requests(list.incoming),
+		outgoing_requests: requests(list.outgoing),
+	}))
+}
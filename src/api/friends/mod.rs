@@ -0,0 +1,260 @@
+//! Friends. Players send each other friend requests, and once one is accepted
+//! they are told whenever the other comes online or goes offline, wherever
+//! they are. Every instance keeps the friends of the players connected to it
+//! in a [FriendIndex], so that it can tell them.
+
+mod accept;
+mod decline;
+mod list;
+mod remove;
+mod send_request;
+
+use std::collections::{HashMap, HashSet};
+
+use aide::axum::ApiRouter;
+use entities::{friend_request, friendship, prelude::*, user};
+use sea_orm::{
+	ColumnTrait, Condition, ConnectionTrait, DbErr, EntityTrait, PaginatorTrait,
+	QueryFilter, Set, prelude::DateTimeWithTimeZone,
+};
+use uuid::Uuid;
+
+use crate::api::ApiState;
+
+/// Max friends a player may have
+const MAX_FRIENDS: u64 = 200;
+/// Max friend requests a player may have waiting on others at once
+const MAX_OUTGOING_REQUESTS: u64 = 50;
+
+/// A player's friends and pending friend requests, by Minecraft UUID, along
+/// with when each friendship or request was made
+#[derive(Debug, Default)]
+pub(crate) struct FriendsList {
+	pub(crate) friends: Vec<(Uuid, DateTimeWithTimeZone)>,
+	/// Requests other players sent this player
+	pub(crate) incoming: Vec<(Uuid, DateTimeWithTimeZone)>,
+	/// Requests this player sent others
+	pub(crate) outgoing: Vec<(Uuid, DateTimeWithTimeZone)>,
+}
+
+pub(crate) async fn load_friends(
+	db: &impl ConnectionTrait,
+	player_id: i32,
+) -> Result<FriendsList, DbErr> {
+	let friendships = Friendship::find()
+		.filter(friendship::Column::PlayerId.eq(player_id))
+		.all(db)
+		.await?;
+	let requests = FriendRequest::find()
+		.filter(
+			Condition::any()
+				.add(friend_request::Column::SenderId.eq(player_id))
+				.add(friend_request::Column::RecipientId.eq(player_id)),
+		)
+		.all(db)
+		.await?;
+
+	let ids = friendships
+		.iter()
+		.map(|friendship| friendship.friend_id)
+		.chain(
+			requests
+				.iter()
+				.flat_map(|request| [request.sender_id, request.recipient_id]),
+		)
+		.collect::<HashSet<_>>();
+	let uuids = User::find()
+		.filter(user::Column::Id.is_in(ids))
+		.all(db)
+		.await?
+		.into_iter()
+		.map(|user| (user.id, user.minecraft_uuid))
+		.collect::<HashMap<_, _>>();
+
+	let mut list = FriendsList {
+		friends: friendships
+			.into_iter()
+			.filter_map(|friendship| {
+				Some((*uuids.get(&friendship.friend_id)?, friendship.created_at))
+			})
+			.collect(),
+		..Default::default()
+	};
+	for request in requests {
+		if request.recipient_id == player_id {
+			if let Some(sender) = uuids.get(&request.sender_id) {
+				list.incoming.push((*sender, request.created_at));
+			}
+		} else if let Some(recipient) = uuids.get(&request.recipient_id) {
+			list.outgoing.push((*recipient, request.created_at));
+		}
+	}
+
+	Ok(list)
+}
+
+async fn are_friends(
+	db: &impl ConnectionTrait,
+	player_id: i32,
+	friend_id: i32,
+) -> Result<bool, DbErr> {
+	Ok(Friendship::find_by_id((player_id, friend_id))
+		.one(db)
+		.await?
+		.is_some())
+}
+
+async fn friend_count(db: &impl ConnectionTrait, player_id: i32) -> Result<u64, DbErr> {
+	Friendship::find()
+		.filter(friendship::Column::PlayerId.eq(player_id))
+		.count(db)
+		.await
+}
+
+/// Makes two players friends, dropping any requests between them. Returns
+/// whether either of them has reached [MAX_FRIENDS] instead.
+async fn befriend(
+	db: &impl ConnectionTrait,
+	player_id: i32,
+	friend_id: i32,
+) -> Result<bool, DbErr> {
+	if friend_count(db, player_id).await? >= MAX_FRIENDS
+		|| friend_count(db, friend_id).await? >= MAX_FRIENDS
+	{
+		return Ok(false);
+	}
+
+	FriendRequest::delete_many()
+		.filter(
+			Condition::any()
+				.add(
+					Condition::all()
+						.add(friend_request::Column::SenderId.eq(player_id))
+						.add(friend_request::Column::RecipientId.eq(friend_id)),
+				)
+				.add(
+					Condition::all()
+						.add(friend_request::Column::SenderId.eq(friend_id))
+						.add(friend_request::Column::RecipientId.eq(player_id)),
+				),
+		)
+		.exec(db)
+		.await?;
+	Friendship::insert_many([(player_id, friend_id), (friend_id, player_id)].map(
+		|(player_id, friend_id)| friendship::ActiveModel {
+			player_id: Set(player_id),
+			friend_id: Set(friend_id),
+			..Default::default()
+		},
+	))
+	.on_conflict_do_nothing()
+	.exec(db)
+	.await?;
+
+	Ok(true)
+}
+
+/// The friends of the players connected to this instance, kept so that they
+/// can be told about their friends without asking the database
+#[derive(Debug, Default)]
+pub(crate) struct FriendIndex {
+	/// The friends of each connected player
+	friends: HashMap<Uuid, HashSet<Uuid>>,
+	/// The connected players each player is friends with
+	friend_of: HashMap<Uuid, HashSet<Uuid>>,
+}
+
+impl FriendIndex {
+	/// Starts tracking a connected player's friends, replacing what was known
+	/// of them
+	pub(crate) fn add_player(&mut self, player: Uuid, friends: HashSet<Uuid>) {
+		self.remove_player(player);
+		for friend in &friends {
+			self.friend_of.entry(*friend).or_default().insert(player);
+		}
+		self.friends.insert(player, friends);
+	}
+
+	/// Stops tracking a player once their last connection is gone
+	pub(crate) fn remove_player(&mut self, player: Uuid) {
+		for friend in self.friends.remove(&player).into_iter().flatten() {
+			self.unlink(friend, player);
+		}
+	}
+
+	/// Records a new friendship, for whichever of the two players is connected
+	pub(crate) fn add_friendship(&mut self, a: Uuid, b: Uuid) {
+		for (player, friend) in [(a, b), (b, a)] {
+			if let Some(friends) = self.friends.get_mut(&player) {
+				friends.insert(friend);
+				self.friend_of.entry(friend).or_default().insert(player);
+			}
+		}
+	}
+
+	pub(crate) fn remove_friendship(&mut self, a: Uuid, b: Uuid) {
+		for (player, friend) in [(a, b), (b, a)] {
+			if let Some(friends) = self.friends.get_mut(&player)
+				&& friends.remove(&friend)
+			{
+				self.unlink(friend, player);
+			}
+		}
+	}
+
+	/// The connected players that are friends with a player
+	pub(crate) fn friends_of(&self, player: Uuid) -> HashSet<Uuid> {
+		self.friend_of.get(&player).cloned().unwrap_or_default()
+	}
+
+	fn unlink(&mut self, friend: Uuid, player: Uuid) {
+		if let Some(players) = self.friend_of.get_mut(&friend) {
+			players.remove(&player);
+			if players.is_empty() {
+				self.friend_of.remove(&friend);
+			}
+		}
+	}
+}
+
+pub(super) async fn setup_router() -> ApiRouter<ApiState> {
+	ApiRouter::new().nest(
+		"/friends",
+		ApiRouter::new()
+			.merge(list::router())
+			.merge(send_request::router())
+			.merge(accept::router())
+			.merge(decline::router())
+			.merge(remove::router()),
+	)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::HashSet;
+
+	use uuid::Uuid;
+
+	use super::FriendIndex;
+
+	#[test]
+	fn tracks_friends_of_connected_players() {
+		let mut index = FriendIndex::default();
+		let (alice, bob, carol) =
+			(Uuid::from_u128(1), Uuid::from_u128(2), Uuid::from_u128(3));
+
+		index.add_player(alice, HashSet::from([bob]));
+		assert_eq!(index.friends_of(bob), HashSet::from([alice]));
+		assert!(index.friends_of(alice).is_empty());
+
+		// Only alice is connected, so only her side is tracked
+		index.add_friendship(carol, alice);
+		assert_eq!(index.friends_of(carol), HashSet::from([alice]));
+
+		index.remove_friendship(alice, bob);
+		assert!(index.friends_of(bob).is_empty());
+
+		index.remove_player(alice);
+		assert!(index.friends_of(carol).is_empty());
+	}
+}
@@ -0,0 +1,96 @@
+use aide::{
+	OperationIo,
+	axum::{ApiRouter, routing::delete_with},
+	transform::TransformOperation,
+};
+use axum::{
+	extract::{Path, State},
+	http::StatusCode,
+	response::IntoResponse,
+};
+use entities::{friendship, prelude::*, user};
+use sea_orm::{ColumnTrait, Condition, EntityTrait, QueryFilter};
+use uuid::Uuid;
+
+use crate::api::{ApiState, account::AuthenticatedPlayer, realtime::RealtimeEvent};
+
+#[derive(thiserror::Error, Debug, OperationIo)]
+pub enum RemoveFriendError {
+	#[error("The players are not friends")]
+	NotFriends,
+	#[error("Unable to query database: {0}")]
+	Database(#[from] sea_orm::error::DbErr),
+}
+
+impl IntoResponse for RemoveFriendError {
+	fn into_response(self) -> axum::response::Response {
+		(
+			match self {
+				Self::NotFriends => StatusCode::NOT_FOUND,
+				Self::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+			},
+			self.to_string(),
+		)
+			.into_response()
+	}
+}
+
+fn endpoint_doc(op: TransformOperation) -> TransformOperation {
+	op.id("removeFriend")
+		.summary("Remove a friend")
+		.description(
+			"Ends the friendship between the authenticated player and a friend. \
+			 Both players are told in game.",
+		)
+		.tag("friends")
+}
+
+pub(super) fn router() -> ApiRouter<ApiState> {
+	ApiRouter::new()
+		.api_route("/{player}", delete_with(self::endpoint, self::endpoint_doc))
+}
+
+#[tracing::instrument(level = "debug", skip(state))]
+async fn endpoint(
+	State(state): State<ApiState>,
+	AuthenticatedPlayer(player): AuthenticatedPlayer,
+	Path(friend): Path<Uuid>,
+) -> Result<StatusCode, RemoveFriendError> {
+	let Some(friend) = User::find()
+		.filter(user::Column::MinecraftUuid.eq(friend))
+		.one(&state.database)
+		.await?
+	else {
+		return Err(RemoveFriendError::NotFriends);
+	};
+
+	let result = Friendship::delete_many()
+		.filter(
+			Condition::any()
+				.add(
+					Condition::all()
+						.add(friendship::Column::PlayerId.eq(player.id))
+						.add(friendship::Column::FriendId.eq(friend.id)),
+				)
+				.add(
+					Condition::all()
+						.add(friendship::Column::PlayerId.eq(friend.id))
+						.add(friendship::Column::FriendId.eq(player.id)),
+				),
+		)
+		.exec(&state.database)
+		.await?;
+	if result.rows_affected == 0 {
+		return Err(RemoveFriendError::NotFriends);
+	}
+
+	state
+		.realtime
+		.publish(RealtimeEvent::FriendshipChanged {
+			players: [player.minecraft_uuid, friend.minecraft_uuid],
+			friends: false,
+		})
+		.await;
+
+	Ok(StatusCode::NO_CONTENT)
+}
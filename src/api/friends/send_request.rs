@@ -0,0 +1,187 @@
+use aide::{
+	OperationIo,
+	axum::{ApiRouter, routing::post_with},
+	transform::TransformOperation,
+};
+use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
+use entities::{friend_request, prelude::*, user};
+use schemars::JsonSchema;
+use sea_orm::{
+	ActiveModelTrait, ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter, Set,
+	TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::api::{
+	ApiState,
+	account::AuthenticatedPlayer,
+	friends::{MAX_FRIENDS, MAX_OUTGOING_REQUESTS, are_friends, befriend},
+	players::profile::{PlayerRef, ProfileError},
+	realtime::RealtimeEvent,
+};
+
+#[derive(thiserror::Error, Debug, OperationIo)]
+pub enum SendFriendRequestError {
+	#[error("The requested player does not use PolyPlus")]
+	MissingPlayer,
+	#[error("Players can't befriend themselves")]
+	OwnPlayer,
+	#[error("The players are already friends")]
+	AlreadyFriends,
+	#[error("A friend request was already sent to the player")]
+	AlreadyRequested,
+	#[error("Too many friend requests are waiting on other players (max {0})")]
+	TooManyRequests(u64),
+	#[error("One of the players already has the maximum of {0} friends")]
+	TooManyFriends(u64),
+	#[error("{0}")]
+	Profile(#[from] ProfileError),
+	#[error("Unable to query database: {0}")]
+	Database(#[from] sea_orm::error::DbErr),
+}
+
+impl IntoResponse for SendFriendRequestError {
+	fn into_response(self) -> axum::response::Response {
+		(
+			match self {
+				Self::MissingPlayer => StatusCode::NOT_FOUND,
+				Self::OwnPlayer => StatusCode::BAD_REQUEST,
+				Self::AlreadyFriends
+				| Self::AlreadyRequested
+				| Self::TooManyRequests(_)
+				| Self::TooManyFriends(_) => StatusCode::CONFLICT,
+				Self::Profile(ProfileError::Request(_)) => StatusCode::BAD_GATEWAY,
+				Self::Profile(ProfileError::Database(_)) | Self::Database(_) => {
+					StatusCode::INTERNAL_SERVER_ERROR
+				}
+			},
+			self.to_string(),
+		)
+			.into_response()
+	}
+}
+
+fn endpoint_doc(op: TransformOperation) -> TransformOperation {
+	op.id("sendFriendRequest")
+		.summary("Send a friend request")
+		.description(
+			"Sends a friend request to a player, given by UUID or by current \
+			 username, who is told about it in game. If the player already sent \
+			 the authenticated player a request, the two become friends straight \
+			 away instead.",
+		)
+		.tag("friends")
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct SendFriendRequestBody {
+	/// The player's UUID or current username
+	player: PlayerRef,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+enum FriendRequestOutcome {
+	/// The request is waiting for the player to accept it
+	Sent,
+	/// The player had already sent a request, so they are now friends
+	Accepted,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+struct SendFriendRequestResponse {
+	outcome: FriendRequestOutcome,
+}
+
+pub(super) fn router() -> ApiRouter<ApiState> {
+	ApiRouter::new().api_route("/requests", post_with(self::endpoint, self::endpoint_doc))
+}
+
+#[tracing::instrument(level = "debug", skip(state))]
+async fn endpoint(
+	State(state): State<ApiState>,
+	AuthenticatedPlayer(player): AuthenticatedPlayer,
+	Json(body): Json<SendFriendRequestBody>,
+) -> Result<Json<SendFriendRequestResponse>, SendFriendRequestError> {
+	let Some(uuid) = state
+		.profiles
+		.resolve(&state.database, &body.player)
+		.await?
+	else {
+		return Err(SendFriendRequestError::MissingPlayer);
+	};
+	if uuid == player.minecraft_uuid {
+		return Err(SendFriendRequestError::OwnPlayer);
+	}
+
+	let txn = state.database.begin().await?;
+	let Some(recipient) = User::find()
+		.filter(user::Column::MinecraftUuid.eq(uuid))
+		.one(&txn)
+		.await?
+	else {
+		return Err(SendFriendRequestError::MissingPlayer);
+	};
+	if are_friends(&txn, player.id, recipient.id).await? {
+		return Err(SendFriendRequestError::AlreadyFriends);
+	}
+
+	// Two players asking each other is as good as accepting
+	let reverse = FriendRequest::find_by_id((recipient.id, player.id))
+		.one(&txn)
+		.await?;
+	if reverse.is_some() {
+		if !befriend(&txn, player.id, recipient.id).await? {
+			return Err(SendFriendRequestError::TooManyFriends(MAX_FRIENDS));
+		}
+		txn.commit().await?;
+
+		state
+			.realtime
+			.publish(RealtimeEvent::FriendshipChanged {
+				players: [player.minecraft_uuid, recipient.minecraft_uuid],
+				friends: true,
+			})
+			.await;
+		return Ok(Json(SendFriendRequestResponse {
+			outcome: FriendRequestOutcome::Accepted,
+		}));
+	}
+
+	let existing = FriendRequest::find_by_id((player.id, recipient.id))
+		.one(&txn)
+		.await?;
+	if existing.is_some() {
+		return Err(SendFriendRequestError::AlreadyRequested);
+	}
+	let outgoing = FriendRequest::find()
+		.filter(friend_request::Column::SenderId.eq(player.id))
+		.count(&txn)
+		.await?;
+	if outgoing >= MAX_OUTGOING_REQUESTS {
+		return Err(SendFriendRequestError::TooManyRequests(
+			MAX_OUTGOING_REQUESTS,
+		));
+	}
+
+	friend_request::ActiveModel {
+		sender_id: Set(player.id),
+		recipient_id: Set(recipient.id),
+		..Default::default()
+	}
+	.insert(&txn)
+	.await?;
+	txn.commit().await?;
+
+	state
+		.realtime
+		.publish(RealtimeEvent::FriendRequestReceived {
+			player: recipient.minecraft_uuid,
+			sender: player.minecraft_uuid,
+		})
+		.await;
+
+	Ok(Json(SendFriendRequestResponse {
+		outcome: FriendRequestOutcome::Sent,
+	}))
+}
@@ -22,7 +22,11 @@ use sea_orm::{
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 
-use crate::api::{ApiState, admin_auth::AdminAuthenticationExtractor};
+use crate::api::{
+	ApiState,
+	admin_auth::{AdminAuthenticationExtractor, LinksRead, LinksWrite},
+	audit::{self, AuditEntry, model_json},
+};
 
 #[derive(thiserror::Error, Debug, OperationIo)]
 pub enum LinksError {
@@ -142,7 +146,7 @@ fn valid_slug(slug: &str) -> bool {
 			.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
 }
 
-fn valid_target(url: &str) -> bool {
+pub(super) fn valid_target(url: &str) -> bool {
 	(url.starts_with("https://") || url.starts_with("http://")) && url.len() <= 2048
 }
 
@@ -196,7 +200,7 @@ fn create_doc(op: TransformOperation) -> TransformOperation {
 		.summary("Create a tracked link")
 		.description(
 			"Creates a tracked short link redirecting `/go/{slug}` to an arbitrary \
-			 absolute http(s) url. Admin password required.",
+			 absolute http(s) url. Requires the `links:write` scope.",
 		)
 		.tag("links")
 }
@@ -205,8 +209,8 @@ fn list_doc(op: TransformOperation) -> TransformOperation {
 	op.id("listTrackedLinks")
 		.summary("List tracked links")
 		.description(
-			"Lists every tracked link with its unique-visit count. Admin password \
-			 required.",
+			"Lists every tracked link with its unique-visit count. Requires the \
+			 `links:read` scope.",
 		)
 		.tag("links")
 }
@@ -214,7 +218,7 @@ fn list_doc(op: TransformOperation) -> TransformOperation {
 fn delete_doc(op: TransformOperation) -> TransformOperation {
 	op.id("deleteTrackedLink")
 		.summary("Delete a tracked link")
-		.description("Deletes the tracked link with the given slug. Admin password required.")
+		.description("Deletes the tracked link with the given slug. Requires the `links:write` scope.")
 		.tag("links")
 }
 
@@ -240,7 +244,7 @@ async fn follow(
 		.ok_or(LinksError::NotFound)?;
 
 	if !is_bot(&visitor.user_agent) {
-		let visitor_hash = visitor.hash(&state.admin_password, &slug);
+		let visitor_hash = visitor.hash(&state.visitor_hash_salt, &slug);
 		let txn = state.database.begin().await?;
 
 		let inserted = TrackedLinkHits::insert(tracked_link_hits::ActiveModel {
@@ -269,10 +273,10 @@ async fn follow(
 	Ok(Redirect::temporary(&link.target_url))
 }
 
-#[tracing::instrument(level = "debug", skip(state, _auth))]
+#[tracing::instrument(level = "debug", skip(state, actor))]
 async fn create(
 	State(state): State<ApiState>,
-	_auth: AdminAuthenticationExtractor,
+	AdminAuthenticationExtractor(actor, ..): AdminAuthenticationExtractor<LinksWrite>,
 	Json(body): Json<CreateRequest>,
 ) -> Result<(StatusCode, Json<LinkInfo>), LinksError> {
 	use entities::{prelude::*, tracked_links};
@@ -284,8 +288,9 @@ async fn create(
 		return Err(LinksError::InvalidUrl);
 	}
 
+	let txn = state.database.begin().await?;
 	if TrackedLinks::find_by_id(&body.slug)
-		.one(&state.database)
+		.one(&txn)
 		.await?
 		.is_some()
 	{
@@ -298,16 +303,24 @@ async fn create(
 		clicks: Set(0),
 		..Default::default()
 	}
-	.insert(&state.database)
+	.insert(&txn)
 	.await?;
 
+	audit::record(
+		&txn,
+		&actor,
+		AuditEntry::new("link.create", "link", &link.slug).after(model_json(&link)),
+	)
+	.await?;
+	txn.commit().await?;
+
 	Ok((StatusCode::CREATED, Json(LinkInfo::from_model(link))))
 }
 
 #[tracing::instrument(level = "debug", skip(state, _auth))]
 async fn list(
 	State(state): State<ApiState>,
-	_auth: AdminAuthenticationExtractor,
+	_auth: AdminAuthenticationExtractor<LinksRead>,
 ) -> Result<Json<ListResponse>, LinksError> {
 	use entities::{prelude::*, tracked_links};
 
@@ -322,19 +335,28 @@ async fn list(
 	Ok(Json(ListResponse { links }))
 }
 
-#[tracing::instrument(level = "debug", skip(state, _auth))]
+#[tracing::instrument(level = "debug", skip(state, actor))]
 async fn delete(
 	State(state): State<ApiState>,
-	_auth: AdminAuthenticationExtractor,
+	AdminAuthenticationExtractor(actor, ..): AdminAuthenticationExtractor<LinksWrite>,
 	Path(slug): Path<String>,
 ) -> Result<StatusCode, LinksError> {
 	use entities::prelude::*;
 
-	let result = TrackedLinks::delete_by_id(slug).exec(&state.database).await?;
+	let txn = state.database.begin().await?;
+	let link = TrackedLinks::find_by_id(&slug)
+		.one(&txn)
+		.await?
+		.ok_or(LinksError::NotFound)?;
+	TrackedLinks::delete_by_id(slug).exec(&txn).await?;
 
-	if result.rows_affected == 0 {
-		return Err(LinksError::NotFound);
-	}
+	audit::record(
+		&txn,
+		&actor,
+		AuditEntry::new("link.delete", "link", &link.slug).before(model_json(&link)),
+	)
+	.await?;
+	txn.commit().await?;
 
 	Ok(StatusCode::NO_CONTENT)
 }
@@ -347,7 +369,7 @@ mod tests {
 
 	fn visitor(ip: &str, user_agent: &str) -> VisitorId {
 		VisitorId {
-			ip: ip.parse::<IpAddr>().unwrap(),
+			ip: ip.parse::<IpAddr>().expect("test ip should parse"),
 			user_agent: user_agent.to_owned(),
 		}
 	}
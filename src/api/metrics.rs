@@ -0,0 +1,218 @@
+//! Process-wide counters, exposed in the Prometheus text format
+
+use std::{
+	fmt::Write as _,
+	sync::atomic::{AtomicU64, Ordering},
+};
+
+use aide::{
+	OperationOutput,
+	axum::{ApiRouter, routing::get_with},
+	openapi::{MediaType, Operation},
+	transform::TransformOperation,
+};
+use axum::{
+	extract::State,
+	http::header,
+	response::{IntoResponse, Response},
+};
+
+use crate::api::{
+	ApiState,
+	admin_auth::{AdminAuthenticationExtractor, AnalyticsRead},
+	rate_limit::RateLimitGroup,
+};
+
+#[derive(Debug, Default)]
+pub(super) struct Metrics {
+	/// Rejections by rate limits, indexed like [`RateLimitGroup::ALL`]
+	rate_limited: [AtomicU64; RateLimitGroup::ALL.len()],
+	/// Packets currently waiting in websocket outbound queues
+	queued_packets: AtomicU64,
+	/// Queued packets dropped because a newer packet made them outdated
+	superseded_packets: AtomicU64,
+	/// Websocket connections closed because their queue filled up
+	slow_consumer_evictions: AtomicU64,
+}
+
+impl Metrics {
+	pub(super) fn record_rate_limited(&self, group: RateLimitGroup) {
+		self.rate_limited[group as usize].fetch_add(1, Ordering::Relaxed);
+	}
+
+	pub(super) fn record_queued(&self) {
+		self.queued_packets.fetch_add(1, Ordering::Relaxed);
+	}
+
+	pub(super) fn record_dequeued(&self, packets: usize) {
+		self.queued_packets
+			.fetch_sub(packets as u64, Ordering::Relaxed);
+	}
+
+	pub(super) fn record_superseded(&self, packets: usize) {
+		self.superseded_packets
+			.fetch_add(packets as u64, Ordering::Relaxed);
+	}
+
+	pub(super) fn record_eviction(&self) {
+		self.slow_consumer_evictions.fetch_add(1, Ordering::Relaxed);
+	}
+
+	fn render(&self) -> String {
+		let mut out = String::new();
+		counter(
+			&mut out,
+			"plus_rate_limited_total",
+			"Requests and websocket packets rejected by rate limits",
+			RateLimitGroup::ALL.iter().map(|group| {
+				(
+					format!("group=\"{}\"", group.name()),
+					self.rate_limited[*group as usize].load(Ordering::Relaxed),
+				)
+			}),
+		);
+		gauge(
+			&mut out,
+			"plus_websocket_queued_packets",
+			"Packets waiting in websocket outbound queues",
+			self.queued_packets.load(Ordering::Relaxed),
+		);
+		counter(
+			&mut out,
+			"plus_websocket_superseded_packets_total",
+			"Queued websocket packets dropped because a newer one made them outdated",
+			[(
+				String::new(),
+				self.superseded_packets.load(Ordering::Relaxed),
+			)]
+			.into_iter(),
+		);
+		counter(
+			&mut out,
+			"plus_websocket_slow_consumer_evictions_total",
+			"Websocket connections closed because they couldn't keep up",
+			[(
+				String::new(),
+				self.slow_consumer_evictions.load(Ordering::Relaxed),
+			)]
+			.into_iter(),
+		);
+		out
+	}
+}
+
+fn counter(
+	out: &mut String,
+	name: &str,
+	help: &str,
+	values: impl Iterator<Item = (String, u64)>,
+) {
+	let _ = writeln!(out, "# HELP {name} {help}");
+	let _ = writeln!(out, "# TYPE {name} counter");
+	for (labels, value) in values {
+		if labels.is_empty() {
+			let _ = writeln!(out, "{name} {value}");
+		} else {
+			let _ = writeln!(out, "{name}{{{labels}}} {value}");
+		}
+	}
+}
+
+fn gauge(out: &mut String, name: &str, help: &str, value: u64) {
+	let _ = writeln!(out, "# HELP {name} {help}");
+	let _ = writeln!(out, "# TYPE {name} gauge");
+	let _ = writeln!(out, "{name} {value}");
+}
+
+/// Metrics rendered in the Prometheus text format
+struct PrometheusText(String);
+
+impl IntoResponse for PrometheusText {
+	fn into_response(self) -> Response {
+		(
+			[(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+			self.0,
+		)
+			.into_response()
+	}
+}
+
+impl OperationOutput for PrometheusText {
+	type Inner = Self;
+
+	fn operation_response(
+		_ctx: &mut aide::generate::GenContext,
+		_operation: &mut Operation,
+	) -> Option<aide::openapi::Response> {
+		Some(aide::openapi::Response {
+			description: "Metrics in the Prometheus text format".into(),
+			content: [("text/plain".into(), MediaType::default())]
+				.into_iter()
+				.collect(),
+			..Default::default()
+		})
+	}
+
+	fn inferred_responses(
+		ctx: &mut aide::generate::GenContext,
+		operation: &mut Operation,
+	) -> Vec<(Option<u16>, aide::openapi::Response)> {
+		Self::operation_response(ctx, operation)
+			.map(|res| Vec::from([(Some(200), res)]))
+			.unwrap_or_default()
+	}
+}
+
+fn endpoint_doc(op: TransformOperation) -> TransformOperation {
+	op.id("getMetrics")
+		.summary("Get process metrics")
+		.description(
+			"Returns counters of this backend instance in the Prometheus text format. \
+			 Requires the `analytics:read` scope.",
+		)
+		.tag("analytics")
+}
+
+pub(super) async fn setup_router() -> ApiRouter<ApiState> {
+	ApiRouter::new().api_route("/metrics", get_with(self::endpoint, self::endpoint_doc))
+}
+
+#[tracing::instrument(level = "debug", skip(state))]
+async fn endpoint(
+	State(state): State<ApiState>,
+	_auth: AdminAuthenticationExtractor<AnalyticsRead>,
+) -> PrometheusText {
+	PrometheusText(state.metrics.render())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::Metrics;
+	use crate::api::rate_limit::RateLimitGroup;
+
+	#[test]
+	fn renders_rate_limit_counters() {
+		let metrics = Metrics::default();
+		metrics.record_rate_limited(RateLimitGroup::Checkout);
+		metrics.record_rate_limited(RateLimitGroup::Checkout);
+
+		let rendered = metrics.render();
+		assert!(rendered.contains("# TYPE plus_rate_limited_total counter\n"));
+		assert!(rendered.contains("plus_rate_limited_total{group=\"checkout\"} 2\n"));
+		assert!(rendered.contains("plus_rate_limited_total{group=\"login\"} 0\n"));
+	}
+
+	#[test]
+	fn renders_websocket_queue_metrics() {
+		let metrics = Metrics::default();
+		metrics.record_queued();
+		metrics.record_queued();
+		metrics.record_dequeued(1);
+		metrics.record_eviction();
+
+		let rendered = metrics.render();
+		assert!(rendered.contains("# TYPE plus_websocket_queued_packets gauge\n"));
+		assert!(rendered.contains("plus_websocket_queued_packets 1\n"));
+		assert!(rendered.contains("plus_websocket_slow_consumer_evictions_total 1\n"));
+	}
+}
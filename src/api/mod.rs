@@ -1,13 +1,24 @@
 mod account;
 pub(crate) mod admin_auth;
 mod analytics;
+mod announcements;
+mod api_keys;
 mod assets;
+mod audit;
+mod bans;
 mod bundles;
 mod category;
 mod collections;
 mod cosmetics;
+mod friends;
 mod links;
+mod metrics;
+mod outfits;
+mod permissions;
 mod players;
+mod presence;
+mod rate_limit;
+mod realtime;
 mod state;
 mod stripe;
 mod tags;
@@ -26,16 +37,23 @@ use aide::{
 use axum::{
 	Extension,
 	http::{Method, header},
+	middleware,
 	routing::get as axum_get,
 };
 use schemars::{JsonSchema, schema_for};
 use tokio::net::TcpListener;
 use tower_http::{cors::CorsLayer, trace::TraceLayer};
 
+pub(crate) use account::verifier::MOJANG_SESSIONSERVER_URL;
+pub(crate) use players::profile::MOJANG_PROFILE_API_URL;
+pub(crate) use rate_limit::RateLimit;
+pub(crate) use realtime::RealtimeBusKind;
+pub(crate) use websocket::queue::SlowConsumerPolicy;
+
 use crate::{
 	api::{
 		state::ApiState,
-		websocket::structs::{ClientBoundPacket, ServerBoundPacket},
+		websocket::structs::{ClientBoundMessage, ServerBoundMessage},
 	},
 	commands::ServeArgs,
 };
@@ -85,11 +103,15 @@ fn init_openapi_spec(spec: TransformOpenApi<'_>) -> TransformOpenApi<'_> {
 			},
 		)
 		.security_scheme(
-			"Admin Password",
+			admin_auth::API_KEY_SECURITY_NAME,
 			SecurityScheme::ApiKey {
 				location: ApiKeyLocation::Header,
 				name: "Authorization".to_string(),
-				description: Some("Admin password".to_string()),
+				description: Some(
+					"A scoped admin API key. The scopes listed on an operation are the \
+					 ones its key needs."
+						.to_string(),
+				),
 				extensions: Default::default(),
 			},
 		)
@@ -106,15 +128,27 @@ pub(crate) async fn start(args: ServeArgs) {
 		.nest("/account", account::setup_router().await)
 		.nest("/transactions", transactions::setup_router().await)
 		.merge(assets::setup_router().await)
+		.merge(bans::setup_router().await)
 		.merge(bundles::setup_router().await)
 		.merge(collections::setup_router().await)
 		.merge(links::setup_router().await)
 		.merge(analytics::setup_router().await)
+		.merge(announcements::setup_router().await)
+		.merge(friends::setup_router().await)
+		.merge(outfits::setup_router().await)
+		.merge(api_keys::setup_router().await)
+		.merge(audit::setup_router().await)
+		.merge(permissions::setup_router().await)
 		.merge(players::setup_router().await)
 		.merge(cosmetics::setup_router().await)
 		.merge(tags::setup_router().await)
 		.merge(category::setup_router().await)
 		.merge(websocket::setup_router().await)
+		.merge(metrics::setup_router().await)
+		.route_layer(middleware::from_fn_with_state(
+			state.clone(),
+			rate_limit::enforce_rate_limits,
+		))
 		.with_state(state);
 
 	// Convert OpenAPI router to normal actix router, and render the doc as JSON
@@ -122,17 +156,17 @@ pub(crate) async fn start(args: ServeArgs) {
 	let app = app.finish_api_with(&mut openapi, init_openapi_spec);
 	if let Some(components) = openapi.components.as_mut() {
 		components.schemas.insert(
-			ClientBoundPacket::schema_name().into_owned(),
+			ClientBoundMessage::schema_name().into_owned(),
 			SchemaObject {
-				json_schema: schema_for!(ClientBoundPacket),
+				json_schema: schema_for!(ClientBoundMessage),
 				example: None,
 				external_docs: None,
 			},
 		);
 		components.schemas.insert(
-			ServerBoundPacket::schema_name().into_owned(),
+			ServerBoundMessage::schema_name().into_owned(),
 			SchemaObject {
-				json_schema: schema_for!(ServerBoundPacket),
+				json_schema: schema_for!(ServerBoundMessage),
 				example: None,
 				external_docs: None,
 			},
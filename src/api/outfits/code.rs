@@ -0,0 +1,162 @@
+//! Share codes, which pack an outfit into a short URL-safe string that players
+//! can pass around. A code is the base64url encoding of:
+//!
+//! - the format version, one byte
+//! - a flags byte, whose lowest bit is set when a particle color follows
+//! - the particle color, four big endian bytes, if there is one
+//! - for each equipped slot, the slot's position in [BodySlot] as one byte
+//!   followed by the cosmetic ID as a LEB128 varint
+//!
+//! Slots are encoded by position, so new slots must only ever be added at the
+//! end of [BodySlot].
+
+use std::collections::{BTreeMap, HashMap};
+
+use base64::Engine as _;
+use entities::sea_orm_active_enums::BodySlot;
+use sea_orm::Iterable as _;
+
+/// The share code format version, bumped whenever the format changes
+const FORMAT_VERSION: u8 = 1;
+/// Set in the flags byte when the code has a particle color
+const HAS_PARTICLE_COLOR: u8 = 1;
+/// Max length of a share code. Codes for outfits filling every slot are far
+/// shorter, so longer ones are turned down without decoding them.
+const MAX_CODE_LENGTH: usize = 128;
+
+/// The contents of an outfit, as carried by a share code
+#[derive(Debug, Default, PartialEq)]
+pub(super) struct SharedOutfit {
+	pub(super) equipped: HashMap<BodySlot, i32>,
+	pub(super) particle_color: Option<i32>,
+}
+
+impl SharedOutfit {
+	pub(super) fn encode(&self) -> String {
+		let mut bytes = vec![FORMAT_VERSION];
+		match self.particle_color {
+			Some(color) => {
+				bytes.push(HAS_PARTICLE_COLOR);
+				bytes.extend(color.to_be_bytes());
+			}
+			None => bytes.push(0),
+		}
+
+		// Sorted, so that the same outfit always gets the same code
+		let slots = self
+			.equipped
+			.iter()
+			.filter_map(|(slot, id)| Some((slot_position(slot)?, *id)))
+			.collect::<BTreeMap<_, _>>();
+		for (position, id) in slots {
+			bytes.push(position);
+			let mut id = id as u32;
+			while id >= 0x80 {
+				bytes.push((id as u8 & 0x7F) | 0x80);
+				id >>= 7;
+			}
+			bytes.push(id as u8);
+		}
+
+		base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(bytes)
+	}
+
+	/// Reads a share code, returning None if it is malformed
+	pub(super) fn decode(code: &str) -> Option<Self> {
+		if code.len() > MAX_CODE_LENGTH {
+			return None;
+		}
+		let bytes = base64::engine::general_purpose::URL_SAFE_NO_PAD
+			.decode(code)
+			.ok()?;
+		let (&version, rest) = bytes.split_first()?;
+		let (&flags, mut rest) = rest.split_first()?;
+		if version != FORMAT_VERSION || flags & !HAS_PARTICLE_COLOR != 0 {
+			return None;
+		}
+
+		let mut outfit = Self::default();
+		if flags & HAS_PARTICLE_COLOR != 0 {
+			let (color, remaining) = rest.split_first_chunk::<4>()?;
+			outfit.particle_color = Some(i32::from_be_bytes(*color));
+			rest = remaining;
+		}
+
+		let slots = BodySlot::iter().collect::<Vec<_>>();
+		while let Some((&position, remaining)) = rest.split_first() {
+			let slot = slots.get(position as usize)?.clone();
+			let mut id = 0u32;
+			let mut shift = 0;
+			rest = remaining;
+			loop {
+				let (&byte, remaining) = rest.split_first()?;
+				rest = remaining;
+				id |= u32::from(byte & 0x7F).checked_shl(shift)?;
+				if byte & 0x80 == 0 {
+					break;
+				}
+				shift += 7;
+			}
+			if outfit
+				.equipped
+				.insert(slot, i32::try_from(id).ok()?)
+				.is_some()
+			{
+				return None;
+			}
+		}
+
+		Some(outfit)
+	}
+}
+
+fn slot_position(slot: &BodySlot) -> Option<u8> {
+	BodySlot::iter()
+		.position(|other| other == *slot)
+		.and_then(|position| u8::try_from(position).ok())
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::HashMap;
+
+	use entities::sea_orm_active_enums::BodySlot;
+
+	use super::SharedOutfit;
+
+	#[test]
+	fn codes_round_trip() {
+		for outfit in [
+			SharedOutfit::default(),
+			SharedOutfit {
+				equipped: HashMap::from([
+					(BodySlot::Cape, 1),
+					(BodySlot::Hat, 300),
+					(BodySlot::Shoulder, i32::MAX),
+				]),
+				particle_color: Some(0xFF_0000),
+			},
+		] {
+			let code = outfit.encode();
+			assert!(
+				code.bytes()
+					.all(|byte| byte.is_ascii_alphanumeric() || b"-_".contains(&byte))
+			);
+			assert_eq!(SharedOutfit::decode(&code), Some(outfit));
+		}
+	}
+
+	#[test]
+	fn rejects_malformed_codes() {
+		let code = SharedOutfit {
+			equipped: HashMap::from([(BodySlot::Wings, 300)]),
+			particle_color: None,
+		}
+		.encode();
+
+		assert_eq!(SharedOutfit::decode(&code[..code.len() - 1]), None);
+		for code in ["", "not a code", "AgA", "AQA_"] {
+			assert_eq!(SharedOutfit::decode(code), None);
+		}
+	}
+}
@@ -0,0 +1,94 @@
+use aide::{
+	OperationIo,
+	axum::{ApiRouter, routing::post_with},
+	transform::TransformOperation,
+};
+use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
+use entities::{outfit, prelude::*};
+use sea_orm::{
+	ActiveModelTrait, ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter, Set,
+	TransactionTrait,
+};
+
+use crate::api::{
+	ApiState,
+	account::AuthenticatedPlayer,
+	outfits::{InvalidOutfit, MAX_OUTFITS, OutfitBody, OutfitInfo, name_taken},
+};
+
+#[derive(thiserror::Error, Debug, OperationIo)]
+pub enum CreateOutfitError {
+	#[error("{0}")]
+	Invalid(#[from] InvalidOutfit),
+	#[error("An outfit with that name already exists")]
+	NameTaken,
+	#[error("Players can have at most {MAX_OUTFITS} outfits")]
+	TooManyOutfits,
+	#[error("Unable to query database: {0}")]
+	Database(#[from] sea_orm::error::DbErr),
+}
+
+impl IntoResponse for CreateOutfitError {
+	fn into_response(self) -> axum::response::Response {
+		(
+			match self {
+				Self::Invalid(InvalidOutfit::Database(_)) | Self::Database(_) => {
+					StatusCode::INTERNAL_SERVER_ERROR
+				}
+				Self::Invalid(_) => StatusCode::BAD_REQUEST,
+				Self::NameTaken | Self::TooManyOutfits => StatusCode::CONFLICT,
+			},
+			self.to_string(),
+		)
+			.into_response()
+	}
+}
+
+fn endpoint_doc(op: TransformOperation) -> TransformOperation {
+	op.id("createOutfit")
+		.summary("Save an outfit")
+		.description(
+			"Saves a named outfit for the authenticated player, made of cosmetics \
+			 they own in slots the cosmetics can be equipped in. Players can have \
+			 up to 20 outfits, with distinct names.",
+		)
+		.tag("outfits")
+}
+
+pub(super) fn router() -> ApiRouter<ApiState> {
+	ApiRouter::new().api_route("/", post_with(self::endpoint, self::endpoint_doc))
+}
+
+#[tracing::instrument(level = "debug", skip(state))]
+async fn endpoint(
+	State(state): State<ApiState>,
+	AuthenticatedPlayer(player): AuthenticatedPlayer,
+	Json(body): Json<OutfitBody>,
+) -> Result<(StatusCode, Json<OutfitInfo>), CreateOutfitError> {
+	let txn = state.database.begin().await?;
+	let name = body.validate(&txn, player.id).await?;
+
+	let outfits = Outfit::find()
+		.filter(outfit::Column::PlayerId.eq(player.id))
+		.count(&txn)
+		.await?;
+	if outfits >= MAX_OUTFITS {
+		return Err(CreateOutfitError::TooManyOutfits);
+	}
+	if name_taken(&txn, player.id, &name, None).await? {
+		return Err(CreateOutfitError::NameTaken);
+	}
+
+	let outfit = outfit::ActiveModel {
+		player_id: Set(player.id),
+		name: Set(name),
+		equipped: Set(serde_json::json!(body.equipped)),
+		particle_color: Set(body.particle_color),
+		..Default::default()
+	}
+	.insert(&txn)
+	.await?;
+	txn.commit().await?;
+
+	Ok((StatusCode::CREATED, Json(outfit.into())))
+}
@@ -0,0 +1,67 @@
+use aide::{
+	OperationIo,
+	axum::{ApiRouter, routing::delete_with},
+	transform::TransformOperation,
+};
+use axum::{
+	extract::{Path, State},
+	http::StatusCode,
+	response::IntoResponse,
+};
+use entities::{outfit, prelude::*};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+
+use crate::api::{ApiState, account::AuthenticatedPlayer};
+
+#[derive(thiserror::Error, Debug, OperationIo)]
+pub enum DeleteOutfitError {
+	#[error("No outfit with that id")]
+	NotFound,
+	#[error("Unable to query database: {0}")]
+	Database(#[from] sea_orm::error::DbErr),
+}
+
+impl IntoResponse for DeleteOutfitError {
+	fn into_response(self) -> axum::response::Response {
+		(
+			match self {
+				Self::NotFound => StatusCode::NOT_FOUND,
+				Self::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+			},
+			self.to_string(),
+		)
+			.into_response()
+	}
+}
+
+fn endpoint_doc(op: TransformOperation) -> TransformOperation {
+	op.id("deleteOutfit")
+		.summary("Delete a saved outfit")
+		.description(
+			"Deletes one of the authenticated player's outfits. The cosmetics the \
+			 player has equipped are left as they are.",
+		)
+		.tag("outfits")
+}
+
+pub(super) fn router() -> ApiRouter<ApiState> {
+	ApiRouter::new().api_route("/{id}", delete_with(self::endpoint, self::endpoint_doc))
+}
+
+#[tracing::instrument(level = "debug", skip(state))]
+async fn endpoint(
+	State(state): State<ApiState>,
+	AuthenticatedPlayer(player): AuthenticatedPlayer,
+	Path(id): Path<i32>,
+) -> Result<StatusCode, DeleteOutfitError> {
+	let result = Outfit::delete_many()
+		.filter(outfit::Column::Id.eq(id))
+		.filter(outfit::Column::PlayerId.eq(player.id))
+		.exec(&state.database)
+		.await?;
+	if result.rows_affected == 0 {
+		return Err(DeleteOutfitError::NotFound);
+	}
+
+	Ok(StatusCode::NO_CONTENT)
+}
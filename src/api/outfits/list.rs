@@ -0,0 +1,62 @@
+use aide::{
+	OperationIo,
+	axum::{ApiRouter, routing::get_with},
+	transform::TransformOperation,
+};
+use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
+use entities::{outfit, prelude::*};
+use schemars::JsonSchema;
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QueryOrder};
+use serde::Serialize;
+
+use crate::api::{ApiState, account::AuthenticatedPlayer, outfits::OutfitInfo};
+
+#[derive(thiserror::Error, Debug, OperationIo)]
+pub enum ListOutfitsError {
+	#[error("Unable to query database: {0}")]
+	Database(#[from] sea_orm::error::DbErr),
+}
+
+impl IntoResponse for ListOutfitsError {
+	fn into_response(self) -> axum::response::Response {
+		(
+			match self {
+				Self::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+			},
+			self.to_string(),
+		)
+			.into_response()
+	}
+}
+
+fn endpoint_doc(op: TransformOperation) -> TransformOperation {
+	op.id("listOutfits")
+		.summary("List saved outfits")
+		.description("Returns the authenticated player's outfits, oldest first.")
+		.tag("outfits")
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+struct ListOutfitsResponse {
+	outfits: Vec<OutfitInfo>,
+}
+
+pub(super) fn router() -> ApiRouter<ApiState> {
+	ApiRouter::new().api_route("/", get_with(self::endpoint, self::endpoint_doc))
+}
+
+#[tracing::instrument(level = "debug", skip(state))]
+async fn endpoint(
+	State(state): State<ApiState>,
+	AuthenticatedPlayer(player): AuthenticatedPlayer,
+) -> Result<Json<ListOutfitsResponse>, ListOutfitsError> {
+	let outfits = Outfit::find()
+		.filter(outfit::Column::PlayerId.eq(player.id))
+		.order_by_asc(outfit::Column::Id)
+		.all(&state.database)
+		.await?;
+
+	Ok(Json(ListOutfitsResponse {
+		outfits: outfits.into_iter().map(OutfitInfo::from).collect(),
+	}))
+}
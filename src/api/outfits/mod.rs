@@ -0,0 +1,186 @@
+//! Outfits, named sets of equipped cosmetics and particle color that players
+//! save, so that they can switch their whole look at once with the
+//! `ApplyOutfit` websocket packet, and share with others through share codes.
+
+mod code;
+mod create;
+mod delete;
+mod list;
+mod resolve;
+mod share;
+mod update;
+
+use std::collections::{HashMap, HashSet};
+
+use aide::axum::ApiRouter;
+use entities::{
+	cosmetic_allowed_slot, outfit, player_owned_cosmetic, prelude::*,
+	sea_orm_active_enums::BodySlot,
+};
+use schemars::JsonSchema;
+use sea_orm::{
+	ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter,
+	prelude::DateTimeWithTimeZone,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::api::ApiState;
+
+/// Max outfits a player may have
+const MAX_OUTFITS: u64 = 20;
+/// Max characters in an outfit's name
+const MAX_NAME_LENGTH: usize = 32;
+
+#[derive(Debug, Serialize, JsonSchema)]
+struct OutfitInfo {
+	id: i32,
+	name: String,
+	/// The cosmetic equipped in each slot. Slots that aren't listed are left
+	/// empty when the outfit is applied.
+	equipped: HashMap<BodySlot, i32>,
+	particle_color: Option<i32>,
+	created_at: DateTimeWithTimeZone,
+	updated_at: DateTimeWithTimeZone,
+}
+
+impl From<outfit::Model> for OutfitInfo {
+	fn from(outfit: outfit::Model) -> Self {
+		Self {
+			equipped: outfit_equipment(&outfit),
+			id: outfit.id,
+			name: outfit.name,
+			particle_color: outfit.particle_color,
+			created_at: outfit.created_at,
+			updated_at: outfit.updated_at,
+		}
+	}
+}
+
+/// The cosmetic an outfit equips in each slot
+pub(crate) fn outfit_equipment(outfit: &outfit::Model) -> HashMap<BodySlot, i32> {
+	serde_json::from_value(outfit.equipped.clone()).unwrap_or_default()
+}
+
+/// The body of requests that save an outfit
+#[derive(Debug, Deserialize, JsonSchema)]
+struct OutfitBody {
+	name: String,
+	/// The cosmetic to equip in each slot, which the player must own
+	#[serde(default)]
+	equipped: HashMap<BodySlot, i32>,
+	particle_color: Option<i32>,
+}
+
+#[derive(thiserror::Error, Debug)]
+enum InvalidOutfit {
+	#[error("The name must be between 1 and {MAX_NAME_LENGTH} characters long")]
+	Name,
+	#[error("The given ID {0} is not owned by the player")]
+	UnownedCosmetic(i32),
+	#[error("The given ID {id} cannot be equipped in slot {slot:?}")]
+	InvalidSlot { slot: BodySlot, id: i32 },
+	#[error("Unable to query database: {0}")]
+	Database(#[from] DbErr),
+}
+
+impl OutfitBody {
+	/// Checks the outfit can be saved for a player, returning its trimmed name
+	async fn validate(
+		&self,
+		db: &impl ConnectionTrait,
+		player_id: i32,
+	) -> Result<String, InvalidOutfit> {
+		let name = self.name.trim();
+		if name.is_empty() || name.chars().count() > MAX_NAME_LENGTH {
+			return Err(InvalidOutfit::Name);
+		}
+
+		let ids = self.equipped.values().copied().collect::<HashSet<_>>();
+		let owned = PlayerOwnedCosmetic::find()
+			.filter(player_owned_cosmetic::Column::PlayerId.eq(player_id))
+			.filter(player_owned_cosmetic::Column::CosmeticId.is_in(ids.clone()))
+			.all(db)
+			.await?
+			.into_iter()
+			.map(|owned| owned.cosmetic_id)
+			.collect::<HashSet<_>>();
+		let allowed = CosmeticAllowedSlot::find()
+			.filter(cosmetic_allowed_slot::Column::CosmeticId.is_in(ids))
+			.all(db)
+			.await?
+			.into_iter()
+			.map(|allowed| (allowed.cosmetic_id, allowed.slot))
+			.collect::<HashSet<_>>();
+
+		for (slot, id) in &self.equipped {
+			if !owned.contains(id) {
+				return Err(InvalidOutfit::UnownedCosmetic(*id));
+			}
+			if !allowed.contains(&(*id, slot.clone())) {
+				return Err(InvalidOutfit::InvalidSlot {
+					slot: slot.clone(),
+					id: *id,
+				});
+			}
+		}
+
+		Ok(name.to_owned())
+	}
+}
+
+/// Whether a player has another outfit with the given name
+async fn name_taken(
+	db: &impl ConnectionTrait,
+	player_id: i32,
+	name: &str,
+	except: Option<i32>,
+) -> Result<bool, DbErr> {
+	let mut query = Outfit::find()
+		.filter(outfit::Column::PlayerId.eq(player_id))
+		.filter(outfit::Column::Name.eq(name));
+	if let Some(id) = except {
+		query = query.filter(outfit::Column::Id.ne(id));
+	}
+	Ok(query.one(db).await?.is_some())
+}
+
+pub(super) async fn setup_router() -> ApiRouter<ApiState> {
+	ApiRouter::new().nest(
+		"/outfits",
+		ApiRouter::new()
+			.merge(list::router())
+			.merge(create::router())
+			.merge(update::router())
+			.merge(delete::router())
+			.merge(share::router())
+			.merge(resolve::router()),
+	)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::HashMap;
+
+	use entities::{outfit, sea_orm_active_enums::BodySlot};
+	use sea_orm::prelude::DateTimeWithTimeZone;
+
+	use super::outfit_equipment;
+
+	#[test]
+	fn reads_equipment_stored_as_json() {
+		let outfit = outfit::Model {
+			id: 1,
+			player_id: 2,
+			name: "Winter".to_owned(),
+			equipped: serde_json::json!({ "cape": 3, "hat": 4 }),
+			particle_color: None,
+			created_at: DateTimeWithTimeZone::default(),
+			updated_at: DateTimeWithTimeZone::default(),
+		};
+
+		assert_eq!(
+			outfit_equipment(&outfit),
+			HashMap::from([(BodySlot::Cape, 3), (BodySlot::Hat, 4)])
+		);
+	}
+}
@@ -0,0 +1,135 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use aide::{
+	OperationIo,
+	axum::{ApiRouter, routing::get_with},
+	transform::TransformOperation,
+};
+use axum::{
+	Json,
+	extract::{Path, State},
+	http::StatusCode,
+	response::IntoResponse,
+};
+use entities::{
+	cosmetic, player_owned_cosmetic, prelude::*, sea_orm_active_enums::BodySlot,
+};
+use schemars::JsonSchema;
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+use serde::Serialize;
+
+use crate::api::{ApiState, account::AuthenticatedPlayer, outfits::code::SharedOutfit};
+
+#[derive(thiserror::Error, Debug, OperationIo)]
+pub enum ResolveShareCodeError {
+	#[error("The share code is malformed")]
+	InvalidCode,
+	#[error("Unable to query database: {0}")]
+	Database(#[from] sea_orm::error::DbErr),
+}
+
+impl IntoResponse for ResolveShareCodeError {
+	fn into_response(self) -> axum::response::Response {
+		(
+			match self {
+				Self::InvalidCode => StatusCode::BAD_REQUEST,
+				Self::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+			},
+			self.to_string(),
+		)
+			.into_response()
+	}
+}
+
+fn endpoint_doc(op: TransformOperation) -> TransformOperation {
+	op.id("resolveOutfitShareCode")
+		.summary("Look up an outfit share code")
+		.description(
+			"Returns the outfit a share code stands for, split into the cosmetics \
+			 the authenticated player owns and the ones they are missing. The \
+			 Stripe price ids of the missing cosmetics that are for sale can be \
+			 passed straight to /stripe/create. Cosmetics that no longer exist are \
+			 left out.",
+		)
+		.tag("outfits")
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+struct MissingCosmetic {
+	id: i32,
+	/// None when the cosmetic isn't for sale
+	stripe_price_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+struct ResolveShareCodeResponse {
+	/// The cosmetic in each slot, leaving out cosmetics that no longer exist
+	equipped: HashMap<BodySlot, i32>,
+	particle_color: Option<i32>,
+	/// The outfit's cosmetics the authenticated player owns
+	owned: Vec<i32>,
+	/// The outfit's cosmetics the authenticated player doesn't own
+	missing: Vec<MissingCosmetic>,
+	/// The distinct Stripe price ids of the missing cosmetics
+	prices: Vec<String>,
+}
+
+pub(super) fn router() -> ApiRouter<ApiState> {
+	ApiRouter::new().api_route(
+		"/share/{code}",
+		get_with(self::endpoint, self::endpoint_doc),
+	)
+}
+
+#[tracing::instrument(level = "debug", skip(state))]
+async fn endpoint(
+	State(state): State<ApiState>,
+	AuthenticatedPlayer(player): AuthenticatedPlayer,
+	Path(code): Path<String>,
+) -> Result<Json<ResolveShareCodeResponse>, ResolveShareCodeError> {
+	let mut outfit =
+		SharedOutfit::decode(&code).ok_or(ResolveShareCodeError::InvalidCode)?;
+
+	let ids = outfit.equipped.values().copied().collect::<HashSet<_>>();
+	let cosmetics = Cosmetic::find()
+		.filter(cosmetic::Column::Id.is_in(ids))
+		.all(&state.database)
+		.await?;
+	// Codes outlive the cosmetics in them, so deleted ones are dropped
+	let known = cosmetics
+		.iter()
+		.map(|cosmetic| cosmetic.id)
+		.collect::<HashSet<_>>();
+	outfit.equipped.retain(|_, id| known.contains(id));
+	let owned = PlayerOwnedCosmetic::find()
+		.filter(player_owned_cosmetic::Column::PlayerId.eq(player.id))
+		.filter(player_owned_cosmetic::Column::CosmeticId.is_in(known))
+		.all(&state.database)
+		.await?
+		.into_iter()
+		.map(|owned| owned.cosmetic_id)
+		.collect::<BTreeSet<_>>();
+
+	let mut missing = cosmetics
+		.into_iter()
+		.filter(|cosmetic| !owned.contains(&cosmetic.id))
+		.map(|cosmetic| MissingCosmetic {
+			id: cosmetic.id,
+			// Disabled cosmetics can't be bought
+			stripe_price_id: cosmetic.stripe_price_id.filter(|_| cosmetic.enabled),
+		})
+		.collect::<Vec<_>>();
+	missing.sort_unstable_by_key(|cosmetic| cosmetic.id);
+	let prices = missing
+		.iter()
+		.filter_map(|cosmetic| cosmetic.stripe_price_id.clone())
+		.collect::<BTreeSet<_>>();
+
+	Ok(Json(ResolveShareCodeResponse {
+		equipped: outfit.equipped,
+		particle_color: outfit.particle_color,
+		owned: owned.into_iter().collect(),
+		missing,
+		prices: prices.into_iter().collect(),
+	}))
+}
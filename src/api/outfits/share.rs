@@ -0,0 +1,125 @@
+use std::collections::HashMap;
+
+use aide::{
+	OperationIo,
+	axum::{ApiRouter, routing::get_with},
+	transform::TransformOperation,
+};
+use axum::{
+	Json,
+	extract::{Query, State},
+	http::StatusCode,
+	response::IntoResponse,
+};
+use entities::{outfit, player_equipped_cosmetic, prelude::*};
+use schemars::JsonSchema;
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+use serde::{Deserialize, Serialize};
+
+use crate::api::{
+	ApiState,
+	account::AuthenticatedPlayer,
+	outfits::{code::SharedOutfit, outfit_equipment},
+};
+
+#[derive(thiserror::Error, Debug, OperationIo)]
+pub enum ShareOutfitError {
+	#[error("No outfit with that id")]
+	NotFound,
+	#[error("Unable to query database: {0}")]
+	Database(#[from] sea_orm::error::DbErr),
+}
+
+impl IntoResponse for ShareOutfitError {
+	fn into_response(self) -> axum::response::Response {
+		(
+			match self {
+				Self::NotFound => StatusCode::NOT_FOUND,
+				Self::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+			},
+			self.to_string(),
+		)
+			.into_response()
+	}
+}
+
+fn endpoint_doc(op: TransformOperation) -> TransformOperation {
+	op.id("shareOutfit")
+		.summary("Get an outfit's share code")
+		.description(
+			"Returns a short, URL-safe code for one of the authenticated player's \
+			 outfits, or for what they are wearing right now when no outfit is \
+			 given. The code carries the cosmetic in each slot and the particle \
+			 color, and anyone can look it up with /outfits/share/{code}.",
+		)
+		.tag("outfits")
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct ShareOutfitQuery {
+	/// The outfit to share, instead of the cosmetics currently equipped
+	outfit_id: Option<i32>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+struct ShareOutfitResponse {
+	code: String,
+}
+
+pub(super) fn router() -> ApiRouter<ApiState> {
+	ApiRouter::new().api_route("/share", get_with(self::endpoint, self::endpoint_doc))
+}
+
+#[tracing::instrument(level = "debug", skip(state))]
+async fn endpoint(
+	State(state): State<ApiState>,
+	AuthenticatedPlayer(player): AuthenticatedPlayer,
+	Query(query): Query<ShareOutfitQuery>,
+) -> Result<Json<ShareOutfitResponse>, ShareOutfitError> {
+	let outfit = match query.outfit_id {
+		Some(id) => {
+			let Some(outfit) = Outfit::find_by_id(id)
+				.filter(outfit::Column::PlayerId.eq(player.id))
+				.one(&state.database)
+				.await?
+			else {
+				return Err(ShareOutfitError::NotFound);
+			};
+			SharedOutfit {
+				equipped: outfit_equipment(&outfit),
+				particle_color: outfit.particle_color,
+			}
+		}
+		None => {
+			// Connected players' runtime state is ahead of the database, which
+			// is written to in the background
+			let runtime = state
+				.realtime
+				.player_runtime
+				.read()
+				.await
+				.get(&player.minecraft_uuid)
+				.cloned();
+			match runtime {
+				Some(runtime) => SharedOutfit {
+					equipped: runtime.equipped,
+					particle_color: runtime.particle_color,
+				},
+				None => SharedOutfit {
+					equipped: PlayerEquippedCosmetic::find()
+						.filter(player_equipped_cosmetic::Column::PlayerId.eq(player.id))
+						.all(&state.database)
+						.await?
+						.into_iter()
+						.map(|equipment| (equipment.slot, equipment.cosmetic_id))
+						.collect::<HashMap<_, _>>(),
+					particle_color: player.particle_color,
+				},
+			}
+		}
+	};
+
+	Ok(Json(ShareOutfitResponse {
+		code: outfit.encode(),
+	}))
+}
@@ -0,0 +1,98 @@
+use aide::{
+	OperationIo,
+	axum::{ApiRouter, routing::put_with},
+	transform::TransformOperation,
+};
+use axum::{
+	Json,
+	extract::{Path, State},
+	http::StatusCode,
+	response::IntoResponse,
+};
+use chrono::Utc;
+use entities::{outfit, prelude::*};
+use sea_orm::{
+	ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set, TransactionTrait,
+};
+
+use crate::api::{
+	ApiState,
+	account::AuthenticatedPlayer,
+	outfits::{InvalidOutfit, OutfitBody, OutfitInfo, name_taken},
+};
+
+#[derive(thiserror::Error, Debug, OperationIo)]
+pub enum UpdateOutfitError {
+	#[error("No outfit with that id")]
+	NotFound,
+	#[error("{0}")]
+	Invalid(#[from] InvalidOutfit),
+	#[error("An outfit with that name already exists")]
+	NameTaken,
+	#[error("Unable to query database: {0}")]
+	Database(#[from] sea_orm::error::DbErr),
+}
+
+impl IntoResponse for UpdateOutfitError {
+	fn into_response(self) -> axum::response::Response {
+		(
+			match self {
+				Self::NotFound => StatusCode::NOT_FOUND,
+				Self::Invalid(InvalidOutfit::Database(_)) | Self::Database(_) => {
+					StatusCode::INTERNAL_SERVER_ERROR
+				}
+				Self::Invalid(_) => StatusCode::BAD_REQUEST,
+				Self::NameTaken => StatusCode::CONFLICT,
+			},
+			self.to_string(),
+		)
+			.into_response()
+	}
+}
+
+fn endpoint_doc(op: TransformOperation) -> TransformOperation {
+	op.id("updateOutfit")
+		.summary("Replace a saved outfit")
+		.description(
+			"Replaces the name, cosmetics and particle color of one of the \
+			 authenticated player's outfits. Applying the outfit in game \
+			 afterwards uses the new contents.",
+		)
+		.tag("outfits")
+}
+
+pub(super) fn router() -> ApiRouter<ApiState> {
+	ApiRouter::new().api_route("/{id}", put_with(self::endpoint, self::endpoint_doc))
+}
+
+#[tracing::instrument(level = "debug", skip(state))]
+async fn endpoint(
+	State(state): State<ApiState>,
+	AuthenticatedPlayer(player): AuthenticatedPlayer,
+	Path(id): Path<i32>,
+	Json(body): Json<OutfitBody>,
+) -> Result<Json<OutfitInfo>, UpdateOutfitError> {
+	let txn = state.database.begin().await?;
+	let Some(existing) = Outfit::find_by_id(id)
+		.filter(outfit::Column::PlayerId.eq(player.id))
+		.one(&txn)
+		.await?
+	else {
+		return Err(UpdateOutfitError::NotFound);
+	};
+
+	let name = body.validate(&txn, player.id).await?;
+	if name_taken(&txn, player.id, &name, Some(id)).await? {
+		return Err(UpdateOutfitError::NameTaken);
+	}
+
+	let mut active: outfit::ActiveModel = existing.into();
+	active.name = Set(name);
+	active.equipped = Set(serde_json::json!(body.equipped));
+	active.particle_color = Set(body.particle_color);
+	active.updated_at = Set(Utc::now().fixed_offset());
+	let outfit = active.update(&txn).await?;
+	txn.commit().await?;
+
+	Ok(Json(outfit.into()))
+}
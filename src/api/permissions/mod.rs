@@ -0,0 +1,68 @@
+mod player;
+mod roles;
+mod set_override;
+mod set_role;
+
+use aide::axum::ApiRouter;
+use entities::{
+	prelude::*,
+	sea_orm_active_enums::{Permission, PlayerRole},
+	user, user_permission_override,
+};
+use schemars::JsonSchema;
+use sea_orm::{ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::api::{ApiState, account::permissions::player_permissions};
+
+/// A permission granted to, or revoked from, a single player regardless of
+/// their role
+#[derive(Debug, Serialize, JsonSchema)]
+struct PermissionOverride {
+	permission: Permission,
+	granted: bool,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+struct PlayerPermissions {
+	player: Uuid,
+	role: PlayerRole,
+	/// Every permission the player holds, after applying their overrides
+	permissions: Vec<Permission>,
+	overrides: Vec<PermissionOverride>,
+}
+
+impl PlayerPermissions {
+	async fn load(db: &impl ConnectionTrait, player: user::Model) -> Result<Self, DbErr> {
+		let permissions = player_permissions(db, &player).await?;
+		let overrides = UserPermissionOverride::find()
+			.filter(user_permission_override::Column::PlayerId.eq(player.id))
+			.all(db)
+			.await?
+			.into_iter()
+			.map(|o| PermissionOverride {
+				permission: o.permission,
+				granted: o.granted,
+			})
+			.collect();
+
+		Ok(Self {
+			player: player.minecraft_uuid,
+			role: player.role,
+			permissions,
+			overrides,
+		})
+	}
+}
+
+pub(super) async fn setup_router() -> ApiRouter<ApiState> {
+	ApiRouter::new().nest(
+		"/permissions",
+		ApiRouter::new()
+			.merge(roles::router())
+			.merge(set_role::router())
+			.merge(player::router())
+			.merge(set_override::router()),
+	)
+}
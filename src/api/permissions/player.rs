@@ -0,0 +1,77 @@
+use aide::{
+	OperationIo,
+	axum::{ApiRouter, routing::get_with},
+	transform::TransformOperation,
+};
+use axum::{
+	Json,
+	extract::{Path, State},
+	http::StatusCode,
+	response::IntoResponse,
+};
+use entities::{prelude::*, user};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+use uuid::Uuid;
+
+use crate::api::{
+	ApiState,
+	account::permissions::{ManageRoles, RequirePermission},
+	permissions::PlayerPermissions,
+};
+
+#[derive(thiserror::Error, Debug, OperationIo)]
+pub enum PlayerPermissionsError {
+	#[error("The requested player does not exist")]
+	PlayerMissing,
+	#[error("Unable to query database: {0}")]
+	Database(#[from] sea_orm::error::DbErr),
+}
+
+impl IntoResponse for PlayerPermissionsError {
+	fn into_response(self) -> axum::response::Response {
+		(
+			match self {
+				Self::PlayerMissing => StatusCode::NOT_FOUND,
+				Self::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+			},
+			self.to_string(),
+		)
+			.into_response()
+	}
+}
+
+fn endpoint_doc(op: TransformOperation) -> TransformOperation {
+	op.id("getPlayerPermissions")
+		.summary("Get player permissions")
+		.description(
+			"Gets the permissions a player holds, and the overrides they have on top \
+			 of their role. Requires the `manage_roles` permission.",
+		)
+		.tag("permissions")
+}
+
+pub(super) fn router() -> ApiRouter<ApiState> {
+	ApiRouter::new().api_route(
+		"/players/{player}",
+		get_with(self::endpoint, self::endpoint_doc),
+	)
+}
+
+#[tracing::instrument(level = "debug", skip(state))]
+async fn endpoint(
+	State(state): State<ApiState>,
+	RequirePermission(_admin, ..): RequirePermission<ManageRoles>,
+	Path(player): Path<Uuid>,
+) -> Result<Json<PlayerPermissions>, PlayerPermissionsError> {
+	let Some(player) = User::find()
+		.filter(user::Column::MinecraftUuid.eq(player))
+		.one(&state.database)
+		.await?
+	else {
+		return Err(PlayerPermissionsError::PlayerMissing);
+	};
+
+	Ok(Json(
+		PlayerPermissions::load(&state.database, player).await?,
+	))
+}
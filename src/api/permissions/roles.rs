@@ -0,0 +1,87 @@
+use std::collections::HashSet;
+
+use aide::{
+	OperationIo,
+	axum::{ApiRouter, routing::get_with},
+	transform::TransformOperation,
+};
+use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
+use entities::{
+	prelude::*,
+	sea_orm_active_enums::{Permission, PlayerRole},
+};
+use schemars::JsonSchema;
+use sea_orm::{EntityTrait, Iterable};
+use serde::Serialize;
+
+use crate::api::{
+	ApiState,
+	account::permissions::{ManageRoles, RequirePermission},
+};
+
+#[derive(thiserror::Error, Debug, OperationIo)]
+pub enum RolesError {
+	#[error("Unable to query database: {0}")]
+	Database(#[from] sea_orm::error::DbErr),
+}
+
+impl IntoResponse for RolesError {
+	fn into_response(self) -> axum::response::Response {
+		(
+			match self {
+				Self::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+			},
+			self.to_string(),
+		)
+			.into_response()
+	}
+}
+
+fn endpoint_doc(op: TransformOperation) -> TransformOperation {
+	op.id("listRolePermissions")
+		.summary("List role permissions")
+		.description(
+			"Lists the permissions granted to every role. Requires the `manage_roles` \
+			 permission.",
+		)
+		.tag("permissions")
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub(super) struct RolePermissions {
+	pub(super) role: PlayerRole,
+	pub(super) permissions: Vec<Permission>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+struct RolesResponse {
+	roles: Vec<RolePermissions>,
+}
+
+pub(super) fn router() -> ApiRouter<ApiState> {
+	ApiRouter::new().api_route("/roles", get_with(self::endpoint, self::endpoint_doc))
+}
+
+#[tracing::instrument(level = "debug", skip(state))]
+async fn endpoint(
+	State(state): State<ApiState>,
+	RequirePermission(_admin, ..): RequirePermission<ManageRoles>,
+) -> Result<Json<RolesResponse>, RolesError> {
+	let mappings = RolePermission::find()
+		.all(&state.database)
+		.await?
+		.into_iter()
+		.map(|mapping| (mapping.role, mapping.permission))
+		.collect::<HashSet<_>>();
+
+	let roles = PlayerRole::iter()
+		.map(|role| RolePermissions {
+			permissions: Permission::iter()
+				.filter(|permission| mappings.contains(&(role.clone(), *permission)))
+				.collect(),
+			role,
+		})
+		.collect();
+
+	Ok(Json(RolesResponse { roles }))
+}
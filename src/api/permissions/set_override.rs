@@ -0,0 +1,137 @@
+use aide::{
+	OperationIo,
+	axum::{ApiRouter, routing::put_with},
+	transform::TransformOperation,
+};
+use axum::{
+	Json,
+	extract::{Path, State},
+	http::StatusCode,
+	response::IntoResponse,
+};
+use entities::{
+	prelude::*, sea_orm_active_enums::Permission, user, user_permission_override,
+};
+use schemars::JsonSchema;
+use sea_orm::{
+	ActiveEnum as _, ColumnTrait, EntityTrait, QueryFilter, Set, TransactionTrait,
+	sea_query::OnConflict,
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::api::{
+	ApiState,
+	account::permissions::{ManageRoles, RequirePermission},
+	audit::{self, AuditEntry},
+	permissions::PlayerPermissions,
+};
+
+#[derive(thiserror::Error, Debug, OperationIo)]
+pub enum SetOverrideError {
+	#[error("The requested player does not exist")]
+	PlayerMissing,
+	#[error("Unable to query database: {0}")]
+	Database(#[from] sea_orm::error::DbErr),
+}
+
+impl IntoResponse for SetOverrideError {
+	fn into_response(self) -> axum::response::Response {
+		(
+			match self {
+				Self::PlayerMissing => StatusCode::NOT_FOUND,
+				Self::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+			},
+			self.to_string(),
+		)
+			.into_response()
+	}
+}
+
+fn endpoint_doc(op: TransformOperation) -> TransformOperation {
+	op.id("setPlayerPermissionOverride")
+		.summary("Override a player permission")
+		.description(
+			"Grants or revokes a single permission for a player, regardless of their \
+			 role. Setting `granted` to null removes the override, so the player falls \
+			 back to their role. Requires the `manage_roles` permission.",
+		)
+		.tag("permissions")
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct SetOverrideRequest {
+	permission: Permission,
+	/// Whether the permission is granted or revoked. Null removes the override.
+	granted: Option<bool>,
+}
+
+pub(super) fn router() -> ApiRouter<ApiState> {
+	ApiRouter::new().api_route(
+		"/players/{player}",
+		put_with(self::endpoint, self::endpoint_doc),
+	)
+}
+
+#[tracing::instrument(level = "debug", skip(state, actor))]
+async fn endpoint(
+	State(state): State<ApiState>,
+	RequirePermission(_admin, actor, ..): RequirePermission<ManageRoles>,
+	Path(player_uuid): Path<Uuid>,
+	Json(body): Json<SetOverrideRequest>,
+) -> Result<Json<PlayerPermissions>, SetOverrideError> {
+	let Some(player) = User::find()
+		.filter(user::Column::MinecraftUuid.eq(player_uuid))
+		.one(&state.database)
+		.await?
+	else {
+		return Err(SetOverrideError::PlayerMissing);
+	};
+	let txn = state.database.begin().await?;
+	let previous = UserPermissionOverride::find_by_id((player.id, body.permission))
+		.one(&txn)
+		.await?
+		.map(|row| row.granted);
+
+	match body.granted {
+		Some(granted) => {
+			UserPermissionOverride::insert(user_permission_override::ActiveModel {
+				player_id: Set(player.id),
+				permission: Set(body.permission),
+				granted: Set(granted),
+			})
+			.on_conflict(
+				OnConflict::columns([
+					user_permission_override::Column::PlayerId,
+					user_permission_override::Column::Permission,
+				])
+				.update_column(user_permission_override::Column::Granted)
+				.to_owned(),
+			)
+			.exec(&txn)
+			.await?;
+		}
+		None => {
+			UserPermissionOverride::delete_by_id((player.id, body.permission))
+				.exec(&txn)
+				.await?;
+		}
+	}
+
+	// Keyed by permission, so the entry still names it after unchanged fields
+	// are dropped
+	let permission = body.permission.to_value();
+	audit::record(
+		&txn,
+		&actor,
+		AuditEntry::new("player.set_permission_override", "player", player_uuid)
+			.before(serde_json::json!({ permission.clone(): previous }))
+			.after(serde_json::json!({ permission: body.granted })),
+	)
+	.await?;
+	txn.commit().await?;
+
+	Ok(Json(
+		PlayerPermissions::load(&state.database, player).await?,
+	))
+}
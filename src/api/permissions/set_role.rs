@@ -0,0 +1,149 @@
+use std::collections::HashSet;
+
+use aide::{
+	OperationIo,
+	axum::{ApiRouter, routing::put_with},
+	transform::TransformOperation,
+};
+use axum::{
+	Json,
+	extract::{Path, State},
+	http::StatusCode,
+	response::IntoResponse,
+};
+use entities::{
+	prelude::*,
+	role_permission,
+	sea_orm_active_enums::{Permission, PlayerRole},
+};
+use schemars::JsonSchema;
+use sea_orm::{
+	ActiveEnum as _, ColumnTrait, EntityTrait, Iterable, QueryFilter, QuerySelect,
+	Set, TransactionTrait,
+};
+use serde::Deserialize;
+
+use crate::api::{
+	ApiState,
+	account::permissions::{ManageRoles, RequirePermission},
+	audit::{self, AuditEntry},
+	permissions::roles::RolePermissions,
+};
+
+#[derive(thiserror::Error, Debug, OperationIo)]
+pub enum SetRoleError {
+	#[error("Unable to query database: {0}")]
+	Database(#[from] sea_orm::error::DbErr),
+	#[error("No other role has the manage_roles permission, so it can't be removed")]
+	LastRoleManager,
+}
+
+impl IntoResponse for SetRoleError {
+	fn into_response(self) -> axum::response::Response {
+		(
+			match self {
+				Self::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+				Self::LastRoleManager => StatusCode::CONFLICT,
+			},
+			self.to_string(),
+		)
+			.into_response()
+	}
+}
+
+fn endpoint_doc(op: TransformOperation) -> TransformOperation {
+	op.id("setRolePermissions")
+		.summary("Set role permissions")
+		.description(
+			"Replaces the permissions granted to every player with a role. Per-player \
+			 overrides still apply on top. Requires the `manage_roles` permission.",
+		)
+		.tag("permissions")
+		.response_with::<{ StatusCode::CONFLICT.as_u16() }, String, _>(|res| {
+			res.description(
+				"The update would leave no role with the `manage_roles` permission",
+			)
+		})
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct SetRoleRequest {
+	permissions: HashSet<Permission>,
+}
+
+pub(super) fn router() -> ApiRouter<ApiState> {
+	ApiRouter::new().api_route(
+		"/roles/{role}",
+		put_with(self::endpoint, self::endpoint_doc),
+	)
+}
+
+#[tracing::instrument(level = "debug", skip(state, actor))]
+async fn endpoint(
+	State(state): State<ApiState>,
+	RequirePermission(_admin, actor, ..): RequirePermission<ManageRoles>,
+	Path(role): Path<PlayerRole>,
+	Json(body): Json<SetRoleRequest>,
+) -> Result<Json<RolePermissions>, SetRoleError> {
+	let txn = state.database.begin().await?;
+	let previous = RolePermission::find()
+		.filter(role_permission::Column::Role.eq(role.clone()))
+		.all(&txn)
+		.await?
+		.into_iter()
+		.map(|row| row.permission)
+		.collect::<HashSet<_>>();
+	if previous.contains(&Permission::ManageRoles)
+		&& !body.permissions.contains(&Permission::ManageRoles)
+	{
+		// Locked so that two updates can't each remove it from one of the last
+		// two roles
+		let other_managers = RolePermission::find()
+			.filter(role_permission::Column::Permission.eq(Permission::ManageRoles))
+			.filter(role_permission::Column::Role.ne(role.clone()))
+			.lock_exclusive()
+			.all(&txn)
+			.await?;
+		if other_managers.is_empty() {
+			return Err(SetRoleError::LastRoleManager);
+		}
+	}
+	// Listed in declaration order, so unchanged sets compare equal in the log
+	let listed = |permissions: &HashSet<Permission>| {
+		serde_json::json!({
+			"permissions": Permission::iter()
+				.filter(|permission| permissions.contains(permission))
+				.collect::<Vec<_>>(),
+		})
+	};
+	RolePermission::delete_many()
+		.filter(role_permission::Column::Role.eq(role.clone()))
+		.exec(&txn)
+		.await?;
+	if !body.permissions.is_empty() {
+		RolePermission::insert_many(body.permissions.iter().map(|permission| {
+			role_permission::ActiveModel {
+				role: Set(role.clone()),
+				permission: Set(*permission),
+			}
+		}))
+		.exec(&txn)
+		.await?;
+	}
+	audit::record(
+		&txn,
+		&actor,
+		AuditEntry::new("role.set_permissions", "role", role.to_value())
+			.before(listed(&previous))
+			.after(listed(&body.permissions)),
+	)
+	.await?;
+	txn.commit().await?;
+
+	Ok(Json(RolePermissions {
+		permissions: Permission::iter()
+			.filter(|permission| body.permissions.contains(permission))
+			.collect(),
+		role,
+	}))
+}
@@ -0,0 +1,86 @@
+use aide::{
+	OperationIo,
+	axum::{ApiRouter, routing::get_with},
+	transform::TransformOperation,
+};
+use axum::{
+	Json,
+	extract::{Query, State},
+	http::StatusCode,
+	response::IntoResponse,
+};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::api::{
+	ApiState,
+	account::AuthenticatedPlayer,
+	players::profile::{Profile, ProfileError},
+};
+
+#[derive(thiserror::Error, Debug, OperationIo)]
+pub enum LookupError {
+	#[error("Exactly one of `name` or `uuid` must be given")]
+	InvalidQuery,
+	#[error("No player with that name or UUID exists")]
+	NotFound,
+	#[error("Unable to look up the player: {0}")]
+	Profile(#[from] ProfileError),
+}
+
+impl IntoResponse for LookupError {
+	fn into_response(self) -> axum::response::Response {
+		(
+			match self {
+				Self::InvalidQuery => StatusCode::BAD_REQUEST,
+				Self::NotFound => StatusCode::NOT_FOUND,
+				Self::Profile(ProfileError::Request(_)) => StatusCode::BAD_GATEWAY,
+				Self::Profile(ProfileError::Database(_)) => {
+					StatusCode::INTERNAL_SERVER_ERROR
+				}
+			},
+			self.to_string(),
+		)
+			.into_response()
+	}
+}
+
+fn endpoint_doc(op: TransformOperation) -> TransformOperation {
+	op.id("lookupPlayer")
+		.summary("Look up a player")
+		.description(
+			"Resolves a Minecraft username to the player's UUID, or a UUID to the \
+			 player's current username. Players don't need to have used Poly+ to be \
+			 found.",
+		)
+		.tag("players")
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct LookupQuery {
+	/// The current username of the player to look up
+	#[schemars(example = &"Hypixel")]
+	name: Option<String>,
+	/// The UUID of the player to look up
+	uuid: Option<Uuid>,
+}
+
+pub(super) fn router() -> ApiRouter<ApiState> {
+	ApiRouter::new().api_route("/lookup", get_with(self::endpoint, self::endpoint_doc))
+}
+
+#[tracing::instrument(level = "debug", skip(state))]
+async fn endpoint(
+	State(state): State<ApiState>,
+	_player: AuthenticatedPlayer,
+	Query(query): Query<LookupQuery>,
+) -> Result<Json<Profile>, LookupError> {
+	let profile = match (query.name, query.uuid) {
+		(Some(name), None) => state.profiles.by_name(&state.database, &name).await?,
+		(None, Some(uuid)) => state.profiles.by_id(&state.database, uuid).await?,
+		_ => return Err(LookupError::InvalidQuery),
+	};
+
+	profile.map(Json).ok_or(LookupError::NotFound)
+}
@@ -0,0 +1,16 @@
+mod lookup;
+pub(crate) mod profile;
+mod role;
+
+use aide::axum::ApiRouter;
+
+use crate::api::ApiState;
+
+pub(super) async fn setup_router() -> ApiRouter<ApiState> {
+	ApiRouter::new().nest(
+		"/players",
+		ApiRouter::new()
+			.merge(lookup::router())
+			.merge(role::router()),
+	)
+}
@@ -0,0 +1,269 @@
+//! Resolves Minecraft usernames to UUIDs and back. Profiles are looked up
+//! through Mojang's profile API, or any server implementing the same lookups,
+//! and cached in memory. Each player's last seen username is kept in
+//! `user.last_known_name`, which is used when the upstream can't be reached
+//! and to show names next to UUIDs without any lookups.
+
+use std::{
+	collections::{HashMap, HashSet},
+	time::Duration,
+};
+
+use entities::{prelude::*, user};
+use moka::future::Cache;
+use reqwest::{Client, StatusCode};
+use schemars::JsonSchema;
+use sea_orm::{
+	ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter,
+	sea_query::{Expr, Func},
+};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+use uuid::Uuid;
+
+pub const MOJANG_PROFILE_API_URL: &str = "https://api.minecraftservices.com";
+
+/// How long a resolved profile is trusted before it is looked up again
+const PROFILE_CACHE_TTL: Duration = Duration::from_hours(1);
+
+/// A player's UUID along with their current username
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct Profile {
+	pub(crate) id: Uuid,
+	pub(crate) name: String,
+}
+
+/// A player given by either their UUID or their current username
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub(crate) enum PlayerRef {
+	Id(Uuid),
+	Name(String),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub(crate) enum ProfileError {
+	#[error("Unable to reach the profile API: {0}")]
+	Request(#[from] reqwest::Error),
+	#[error("Unable to query database: {0}")]
+	Database(#[from] DbErr),
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct ProfileResolver {
+	client: Client,
+	base_url: String,
+	by_id: Cache<Uuid, Profile>,
+	/// Keyed by lowercase username
+	by_name: Cache<String, Profile>,
+}
+
+impl ProfileResolver {
+	pub(crate) fn new(client: Client, base_url: &str) -> Self {
+		Self {
+			client,
+			base_url: base_url.trim_end_matches('/').to_owned(),
+			by_id: Cache::builder()
+				.max_capacity(100_000)
+				.time_to_live(PROFILE_CACHE_TTL)
+				.build(),
+			by_name: Cache::builder()
+				.max_capacity(100_000)
+				.time_to_live(PROFILE_CACHE_TTL)
+				.build(),
+		}
+	}
+
+	/// Looks up the profile of a player by UUID
+	pub(crate) async fn by_id(
+		&self,
+		db: &impl ConnectionTrait,
+		id: Uuid,
+	) -> Result<Option<Profile>, ProfileError> {
+		if let Some(profile) = self.by_id.get(&id).await {
+			return Ok(Some(profile));
+		}
+
+		let url = format!("{}/minecraft/profile/lookup/{}", self.base_url, id.simple());
+		match self.fetch(&url).await {
+			Ok(Some(profile)) => {
+				self.update(db, profile.clone()).await?;
+				Ok(Some(profile))
+			}
+			Ok(None) => Ok(None),
+			Err(error) => {
+				let Some(name) = User::find()
+					.filter(user::Column::MinecraftUuid.eq(id))
+					.one(db)
+					.await?
+					.and_then(|player| player.last_known_name)
+				else {
+					return Err(error.into());
+				};
+				warn!(
+					"Unable to look up profile {id}, using its last known name: {error}"
+				);
+				Ok(Some(Profile { id, name }))
+			}
+		}
+	}
+
+	/// Looks up the profile of a player by their current username
+	pub(crate) async fn by_name(
+		&self,
+		db: &impl ConnectionTrait,
+		name: &str,
+	) -> Result<Option<Profile>, ProfileError> {
+		if !valid_username(name) {
+			return Ok(None);
+		}
+		if let Some(profile) = self.by_name.get(&name.to_lowercase()).await {
+			return Ok(Some(profile));
+		}
+
+		let url = format!("{}/minecraft/profile/lookup/name/{name}", self.base_url);
+		match self.fetch(&url).await {
+			Ok(Some(profile)) => {
+				self.update(db, profile.clone()).await?;
+				Ok(Some(profile))
+			}
+			Ok(None) => Ok(None),
+			Err(error) => {
+				let Some(player) = User::find()
+					.filter(
+						Expr::expr(Func::lower(Expr::col(user::Column::LastKnownName)))
+							.eq(name.to_lowercase()),
+					)
+					.one(db)
+					.await?
+				else {
+					return Err(error.into());
+				};
+				warn!(
+					"Unable to look up profile {name}, using the last known owner: {error}"
+				);
+				Ok(Some(Profile {
+					id: player.minecraft_uuid,
+					name: player.last_known_name.unwrap_or_else(|| name.to_owned()),
+				}))
+			}
+		}
+	}
+
+	/// Resolves a player given by UUID or username to their UUID. Players
+	/// given by UUID are taken as is, without checking they exist.
+	pub(crate) async fn resolve(
+		&self,
+		db: &impl ConnectionTrait,
+		player: &PlayerRef,
+	) -> Result<Option<Uuid>, ProfileError> {
+		match player {
+			PlayerRef::Id(id) => Ok(Some(*id)),
+			PlayerRef::Name(name) => {
+				Ok(self.by_name(db, name).await?.map(|profile| profile.id))
+			}
+		}
+	}
+
+	/// Records a profile seen elsewhere, like in a verified login, caching it
+	/// and storing the name as the player's last known name
+	pub(crate) async fn update(
+		&self,
+		db: &impl ConnectionTrait,
+		profile: Profile,
+	) -> Result<(), DbErr> {
+		// Names can be taken by someone else once they are changed, so the name
+		// is only ever the last known name of one player
+		User::update_many()
+			.col_expr(
+				user::Column::LastKnownName,
+				Expr::value(Option::<String>::None),
+			)
+			.filter(
+				Expr::expr(Func::lower(Expr::col(user::Column::LastKnownName)))
+					.eq(profile.name.to_lowercase()),
+			)
+			.filter(user::Column::MinecraftUuid.ne(profile.id))
+			.exec(db)
+			.await?;
+		User::update_many()
+			.col_expr(
+				user::Column::LastKnownName,
+				Expr::value(profile.name.clone()),
+			)
+			.filter(user::Column::MinecraftUuid.eq(profile.id))
+			.exec(db)
+			.await?;
+
+		self.by_name
+			.insert(profile.name.to_lowercase(), profile.clone())
+			.await;
+		self.by_id.insert(profile.id, profile).await;
+		Ok(())
+	}
+
+	/// Fetches a profile from the upstream, which answers unknown players with
+	/// a 404
+	async fn fetch(&self, url: &str) -> Result<Option<Profile>, reqwest::Error> {
+		let response = self.client.get(url).send().await?;
+		if response.status() == StatusCode::NOT_FOUND {
+			return Ok(None);
+		}
+		Ok(Some(response.error_for_status()?.json().await?))
+	}
+}
+
+/// Whether a string could be a Minecraft username, so lookups of anything else
+/// never reach the upstream
+fn valid_username(name: &str) -> bool {
+	(1..=16).contains(&name.len())
+		&& name.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_')
+}
+
+/// The last known names of players, for showing next to their UUIDs. Players
+/// without a known name are left out.
+pub(crate) async fn known_names(
+	db: &impl ConnectionTrait,
+	players: HashSet<Uuid>,
+) -> Result<HashMap<Uuid, String>, DbErr> {
+	if players.is_empty() {
+		return Ok(HashMap::new());
+	}
+
+	Ok(User::find()
+		.filter(user::Column::MinecraftUuid.is_in(players))
+		.filter(user::Column::LastKnownName.is_not_null())
+		.all(db)
+		.await?
+		.into_iter()
+		.filter_map(|player| Some((player.minecraft_uuid, player.last_known_name?)))
+		.collect())
+}
+
+#[cfg(test)]
+mod tests {
+	use uuid::Uuid;
+
+	use super::{PlayerRef, valid_username};
+
+	#[test]
+	fn validates_usernames() {
+		assert!(valid_username("Hypixel"));
+		assert!(valid_username("a_b_1"));
+		assert!(!valid_username(""));
+		assert!(!valid_username("seventeen_chars__"));
+		assert!(!valid_username("../profile"));
+	}
+
+	#[test]
+	fn parses_players_by_uuid_or_name() {
+		let id = Uuid::nil();
+		let player: PlayerRef =
+			serde_json::from_value(serde_json::json!(id)).expect("uuid should parse");
+		assert!(matches!(player, PlayerRef::Id(parsed) if parsed == id));
+
+		let player: PlayerRef = serde_json::from_value(serde_json::json!("Hypixel"))
+			.expect("name should parse");
+		assert!(matches!(player, PlayerRef::Name(name) if name == "Hypixel"));
+	}
+}
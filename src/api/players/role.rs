@@ -0,0 +1,108 @@
+use aide::{
+	OperationIo,
+	axum::{ApiRouter, routing::put_with},
+	transform::TransformOperation,
+};
+use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
+use entities::sea_orm_active_enums::PlayerRole;
+use schemars::JsonSchema;
+use sea_orm::{
+	ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set, TransactionTrait,
+};
+use serde::Deserialize;
+
+use crate::api::{
+	ApiState,
+	account::permissions::{ManageRoles, RequirePermission},
+	audit::{self, AuditEntry, model_json},
+	players::profile::{PlayerRef, ProfileError},
+};
+
+#[derive(thiserror::Error, Debug, OperationIo)]
+pub enum RoleError {
+	#[error("The requested player does not exist")]
+	PlayerMissing,
+	#[error("Unable to resolve the player: {0}")]
+	Profile(#[from] ProfileError),
+	#[error("Unable to query database: {0}")]
+	Database(#[from] sea_orm::error::DbErr),
+}
+
+impl IntoResponse for RoleError {
+	fn into_response(self) -> axum::response::Response {
+		(
+			match self {
+				Self::PlayerMissing => StatusCode::NOT_FOUND,
+				Self::Profile(ProfileError::Request(_)) => StatusCode::BAD_GATEWAY,
+				Self::Profile(ProfileError::Database(_)) | Self::Database(_) => {
+					StatusCode::INTERNAL_SERVER_ERROR
+				}
+			},
+			self.to_string(),
+		)
+			.into_response()
+	}
+}
+
+fn endpoint_doc(op: TransformOperation) -> TransformOperation {
+	op.id("setPlayerRole")
+		.summary("Set a player role")
+		.description(
+			"Sets a player's role. The player can be given by UUID or by current \
+			 username. Requires the `manage_roles` permission.",
+		)
+		.tag("players")
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+struct RoleRequest {
+	/// The player's UUID or current username
+	player: PlayerRef,
+	role: PlayerRole,
+}
+
+pub(super) fn router() -> ApiRouter<ApiState> {
+	ApiRouter::new().api_route("/role", put_with(self::endpoint, self::endpoint_doc))
+}
+
+#[tracing::instrument(level = "debug", skip(state, actor))]
+async fn endpoint(
+	State(state): State<ApiState>,
+	RequirePermission(_admin, actor, ..): RequirePermission<ManageRoles>,
+	Json(body): Json<RoleRequest>,
+) -> Result<StatusCode, RoleError> {
+	use entities::{prelude::*, user};
+
+	let Some(player_uuid) = state
+		.profiles
+		.resolve(&state.database, &body.player)
+		.await?
+	else {
+		return Err(RoleError::PlayerMissing);
+	};
+	let txn = state.database.begin().await?;
+	let Some(player) = User::find()
+		.filter(user::Column::MinecraftUuid.eq(player_uuid))
+		.one(&txn)
+		.await?
+	else {
+		return Err(RoleError::PlayerMissing);
+	};
+
+	let before = model_json(&player);
+	let mut player: user::ActiveModel = player.into();
+	player.role = Set(body.role);
+	let player = player.update(&txn).await?;
+
+	audit::record(
+		&txn,
+		&actor,
+		AuditEntry::new("player.set_role", "player", player_uuid)
+			.before(before)
+			.after(model_json(&player)),
+	)
+	.await?;
+	txn.commit().await?;
+
+	Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,353 @@
+//! Keeps [RealtimeState::online] right across backend instances. Presence
+//! events keep it up to date while instances run, but an instance that stops
+//! without closing its connections never sends their offline events, and an
+//! instance that just started missed the events of players that were already
+//! connected. So each instance also stores the players connected to it, next
+//! to a heartbeat. Instances load every live instance's players when they
+//! start or may have missed events, and take the players of an instance
+//! offline once its heartbeat lapses. The same goes for the servers players
+//! are on and the pending emote invites, which instances only hear about
+//! through events otherwise.
+
+use std::{
+	collections::{HashMap, HashSet},
+	time::Duration,
+};
+
+use chrono::Utc;
+use entities::{emote_invite, player_presence, prelude::*, realtime_instance};
+use sea_orm::{
+	ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter, QuerySelect, Set,
+	sea_query::{Expr, OnConflict},
+};
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::api::{
+	realtime::RealtimeEvent,
+	state::{EmoteInvite, InstanceId, RealtimeState},
+};
+
+/// How often instances send a heartbeat
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(10);
+/// How long after its last heartbeat an instance is considered gone
+const INSTANCE_TTL_SECONDS: i32 = 30;
+
+/// Registers this instance, and loads the players connected to every other
+/// live instance along with their servers and pending emote invites
+pub(super) async fn register_instance(realtime: &RealtimeState) -> Result<(), DbErr> {
+	let db = &realtime.database;
+	reap_instances(db).await?;
+	RealtimeInstance::insert(realtime_instance::ActiveModel {
+		id: Set(realtime.instance_id),
+		..Default::default()
+	})
+	.exec(db)
+	.await?;
+	synchronize(realtime).await
+}
+
+/// Brings what this instance knows of other instances' players in line with
+/// what they stored, for when it starts or may have missed their events
+pub(super) async fn synchronize(realtime: &RealtimeState) -> Result<(), DbErr> {
+	let db = &realtime.database;
+	let presence = PlayerPresence::find().all(db).await?;
+	let invites = emote_invite::Entity::find()
+		.filter(emote_invite::Column::ExpiresAt.gt(Utc::now()))
+		.all(db)
+		.await?;
+
+	// This instance's own players are known better from its connections
+	let stored = presence
+		.iter()
+		.map(|presence| (presence.player, presence.instance_id))
+		.filter(|(_, instance)| *instance != realtime.instance_id)
+		.collect::<HashSet<_>>();
+	let known = presence_pairs(&*realtime.online.read().await)
+		.filter(|(_, instance)| *instance != realtime.instance_id)
+		.collect::<HashSet<_>>();
+	let changes = known
+		.difference(&stored)
+		.map(|(player, instance)| (*player, *instance, false))
+		.chain(
+			stored
+				.difference(&known)
+				.map(|(player, instance)| (*player, *instance, true)),
+		)
+		.collect::<Vec<_>>();
+	for (player, instance, online) in changes {
+		realtime
+			.apply(RealtimeEvent::Presence {
+				player,
+				instance,
+				online,
+			})
+			.await;
+	}
+
+	let mut servers = HashMap::<Uuid, Option<String>>::new();
+	for presence in presence {
+		let server = servers.entry(presence.player).or_default();
+		if presence.server.is_some() {
+			*server = presence.server;
+		}
+	}
+	for (player, server) in servers {
+		let runtime = realtime.player_runtime.read().await.get(&player).cloned();
+		match runtime {
+			// Connections may be watching the player through their server
+			Some(mut runtime) => {
+				runtime.server = server;
+				realtime
+					.apply(RealtimeEvent::ServerChanged { player, runtime })
+					.await;
+			}
+			None => {
+				realtime.servers.write().await.move_player(player, server);
+			}
+		}
+	}
+
+	// Invites stay stored until they expire, even once either player went
+	// offline
+	let invites = {
+		let online = realtime.online.read().await;
+		invites
+			.into_iter()
+			.filter(|invite| {
+				online.contains_key(&invite.initiator)
+					&& online.contains_key(&invite.target)
+			})
+			.map(|invite| {
+				let expires_at = invite.expires_at.timestamp_millis();
+				(
+					invite.id,
+					EmoteInvite {
+						initiator: invite.initiator,
+						target: invite.target,
+						emote_id: invite.emote_id,
+						expires_at,
+					},
+				)
+			})
+			.collect()
+	};
+	*realtime.emote_invites.write().await = invites;
+	Ok(())
+}
+
+/// Sends this instance's heartbeat and takes the players of lapsed instances
+/// offline, periodically
+pub(super) async fn heartbeat_loop(realtime: RealtimeState) {
+	let mut interval = tokio::time::interval(HEARTBEAT_INTERVAL);
+	interval.tick().await;
+
+	loop {
+		interval.tick().await;
+		if let Err(error) = heartbeat(&realtime).await {
+			warn!("Unable to send realtime instance heartbeat: {error}");
+		}
+	}
+}
+
+async fn heartbeat(realtime: &RealtimeState) -> Result<(), DbErr> {
+	let db = &realtime.database;
+	let refreshed = RealtimeInstance::update_many()
+		.col_expr(
+			realtime_instance::Column::HeartbeatAt,
+			Expr::current_timestamp().into(),
+		)
+		.filter(realtime_instance::Column::Id.eq(realtime.instance_id))
+		.exec(db)
+		.await?
+		.rows_affected
+		> 0;
+	if !refreshed {
+		rejoin(realtime).await?;
+	}
+
+	reap_instances(db).await?;
+	emote_invite::Entity::delete_many()
+		.filter(emote_invite::Column::ExpiresAt.lte(Utc::now()))
+		.exec(db)
+		.await?;
+	let live = RealtimeInstance::find()
+		.select_only()
+		.column(realtime_instance::Column::Id)
+		.into_tuple::<InstanceId>()
+		.all(db)
+		.await?
+		.into_iter()
+		.collect::<HashSet<_>>();
+
+	let lapsed =
+		lapsed_presence(&*realtime.online.read().await, &live, realtime.instance_id);
+	for (player, instance) in lapsed {
+		realtime
+			.apply(RealtimeEvent::Presence {
+				player,
+				instance,
+				online: false,
+			})
+			.await;
+	}
+	Ok(())
+}
+
+/// The players counted as online through an instance that is no longer live
+fn lapsed_presence(
+	online: &HashMap<Uuid, HashSet<InstanceId>>,
+	live: &HashSet<InstanceId>,
+	own_instance: InstanceId,
+) -> Vec<(Uuid, InstanceId)> {
+	presence_pairs(online)
+		.filter(|(_, instance)| *instance != own_instance && !live.contains(instance))
+		.collect()
+}
+
+/// Every player along with each instance they are connected to
+fn presence_pairs(
+	online: &HashMap<Uuid, HashSet<InstanceId>>,
+) -> impl Iterator<Item = (Uuid, InstanceId)> + '_ {
+	online.iter().flat_map(|(player, instances)| {
+		instances.iter().map(move |instance| (*player, *instance))
+	})
+}
+
+/// Registers this instance again after others took it for gone, which also
+/// dropped its players, and tells every instance they are online again and
+/// which servers they are on
+async fn rejoin(realtime: &RealtimeState) -> Result<(), DbErr> {
+	warn!("Realtime instance heartbeat lapsed, registering it again");
+	RealtimeInstance::insert(realtime_instance::ActiveModel {
+		id: Set(realtime.instance_id),
+		..Default::default()
+	})
+	.on_conflict(
+		OnConflict::column(realtime_instance::Column::Id)
+			.update_column(realtime_instance::Column::HeartbeatAt)
+			.to_owned(),
+	)
+	.exec(&realtime.database)
+	.await?;
+
+	let players = realtime
+		.connections_by_owner
+		.read()
+		.await
+		.keys()
+		.copied()
+		.collect::<Vec<_>>();
+	for player in players {
+		set_presence(&realtime.database, realtime.instance_id, player, true).await?;
+		realtime
+			.publish(RealtimeEvent::Presence {
+				player,
+				instance: realtime.instance_id,
+				online: true,
+			})
+			.await;
+
+		let runtime = realtime.player_runtime.read().await.get(&player).cloned();
+		if let Some(runtime) = runtime.filter(|runtime| runtime.server.is_some()) {
+			set_presence_server(
+				&realtime.database,
+				realtime.instance_id,
+				player,
+				runtime.server.clone(),
+			)
+			.await?;
+			realtime
+				.publish(RealtimeEvent::ServerChanged { player, runtime })
+				.await;
+		}
+	}
+	Ok(())
+}
+
+/// Removes the instances whose heartbeat lapsed, along with their players
+async fn reap_instances(db: &impl ConnectionTrait) -> Result<(), DbErr> {
+	RealtimeInstance::delete_many()
+		.filter(Expr::cust_with_values(
+			"heartbeat_at < now() - $1 * interval '1 second'",
+			[INSTANCE_TTL_SECONDS],
+		))
+		.exec(db)
+		.await?;
+	Ok(())
+}
+
+/// Stores whether a player is connected to an instance
+pub(super) async fn set_presence(
+	db: &impl ConnectionTrait,
+	instance: InstanceId,
+	player: Uuid,
+	online: bool,
+) -> Result<(), DbErr> {
+	if online {
+		PlayerPresence::insert(player_presence::ActiveModel {
+			instance_id: Set(instance),
+			player: Set(player),
+			server: Set(None),
+		})
+		.on_conflict(
+			OnConflict::columns([
+				player_presence::Column::InstanceId,
+				player_presence::Column::Player,
+			])
+			.do_nothing()
+			.to_owned(),
+		)
+		.exec_without_returning(db)
+		.await?;
+	} else {
+		PlayerPresence::delete_by_id((instance, player))
+			.exec(db)
+			.await?;
+	}
+	Ok(())
+}
+
+/// Stores the server a player connected to an instance is on
+pub(super) async fn set_presence_server(
+	db: &impl ConnectionTrait,
+	instance: InstanceId,
+	player: Uuid,
+	server: Option<String>,
+) -> Result<(), DbErr> {
+	PlayerPresence::update_many()
+		.col_expr(player_presence::Column::Server, Expr::value(server))
+		.filter(player_presence::Column::InstanceId.eq(instance))
+		.filter(player_presence::Column::Player.eq(player))
+		.exec(db)
+		.await?;
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::{HashMap, HashSet};
+
+	use uuid::Uuid;
+
+	use super::lapsed_presence;
+
+	#[test]
+	fn players_of_lapsed_instances_are_found() {
+		let own = Uuid::new_v4();
+		let live = Uuid::new_v4();
+		let lapsed = Uuid::new_v4();
+		let [first, second, third] = [Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4()];
+		let online = HashMap::from([
+			(first, HashSet::from([own])),
+			(second, HashSet::from([live, lapsed])),
+			(third, HashSet::from([lapsed])),
+		]);
+
+		let mut found = lapsed_presence(&online, &HashSet::from([live]), own);
+		found.sort();
+		let mut expected = vec![(second, lapsed), (third, lapsed)];
+		expected.sort();
+		assert_eq!(found, expected);
+	}
+}
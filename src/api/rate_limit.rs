@@ -0,0 +1,323 @@
+//! Keyed token bucket rate limits. HTTP routes are limited per route group by
+//! [`enforce_rate_limits`], keyed by the authenticated player when the request
+//! carries a valid access token and by the client IP otherwise. Websocket
+//! connections each get their own [`TokenBucket`] for the packets they send.
+
+use std::{
+	fmt::Display,
+	net::IpAddr,
+	str::FromStr,
+	sync::{Arc, Mutex},
+	time::{Duration, Instant},
+};
+
+use axum::{
+	extract::{FromRequestParts as _, MatchedPath, Request, State},
+	middleware::Next,
+	response::{IntoResponse, Response},
+};
+use axum_client_ip::ClientIp;
+use http::{HeaderValue, StatusCode, header::RETRY_AFTER};
+use moka::future::Cache;
+use uuid::Uuid;
+
+use crate::api::{ApiState, account::decrypt_access_token};
+
+/// At most `requests` within `period`, allowing bursts of up to `requests`.
+/// Parsed from `<requests>/<seconds>`, e.g. `10/60`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct RateLimit {
+	requests: u32,
+	period: Duration,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("Rate limits must be formatted as <requests>/<seconds>, e.g. 10/60")]
+pub(crate) struct InvalidRateLimit;
+
+impl FromStr for RateLimit {
+	type Err = InvalidRateLimit;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let (requests, seconds) = s.split_once('/').ok_or(InvalidRateLimit)?;
+		let requests = requests.trim().parse().map_err(|_| InvalidRateLimit)?;
+		let seconds = seconds.trim().parse().map_err(|_| InvalidRateLimit)?;
+		if requests == 0 || seconds == 0 {
+			return Err(InvalidRateLimit);
+		}
+
+		Ok(Self {
+			requests,
+			period: Duration::from_secs(seconds),
+		})
+	}
+}
+
+impl Display for RateLimit {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}/{}", self.requests, self.period.as_secs())
+	}
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct TokenBucket {
+	tokens: f64,
+	updated_at: Instant,
+}
+
+impl TokenBucket {
+	pub(super) fn new(limit: RateLimit, now: Instant) -> Self {
+		Self {
+			tokens: f64::from(limit.requests),
+			updated_at: now,
+		}
+	}
+
+	/// Takes a token out of the bucket, or returns how long until the next one
+	/// is available
+	pub(super) fn try_acquire(
+		&mut self,
+		limit: RateLimit,
+		now: Instant,
+	) -> Result<(), Duration> {
+		let capacity = f64::from(limit.requests);
+		let refill_per_second = capacity / limit.period.as_secs_f64();
+
+		let elapsed = now.saturating_duration_since(self.updated_at).as_secs_f64();
+		self.tokens = (self.tokens + elapsed * refill_per_second).min(capacity);
+		self.updated_at = now;
+
+		if self.tokens >= 1.0 {
+			self.tokens -= 1.0;
+			Ok(())
+		} else {
+			Err(Duration::from_secs_f64(
+				(1.0 - self.tokens) / refill_per_second,
+			))
+		}
+	}
+}
+
+/// Who a rate limit is applied to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(super) enum RateLimitKey {
+	Player(Uuid),
+	Ip(IpAddr),
+}
+
+/// The token buckets of every client for a single route group
+#[derive(Debug)]
+pub(super) struct RateLimiter {
+	limit: RateLimit,
+	buckets: Cache<RateLimitKey, Arc<Mutex<TokenBucket>>>,
+}
+
+impl RateLimiter {
+	pub(super) fn new(limit: RateLimit) -> Self {
+		Self {
+			limit,
+			// Once idle for a whole period a bucket is full again, which is the
+			// same as not having one at all
+			buckets: Cache::builder()
+				.max_capacity(100_000)
+				.time_to_idle(limit.period)
+				.build(),
+		}
+	}
+
+	pub(super) async fn check(&self, key: RateLimitKey) -> Result<(), Duration> {
+		let now = Instant::now();
+		let bucket = self
+			.buckets
+			.get_with(key, async {
+				Arc::new(Mutex::new(TokenBucket::new(self.limit, now)))
+			})
+			.await;
+		let mut bucket = bucket.lock().unwrap_or_else(|e| e.into_inner());
+		bucket.try_acquire(self.limit, now)
+	}
+}
+
+/// Routes that share a rate limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum RateLimitGroup {
+	/// Logins, which are proxied to the session server, and device code
+	/// requests
+	Login,
+	/// Stripe checkout session creation
+	Checkout,
+	/// Tracked link follows, which each record a hit
+	LinkFollow,
+	/// Polls for the session of a device code
+	DevicePoll,
+	/// Player lookups, which are proxied to the Mojang profile API
+	ProfileLookup,
+	/// Packets sent over a websocket connection
+	WebsocketPacket,
+}
+
+impl RateLimitGroup {
+	pub(super) const ALL: [Self; 6] = [
+		Self::Login,
+		Self::Checkout,
+		Self::LinkFollow,
+		Self::DevicePoll,
+		Self::ProfileLookup,
+		Self::WebsocketPacket,
+	];
+
+	/// The group a route belongs to, by its path template
+	fn for_route(path: &str) -> Option<Self> {
+		match path {
+			"/account/login" | "/account/device" => Some(Self::Login),
+			"/stripe/create" => Some(Self::Checkout),
+			"/go/{slug}" => Some(Self::LinkFollow),
+			"/account/device/token" => Some(Self::DevicePoll),
+			"/players/lookup" => Some(Self::ProfileLookup),
+			_ => None,
+		}
+	}
+
+	pub(super) fn name(self) -> &'static str {
+		match self {
+			Self::Login => "login",
+			Self::Checkout => "checkout",
+			Self::LinkFollow => "link_follow",
+			Self::DevicePoll => "device_poll",
+			Self::ProfileLookup => "profile_lookup",
+			Self::WebsocketPacket => "websocket_packet",
+		}
+	}
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct RateLimiters {
+	pub(super) login: Arc<RateLimiter>,
+	pub(super) checkout: Arc<RateLimiter>,
+	pub(super) link_follow: Arc<RateLimiter>,
+	pub(super) device_poll: Arc<RateLimiter>,
+	pub(super) profile_lookup: Arc<RateLimiter>,
+	/// Applied to each websocket connection separately
+	pub(super) websocket_packet: RateLimit,
+}
+
+impl RateLimiters {
+	fn for_group(&self, group: RateLimitGroup) -> Option<&RateLimiter> {
+		match group {
+			RateLimitGroup::Login => Some(&self.login),
+			RateLimitGroup::Checkout => Some(&self.checkout),
+			RateLimitGroup::LinkFollow => Some(&self.link_follow),
+			RateLimitGroup::DevicePoll => Some(&self.device_poll),
+			RateLimitGroup::ProfileLookup => Some(&self.profile_lookup),
+			RateLimitGroup::WebsocketPacket => None,
+		}
+	}
+}
+
+/// Rejects requests to rate limited routes once their client has used up its
+/// bucket. Must be added with `route_layer`, so the matched route is known.
+pub(super) async fn enforce_rate_limits(
+	State(state): State<ApiState>,
+	request: Request,
+	next: Next,
+) -> Response {
+	let Some(group) = request
+		.extensions()
+		.get::<MatchedPath>()
+		.and_then(|path| RateLimitGroup::for_route(path.as_str()))
+	else {
+		return next.run(request).await;
+	};
+	let Some(limiter) = state.rate_limits.for_group(group) else {
+		return next.run(request).await;
+	};
+
+	let (mut parts, body) = request.into_parts();
+	let key = match decrypt_access_token(&parts, &state) {
+		Ok((player, _)) => RateLimitKey::Player(player),
+		Err(_) => match ClientIp::from_request_parts(&mut parts, &state).await {
+			Ok(ClientIp(ip)) => RateLimitKey::Ip(ip),
+			Err(rejection) => return rejection.into_response(),
+		},
+	};
+
+	if let Err(retry_after) = limiter.check(key).await {
+		state.metrics.record_rate_limited(group);
+		return rate_limited_response(retry_after);
+	}
+
+	next.run(Request::from_parts(parts, body)).await
+}
+
+fn rate_limited_response(retry_after: Duration) -> Response {
+	// Retry-After only has second precision, so round up to never be early
+	let seconds = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+	(
+		StatusCode::TOO_MANY_REQUESTS,
+		[(RETRY_AFTER, HeaderValue::from(seconds))],
+		"Too many requests, try again later",
+	)
+		.into_response()
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::{Duration, Instant};
+
+	use super::{RateLimit, RateLimitGroup, TokenBucket};
+
+	#[test]
+	fn parses_rate_limits() {
+		let limit: RateLimit = "10/60".parse().expect("rate limit should parse");
+		assert_eq!(limit.requests, 10);
+		assert_eq!(limit.period, Duration::from_secs(60));
+		assert!("10".parse::<RateLimit>().is_err());
+		assert!("0/60".parse::<RateLimit>().is_err());
+		assert!("10/0".parse::<RateLimit>().is_err());
+	}
+
+	#[test]
+	fn bucket_allows_bursts_then_refills() {
+		let limit: RateLimit = "2/10".parse().expect("rate limit should parse");
+		let start = Instant::now();
+		let mut bucket = TokenBucket::new(limit, start);
+
+		assert!(bucket.try_acquire(limit, start).is_ok());
+		assert!(bucket.try_acquire(limit, start).is_ok());
+		let retry_after = bucket
+			.try_acquire(limit, start)
+			.expect_err("bucket should be empty");
+		assert_eq!(retry_after, Duration::from_secs(5));
+
+		assert!(
+			bucket
+				.try_acquire(limit, start + Duration::from_secs(5))
+				.is_ok()
+		);
+		assert!(
+			bucket
+				.try_acquire(limit, start + Duration::from_secs(5))
+				.is_err()
+		);
+	}
+
+	#[test]
+	fn proxied_routes_are_limited() {
+		assert_eq!(
+			RateLimitGroup::for_route("/account/device/token"),
+			Some(RateLimitGroup::DevicePoll)
+		);
+		assert_eq!(
+			RateLimitGroup::for_route("/players/lookup"),
+			Some(RateLimitGroup::ProfileLookup)
+		);
+		assert_eq!(RateLimitGroup::for_route("/cosmetics"), None);
+	}
+
+	#[test]
+	fn groups_are_listed_in_declaration_order() {
+		for (index, group) in RateLimitGroup::ALL.iter().enumerate() {
+			assert_eq!(*group as usize, index);
+		}
+	}
+}
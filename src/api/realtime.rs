@@ -0,0 +1,1100 @@
+//! Fans realtime events out to every backend instance. Instead of sending
+//! packets to connections directly, handlers publish a [`RealtimeEvent`] on the
+//! configured [`RealtimeBus`], and every instance (the publishing one
+//! included) applies it to the connections and state it holds in
+//! [`dispatch_events`].
+
+use std::{
+	collections::{HashMap, HashSet},
+	fmt::Debug,
+	str::FromStr,
+	time::Duration,
+};
+
+use axum::extract::ws::CloseFrame;
+use chrono::Utc;
+use entities::{prelude::CatalogChange, sea_orm_active_enums::BodySlot};
+use futures::future::BoxFuture;
+use sea_orm::{
+	ConnectionTrait, DatabaseConnection, DbBackend, DbErr, EntityTrait, Statement,
+	sqlx::{
+		self,
+		postgres::{PgListener, PgPool},
+	},
+};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::api::{
+	announcements::record_deliveries,
+	presence::synchronize,
+	state::{ConnectionId, EmoteInvite, InstanceId, PlayerRuntimeState, RealtimeState},
+	websocket::structs::{
+		AnnouncementInfo, Capability, ClientBoundPacket, MAX_PLAYER_SUBSCRIPTIONS,
+	},
+};
+
+/// The Postgres channel realtime events are sent over
+const NOTIFY_CHANNEL: &str = "plus_realtime";
+/// The largest notification payload Postgres accepts, in bytes
+const MAX_NOTIFY_PAYLOAD: usize = 7999;
+/// How long to wait before listening again after the bus connection failed
+const RELISTEN_DELAY: Duration = Duration::from_secs(1);
+
+/// Something that happened to a player, which every instance delivers to the
+/// connections it holds
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub(super) enum RealtimeEvent {
+	CosmeticEquipped {
+		player: Uuid,
+		slot: BodySlot,
+		cosmetic_id: Option<i32>,
+	},
+	ParticleColorChanged {
+		player: Uuid,
+		color: Option<i32>,
+	},
+	/// A player applied one of their outfits. Only carries the slots that
+	/// changed.
+	OutfitApplied {
+		player: Uuid,
+		changes: HashMap<BodySlot, Option<i32>>,
+		particle_color: Option<i32>,
+	},
+	EmoteStarted {
+		player: Uuid,
+		emote_id: i32,
+	},
+	EmoteStopped {
+		player: Uuid,
+	},
+	/// A player invited another to do a paired emote
+	EmoteInvited {
+		invite_id: Uuid,
+		invite: EmoteInvite,
+	},
+	EmoteInviteDeclined {
+		invite_id: Uuid,
+		initiator: Uuid,
+		target: Uuid,
+	},
+	/// A player accepted an emote invite
+	PairedEmoteStarted {
+		invite_id: Uuid,
+		initiator: Uuid,
+		partner: Uuid,
+		emote_id: i32,
+		starts_at: i64,
+	},
+	/// A player's first connection to an instance opened, or their last one
+	/// closed
+	Presence {
+		player: Uuid,
+		instance: InstanceId,
+		online: bool,
+	},
+	/// A player moved to another Minecraft server, or left theirs. Carries the
+	/// player's state, so that instances that don't hold it yet can tell their
+	/// watchers about the player.
+	ServerChanged {
+		player: Uuid,
+		runtime: PlayerRuntimeState,
+	},
+	/// A player gained or lost cosmetics, sent to the player themselves. Built
+	/// with [`RealtimeEvent::ownership_updated`], which keeps it small enough
+	/// for the bus.
+	OwnershipUpdated {
+		player: Uuid,
+		cosmetic_ids: Vec<i32>,
+		emote_ids: Vec<i32>,
+		revoked: bool,
+	},
+	/// A player was sent a friend request
+	FriendRequestReceived {
+		player: Uuid,
+		sender: Uuid,
+	},
+	/// Two players became friends, or stopped being friends
+	FriendshipChanged {
+		players: [Uuid; 2],
+		friends: bool,
+	},
+	/// An announcement was made, for the given players or for every player
+	Announcement {
+		announcement: AnnouncementInfo,
+		players: Option<Vec<Uuid>>,
+	},
+	/// Catalog entries changed, bumping the catalog version. Only carries the
+	/// version, as the change can name any number of entries, so instances
+	/// load what changed from the database.
+	CatalogChanged {
+		catalog_version: i64,
+	},
+	/// Closes every connection of a player
+	CloseConnections {
+		player: Uuid,
+		code: u16,
+		reason: String,
+	},
+	/// The bus may have missed events from other instances. Only ever sent by
+	/// the bus to its own instance.
+	#[serde(skip)]
+	Resynchronize,
+}
+
+/// The most cosmetic and emote ids a [`RealtimeEvent::OwnershipUpdated`]
+/// carries, which keeps it well under the size limit of Postgres notifications
+const MAX_OWNERSHIP_IDS: usize = 500;
+
+impl RealtimeEvent {
+	/// Tells a player about cosmetics they gained or lost, split over as many
+	/// events as it takes to carry every id
+	pub(super) fn ownership_updated(
+		player: Uuid,
+		cosmetic_ids: Vec<i32>,
+		emote_ids: Vec<i32>,
+		revoked: bool,
+	) -> Vec<Self> {
+		let event = |cosmetic_ids: &[i32], emote_ids: &[i32]| Self::OwnershipUpdated {
+			player,
+			cosmetic_ids: cosmetic_ids.to_vec(),
+			emote_ids: emote_ids.to_vec(),
+			revoked,
+		};
+		if cosmetic_ids.len() + emote_ids.len() <= MAX_OWNERSHIP_IDS {
+			return vec![event(&cosmetic_ids, &emote_ids)];
+		}
+		cosmetic_ids
+			.chunks(MAX_OWNERSHIP_IDS)
+			.map(|ids| event(ids, &[]))
+			.chain(
+				emote_ids
+					.chunks(MAX_OWNERSHIP_IDS)
+					.map(|ids| event(&[], ids)),
+			)
+			.collect()
+	}
+}
+
+#[derive(thiserror::Error, Debug)]
+pub(super) enum BusError {
+	#[error("Unable to serialize realtime event: {0}")]
+	Serialization(#[from] serde_json::Error),
+	#[error("The realtime event is {0} bytes, more than the bus can carry")]
+	TooLarge(usize),
+	#[error("Unable to notify other instances: {0}")]
+	Database(#[from] DbErr),
+}
+
+/// Carries realtime events between backend instances
+pub(super) trait RealtimeBus: Debug + Send + Sync {
+	/// Sends an event to every instance, this one included
+	fn publish<'a>(
+		&'a self,
+		event: &'a RealtimeEvent,
+	) -> BoxFuture<'a, Result<(), BusError>>;
+}
+
+/// Which [`RealtimeBus`] to use
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RealtimeBusKind {
+	/// Events never leave the instance, for running a single instance
+	InProcess,
+	/// Events are sent with Postgres `NOTIFY`, for running multiple instances
+	/// against the same database
+	Postgres,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("The realtime bus must be either in_process or postgres")]
+pub(crate) struct InvalidRealtimeBus;
+
+impl FromStr for RealtimeBusKind {
+	type Err = InvalidRealtimeBus;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"in_process" => Ok(Self::InProcess),
+			"postgres" => Ok(Self::Postgres),
+			_ => Err(InvalidRealtimeBus),
+		}
+	}
+}
+
+/// Delivers events straight back to this instance
+#[derive(Debug)]
+pub(super) struct InProcessBus {
+	tx: mpsc::UnboundedSender<RealtimeEvent>,
+}
+
+impl InProcessBus {
+	pub(super) fn new(tx: mpsc::UnboundedSender<RealtimeEvent>) -> Self {
+		Self { tx }
+	}
+}
+
+impl RealtimeBus for InProcessBus {
+	fn publish<'a>(
+		&'a self,
+		event: &'a RealtimeEvent,
+	) -> BoxFuture<'a, Result<(), BusError>> {
+		// The receiver lives as long as the server does
+		let _ = self.tx.send(event.clone());
+		Box::pin(async { Ok(()) })
+	}
+}
+
+/// Sends events to every instance listening on the same database. Postgres
+/// refuses notifications of 8000 bytes or more, so events that could grow
+/// past that carry ids to load instead, or are split up, and any event that
+/// is still too large is refused before it is sent.
+#[derive(Debug)]
+pub(super) struct PostgresBus {
+	database: DatabaseConnection,
+}
+
+impl PostgresBus {
+	/// Starts listening for events from every instance, forwarding them to `tx`
+	pub(super) async fn connect(
+		database: DatabaseConnection,
+		tx: mpsc::UnboundedSender<RealtimeEvent>,
+	) -> Result<Self, sqlx::Error> {
+		let pool = database.get_postgres_connection_pool().clone();
+		let mut listener = listen(&pool).await?;
+
+		tokio::spawn(async move {
+			loop {
+				let notification = match listener.try_recv().await {
+					Ok(Some(notification)) => notification,
+					// The listener reconnected by itself, but anything sent in the
+					// meantime was missed
+					Ok(None) => {
+						warn!("Lost the realtime bus connection, resynchronizing");
+						if tx.send(RealtimeEvent::Resynchronize).is_err() {
+							break;
+						}
+						continue;
+					}
+					Err(error) => {
+						warn!("Unable to receive realtime events: {error}");
+						listener = relisten(&pool).await;
+						if tx.send(RealtimeEvent::Resynchronize).is_err() {
+							break;
+						}
+						continue;
+					}
+				};
+				match serde_json::from_str(notification.payload()) {
+					Ok(event) => {
+						if tx.send(event).is_err() {
+							break;
+						}
+					}
+					Err(error) => warn!("Received an invalid realtime event: {error}"),
+				}
+			}
+		});
+
+		Ok(Self { database })
+	}
+}
+
+async fn listen(pool: &PgPool) -> Result<PgListener, sqlx::Error> {
+	let mut listener = PgListener::connect_with(pool).await?;
+	listener.listen(NOTIFY_CHANNEL).await?;
+	Ok(listener)
+}
+
+/// Listens for events on a new connection, retrying until it can
+async fn relisten(pool: &PgPool) -> PgListener {
+	loop {
+		tokio::time::sleep(RELISTEN_DELAY).await;
+		match listen(pool).await {
+			Ok(listener) => return listener,
+			Err(error) => warn!("Unable to listen for realtime events: {error}"),
+		}
+	}
+}
+
+impl RealtimeBus for PostgresBus {
+	fn publish<'a>(
+		&'a self,
+		event: &'a RealtimeEvent,
+	) -> BoxFuture<'a, Result<(), BusError>> {
+		Box::pin(async move {
+			let payload = serde_json::to_string(event)?;
+			if payload.len() > MAX_NOTIFY_PAYLOAD {
+				return Err(BusError::TooLarge(payload.len()));
+			}
+			self.database
+				.execute(Statement::from_sql_and_values(
+					DbBackend::Postgres,
+					"SELECT pg_notify($1, $2)",
+					[NOTIFY_CHANNEL.into(), payload.into()],
+				))
+				.await?;
+			Ok(())
+		})
+	}
+}
+
+/// Applies the events received from the bus, until the bus shuts down
+pub(super) async fn dispatch_events(
+	realtime: RealtimeState,
+	mut rx: mpsc::UnboundedReceiver<RealtimeEvent>,
+) {
+	while let Some(event) = rx.recv().await {
+		realtime.apply(event).await;
+	}
+}
+
+impl RealtimeState {
+	/// Sends an event to every instance. Failures are only logged, as a missed
+	/// event is corrected by the next snapshot.
+	pub(super) async fn publish(&self, event: RealtimeEvent) {
+		if let Err(error) = self.bus.publish(&event).await {
+			warn!("Unable to publish realtime event: {error}");
+		}
+	}
+
+	pub(super) async fn apply(&self, event: RealtimeEvent) {
+		match event {
+			RealtimeEvent::CosmeticEquipped {
+				player,
+				slot,
+				cosmetic_id,
+			} => {
+				// Players without runtime state here are loaded from the database
+				// when someone subscribes to them
+				if let Some(runtime) = self.player_runtime.write().await.get_mut(&player)
+				{
+					match cosmetic_id {
+						Some(cosmetic_id) => {
+							runtime.equipped.insert(slot.clone(), cosmetic_id)
+						}
+						None => runtime.equipped.remove(&slot),
+					};
+				}
+				self.send_to_watchers(player, || {
+					ClientBoundPacket::PlayerCosmeticEquipped {
+						player,
+						slot: slot.clone(),
+						cosmetic_id,
+					}
+				})
+				.await;
+			}
+			RealtimeEvent::ParticleColorChanged { player, color } => {
+				if let Some(runtime) = self.player_runtime.write().await.get_mut(&player)
+				{
+					runtime.particle_color = color;
+				}
+				self.send_to_watchers(player, || {
+					ClientBoundPacket::PlayerParticleColorChanged { player, color }
+				})
+				.await;
+			}
+			RealtimeEvent::OutfitApplied {
+				player,
+				changes,
+				particle_color,
+			} => {
+				// Every slot changes under one lock, so that subscribers never see
+				// half of the outfit
+				let mut runtime = self.player_runtime.write().await;
+				let color_changed = match runtime.get_mut(&player) {
+					Some(runtime) => {
+						for (slot, cosmetic_id) in &changes {
+							match cosmetic_id {
+								Some(cosmetic_id) => {
+									runtime.equipped.insert(slot.clone(), *cosmetic_id)
+								}
+								None => runtime.equipped.remove(slot),
+							};
+						}
+						let changed = runtime.particle_color != particle_color;
+						runtime.particle_color = particle_color;
+						changed
+					}
+					None => true,
+				};
+				drop(runtime);
+
+				for (slot, cosmetic_id) in changes {
+					self.send_to_watchers(player, || {
+						ClientBoundPacket::PlayerCosmeticEquipped {
+							player,
+							slot: slot.clone(),
+							cosmetic_id,
+						}
+					})
+					.await;
+				}
+				if color_changed {
+					self.send_to_watchers(player, || {
+						ClientBoundPacket::PlayerParticleColorChanged {
+							player,
+							color: particle_color,
+						}
+					})
+					.await;
+				}
+			}
+			RealtimeEvent::EmoteStarted { player, emote_id } => {
+				if let Some(runtime) = self.player_runtime.write().await.get_mut(&player)
+				{
+					runtime.active_emote = Some(emote_id);
+				}
+				self.send_to_watchers(player, || ClientBoundPacket::PlayerEmoteStarted {
+					player,
+					emote_id,
+				})
+				.await;
+			}
+			RealtimeEvent::EmoteStopped { player } => {
+				if let Some(runtime) = self.player_runtime.write().await.get_mut(&player)
+				{
+					runtime.active_emote = None;
+				}
+				self.send_to_watchers(player, || ClientBoundPacket::PlayerEmoteStopped {
+					player,
+				})
+				.await;
+			}
+			RealtimeEvent::EmoteInvited { invite_id, invite } => {
+				let (target, initiator) = (invite.target, invite.initiator);
+				let (emote_id, expires_at) = (invite.emote_id, invite.expires_at);
+				{
+					// Players only have one pending invite at a time
+					let now = Utc::now().timestamp_millis();
+					let mut invites = self.emote_invites.write().await;
+					invites.retain(|_, pending| {
+						pending.initiator != initiator && pending.expires_at > now
+					});
+					invites.insert(invite_id, invite);
+				}
+				self.send_to_owner(target, || ClientBoundPacket::EmoteInviteReceived {
+					invite_id,
+					player: initiator,
+					emote_id,
+					expires_at,
+				})
+				.await;
+			}
+			RealtimeEvent::EmoteInviteDeclined {
+				invite_id,
+				initiator,
+				target,
+			} => {
+				self.emote_invites.write().await.remove(&invite_id);
+				self.send_to_owner(initiator, || {
+					ClientBoundPacket::EmoteInviteDeclined {
+						invite_id,
+						player: target,
+					}
+				})
+				.await;
+			}
+			RealtimeEvent::PairedEmoteStarted {
+				invite_id,
+				initiator,
+				partner,
+				emote_id,
+				starts_at,
+			} => {
+				self.emote_invites.write().await.remove(&invite_id);
+				{
+					let mut runtime = self.player_runtime.write().await;
+					for player in [initiator, partner] {
+						if let Some(runtime) = runtime.get_mut(&player) {
+							runtime.active_emote = Some(emote_id);
+						}
+					}
+				}
+
+				// Connections watching both players get the packet once
+				let mut connection_ids = HashSet::new();
+				{
+					let watchers = self.watchers.read().await;
+					for player in [initiator, partner] {
+						connection_ids
+							.extend(watchers.get(&player).into_iter().flatten());
+					}
+				}
+				{
+					let owners = self.connections_by_owner.read().await;
+					for player in [initiator, partner] {
+						connection_ids.extend(owners.get(&player).into_iter().flatten());
+					}
+				}
+				self.send_to_connections(connection_ids, || {
+					ClientBoundPacket::PairedEmoteStarted {
+						emote_id,
+						initiator,
+						partner,
+						starts_at,
+					}
+				})
+				.await;
+			}
+			RealtimeEvent::Presence {
+				player,
+				instance,
+				online,
+			} => {
+				let changed = update_presence(
+					&mut *self.online.write().await,
+					player,
+					instance,
+					online,
+				);
+				let Some(online) = changed else {
+					return;
+				};
+				if !online {
+					if let Some(runtime) =
+						self.player_runtime.write().await.get_mut(&player)
+					{
+						runtime.active_emote = None;
+						runtime.server = None;
+					}
+					self.emote_invites.write().await.retain(|_, invite| {
+						invite.initiator != player && invite.target != player
+					});
+					let previous = self.servers.write().await.move_player(player, None);
+					if let Some(previous) = previous {
+						self.leave_server(player, &previous).await;
+					}
+				}
+				self.send_to_watchers(player, || ClientBoundPacket::PlayerPresence {
+					player,
+					online,
+				})
+				.await;
+				self.send_to_friends(player, || ClientBoundPacket::FriendPresence {
+					player,
+					online,
+				})
+				.await;
+			}
+			RealtimeEvent::ServerChanged { player, runtime } => {
+				let server = runtime.server.clone();
+				let previous = self
+					.servers
+					.write()
+					.await
+					.move_player(player, server.clone());
+				if let Some(existing) = self.player_runtime.write().await.get_mut(&player)
+				{
+					existing.server = server.clone();
+				}
+				if previous == server {
+					return;
+				}
+
+				if let Some(previous) = previous {
+					self.leave_server(player, &previous).await;
+				}
+				self.join_server(player, runtime).await;
+			}
+			RealtimeEvent::OwnershipUpdated {
+				player,
+				cosmetic_ids,
+				emote_ids,
+				revoked,
+			} => {
+				self.send_to_owner(player, || ClientBoundPacket::OwnershipUpdated {
+					player,
+					cosmetic_ids: cosmetic_ids.clone(),
+					emote_ids: emote_ids.clone(),
+					revoked,
+				})
+				.await;
+			}
+			RealtimeEvent::FriendRequestReceived { player, sender } => {
+				self.send_to_owner(player, || ClientBoundPacket::FriendRequestReceived {
+					player: sender,
+				})
+				.await;
+			}
+			RealtimeEvent::FriendshipChanged {
+				players: [a, b],
+				friends,
+			} => {
+				{
+					let mut index = self.local_friends.write().await;
+					if friends {
+						index.add_friendship(a, b);
+					} else {
+						index.remove_friendship(a, b);
+					}
+				}
+
+				let [a_online, b_online] = {
+					let online = self.online.read().await;
+					[a, b].map(|player| online.contains_key(&player))
+				};
+				for (player, friend, online) in [(a, b, b_online), (b, a, a_online)] {
+					self.send_to_owner(player, || {
+						if friends {
+							ClientBoundPacket::FriendAdded {
+								player: friend,
+								online,
+							}
+						} else {
+							ClientBoundPacket::FriendRemoved { player: friend }
+						}
+					})
+					.await;
+				}
+			}
+			RealtimeEvent::Announcement {
+				announcement,
+				players,
+			} => {
+				let connection_ids = match players {
+					None => self.connections.read().await.keys().copied().collect(),
+					Some(players) => {
+						let owners = self.connections_by_owner.read().await;
+						players
+							.iter()
+							.filter_map(|player| owners.get(player))
+							.flatten()
+							.copied()
+							.collect::<Vec<_>>()
+					}
+				};
+
+				// Only connections that negotiated announcements are sent them,
+				// and count as a delivery to their owner
+				let mut recipients = HashSet::new();
+				{
+					let mut connections = self.connections.write().await;
+					for connection_id in connection_ids {
+						let Some(connection) = connections.get_mut(&connection_id) else {
+							continue;
+						};
+						if connection.capabilities.contains(&Capability::Announcements) {
+							connection.send(ClientBoundPacket::Announcement {
+								announcement: announcement.clone(),
+							});
+							recipients.insert(connection.owner);
+						}
+					}
+				}
+				if recipients.is_empty() {
+					return;
+				}
+
+				// Recorded in the background so other events aren't held up
+				let database = self.database.clone();
+				tokio::spawn(async move {
+					if let Err(error) =
+						record_deliveries(&database, announcement.id, recipients).await
+					{
+						warn!("Unable to record announcement deliveries: {error}");
+					}
+				});
+			}
+			RealtimeEvent::CatalogChanged { catalog_version } => {
+				let change = match CatalogChange::find_by_id(catalog_version)
+					.one(&self.database)
+					.await
+				{
+					Ok(Some(change)) => change,
+					Ok(None) => return,
+					Err(error) => {
+						warn!("Unable to load catalog change {catalog_version}: {error}");
+						return;
+					}
+				};
+				let [cosmetic_ids, group_ids, bundle_ids] =
+					[change.cosmetic_ids, change.group_ids, change.bundle_ids].map(
+						|ids| serde_json::from_value::<Vec<i32>>(ids).unwrap_or_default(),
+					);
+				self.send_to_all(|| ClientBoundPacket::CatalogChanged {
+					cosmetic_ids: cosmetic_ids.clone(),
+					group_ids: group_ids.clone(),
+					bundle_ids: bundle_ids.clone(),
+					catalog_version,
+				})
+				.await;
+			}
+			RealtimeEvent::CloseConnections {
+				player,
+				code,
+				reason,
+			} => {
+				let Some(connection_ids) =
+					self.connections_by_owner.read().await.get(&player).cloned()
+				else {
+					return;
+				};
+				let connections = self.connections.read().await;
+				for connection_id in connection_ids {
+					if let Some(connection) = connections.get(&connection_id) {
+						let _ = connection.close_tx.try_send(CloseFrame {
+							code,
+							reason: reason.as_str().into(),
+						});
+					}
+				}
+			}
+			RealtimeEvent::Resynchronize => {
+				// Boxed, as synchronizing applies events in turn
+				if let Err(error) = Box::pin(synchronize(self)).await {
+					warn!("Unable to resynchronize realtime state: {error}");
+				}
+			}
+		}
+	}
+
+	/// Sends a packet to every connection on this instance subscribed to a
+	/// player
+	async fn send_to_watchers(
+		&self,
+		player: Uuid,
+		make_packet: impl Fn() -> ClientBoundPacket,
+	) {
+		let Some(connection_ids) = self.watchers.read().await.get(&player).cloned()
+		else {
+			return;
+		};
+		self.send_to_connections(connection_ids, make_packet).await;
+	}
+
+	/// Sends a packet to every connection on this instance owned by a player
+	async fn send_to_owner(
+		&self,
+		owner: Uuid,
+		make_packet: impl Fn() -> ClientBoundPacket,
+	) {
+		let Some(connection_ids) =
+			self.connections_by_owner.read().await.get(&owner).cloned()
+		else {
+			return;
+		};
+		self.send_to_connections(connection_ids, make_packet).await;
+	}
+
+	/// Sends a packet to the given connections that are on this instance
+	async fn send_to_connections(
+		&self,
+		connection_ids: impl IntoIterator<Item = ConnectionId>,
+		make_packet: impl Fn() -> ClientBoundPacket,
+	) {
+		let mut connections = self.connections.write().await;
+		for connection_id in connection_ids {
+			if let Some(connection) = connections.get_mut(&connection_id) {
+				connection.send(make_packet());
+			}
+		}
+	}
+
+	/// Sends a packet to every connection on this instance
+	async fn send_to_all(&self, make_packet: impl Fn() -> ClientBoundPacket) {
+		for connection in self.connections.write().await.values_mut() {
+			connection.send(make_packet());
+		}
+	}
+
+	/// Sends a packet to every connection on this instance owned by a friend of
+	/// a player
+	async fn send_to_friends(
+		&self,
+		player: Uuid,
+		make_packet: impl Fn() -> ClientBoundPacket,
+	) {
+		let friends = self.local_friends.read().await.friends_of(player);
+		for friend in friends {
+			self.send_to_owner(friend, &make_packet).await;
+		}
+	}
+
+	/// Stops a connection on this instance from being sent anything about the
+	/// given players
+	pub(super) async fn remove_watchers(
+		&self,
+		connection_id: ConnectionId,
+		players: impl IntoIterator<Item = Uuid>,
+	) {
+		let mut watchers = self.watchers.write().await;
+		for player in players {
+			if let Some(player_watchers) = watchers.get_mut(&player) {
+				player_watchers.remove(&connection_id);
+				if player_watchers.is_empty() {
+					watchers.remove(&player);
+				}
+			}
+		}
+	}
+
+	/// Subscribes every connection on this instance that watches the player's
+	/// new server to the player, as far as their subscription limits allow
+	async fn join_server(&self, player: Uuid, runtime: PlayerRuntimeState) {
+		let Some(server) = runtime.server.as_deref() else {
+			return;
+		};
+		let connection_ids = self.servers.read().await.watchers_of(server);
+		if connection_ids.is_empty() {
+			return;
+		}
+
+		let online = self.online.read().await.contains_key(&player);
+		let snapshot = || ClientBoundPacket::SubscriptionSnapshot {
+			equipped: HashMap::from([(player, runtime.equipped.clone())]),
+			active_emotes: runtime
+				.active_emote
+				.map(|emote_id| (player, emote_id))
+				.into_iter()
+				.collect(),
+			particle_colors: runtime
+				.particle_color
+				.map(|color| (player, color))
+				.into_iter()
+				.collect(),
+			users: online.then_some(player).into_iter().collect(),
+		};
+
+		let mut subscribed = Vec::new();
+		{
+			let mut connections = self.connections.write().await;
+			for connection_id in connection_ids {
+				let Some(connection) = connections.get_mut(&connection_id) else {
+					continue;
+				};
+				// Players the connection already subscribed to by hand are left
+				// alone
+				if connection.owner == player
+					|| connection.subscriptions.len() >= MAX_PLAYER_SUBSCRIPTIONS
+					|| !connection.subscriptions.insert(player)
+				{
+					continue;
+				}
+				connection.server_players.insert(player);
+				connection.send(snapshot());
+				subscribed.push(connection_id);
+			}
+		}
+		if subscribed.is_empty() {
+			return;
+		}
+
+		self.watchers
+			.write()
+			.await
+			.entry(player)
+			.or_default()
+			.extend(subscribed);
+		self.player_runtime
+			.write()
+			.await
+			.entry(player)
+			.or_insert(runtime);
+	}
+
+	/// Unsubscribes the connections on this instance that were only subscribed
+	/// to a player because they watch the server the player left
+	async fn leave_server(&self, player: Uuid, server: &str) {
+		let connection_ids = self.servers.read().await.watchers_of(server);
+
+		let mut unsubscribed = Vec::new();
+		{
+			let mut connections = self.connections.write().await;
+			for connection_id in connection_ids {
+				let Some(connection) = connections.get_mut(&connection_id) else {
+					continue;
+				};
+				if connection.server_players.remove(&player) {
+					connection.subscriptions.remove(&player);
+					connection.send(ClientBoundPacket::ServerPlayerLeft { player });
+					unsubscribed.push(connection_id);
+				}
+			}
+		}
+
+		for connection_id in unsubscribed {
+			self.remove_watchers(connection_id, [player]).await;
+		}
+	}
+}
+
+/// Which Minecraft server each player is on, and which connections watch each
+/// server
+#[derive(Debug, Default)]
+pub(super) struct ServerIndex {
+	players: HashMap<String, HashSet<Uuid>>,
+	player_servers: HashMap<Uuid, String>,
+	watchers: HashMap<String, HashSet<ConnectionId>>,
+}
+
+impl ServerIndex {
+	/// Moves a player to a server, or off every server. Returns the server the
+	/// player was on before.
+	pub(super) fn move_player(
+		&mut self,
+		player: Uuid,
+		server: Option<String>,
+	) -> Option<String> {
+		let previous = match &server {
+			Some(server) => self.player_servers.insert(player, server.clone()),
+			None => self.player_servers.remove(&player),
+		};
+		if let Some(previous) = &previous
+			&& let Some(players) = self.players.get_mut(previous)
+		{
+			players.remove(&player);
+			if players.is_empty() {
+				self.players.remove(previous);
+			}
+		}
+		if let Some(server) = server {
+			self.players.entry(server).or_default().insert(player);
+		}
+
+		previous
+	}
+
+	pub(super) fn players_on(&self, server: &str) -> impl Iterator<Item = Uuid> {
+		self.players.get(server).into_iter().flatten().copied()
+	}
+
+	pub(super) fn watch(&mut self, server: String, connection_id: ConnectionId) {
+		self.watchers
+			.entry(server)
+			.or_default()
+			.insert(connection_id);
+	}
+
+	pub(super) fn unwatch(&mut self, server: &str, connection_id: ConnectionId) {
+		if let Some(watchers) = self.watchers.get_mut(server) {
+			watchers.remove(&connection_id);
+			if watchers.is_empty() {
+				self.watchers.remove(server);
+			}
+		}
+	}
+
+	fn watchers_of(&self, server: &str) -> HashSet<ConnectionId> {
+		self.watchers.get(server).cloned().unwrap_or_default()
+	}
+}
+
+/// Records whether a player is connected to an instance. Returns whether the
+/// player is now online anywhere, if that changed.
+fn update_presence(
+	online: &mut HashMap<Uuid, HashSet<InstanceId>>,
+	player: Uuid,
+	instance: InstanceId,
+	is_online: bool,
+) -> Option<bool> {
+	let was_online = online.contains_key(&player);
+	if is_online {
+		online.entry(player).or_default().insert(instance);
+	} else if let Some(instances) = online.get_mut(&player) {
+		instances.remove(&instance);
+		if instances.is_empty() {
+			online.remove(&player);
+		}
+	}
+
+	let now_online = online.contains_key(&player);
+	(was_online != now_online).then_some(now_online)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::HashMap;
+
+	use entities::sea_orm_active_enums::BodySlot;
+	use uuid::Uuid;
+
+	use super::{MAX_NOTIFY_PAYLOAD, RealtimeEvent, ServerIndex, update_presence};
+
+	#[test]
+	fn presence_changes_only_with_the_first_and_last_instance() {
+		let mut online = HashMap::new();
+		let player = Uuid::nil();
+		let (first, second) = (Uuid::from_u128(1), Uuid::from_u128(2));
+
+		assert_eq!(
+			update_presence(&mut online, player, first, true),
+			Some(true)
+		);
+		assert_eq!(update_presence(&mut online, player, second, true), None);
+		assert_eq!(update_presence(&mut online, player, first, false), None);
+		assert_eq!(
+			update_presence(&mut online, player, second, false),
+			Some(false)
+		);
+		assert_eq!(update_presence(&mut online, player, second, false), None);
+	}
+
+	#[test]
+	fn events_round_trip_through_json() {
+		let event = RealtimeEvent::CosmeticEquipped {
+			player: Uuid::nil(),
+			slot: BodySlot::Cape,
+			cosmetic_id: Some(3),
+		};
+
+		let serialized = serde_json::to_string(&event).expect("event should serialize");
+		let parsed: RealtimeEvent =
+			serde_json::from_str(&serialized).expect("event should parse");
+		assert_eq!(parsed, event);
+	}
+
+	#[test]
+	fn ownership_updates_are_split_to_fit_the_bus() {
+		let small =
+			RealtimeEvent::ownership_updated(Uuid::nil(), vec![1], vec![2], false);
+		assert_eq!(small.len(), 1);
+
+		let events = RealtimeEvent::ownership_updated(
+			Uuid::nil(),
+			vec![i32::MIN; 1_200],
+			vec![i32::MIN; 10],
+			true,
+		);
+		assert_eq!(events.len(), 4);
+		let mut carried = 0;
+		for event in &events {
+			let payload = serde_json::to_string(event).expect("event should serialize");
+			assert!(payload.len() <= MAX_NOTIFY_PAYLOAD);
+			if let RealtimeEvent::OwnershipUpdated {
+				cosmetic_ids,
+				emote_ids,
+				..
+			} = event
+			{
+				carried += cosmetic_ids.len() + emote_ids.len();
+			}
+		}
+		assert_eq!(carried, 1_210);
+	}
+
+	#[test]
+	fn moves_players_between_servers() {
+		let mut index = ServerIndex::default();
+		let (first, second) = (Uuid::from_u128(1), Uuid::from_u128(2));
+		let hypixel = "mc.hypixel.net".to_owned();
+
+		assert_eq!(index.move_player(first, Some(hypixel.clone())), None);
+		assert_eq!(index.move_player(second, Some(hypixel.clone())), None);
+		assert_eq!(
+			index.move_player(second, Some("play.cubecraft.net".to_owned())),
+			Some(hypixel.clone())
+		);
+		assert_eq!(index.players_on(&hypixel).collect::<Vec<_>>(), vec![first]);
+
+		assert_eq!(index.move_player(first, None), Some(hypixel.clone()));
+		assert_eq!(index.players_on(&hypixel).count(), 0);
+		assert_eq!(
+			index.players_on("play.cubecraft.net").collect::<Vec<_>>(),
+			vec![second]
+		);
+	}
+}
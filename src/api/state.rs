@@ -1,26 +1,48 @@
 use std::{
-	collections::{HashMap, HashSet},
+	collections::{BTreeSet, HashMap, HashSet},
 	sync::Arc,
 	time::Duration,
 };
 
+use axum::extract::ws::CloseFrame;
 use chrono::{DateTime, Utc};
 use entities::prelude::*;
 use entities::sea_orm_active_enums::BodySlot;
 use migrations::{Migrator, MigratorTrait};
 use moka::future::Cache;
-use pasetors::{
-	keys::{Generate, SymmetricKey},
-	version4::V4,
-};
 use reqwest::{Client, ClientBuilder};
 use s3::{Bucket, creds::Credentials};
 use sea_orm::{ConnectOptions, Database, DatabaseConnection, EntityTrait};
+use serde::{Deserialize, Serialize};
 use stripe_client::Client as StripeClient;
 use tracing::{info, warn};
 use uuid::Uuid;
 
-use crate::{api::cosmetics::CachedAssetInfo, commands::ServeArgs};
+use crate::{
+	api::{
+		account::{
+			deletion::process_deletions_loop,
+			verifier::{DevVerifier, MojangVerifier, SessionVerifier},
+		},
+		cosmetics::CachedAssetInfo,
+		friends::FriendIndex,
+		metrics::Metrics,
+		players::profile::ProfileResolver,
+		presence::{heartbeat_loop, register_instance},
+		rate_limit::{RateLimiter, RateLimiters},
+		realtime::{
+			InProcessBus, PostgresBus, RealtimeBus, RealtimeBusKind, RealtimeEvent,
+			ServerIndex, dispatch_events,
+		},
+		websocket::{
+			queue::{OutboundQueue, SlowConsumerPolicy},
+			resume::Resumable,
+			structs::{Capability, ClientBoundPacket},
+		},
+	},
+	commands::ServeArgs,
+	keyring::PasetoKeyring,
+};
 
 impl ApiState {
 	#[tracing::instrument(skip_all, name = "initialize_state", level = "debug")]
@@ -59,6 +81,42 @@ impl ApiState {
 		.with_path_style()
 		.into();
 
+		let paseto_keys = match &args.paseto_keys {
+			Some(keys) => keys.clone(),
+			None => {
+				warn!(
+					"No PASETO keys were configured, generating an ephemeral key. All \
+					 issued tokens will be invalidated when the backend restarts"
+				);
+				PasetoKeyring::generate().expect("Unable to generate paseto signing key")
+			}
+		};
+		info!("Loaded PASETO keyring: {paseto_keys:?}");
+
+		let session_verifier: Arc<dyn SessionVerifier> = if args.dev_auth {
+			warn!(
+				"Development authentication is enabled, anyone can log in as any player \
+				 without being verified. Never use this in production!"
+			);
+			Arc::new(DevVerifier)
+		} else {
+			Arc::new(MojangVerifier::new(
+				ClientBuilder::new()
+					.user_agent("PolyPlus Backend")
+					.build()
+					.expect("Unable to build reqwest session server client"),
+				&args.sessionserver_url,
+			))
+		};
+
+		let profiles = ProfileResolver::new(
+			ClientBuilder::new()
+				.user_agent("PolyPlus Backend")
+				.build()
+				.expect("Unable to build reqwest profile client"),
+			&args.profile_api_url,
+		);
+
 		// Initialize asset cache with initial values
 		let asset_cache = Cache::builder()
 			.time_to_live(Duration::from_hours(2))
@@ -95,14 +153,27 @@ impl ApiState {
 			particle_color_persist_rx,
 		));
 
-		let realtime = RealtimeState::default();
+		let (realtime_tx, realtime_rx) = tokio::sync::mpsc::unbounded_channel();
+		let bus: Arc<dyn RealtimeBus> = match args.realtime_bus {
+			RealtimeBusKind::InProcess => Arc::new(InProcessBus::new(realtime_tx)),
+			RealtimeBusKind::Postgres => Arc::new(
+				PostgresBus::connect(database.clone(), realtime_tx)
+					.await
+					.expect("Unable to listen for realtime events"),
+			),
+		};
+		let realtime = RealtimeState::new(bus, database.clone());
+		register_instance(&realtime)
+			.await
+			.expect("Unable to register realtime instance");
+		tokio::spawn(dispatch_events(realtime.clone(), realtime_rx));
+		tokio::spawn(heartbeat_loop(realtime.clone()));
 		tokio::spawn(flush_playtime_loop(
 			database.clone(),
 			realtime.playtime.clone(),
 		));
 
-		// Return final state
-		ApiState {
+		let state = ApiState {
 			stripe: StripeApiState {
 				client: StripeClient::new(args.stripe_secret.clone()),
 				webhook_secret: args.stripe_webhook_secret.clone(),
@@ -110,25 +181,39 @@ impl ApiState {
 				cancel_url: args.stripe_cancel_url.clone(),
 			},
 			database,
-			client: ClientBuilder::new()
-				.https_only(true)
-				.user_agent("PolyPlus Backend")
-				.build()
-				.expect("Unable to build reqwest HTTPS client"),
 			render_client: ClientBuilder::new()
 				.user_agent("PolyPlus Backend")
 				.build()
 				.expect("Unable to build reqwest render client"),
-			paseto_key: SymmetricKey::generate()
-				.expect("Unable to generate paseto signing key"),
+			paseto_keys,
+			session_verifier,
+			profiles,
 			s3_bucket,
 			asset_cache,
 			realtime,
 			equipment_persist_tx,
 			particle_color_persist_tx,
-			admin_password: args.admin_password.clone(),
+			visitor_hash_salt: args.visitor_hash_salt.clone(),
 			render_service_url: args.render_service_url.clone(),
-		}
+			rate_limits: RateLimiters {
+				login: Arc::new(RateLimiter::new(args.login_rate_limit)),
+				checkout: Arc::new(RateLimiter::new(args.checkout_rate_limit)),
+				link_follow: Arc::new(RateLimiter::new(args.link_rate_limit)),
+				device_poll: Arc::new(RateLimiter::new(args.device_poll_rate_limit)),
+				profile_lookup: Arc::new(RateLimiter::new(args.lookup_rate_limit)),
+				websocket_packet: args.websocket_packet_rate_limit,
+			},
+			websocket: WebsocketSettings {
+				queue_size: args.websocket_queue_size,
+				slow_consumer_policy: args.slow_consumer_policy,
+				ping_interval: Duration::from_secs(args.websocket_ping_interval),
+				idle_timeout: Duration::from_secs(args.websocket_idle_timeout),
+			},
+			metrics: Arc::default(),
+		};
+		tokio::spawn(process_deletions_loop(state.clone()));
+
+		state
 	}
 }
 
@@ -136,17 +221,31 @@ impl ApiState {
 pub(super) struct ApiState {
 	pub(super) stripe: StripeApiState,
 	pub(super) database: DatabaseConnection,
-	pub(super) client: Client,
 	pub(super) render_client: Client,
-	pub(super) paseto_key: SymmetricKey<V4>,
+	pub(super) paseto_keys: PasetoKeyring,
+	pub(super) session_verifier: Arc<dyn SessionVerifier>,
+	pub(super) profiles: ProfileResolver,
 	pub(super) s3_bucket: Arc<Bucket>,
 	pub(super) asset_cache: Cache<i32, CachedAssetInfo>,
 	pub(super) realtime: RealtimeState,
 	pub(super) equipment_persist_tx: tokio::sync::mpsc::Sender<EquipmentPersistence>,
 	pub(super) particle_color_persist_tx:
 		tokio::sync::mpsc::Sender<ParticleColorPersistence>,
-	pub(super) admin_password: String,
+	pub(super) visitor_hash_salt: String,
 	pub(super) render_service_url: String,
+	pub(super) rate_limits: RateLimiters,
+	pub(super) websocket: WebsocketSettings,
+	pub(super) metrics: Arc<Metrics>,
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct WebsocketSettings {
+	/// How many packets may wait to be sent to each connection
+	pub(super) queue_size: usize,
+	pub(super) slow_consumer_policy: SlowConsumerPolicy,
+	pub(super) ping_interval: Duration,
+	/// How long a connection may stay silent before it is considered dropped
+	pub(super) idle_timeout: Duration,
 }
 
 #[derive(Clone)]
@@ -164,8 +263,12 @@ impl std::fmt::Debug for StripeApiState {
 	}
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone)]
 pub(super) struct RealtimeState {
+	/// Tells this instance apart from others sharing the realtime bus
+	pub(super) instance_id: InstanceId,
+	pub(super) bus: Arc<dyn RealtimeBus>,
+	pub(super) database: DatabaseConnection,
 	pub(super) connections:
 		Arc<tokio::sync::RwLock<HashMap<ConnectionId, RealtimeConnection>>>,
 	pub(super) connections_by_owner:
@@ -174,17 +277,92 @@ pub(super) struct RealtimeState {
 		Arc<tokio::sync::RwLock<HashMap<Uuid, PlayerRuntimeState>>>,
 	pub(super) watchers: Arc<tokio::sync::RwLock<HashMap<Uuid, HashSet<ConnectionId>>>>,
 	pub(super) playtime: Arc<tokio::sync::RwLock<HashMap<Uuid, PlaytimeSession>>>,
+	/// The instances each player is connected to, across every instance
+	pub(super) online: Arc<tokio::sync::RwLock<HashMap<Uuid, HashSet<InstanceId>>>>,
+	/// The Minecraft servers players are on, across every instance, and the
+	/// connections on this instance watching each server. The servers are
+	/// also stored next to the players' presence, and loaded when the instance
+	/// starts.
+	pub(super) servers: Arc<tokio::sync::RwLock<ServerIndex>>,
+	/// The friends of the players connected to this instance only, loaded as
+	/// they connect
+	pub(super) local_friends: Arc<tokio::sync::RwLock<FriendIndex>>,
+	/// Pending paired emote invites by ID, across every instance. Also stored,
+	/// and loaded when the instance starts.
+	pub(super) emote_invites: Arc<tokio::sync::RwLock<HashMap<Uuid, EmoteInvite>>>,
 }
 
 pub(super) type ConnectionId = Uuid;
+pub(super) type InstanceId = Uuid;
 
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub(super) struct RealtimeConnection {
 	pub(super) owner: Uuid,
-	pub(super) tx: tokio::sync::mpsc::UnboundedSender<
-		crate::api::websocket::structs::ClientBoundPacket,
-	>,
+	pub(super) queue: Arc<OutboundQueue>,
+	/// What the connection negotiated in its handshake
+	pub(super) capabilities: BTreeSet<Capability>,
 	pub(super) subscriptions: HashSet<Uuid>,
+	/// Asks the connection to close itself with the given frame
+	pub(super) close_tx: tokio::sync::mpsc::Sender<CloseFrame>,
+	/// Set if the connection negotiated the resume capability
+	pub(super) resumable: Option<Resumable>,
+	/// The server whose players the connection is subscribed to
+	pub(super) watched_server: Option<String>,
+	/// The subscriptions that were added because of `watched_server`
+	pub(super) server_players: HashSet<Uuid>,
+}
+
+impl RealtimeConnection {
+	/// Sends a packet to the connection, numbering it if it can be resumed
+	pub(super) fn send(&mut self, packet: ClientBoundPacket) {
+		match &mut self.resumable {
+			// Packets for dropped connections are only kept for replay
+			Some(resumable) if resumable.is_detached() => {
+				resumable.sequence(packet);
+			}
+			Some(resumable) => self.queue.push(resumable.sequence(packet)),
+			None => self.queue.push(Arc::new(packet.into())),
+		}
+	}
+}
+
+impl RealtimeState {
+	fn new(bus: Arc<dyn RealtimeBus>, database: DatabaseConnection) -> Self {
+		Self {
+			instance_id: Uuid::new_v4(),
+			bus,
+			database,
+			connections: Arc::default(),
+			connections_by_owner: Arc::default(),
+			player_runtime: Arc::default(),
+			watchers: Arc::default(),
+			playtime: Arc::default(),
+			online: Arc::default(),
+			servers: Arc::default(),
+			local_friends: Arc::default(),
+			emote_invites: Arc::default(),
+		}
+	}
+
+	/// Closes every live connection of a player, on every instance. The
+	/// connections clean up after themselves as they shut down.
+	pub(super) async fn close_connections(&self, owner: Uuid, code: u16, reason: &str) {
+		self.publish(RealtimeEvent::CloseConnections {
+			player: owner,
+			code,
+			reason: reason.to_owned(),
+		})
+		.await;
+	}
+
+	/// Drops everything kept in memory about a player, without recording any
+	/// outstanding playtime
+	pub(super) async fn forget_player(&self, player: Uuid) {
+		self.playtime.write().await.remove(&player);
+		self.player_runtime.write().await.remove(&player);
+		self.watchers.write().await.remove(&player);
+		self.local_friends.write().await.remove_player(player);
+	}
 }
 
 #[derive(Debug, Clone)]
@@ -194,11 +372,23 @@ pub(super) struct PlaytimeSession {
 	pub(super) last_accounted_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub(super) struct PlayerRuntimeState {
 	pub(super) equipped: HashMap<BodySlot, i32>,
 	pub(super) active_emote: Option<i32>,
 	pub(super) particle_color: Option<i32>,
+	/// The Minecraft server the player reported being on
+	pub(super) server: Option<String>,
+}
+
+/// An invite to do a paired emote, kept until it is answered or expires
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(super) struct EmoteInvite {
+	pub(super) initiator: Uuid,
+	pub(super) target: Uuid,
+	pub(super) emote_id: i32,
+	/// Unix timestamp in milliseconds
+	pub(super) expires_at: i64,
 }
 
 #[derive(Debug, Clone)]
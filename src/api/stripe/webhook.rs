@@ -20,10 +20,7 @@ use tracing::{info, warn};
 use uuid::Uuid;
 
 use crate::{
-	api::{
-		ApiState, stripe::pricing::cosmetics_for_price,
-		websocket::structs::ClientBoundPacket,
-	},
+	api::{ApiState, realtime::RealtimeEvent, stripe::pricing::cosmetics_for_price},
 	database::{DatabaseTransactionExt, DatabaseUserExt},
 };
 
@@ -204,27 +201,14 @@ pub(super) async fn endpoint(
 	);
 
 	if !grant.cosmetic_ids.is_empty() || !grant.emote_ids.is_empty() {
-		let connection_ids = state
-			.realtime
-			.connections_by_owner
-			.read()
-			.await
-			.get(&player)
-			.cloned()
-			.unwrap_or_default();
-		if !connection_ids.is_empty() {
-			let connections = state.realtime.connections.read().await;
-			for connection_id in connection_ids {
-				let Some(connection) = connections.get(&connection_id) else {
-					continue;
-				};
-				let _ = connection.tx.send(ClientBoundPacket::OwnershipUpdated {
-					player,
-					cosmetic_ids: grant.cosmetic_ids.clone(),
-					emote_ids: grant.emote_ids.clone(),
-					revoked: false,
-				});
-			}
+		let events = RealtimeEvent::ownership_updated(
+			player,
+			grant.cosmetic_ids,
+			grant.emote_ids,
+			false,
+		);
+		for event in events {
+			state.realtime.publish(event).await;
 		}
 	}
 
@@ -371,27 +355,14 @@ async fn handle_refund(state: &ApiState, charge: Charge) -> StatusCode {
 	);
 
 	if !revoked.cosmetic_ids.is_empty() || !revoked.emote_ids.is_empty() {
-		let connection_ids = state
-			.realtime
-			.connections_by_owner
-			.read()
-			.await
-			.get(&player)
-			.cloned()
-			.unwrap_or_default();
-		if !connection_ids.is_empty() {
-			let connections = state.realtime.connections.read().await;
-			for connection_id in connection_ids {
-				let Some(connection) = connections.get(&connection_id) else {
-					continue;
-				};
-				let _ = connection.tx.send(ClientBoundPacket::OwnershipUpdated {
-					player,
-					cosmetic_ids: revoked.cosmetic_ids.clone(),
-					emote_ids: revoked.emote_ids.clone(),
-					revoked: true,
-				});
-			}
+		let events = RealtimeEvent::ownership_updated(
+			player,
+			revoked.cosmetic_ids,
+			revoked.emote_ids,
+			true,
+		);
+		for event in events {
+			state.realtime.publish(event).await;
 		}
 	}
 
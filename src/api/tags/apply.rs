@@ -8,7 +8,11 @@ use schemars::JsonSchema;
 use sea_orm::{EntityTrait, Set, TransactionTrait};
 use serde::Deserialize;
 
-use crate::api::{ApiState, admin_auth::AdminAuthenticationExtractor};
+use crate::api::{
+	ApiState,
+	admin_auth::{AdminAuthenticationExtractor, CatalogWrite},
+	audit::{self, AuditEntry},
+};
 
 #[derive(thiserror::Error, Debug, OperationIo)]
 pub enum ApplyError {
@@ -46,7 +50,7 @@ fn endpoint_doc(op: TransformOperation) -> TransformOperation {
 			"Applies a tag to each of the given cosmetics. A cosmetic that belongs \
 			 to a group tags every variant of that group, matching how grants and \
 			 the catalog treat groups. Already-tagged cosmetics are left alone. \
-			 Admin password required.",
+			 Requires the `catalog:write` scope.",
 		)
 		.tag("tags")
 }
@@ -55,10 +59,10 @@ pub(super) fn router() -> ApiRouter<ApiState> {
 	ApiRouter::new().api_route("/apply", post_with(self::endpoint, self::endpoint_doc))
 }
 
-#[tracing::instrument(level = "debug", skip(state, _auth))]
+#[tracing::instrument(level = "debug", skip(state, actor))]
 async fn endpoint(
 	State(state): State<ApiState>,
-	_auth: AdminAuthenticationExtractor,
+	AdminAuthenticationExtractor(actor, ..): AdminAuthenticationExtractor<CatalogWrite>,
 	Json(body): Json<ApplyRequest>,
 ) -> Result<StatusCode, ApplyError> {
 	use entities::{prelude::*, tags_cosmetic};
@@ -76,7 +80,7 @@ async fn endpoint(
 		return Err(ApplyError::MissingCosmetic);
 	};
 
-	TagsCosmetic::insert_many(cosmetic_ids.into_iter().map(|cosmetic_id| {
+	TagsCosmetic::insert_many(cosmetic_ids.iter().map(|&cosmetic_id| {
 		tags_cosmetic::ActiveModel {
 			tag_id: Set(body.tag_id),
 			cosmetic_id: Set(cosmetic_id),
@@ -85,6 +89,13 @@ async fn endpoint(
 	.on_conflict_do_nothing()
 	.exec(&txn)
 	.await?;
+	audit::record(
+		&txn,
+		&actor,
+		AuditEntry::new("tag.apply", "tag", body.tag_id)
+			.after(serde_json::json!({ "cosmetic_ids": cosmetic_ids })),
+	)
+	.await?;
 	txn.commit().await?;
 
 	Ok(StatusCode::NO_CONTENT)
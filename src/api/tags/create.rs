@@ -7,10 +7,14 @@ use axum::{Json, extract::State, http::StatusCode, response::IntoResponse};
 use chrono::{DateTime, FixedOffset};
 use entities::sea_orm_active_enums::TagType;
 use schemars::JsonSchema;
-use sea_orm::{ActiveModelTrait, Set};
+use sea_orm::{ActiveModelTrait, Set, TransactionTrait};
 use serde::{Deserialize, Serialize};
 
-use crate::api::{ApiState, admin_auth::AdminAuthenticationExtractor};
+use crate::api::{
+	ApiState,
+	admin_auth::{AdminAuthenticationExtractor, CatalogWrite},
+	audit::{self, AuditEntry, model_json},
+};
 
 #[derive(thiserror::Error, Debug, OperationIo)]
 pub enum CreateError {
@@ -52,7 +56,7 @@ pub struct CreateResponse {
 fn endpoint_doc(op: TransformOperation) -> TransformOperation {
 	op.id("createTag")
 		.summary("Create a tag")
-		.description("Creates a new color or custom tag. Also use to create categories with type = category. Admin password required.")
+		.description("Creates a new color or custom tag. Also use to create categories with type = category. Requires the `catalog:write` scope.")
 		.tag("tags")
 }
 
@@ -60,14 +64,15 @@ pub(super) fn router() -> ApiRouter<ApiState> {
 	ApiRouter::new().api_route("/create", post_with(self::endpoint, self::endpoint_doc))
 }
 
-#[tracing::instrument(level = "debug", skip(state, _auth))]
+#[tracing::instrument(level = "debug", skip(state, actor))]
 async fn endpoint(
 	State(state): State<ApiState>,
-	_auth: AdminAuthenticationExtractor,
+	AdminAuthenticationExtractor(actor, ..): AdminAuthenticationExtractor<CatalogWrite>,
 	Json(body): Json<CreateRequest>,
 ) -> Result<(StatusCode, Json<CreateResponse>), CreateError> {
 	use entities::tags;
 
+	let txn = state.database.begin().await?;
 	let tag = tags::ActiveModel {
 		name: Set(body.name),
 		display_name: Set(body.display_name),
@@ -75,8 +80,16 @@ async fn endpoint(
 		tag_type: Set(body.tag_type),
 		..Default::default()
 	}
-	.insert(&state.database)
+	.insert(&txn)
+	.await?;
+
+	audit::record(
+		&txn,
+		&actor,
+		AuditEntry::new("tag.create", "tag", tag.id).after(model_json(&tag)),
+	)
 	.await?;
+	txn.commit().await?;
 
 	Ok((
 		StatusCode::CREATED,
@@ -8,7 +8,11 @@ use schemars::JsonSchema;
 use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, TransactionTrait};
 use serde::Deserialize;
 
-use crate::api::{ApiState, admin_auth::AdminAuthenticationExtractor};
+use crate::api::{
+	ApiState,
+	admin_auth::{AdminAuthenticationExtractor, CatalogWrite},
+	audit::{self, AuditEntry},
+};
 
 #[derive(thiserror::Error, Debug, OperationIo)]
 pub enum RemoveError {
@@ -46,7 +50,7 @@ fn endpoint_doc(op: TransformOperation) -> TransformOperation {
 			"Removes a tag from each of the given cosmetics. A cosmetic that \
 			 belongs to a group untags every variant of that group, mirroring how \
 			 the tag was applied. Cosmetics that do not carry the tag are left \
-			 alone. Admin password required.",
+			 alone. Requires the `catalog:write` scope.",
 		)
 		.tag("tags")
 }
@@ -55,10 +59,10 @@ pub(super) fn router() -> ApiRouter<ApiState> {
 	ApiRouter::new().api_route("/remove", post_with(self::endpoint, self::endpoint_doc))
 }
 
-#[tracing::instrument(level = "debug", skip(state, _auth))]
+#[tracing::instrument(level = "debug", skip(state, actor))]
 async fn endpoint(
 	State(state): State<ApiState>,
-	_auth: AdminAuthenticationExtractor,
+	AdminAuthenticationExtractor(actor, ..): AdminAuthenticationExtractor<CatalogWrite>,
 	Json(body): Json<RemoveRequest>,
 ) -> Result<StatusCode, RemoveError> {
 	use entities::{prelude::*, tags_cosmetic};
@@ -78,9 +82,16 @@ async fn endpoint(
 
 	TagsCosmetic::delete_many()
 		.filter(tags_cosmetic::Column::TagId.eq(body.tag_id))
-		.filter(tags_cosmetic::Column::CosmeticId.is_in(cosmetic_ids))
+		.filter(tags_cosmetic::Column::CosmeticId.is_in(cosmetic_ids.iter().copied()))
 		.exec(&txn)
 		.await?;
+	audit::record(
+		&txn,
+		&actor,
+		AuditEntry::new("tag.remove", "tag", body.tag_id)
+			.before(serde_json::json!({ "cosmetic_ids": cosmetic_ids })),
+	)
+	.await?;
 	txn.commit().await?;
 
 	Ok(StatusCode::NO_CONTENT)
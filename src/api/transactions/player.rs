@@ -1,3 +1,5 @@
+use std::collections::{HashMap, HashSet};
+
 use aide::{OperationIo, transform::TransformOperation};
 use axum::{
 	Json,
@@ -5,7 +7,9 @@ use axum::{
 	http::StatusCode,
 	response::IntoResponse,
 };
-use entities::sea_orm_active_enums::{TransactionProvider, TransactionStatus};
+use entities::sea_orm_active_enums::{
+	Permission, TransactionProvider, TransactionStatus,
+};
 use schemars::JsonSchema;
 use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
 use serde::{Deserialize, Serialize};
@@ -13,7 +17,8 @@ use uuid::Uuid;
 
 use crate::api::{
 	ApiState,
-	account::{AuthenticatedPlayer, role_at_least},
+	account::{AuthenticatedPlayer, permissions::has_permission},
+	players::profile::known_names,
 };
 
 #[derive(thiserror::Error, Debug, OperationIo)]
@@ -43,7 +48,7 @@ impl IntoResponse for TransactionsError {
 pub fn endpoint_doc(op: TransformOperation) -> TransformOperation {
 	op.id("getPlayerTransactions")
 		.summary("Get player transactions")
-		.description("Lists transactions for the authenticated player or for another player when the caller is elevated.")
+		.description("Lists transactions for the authenticated player or for another player when the caller has the `view_transactions` permission.")
 		.tag("transactions")
 }
 
@@ -51,6 +56,9 @@ pub fn endpoint_doc(op: TransformOperation) -> TransformOperation {
 pub struct TransactionsQuery {
 	#[serde(default)]
 	player: Option<Uuid>,
+	/// Also return the last known usernames of the players involved
+	#[serde(default)]
+	include_names: bool,
 }
 
 #[derive(Debug, Serialize, JsonSchema)]
@@ -68,6 +76,10 @@ struct TransactionInfo {
 #[derive(Debug, Default, Serialize, JsonSchema)]
 pub struct TransactionsResponse {
 	transactions: Vec<TransactionInfo>,
+	/// The last known usernames of the player and buyers, when requested with
+	/// `include_names`. Players whose name isn't known yet are left out.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	names: Option<HashMap<Uuid, String>>,
 }
 
 #[tracing::instrument(level = "debug", skip(state))]
@@ -80,10 +92,13 @@ pub(super) async fn endpoint(
 
 	let target_uuid = query.player.unwrap_or(authenticated.minecraft_uuid);
 	if target_uuid != authenticated.minecraft_uuid
-		&& !role_at_least(
-			&authenticated.role,
-			&entities::sea_orm_active_enums::PlayerRole::Moderator,
-		) {
+		&& !has_permission(
+			&state.database,
+			&authenticated,
+			Permission::ViewTransactions,
+		)
+		.await?
+	{
 		return Err(TransactionsError::Forbidden);
 	}
 
@@ -135,5 +150,19 @@ pub(super) async fn endpoint(
 	}))
 	.await?;
 
-	Ok(Json(TransactionsResponse { transactions }))
+	let names = if query.include_names {
+		let players = transactions
+			.iter()
+			.filter_map(|transaction| transaction.buyer)
+			.chain([target_uuid])
+			.collect::<HashSet<_>>();
+		Some(known_names(&state.database, players).await?)
+	} else {
+		None
+	};
+
+	Ok(Json(TransactionsResponse {
+		transactions,
+		names,
+	}))
 }
@@ -0,0 +1,311 @@
+//! The encodings websocket packets can be sent in, negotiated with the
+//! `Sec-WebSocket-Protocol` header. Clients that don't ask for a subprotocol
+//! get JSON.
+
+use axum::extract::ws::Message;
+use serde::{Serialize, de::DeserializeOwned};
+
+use crate::api::websocket::structs::WebsocketError;
+
+/// Every packet is a JSON object in a text frame
+pub(super) const JSON_PROTOCOL: &str = "polyplus.json";
+/// Every packet is a MessagePack map in a binary frame, with the same fields
+/// as in JSON. UUIDs are 16 byte binary strings instead of text.
+pub(super) const MSGPACK_PROTOCOL: &str = "polyplus.msgpack";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(super) enum Codec {
+	#[default]
+	Json,
+	MessagePack,
+}
+
+impl Codec {
+	/// The subprotocols offered to clients, most preferred first
+	pub(super) const PROTOCOLS: [&str; 2] = [MSGPACK_PROTOCOL, JSON_PROTOCOL];
+
+	pub(super) fn from_protocol(protocol: &str) -> Option<Self> {
+		match protocol {
+			JSON_PROTOCOL => Some(Self::Json),
+			MSGPACK_PROTOCOL => Some(Self::MessagePack),
+			_ => None,
+		}
+	}
+
+	pub(super) fn encode(
+		self,
+		packet: &impl Serialize,
+	) -> Result<Message, WebsocketError> {
+		Ok(match self {
+			Self::Json => Message::Text(serde_json::to_string(packet)?.into()),
+			Self::MessagePack => Message::Binary(rmp_serde::to_vec_named(packet)?.into()),
+		})
+	}
+
+	/// Decodes a packet from any data frame, regardless of whether it is text
+	/// or binary
+	pub(super) fn decode<T: DeserializeOwned>(
+		self,
+		msg: Message,
+	) -> Result<T, WebsocketError> {
+		let data = msg.into_data();
+		match self {
+			Self::Json => {
+				serde_json::from_slice(&data).map_err(WebsocketError::Deserialization)
+			}
+			Self::MessagePack => rmp_serde::from_slice(&data)
+				.map_err(WebsocketError::BinaryDeserialization),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::{BTreeSet, HashMap};
+
+	use entities::sea_orm_active_enums::{AnnouncementLevel, BodySlot};
+	use serde::{Serialize, de::DeserializeOwned};
+	use uuid::Uuid;
+
+	use super::Codec;
+	use crate::api::websocket::structs::{
+		AnnouncementInfo, Capability, ClientBoundMessage, ClientBoundPacket,
+		ConnectionLimits, PROTOCOL_VERSION, ServerBoundMessage, ServerBoundPacket,
+	};
+
+	const CODECS: [Codec; 2] = [Codec::Json, Codec::MessagePack];
+
+	/// Encodes and decodes a packet, comparing both through their JSON form
+	fn assert_round_trips<T: Serialize + DeserializeOwned>(packet: &T) {
+		let expected = serde_json::to_value(packet).expect("packet should serialize");
+		for codec in CODECS {
+			let encoded = codec.encode(packet).expect("packet should encode");
+			let decoded: T = codec.decode(encoded).expect("packet should decode");
+			assert_eq!(
+				serde_json::to_value(&decoded).expect("packet should serialize"),
+				expected,
+				"{codec:?} should round trip"
+			);
+		}
+	}
+
+	#[test]
+	fn server_bound_packets_round_trip() {
+		let player = Uuid::from_u128(0x424e_f6d0_4774_4f8c_8bef_8f62_ebda_c9c0);
+		for packet in [
+			ServerBoundPacket::Hello {
+				protocol_version: PROTOCOL_VERSION,
+				mod_version: "1.0.0".to_owned(),
+				capabilities: vec![Capability::Presence],
+			},
+			ServerBoundPacket::Resume {
+				session_id: player,
+				last_seq: 12,
+			},
+			ServerBoundPacket::GetActiveCosmetics {
+				players: vec![player],
+			},
+			ServerBoundPacket::SubscribePlayers {
+				players: vec![player, Uuid::nil()],
+			},
+			ServerBoundPacket::UnsubscribePlayers {
+				players: vec![player],
+			},
+			ServerBoundPacket::SetEquippedCosmetic {
+				slot: BodySlot::Cape,
+				cosmetic_id: Some(4),
+			},
+			ServerBoundPacket::SetEquippedCosmetic {
+				slot: BodySlot::Hat,
+				cosmetic_id: None,
+			},
+			ServerBoundPacket::SetParticleColor { color: Some(0xFF) },
+			ServerBoundPacket::PlayEmote { emote_id: 6 },
+			ServerBoundPacket::StopEmote,
+			ServerBoundPacket::ConfirmDeviceCode {
+				user_code: "BCDF-GHJK".to_owned(),
+			},
+			ServerBoundPacket::SetServer {
+				server: Some("mc.hypixel.net".to_owned()),
+			},
+			ServerBoundPacket::SetServer { server: None },
+			ServerBoundPacket::SubscribeServer {
+				server: "mc.hypixel.net".to_owned(),
+			},
+			ServerBoundPacket::UnsubscribeServer,
+			ServerBoundPacket::InviteEmote {
+				target: player,
+				emote_id: 6,
+			},
+			ServerBoundPacket::AcceptEmoteInvite { invite_id: player },
+			ServerBoundPacket::DeclineEmoteInvite { invite_id: player },
+			ServerBoundPacket::ApplyOutfit { outfit_id: 2 },
+		] {
+			assert_round_trips(&packet);
+		}
+	}
+
+	#[test]
+	fn client_bound_packets_round_trip() {
+		let player = Uuid::from_u128(0x424e_f6d0_4774_4f8c_8bef_8f62_ebda_c9c0);
+		for packet in [
+			ClientBoundPacket::Welcome {
+				server_version: "1.0.0".to_owned(),
+				session_id: player,
+				protocol_version: PROTOCOL_VERSION,
+				capabilities: BTreeSet::from([
+					Capability::Presence,
+					Capability::Resume,
+					Capability::Friends,
+					Capability::PairedEmotes,
+					Capability::Announcements,
+					Capability::CatalogChanges,
+				]),
+				limits: ConnectionLimits {
+					max_players_per_request: 64,
+					max_player_subscriptions: 128,
+				},
+			},
+			ClientBoundPacket::CosmeticsInfo {
+				cosmetics: HashMap::from([(player, vec![1, 2])]),
+			},
+			ClientBoundPacket::SubscriptionSnapshot {
+				equipped: HashMap::from([(player, HashMap::from([(BodySlot::Cape, 1)]))]),
+				active_emotes: HashMap::from([(player, 6)]),
+				particle_colors: HashMap::from([(player, 0xFF_0000)]),
+				users: vec![player],
+			},
+			ClientBoundPacket::PlayerPresence {
+				player,
+				online: true,
+			},
+			ClientBoundPacket::PlayerCosmeticEquipped {
+				player,
+				slot: BodySlot::Boots,
+				cosmetic_id: Some(3),
+			},
+			ClientBoundPacket::PlayerParticleColorChanged {
+				player,
+				color: None,
+			},
+			ClientBoundPacket::PlayerEmoteStarted {
+				player,
+				emote_id: 6,
+			},
+			ClientBoundPacket::PlayerEmoteStopped { player },
+			ClientBoundPacket::OwnershipUpdated {
+				player,
+				cosmetic_ids: vec![1],
+				emote_ids: vec![6],
+				revoked: false,
+			},
+			ClientBoundPacket::DeviceCodeConfirmed {
+				user_code: "BCDF-GHJK".to_owned(),
+			},
+			ClientBoundPacket::FriendsSnapshot {
+				friends: vec![player],
+				online_friends: vec![player],
+				incoming_requests: vec![Uuid::nil()],
+				outgoing_requests: Vec::new(),
+			},
+			ClientBoundPacket::FriendPresence {
+				player,
+				online: false,
+			},
+			ClientBoundPacket::FriendRequestReceived { player },
+			ClientBoundPacket::FriendAdded {
+				player,
+				online: true,
+			},
+			ClientBoundPacket::FriendRemoved { player },
+			ClientBoundPacket::ServerPlayerLeft { player },
+			ClientBoundPacket::EmoteInviteSent {
+				invite_id: Uuid::nil(),
+				target: player,
+				expires_at: 1_792_000_000_000,
+			},
+			ClientBoundPacket::EmoteInviteReceived {
+				invite_id: Uuid::nil(),
+				player,
+				emote_id: 6,
+				expires_at: 1_792_000_000_000,
+			},
+			ClientBoundPacket::EmoteInviteDeclined {
+				invite_id: Uuid::nil(),
+				player,
+			},
+			ClientBoundPacket::PairedEmoteStarted {
+				emote_id: 6,
+				initiator: player,
+				partner: Uuid::nil(),
+				starts_at: 1_792_000_000_500,
+			},
+			ClientBoundPacket::Announcement {
+				announcement: AnnouncementInfo {
+					id: 3,
+					level: AnnouncementLevel::Warning,
+					title: "Maintenance".to_owned(),
+					body: "PolyPlus will be down for an hour tonight".to_owned(),
+					link: None,
+					expires_at: 1_792_000_000_000,
+				},
+			},
+			ClientBoundPacket::CatalogChanged {
+				cosmetic_ids: vec![4, 5],
+				group_ids: vec![2],
+				bundle_ids: Vec::new(),
+				catalog_version: 17,
+			},
+			ClientBoundPacket::Ack,
+		] {
+			assert_round_trips(&packet);
+		}
+	}
+
+	#[test]
+	fn messages_round_trip_with_request_ids() {
+		for request_id in [None, Some(7)] {
+			assert_round_trips(&ServerBoundMessage {
+				request_id,
+				packet: ServerBoundPacket::UnsubscribePlayers {
+					players: vec![Uuid::nil()],
+				},
+			});
+			assert_round_trips(&ClientBoundMessage {
+				request_id,
+				seq: request_id.map(u64::from),
+				packet: ClientBoundPacket::Ack,
+			});
+		}
+
+		let message =
+			serde_json::to_value(ClientBoundMessage::from(ClientBoundPacket::Ack))
+				.expect("message should serialize");
+		assert_eq!(message, serde_json::json!({ "type": "Ack" }));
+	}
+
+	#[test]
+	fn msgpack_encodes_uuids_as_bytes() {
+		let player = Uuid::from_u128(0x424e_f6d0_4774_4f8c_8bef_8f62_ebda_c9c0);
+		let encoded = Codec::MessagePack
+			.encode(&ClientBoundPacket::PlayerEmoteStopped { player })
+			.expect("packet should encode")
+			.into_data();
+
+		// bin 8 header followed by the raw UUID
+		let mut raw = vec![0xC4, 16];
+		raw.extend_from_slice(player.as_bytes());
+		assert!(encoded.windows(raw.len()).any(|window| window == raw));
+		assert!(!encoded.windows(4).any(|window| window == b"424e"));
+	}
+
+	#[test]
+	fn negotiates_known_protocols_only() {
+		assert_eq!(Codec::from_protocol("polyplus.json"), Some(Codec::Json));
+		assert_eq!(
+			Codec::from_protocol("polyplus.msgpack"),
+			Some(Codec::MessagePack)
+		);
+		assert_eq!(Codec::from_protocol("polyplus.cbor"), None);
+	}
+}
@@ -0,0 +1,193 @@
+//! Paired emotes, like handshakes, that two players do together. One player
+//! invites another with [ServerBoundPacket::InviteEmote], and once the other
+//! accepts, everyone watching either of them is sent a single
+//! [ClientBoundPacket::PairedEmoteStarted]. Every instance keeps the pending
+//! invites, so that they can be answered from any of them, and they are stored
+//! for instances that start while they are pending.
+
+use std::collections::HashMap;
+
+use chrono::{TimeDelta, Utc};
+use entities::emote_invite;
+use sea_orm::{ColumnTrait, Condition, EntityTrait, QueryFilter, Set};
+use uuid::Uuid;
+
+use crate::api::{
+	ApiState,
+	realtime::RealtimeEvent,
+	state::EmoteInvite,
+	websocket::{
+		endpoint::validate_emote,
+		structs::{ClientBoundPacket, WebsocketError},
+	},
+};
+
+/// How long an emote invite can be answered for, in milliseconds
+const EMOTE_INVITE_TTL_MS: i64 = 30_000;
+/// How far ahead paired emotes start, in milliseconds, so that both players'
+/// clients hear about them in time
+const PAIRED_EMOTE_LEAD_MS: i64 = 500;
+
+/// Invites another player to a paired emote, if the player owns it
+pub(super) async fn invite_emote(
+	state: &ApiState,
+	player: &entities::user::Model,
+	target: Uuid,
+	emote_id: i32,
+) -> Result<ClientBoundPacket, WebsocketError> {
+	let initiator = player.minecraft_uuid;
+	if target == initiator || !state.realtime.online.read().await.contains_key(&target) {
+		return Err(WebsocketError::InvalidEmoteTarget);
+	}
+	validate_emote(state, player.id, emote_id).await?;
+
+	let invite_id = Uuid::new_v4();
+	let now = Utc::now();
+	let expiry = now + TimeDelta::milliseconds(EMOTE_INVITE_TTL_MS);
+	// Players only have one pending invite at a time
+	emote_invite::Entity::delete_many()
+		.filter(
+			Condition::any()
+				.add(emote_invite::Column::Initiator.eq(initiator))
+				.add(emote_invite::Column::ExpiresAt.lte(now)),
+		)
+		.exec(&state.database)
+		.await?;
+	emote_invite::Entity::insert(emote_invite::ActiveModel {
+		id: Set(invite_id),
+		initiator: Set(initiator),
+		target: Set(target),
+		emote_id: Set(emote_id),
+		expires_at: Set(expiry.fixed_offset()),
+	})
+	.exec_without_returning(&state.database)
+	.await?;
+
+	let expires_at = expiry.timestamp_millis();
+	state
+		.realtime
+		.publish(RealtimeEvent::EmoteInvited {
+			invite_id,
+			invite: EmoteInvite {
+				initiator,
+				target,
+				emote_id,
+				expires_at,
+			},
+		})
+		.await;
+
+	Ok(ClientBoundPacket::EmoteInviteSent {
+		invite_id,
+		target,
+		expires_at,
+	})
+}
+
+/// Starts the paired emote of an invite sent to the player
+pub(super) async fn accept_emote_invite(
+	state: &ApiState,
+	player: Uuid,
+	invite_id: Uuid,
+) -> Result<(), WebsocketError> {
+	let now = Utc::now().timestamp_millis();
+	let invite = take_invite(
+		&mut *state.realtime.emote_invites.write().await,
+		invite_id,
+		player,
+		now,
+	)
+	.ok_or(WebsocketError::InvalidEmoteInvite)?;
+	emote_invite::Entity::delete_by_id(invite_id)
+		.exec(&state.database)
+		.await?;
+
+	state
+		.realtime
+		.publish(RealtimeEvent::PairedEmoteStarted {
+			invite_id,
+			initiator: invite.initiator,
+			partner: player,
+			emote_id: invite.emote_id,
+			starts_at: now + PAIRED_EMOTE_LEAD_MS,
+		})
+		.await;
+	Ok(())
+}
+
+pub(super) async fn decline_emote_invite(
+	state: &ApiState,
+	player: Uuid,
+	invite_id: Uuid,
+) -> Result<(), WebsocketError> {
+	let invite = take_invite(
+		&mut *state.realtime.emote_invites.write().await,
+		invite_id,
+		player,
+		Utc::now().timestamp_millis(),
+	)
+	.ok_or(WebsocketError::InvalidEmoteInvite)?;
+	emote_invite::Entity::delete_by_id(invite_id)
+		.exec(&state.database)
+		.await?;
+
+	state
+		.realtime
+		.publish(RealtimeEvent::EmoteInviteDeclined {
+			invite_id,
+			initiator: invite.initiator,
+			target: player,
+		})
+		.await;
+	Ok(())
+}
+
+/// Removes an invite sent to `target` from the pending invites, returning it
+/// unless it has expired
+fn take_invite(
+	invites: &mut HashMap<Uuid, EmoteInvite>,
+	invite_id: Uuid,
+	target: Uuid,
+	now: i64,
+) -> Option<EmoteInvite> {
+	if invites.get(&invite_id)?.target != target {
+		return None;
+	}
+	invites
+		.remove(&invite_id)
+		.filter(|invite| invite.expires_at > now)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::HashMap;
+
+	use uuid::Uuid;
+
+	use super::take_invite;
+	use crate::api::state::EmoteInvite;
+
+	#[test]
+	fn only_the_target_can_take_an_unexpired_invite() {
+		let (initiator, target) = (Uuid::from_u128(1), Uuid::from_u128(2));
+		let (pending, expired) = (Uuid::from_u128(3), Uuid::from_u128(4));
+		let invite = |expires_at| EmoteInvite {
+			initiator,
+			target,
+			emote_id: 6,
+			expires_at,
+		};
+		let mut invites =
+			HashMap::from([(pending, invite(2_000)), (expired, invite(500))]);
+
+		assert_eq!(take_invite(&mut invites, pending, initiator, 1_000), None);
+		assert_eq!(
+			take_invite(&mut invites, pending, target, 1_000),
+			Some(invite(2_000))
+		);
+		assert_eq!(take_invite(&mut invites, pending, target, 1_000), None);
+
+		assert_eq!(take_invite(&mut invites, expired, target, 1_000), None);
+		assert!(invites.is_empty());
+	}
+}
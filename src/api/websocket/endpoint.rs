@@ -1,4 +1,8 @@
-use std::collections::{HashMap, HashSet};
+use std::{
+	collections::{HashMap, HashSet},
+	sync::Arc,
+	time::Instant,
+};
 
 use aide::{
 	axum::{ApiRouter, routing::ApiMethodDocs},
@@ -9,7 +13,7 @@ use axum::{
 	body::Body,
 	extract::{
 		State, WebSocketUpgrade,
-		ws::{Message, WebSocket},
+		ws::{CloseFrame, Message, WebSocket},
 	},
 	routing::get,
 };
@@ -17,25 +21,41 @@ use chrono::Utc;
 use entities::sea_orm_active_enums::BodySlot;
 use http::{Response, StatusCode};
 use sea_orm::{ColumnTrait as _, EntityTrait as _, QueryFilter};
-use tokio::sync::mpsc;
+use tokio::{sync::mpsc, time::MissedTickBehavior};
 use tracing::warn;
 use uuid::Uuid;
 
 use crate::api::{
 	ApiState,
-	account::AuthenticatedPlayer,
+	account::{
+		AuthenticatedPlayer,
+		device::{ConfirmDeviceError, confirm_device_code},
+	},
+	announcements::{announcements_for, record_deliveries},
+	friends::load_friends,
+	presence::set_presence,
+	rate_limit::{RateLimitGroup, TokenBucket},
+	realtime::RealtimeEvent,
 	state::{
 		ConnectionId, EquipmentPersistence, ParticleColorPersistence, PlayerRuntimeState,
 		PlaytimeSession, RealtimeConnection,
 	},
-	websocket::structs::{ClientBoundPacket, ServerBoundPacket, WebsocketError},
+	websocket::{
+		codec::Codec,
+		emotes::{accept_emote_invite, decline_emote_invite, invite_emote},
+		handshake::{Greeting, Session, handshake},
+		outfits::apply_outfit,
+		queue::OutboundQueue,
+		resume::{Resumable, detach_connection, resume_connection},
+		servers::{set_server, subscribe_server, unsubscribe_server},
+		structs::{
+			CLOSE_CODE_SLOW_CONSUMER, Capability, ClientBoundMessage, ClientBoundPacket,
+			ConnectionLimits, MAX_PLAYER_SUBSCRIPTIONS, MAX_PLAYERS_PER_REQUEST,
+			ServerBoundMessage, ServerBoundPacket, WebsocketError,
+		},
+	},
 };
 
-/// Max UUIDs in a single `SubscribePlayers` or `GetActiveCosmetics` message.
-const MAX_PLAYERS_PER_REQUEST: usize = 64;
-/// Max distinct players a connection may subscribe to at once (render distance).
-const MAX_PLAYER_SUBSCRIPTIONS: usize = 128;
-
 fn enforce_max_players_per_request(players: &[Uuid]) -> Result<(), WebsocketError> {
 	if players.len() > MAX_PLAYERS_PER_REQUEST {
 		return Err(WebsocketError::TooManyPlayersInRequest {
@@ -58,9 +78,44 @@ pub(super) fn router() -> ApiRouter<ApiState> {
 					.summary("Open a websocket connection to the server")
 					.description(
 						"Establishes a websocket connection to the server. Websocket \
-						 packets can examined from the ClientBoundPacket and \
-						 ServerBoundPacket OpenAPI schemas. This largely follows a \
-						 request-response model, but that may not always be true.",
+						 packets can examined from the ClientBoundMessage and \
+						 ServerBoundMessage OpenAPI schemas. This largely follows a \
+						 request-response model, but that may not always be true.\n\n\
+						 Every connection must start with a Hello packet, which the \
+						 server answers with a Welcome packet. Clients that don't send \
+						 Hello within 10 seconds are disconnected with close code 4006, \
+						 and clients whose protocol version is no longer supported are \
+						 disconnected with close code 4005. Sessions that negotiate the \
+						 `resume` capability can be picked back up within 30 seconds of \
+						 dropping by sending Resume instead of Hello.\n\n\
+						 Clients can report the Minecraft server the player is on with \
+						 SetServer, and subscribe to every PolyPlus user on a server \
+						 with SubscribeServer. Both need protocol version 2. Sessions \
+						 that negotiate the `friends` capability are sent the friends \
+						 list after Welcome, and told about friends and friend \
+						 requests.\n\n\
+						 Clients on protocol version 3 can invite other players to \
+						 paired emotes with InviteEmote. Sessions need the \
+						 `paired_emotes` capability to be sent invites and paired \
+						 emotes. Sessions that negotiate the `announcements` capability \
+						 are sent announcements from the PolyPlus team, including the \
+						 active ones they missed after Welcome, and sessions that \
+						 negotiate `catalog_changes` are told which catalog entries \
+						 changed, so that they can re-fetch them from \
+						 /cosmetics/changes.\n\n\
+						 Clients on protocol version 4 can switch to one of the \
+						 player's outfits, saved through /outfits, with ApplyOutfit.\n\n\
+						 The server pings connections regularly, and treats connections \
+						 that stay silent for too long as dropped. Clients that can't \
+						 keep up with the packets sent to them are disconnected with \
+						 close code 4007.\n\n\
+						 Packets may carry a `request_id`, which is echoed on the reply \
+						 or error they cause. Packets that have no other reply are \
+						 answered with an Ack packet when they carry a `request_id`.\n\n\
+						 Packets are sent as JSON text frames by default. Clients can \
+						 request the `polyplus.msgpack` subprotocol to use MessagePack \
+						 binary frames instead, where UUIDs are encoded as 16 bytes, \
+						 or `polyplus.json` to explicitly use JSON.",
 					)
 					.tag("misc")
 					.response_with::<{ StatusCode::SWITCHING_PROTOCOLS.as_u16() }, (), _>(
@@ -78,10 +133,18 @@ pub(super) fn router() -> ApiRouter<ApiState> {
 
 async fn send_packet(
 	socket: &mut WebSocket,
-	packet: ClientBoundPacket,
+	codec: Codec,
+	message: impl Into<ClientBoundMessage>,
 ) -> Result<(), WebsocketError> {
-	let serialized = serde_json::to_string(&packet)?;
-	socket.send(Message::Text(serialized.into())).await?;
+	send_message(socket, codec, &message.into()).await
+}
+
+async fn send_message(
+	socket: &mut WebSocket,
+	codec: Codec,
+	message: &ClientBoundMessage,
+) -> Result<(), WebsocketError> {
+	socket.send(codec.encode(message)?).await?;
 	Ok(())
 }
 
@@ -107,7 +170,7 @@ async fn active_cosmetics(
 		}))
 }
 
-async fn load_equipped(
+pub(super) async fn load_equipped(
 	state: &ApiState,
 	player_id: i32,
 ) -> Result<HashMap<BodySlot, i32>, WebsocketError> {
@@ -174,7 +237,7 @@ async fn load_particle_colors_for_players(
 		.collect())
 }
 
-async fn validate_cosmetic(
+pub(super) async fn validate_cosmetic(
 	state: &ApiState,
 	player_id: i32,
 	slot: &BodySlot,
@@ -208,7 +271,7 @@ async fn validate_cosmetic(
 	Ok(())
 }
 
-async fn validate_emote(
+pub(super) async fn validate_emote(
 	state: &ApiState,
 	player_id: i32,
 	emote_id: i32,
@@ -235,21 +298,20 @@ async fn validate_emote(
 async fn register_connection(
 	state: &ApiState,
 	player_id: i32,
-	owner: Uuid,
-	tx: mpsc::UnboundedSender<ClientBoundPacket>,
+	connection: RealtimeConnection,
 	equipped: HashMap<BodySlot, i32>,
 	particle_color: Option<i32>,
+	friends: HashSet<Uuid>,
 ) -> ConnectionId {
 	let connection_id = Uuid::new_v4();
+	let owner = connection.owner;
 
-	state.realtime.connections.write().await.insert(
-		connection_id,
-		RealtimeConnection {
-			owner,
-			tx,
-			subscriptions: HashSet::new(),
-		},
-	);
+	state
+		.realtime
+		.connections
+		.write()
+		.await
+		.insert(connection_id, connection);
 	let is_first_connection = {
 		let mut connections_by_owner = state.realtime.connections_by_owner.write().await;
 		let owner_connections = connections_by_owner.entry(owner).or_default();
@@ -280,16 +342,31 @@ async fn register_connection(
 				equipped,
 				active_emote: None,
 				particle_color,
+				server: None,
 			});
 	}
+	state
+		.realtime
+		.local_friends
+		.write()
+		.await
+		.add_player(owner, friends);
 
 	// Notify anyone already watching this player that they are now online.
 	if is_first_connection {
-		broadcast_to_watchers(state, owner, || ClientBoundPacket::PlayerPresence {
-			player: owner,
-			online: true,
-		})
-		.await;
+		if let Err(error) =
+			set_presence(&state.database, state.realtime.instance_id, owner, true).await
+		{
+			warn!("Unable to store player presence: {error}");
+		}
+		state
+			.realtime
+			.publish(RealtimeEvent::Presence {
+				player: owner,
+				instance: state.realtime.instance_id,
+				online: true,
+			})
+			.await;
 	}
 
 	connection_id
@@ -305,7 +382,16 @@ async fn unregister_connection(state: &ApiState, connection_id: ConnectionId) {
 	else {
 		return;
 	};
+	release_connection(state, connection_id, connection).await;
+}
 
+/// Cleans up after a connection that was removed from
+/// [RealtimeState::connections](crate::api::state::RealtimeState::connections)
+pub(super) async fn release_connection(
+	state: &ApiState,
+	connection_id: ConnectionId,
+	connection: RealtimeConnection,
+) {
 	let owner_still_connected = {
 		let mut connections_by_owner = state.realtime.connections_by_owner.write().await;
 		if let Some(owner_connections) = connections_by_owner.get_mut(&connection.owner) {
@@ -321,16 +407,6 @@ async fn unregister_connection(state: &ApiState, connection_id: ConnectionId) {
 	};
 
 	if !owner_still_connected {
-		if let Some(runtime) = state
-			.realtime
-			.player_runtime
-			.write()
-			.await
-			.get_mut(&connection.owner)
-		{
-			runtime.active_emote = None;
-		}
-
 		let session = state
 			.realtime
 			.playtime
@@ -352,25 +428,117 @@ async fn unregister_connection(state: &ApiState, connection_id: ConnectionId) {
 			}
 		}
 
-		// Notify anyone watching this player that they are now offline.
-		broadcast_to_watchers(state, connection.owner, || {
-			ClientBoundPacket::PlayerPresence {
+		state
+			.realtime
+			.local_friends
+			.write()
+			.await
+			.remove_player(connection.owner);
+
+		if let Err(error) = set_presence(
+			&state.database,
+			state.realtime.instance_id,
+			connection.owner,
+			false,
+		)
+		.await
+		{
+			warn!("Unable to store player presence: {error}");
+		}
+
+		// Notify anyone watching this player that they are now offline, unless
+		// they are still connected to another instance. This also ends their
+		// emote.
+		state
+			.realtime
+			.publish(RealtimeEvent::Presence {
 				player: connection.owner,
+				instance: state.realtime.instance_id,
 				online: false,
-			}
-		})
-		.await;
+			})
+			.await;
 	}
 
-	let mut watchers = state.realtime.watchers.write().await;
-	for player in connection.subscriptions {
-		if let Some(player_watchers) = watchers.get_mut(&player) {
-			player_watchers.remove(&connection_id);
-			if player_watchers.is_empty() {
-				watchers.remove(&player);
-			}
-		}
+	if let Some(server) = &connection.watched_server {
+		state
+			.realtime
+			.servers
+			.write()
+			.await
+			.unwatch(server, connection_id);
 	}
+	state
+		.realtime
+		.remove_watchers(connection_id, connection.subscriptions)
+		.await;
+}
+
+/// Registers a new connection for a player that said Hello, returning its ID
+/// along with the packets to send after [ClientBoundPacket::Welcome]: the
+/// player's [ClientBoundPacket::FriendsSnapshot] and the active announcements
+/// they haven't been sent yet
+async fn start_connection(
+	state: &ApiState,
+	player: &entities::user::Model,
+	connection: RealtimeConnection,
+) -> Result<(ConnectionId, Vec<ClientBoundPacket>), WebsocketError> {
+	let wants_announcements =
+		connection.capabilities.contains(&Capability::Announcements);
+	let equipped = load_equipped(state, player.id).await?;
+	let list = load_friends(&state.database, player.id).await?;
+	let friends = list
+		.friends
+		.into_iter()
+		.map(|(friend, _)| friend)
+		.collect::<Vec<_>>();
+
+	let connection_id = register_connection(
+		state,
+		player.id,
+		connection,
+		equipped,
+		player.particle_color,
+		friends.iter().copied().collect(),
+	)
+	.await;
+
+	let online_friends = {
+		let online = state.realtime.online.read().await;
+		friends
+			.iter()
+			.copied()
+			.filter(|friend| online.contains_key(friend))
+			.collect()
+	};
+	let snapshot = ClientBoundPacket::FriendsSnapshot {
+		friends,
+		online_friends,
+		incoming_requests: list.incoming.into_iter().map(|(player, _)| player).collect(),
+		outgoing_requests: list.outgoing.into_iter().map(|(player, _)| player).collect(),
+	};
+
+	// Announcements are only marked as delivered to connections that receive them
+	let announcements = if wants_announcements {
+		let announcements = announcements_for(&state.database, player).await?;
+		for announcement in &announcements {
+			record_deliveries(&state.database, announcement.id, [player.minecraft_uuid])
+				.await?;
+		}
+		announcements
+	} else {
+		Vec::new()
+	};
+
+	Ok((
+		connection_id,
+		std::iter::once(snapshot)
+			.chain(
+				announcements
+					.into_iter()
+					.map(|announcement| ClientBoundPacket::Announcement { announcement }),
+			)
+			.collect(),
+	))
 }
 
 async fn subscribe(
@@ -406,6 +574,16 @@ async fn subscribe(
 			.collect::<Vec<_>>()
 	};
 
+	subscription_snapshot(state, connection_id, newly_subscribed).await
+}
+
+/// Starts sending a connection updates about players it was just subscribed
+/// to, and returns their current state
+pub(super) async fn subscription_snapshot(
+	state: &ApiState,
+	connection_id: ConnectionId,
+	newly_subscribed: Vec<Uuid>,
+) -> Result<ClientBoundPacket, WebsocketError> {
 	if newly_subscribed.is_empty() {
 		return Ok(ClientBoundPacket::SubscriptionSnapshot {
 			equipped: HashMap::new(),
@@ -455,6 +633,7 @@ async fn subscribe(
 					equipped: equipped.clone(),
 					active_emote: None,
 					particle_color: loaded_particle_colors.get(player).copied().flatten(),
+					server: None,
 				});
 		}
 	}
@@ -465,13 +644,14 @@ async fn subscribe(
 		}
 	}
 
-	// A player is a live PolyPlus user if they currently hold a connection.
+	// A player is a live PolyPlus user if they currently hold a connection to
+	// any instance.
 	let users = {
-		let connections_by_owner = state.realtime.connections_by_owner.read().await;
+		let online = state.realtime.online.read().await;
 		newly_subscribed
 			.iter()
 			.copied()
-			.filter(|player| connections_by_owner.contains_key(player))
+			.filter(|player| online.contains_key(player))
 			.collect::<Vec<_>>()
 	};
 
@@ -493,48 +673,23 @@ async fn unsubscribe(state: &ApiState, connection_id: ConnectionId, players: Vec
 
 		requested
 			.into_iter()
-			.filter(|player| connection.subscriptions.remove(player))
+			.filter(|player| {
+				connection.server_players.remove(player);
+				connection.subscriptions.remove(player)
+			})
 			.collect::<Vec<_>>()
 	};
 
-	let mut watchers = state.realtime.watchers.write().await;
-	for player in removed {
-		if let Some(player_watchers) = watchers.get_mut(&player) {
-			player_watchers.remove(&connection_id);
-			if player_watchers.is_empty() {
-				watchers.remove(&player);
-			}
-		}
-	}
-}
-
-async fn broadcast_to_watchers(
-	state: &ApiState,
-	player: Uuid,
-	mut make_packet: impl FnMut() -> ClientBoundPacket,
-) {
-	let connection_ids = state
-		.realtime
-		.watchers
-		.read()
-		.await
-		.get(&player)
-		.map(|watchers| watchers.iter().copied().collect::<Vec<_>>())
-		.unwrap_or_default();
-
-	let mut connections = state.realtime.connections.write().await;
-	for connection_id in connection_ids {
-		if let Some(connection) = connections.get_mut(&connection_id) {
-			let _ = connection.tx.send(make_packet());
-		}
-	}
+	state.realtime.remove_watchers(connection_id, removed).await;
 }
 
 async fn handle_msg(
 	socket: &mut WebSocket,
+	session: &Session,
 	state: &ApiState,
 	player: &entities::user::Model,
 	connection_id: ConnectionId,
+	packet_limit: &mut TokenBucket,
 	msg: Result<Message, axum::Error>,
 ) -> Result<(), WebsocketError> {
 	let msg = msg?;
@@ -542,124 +697,167 @@ async fn handle_msg(
 	// Ignore control/keepalive frames. Ping/Pong carry an opaque payload (Ktor
 	// sends a Ping every pingInterval) that is not a serializable request, and
 	// Close needs no response.
-	if matches!(
-		msg,
-		Message::Close(_) | Message::Ping(_) | Message::Pong(_)
-	) {
+	if matches!(msg, Message::Close(_) | Message::Ping(_) | Message::Pong(_)) {
 		return Ok(());
 	}
 
-	let parsed = serde_json::from_slice::<ServerBoundPacket>(&msg.into_data())
-		.map_err(WebsocketError::Deserialization)?;
+	// Take a token before parsing, but only report running out after, so the
+	// error can carry the request ID
+	let limited =
+		packet_limit.try_acquire(state.rate_limits.websocket_packet, Instant::now());
+	let message = session.codec.decode::<ServerBoundMessage>(msg)?;
+	let request_id = message.request_id;
+
+	let reply = match limited {
+		Ok(()) => {
+			handle_packet(state, session, player, connection_id, message.packet).await
+		}
+		Err(retry_after) => {
+			state
+				.metrics
+				.record_rate_limited(RateLimitGroup::WebsocketPacket);
+			Err(WebsocketError::RateLimited {
+				retry_after_ms: u64::try_from(retry_after.as_millis())
+					.unwrap_or(u64::MAX),
+			})
+		}
+	};
+	let packet = match reply {
+		Ok(Some(packet)) => packet,
+		Ok(None) if request_id.is_some() => ClientBoundPacket::Ack,
+		Ok(None) => return Ok(()),
+		Err(error) => ClientBoundPacket::Error { error },
+	};
+
+	let reply = ClientBoundMessage {
+		request_id,
+		seq: None,
+		packet,
+	};
+	send_packet(socket, session.codec, reply).await
+}
+
+/// Handles a packet, returning the reply if it has one
+async fn handle_packet(
+	state: &ApiState,
+	session: &Session,
+	player: &entities::user::Model,
+	connection_id: ConnectionId,
+	packet: ServerBoundPacket,
+) -> Result<Option<ClientBoundPacket>, WebsocketError> {
+	session.check_supported(&packet)?;
 
-	match parsed {
+	match packet {
+		ServerBoundPacket::Hello { .. } | ServerBoundPacket::Resume { .. } => {
+			Err(WebsocketError::DuplicateHandshake)
+		}
 		ServerBoundPacket::GetActiveCosmetics { players } => {
 			enforce_max_players_per_request(&players)?;
-			send_packet(
-				socket,
-				ClientBoundPacket::CosmeticsInfo {
-					cosmetics: active_cosmetics(state, players).await?,
-				},
-			)
-			.await?;
+			Ok(Some(ClientBoundPacket::CosmeticsInfo {
+				cosmetics: active_cosmetics(state, players).await?,
+			}))
 		}
 		ServerBoundPacket::SubscribePlayers { players } => {
 			enforce_max_players_per_request(&players)?;
-			let snapshot = subscribe(state, connection_id, players).await?;
-			send_packet(socket, snapshot).await?;
+			Ok(Some(subscribe(state, connection_id, players).await?))
 		}
 		ServerBoundPacket::UnsubscribePlayers { players } => {
 			unsubscribe(state, connection_id, players).await;
+			Ok(None)
 		}
 		ServerBoundPacket::SetEquippedCosmetic { slot, cosmetic_id } => {
 			if let Some(cosmetic_id) = cosmetic_id {
 				validate_cosmetic(state, player.id, &slot, cosmetic_id).await?;
 			}
 
-			{
-				let mut player_runtime = state.realtime.player_runtime.write().await;
-				let equipment = &mut player_runtime
-					.entry(player.minecraft_uuid)
-					.or_default()
-					.equipped;
-				if let Some(cosmetic_id) = cosmetic_id {
-					equipment.insert(slot.clone(), cosmetic_id);
-				} else {
-					equipment.remove(&slot);
-				}
-			}
 			let _ = state.equipment_persist_tx.try_send(EquipmentPersistence {
 				player: player.minecraft_uuid,
 				slot: slot.clone(),
 				cosmetic_id,
 			});
-			broadcast_to_watchers(state, player.minecraft_uuid, || {
-				ClientBoundPacket::PlayerCosmeticEquipped {
+			state
+				.realtime
+				.publish(RealtimeEvent::CosmeticEquipped {
 					player: player.minecraft_uuid,
-					slot: slot.clone(),
+					slot,
 					cosmetic_id,
-				}
-			})
-			.await;
+				})
+				.await;
+			Ok(None)
 		}
 		ServerBoundPacket::SetParticleColor { color } => {
-			{
-				let mut player_runtime = state.realtime.player_runtime.write().await;
-				player_runtime
-					.entry(player.minecraft_uuid)
-					.or_default()
-					.particle_color = color;
-			}
 			let _ = state
 				.particle_color_persist_tx
 				.try_send(ParticleColorPersistence {
 					player: player.minecraft_uuid,
 					color,
 				});
-			broadcast_to_watchers(state, player.minecraft_uuid, || {
-				ClientBoundPacket::PlayerParticleColorChanged {
+			state
+				.realtime
+				.publish(RealtimeEvent::ParticleColorChanged {
 					player: player.minecraft_uuid,
 					color,
-				}
-			})
-			.await;
+				})
+				.await;
+			Ok(None)
 		}
 		ServerBoundPacket::PlayEmote { emote_id } => {
 			validate_emote(state, player.id, emote_id).await?;
 
-			{
-				let mut player_runtime = state.realtime.player_runtime.write().await;
-				player_runtime
-					.entry(player.minecraft_uuid)
-					.or_default()
-					.active_emote = Some(emote_id);
-			}
-			broadcast_to_watchers(state, player.minecraft_uuid, || {
-				ClientBoundPacket::PlayerEmoteStarted {
+			state
+				.realtime
+				.publish(RealtimeEvent::EmoteStarted {
 					player: player.minecraft_uuid,
 					emote_id,
-				}
-			})
-			.await;
+				})
+				.await;
+			Ok(None)
 		}
 		ServerBoundPacket::StopEmote => {
-			{
-				let mut player_runtime = state.realtime.player_runtime.write().await;
-				player_runtime
-					.entry(player.minecraft_uuid)
-					.or_default()
-					.active_emote = None;
-			}
-			broadcast_to_watchers(state, player.minecraft_uuid, || {
-				ClientBoundPacket::PlayerEmoteStopped {
+			state
+				.realtime
+				.publish(RealtimeEvent::EmoteStopped {
 					player: player.minecraft_uuid,
-				}
-			})
-			.await;
+				})
+				.await;
+			Ok(None)
+		}
+		ServerBoundPacket::ConfirmDeviceCode { user_code } => {
+			confirm_device_code(&state.database, player, &user_code)
+				.await
+				.map_err(|e| match e {
+					ConfirmDeviceError::InvalidCode => WebsocketError::InvalidDeviceCode,
+					ConfirmDeviceError::Database(e) => WebsocketError::DatabaseQuery(e),
+				})?;
+			Ok(Some(ClientBoundPacket::DeviceCodeConfirmed { user_code }))
+		}
+		ServerBoundPacket::SetServer { server } => {
+			set_server(state, player.minecraft_uuid, server).await?;
+			Ok(None)
+		}
+		ServerBoundPacket::SubscribeServer { server } => {
+			Ok(Some(subscribe_server(state, connection_id, server).await?))
+		}
+		ServerBoundPacket::UnsubscribeServer => {
+			unsubscribe_server(state, connection_id).await;
+			Ok(None)
+		}
+		ServerBoundPacket::InviteEmote { target, emote_id } => {
+			Ok(Some(invite_emote(state, player, target, emote_id).await?))
+		}
+		ServerBoundPacket::AcceptEmoteInvite { invite_id } => {
+			accept_emote_invite(state, player.minecraft_uuid, invite_id).await?;
+			Ok(None)
+		}
+		ServerBoundPacket::DeclineEmoteInvite { invite_id } => {
+			decline_emote_invite(state, player.minecraft_uuid, invite_id).await?;
+			Ok(None)
+		}
+		ServerBoundPacket::ApplyOutfit { outfit_id } => {
+			apply_outfit(state, player, outfit_id).await?;
+			Ok(None)
 		}
 	}
-
-	Ok(())
 }
 
 #[tracing::instrument(level = "debug", skip(state))]
@@ -668,54 +866,189 @@ async fn endpoint(
 	AuthenticatedPlayer(player): AuthenticatedPlayer,
 	ws: WebSocketUpgrade,
 ) -> Response<Body> {
+	let ws = ws.protocols(Codec::PROTOCOLS);
+	let codec = ws
+		.selected_protocol()
+		.and_then(|protocol| protocol.to_str().ok())
+		.and_then(Codec::from_protocol)
+		.unwrap_or_default();
+
 	ws.on_upgrade(async move |mut socket| {
-		let (tx, mut rx) = mpsc::unbounded_channel();
-		let equipped = match load_equipped(&state, player.id).await {
-			Ok(equipped) => equipped,
-			Err(error) => {
-				let _ =
-					send_packet(&mut socket, ClientBoundPacket::Error { error }).await;
+		let (session, connection_id, request_id, queue, mut close_rx, greeting) = loop {
+			let Some((greeting, request_id)) = handshake(&mut socket, codec).await else {
 				return;
-			}
-		};
-		let connection_id = register_connection(
-			&state,
-			player.id,
-			player.minecraft_uuid,
-			tx,
-			equipped,
-			player.particle_color,
-		)
-		.await;
-
-		loop {
-			let result = tokio::select! {
-				msg = socket.recv() => {
-					let Some(msg) = msg else {
-						break;
+			};
+			let queue = Arc::new(OutboundQueue::new(
+				state.websocket.queue_size,
+				state.websocket.slow_consumer_policy,
+				state.metrics.clone(),
+			));
+			let (close_tx, close_rx) = mpsc::channel(1);
+
+			let accepted = match greeting {
+				Greeting::Hello(session) => {
+					let connection = RealtimeConnection {
+						owner: player.minecraft_uuid,
+						queue: queue.clone(),
+						capabilities: session.capabilities.clone(),
+						subscriptions: HashSet::new(),
+						close_tx,
+						resumable: session
+							.has(Capability::Resume)
+							.then(|| Resumable::new(&session)),
+						watched_server: None,
+						server_players: HashSet::new(),
 					};
-					handle_msg(&mut socket, &state, &player, connection_id, msg).await
+					start_connection(&state, &player, connection).await.map(
+						|(connection_id, greeting)| (session, connection_id, greeting),
+					)
 				}
-				packet = rx.recv() => {
-					let Some(packet) = packet else {
-						break;
+				Greeting::Resume {
+					session_id,
+					last_seq,
+				} => resume_connection(
+					&state,
+					player.minecraft_uuid,
+					codec,
+					session_id,
+					last_seq,
+					queue.clone(),
+					close_tx,
+				)
+				.await
+				.map(|session| (session, session_id, Vec::new()))
+				.ok_or(WebsocketError::ResumeFailed),
+			};
+
+			match accepted {
+				Ok((session, connection_id, greeting)) => {
+					break (
+						session,
+						connection_id,
+						request_id,
+						queue,
+						close_rx,
+						greeting,
+					);
+				}
+				Err(error) => {
+					let error = ClientBoundMessage {
+						request_id,
+						seq: None,
+						packet: ClientBoundPacket::Error { error },
 					};
-					send_packet(&mut socket, packet).await
+					if send_packet(&mut socket, codec, error).await.is_err() {
+						return;
+					}
 				}
-			};
+			}
+		};
 
-			match result {
-				Ok(_) => continue,
-				Err(WebsocketError::Fatal(_)) => break,
-				Err(e) => {
-					let e = ClientBoundPacket::Error { error: e };
-					if send_packet(&mut socket, e).await.is_err() {
+		let welcome = ClientBoundMessage {
+			request_id,
+			seq: None,
+			packet: ClientBoundPacket::Welcome {
+				server_version: env!("CARGO_PKG_VERSION").to_owned(),
+				session_id: connection_id,
+				protocol_version: session.protocol_version,
+				capabilities: session.capabilities.clone(),
+				limits: ConnectionLimits {
+					max_players_per_request: MAX_PLAYERS_PER_REQUEST,
+					max_player_subscriptions: MAX_PLAYER_SUBSCRIPTIONS,
+				},
+			},
+		};
+		let mut packet_limit =
+			TokenBucket::new(state.rate_limits.websocket_packet, Instant::now());
+		// Connections closed on purpose, by either side, aren't kept around
+		let mut resumable = session.has(Capability::Resume);
+		let mut last_received = Instant::now();
+		let mut ping = tokio::time::interval_at(
+			(Instant::now() + state.websocket.ping_interval).into(),
+			state.websocket.ping_interval,
+		);
+		ping.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+		// Resumed sessions are replayed what they missed instead
+		let mut greeted = send_packet(&mut socket, codec, welcome).await.is_ok();
+		for packet in greeting.into_iter().filter(|packet| session.wants(packet)) {
+			if !greeted {
+				break;
+			}
+			greeted = send_packet(&mut socket, codec, packet).await.is_ok();
+		}
+
+		if greeted {
+			loop {
+				let result = tokio::select! {
+					msg = socket.recv() => {
+						let Some(msg) = msg else {
+							break;
+						};
+						last_received = Instant::now();
+						if let Ok(Message::Close(_)) = msg {
+							resumable = false;
+							break;
+						}
+						handle_msg(
+							&mut socket,
+							&session,
+							&state,
+							&player,
+							connection_id,
+							&mut packet_limit,
+							msg,
+						)
+						.await
+					}
+					message = queue.recv() => {
+						let Some(message) = message else {
+							let frame = CloseFrame {
+								code: CLOSE_CODE_SLOW_CONSUMER,
+								reason: "Too slow to keep up with packets".into(),
+							};
+							let _ = socket.send(Message::Close(Some(frame))).await;
+							resumable = false;
+							break;
+						};
+						if !session.wants(&message.packet) {
+							continue;
+						}
+						send_message(&mut socket, codec, &message).await
+					}
+					Some(frame) = close_rx.recv() => {
+						let _ = socket.send(Message::Close(Some(frame))).await;
+						resumable = false;
 						break;
-					};
+					}
+					_ = ping.tick() => {
+						// The connection is treated like it dropped, so it can
+						// still be resumed
+						if last_received.elapsed() >= state.websocket.idle_timeout {
+							break;
+						}
+						let ping = Message::Ping(Default::default());
+						socket.send(ping).await.map_err(Into::into)
+					}
+				};
+
+				match result {
+					Ok(_) => continue,
+					Err(WebsocketError::Fatal(_)) => break,
+					Err(e) => {
+						let e = ClientBoundPacket::Error { error: e };
+						if send_packet(&mut socket, codec, e).await.is_err() {
+							break;
+						};
+					}
 				}
 			}
 		}
 
-		unregister_connection(&state, connection_id).await;
+		if resumable {
+			detach_connection(&state, connection_id).await;
+		} else {
+			unregister_connection(&state, connection_id).await;
+		}
 	})
 }
@@ -0,0 +1,242 @@
+//! The handshake that starts every websocket connection. The client says
+//! which protocol version and capabilities it speaks with
+//! [ServerBoundPacket::Hello], and is disconnected if it's too old. Clients can
+//! also pick up a dropped session with [ServerBoundPacket::Resume].
+
+use std::{collections::BTreeSet, time::Duration};
+
+use uuid::Uuid;
+
+use axum::extract::ws::{CloseFrame, Message, WebSocket};
+use tracing::debug;
+
+use crate::api::websocket::{
+	codec::Codec,
+	structs::{
+		CLOSE_CODE_HANDSHAKE_REQUIRED, CLOSE_CODE_UNSUPPORTED_PROTOCOL, Capability,
+		ClientBoundPacket, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION, RequestId,
+		ServerBoundMessage, ServerBoundPacket, WebsocketError,
+	},
+};
+
+/// How long a client has to send [ServerBoundPacket::Hello] after connecting,
+/// or after a failed [ServerBoundPacket::Resume]
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+/// The capabilities this server supports
+const SUPPORTED_CAPABILITIES: &[Capability] = &[
+	Capability::Presence,
+	Capability::Resume,
+	Capability::Friends,
+	Capability::PairedEmotes,
+	Capability::Announcements,
+	Capability::CatalogChanges,
+];
+
+/// What was negotiated for a connection
+#[derive(Debug)]
+pub(super) struct Session {
+	pub(super) codec: Codec,
+	pub(super) protocol_version: u32,
+	pub(super) capabilities: BTreeSet<Capability>,
+}
+
+impl Session {
+	fn negotiate(
+		codec: Codec,
+		protocol_version: u32,
+		requested: Vec<Capability>,
+	) -> Self {
+		Self {
+			codec,
+			protocol_version: protocol_version.min(PROTOCOL_VERSION),
+			capabilities: requested
+				.into_iter()
+				.filter(|capability| SUPPORTED_CAPABILITIES.contains(capability))
+				.collect(),
+		}
+	}
+
+	pub(super) fn has(&self, capability: Capability) -> bool {
+		self.capabilities.contains(&capability)
+	}
+
+	/// Whether the client should be sent a packet, based on its capabilities
+	pub(super) fn wants(&self, packet: &ClientBoundPacket) -> bool {
+		packet
+			.required_capability()
+			.is_none_or(|capability| self.capabilities.contains(&capability))
+	}
+
+	/// Rejects packets that don't exist in the negotiated protocol version
+	pub(super) fn check_supported(
+		&self,
+		packet: &ServerBoundPacket,
+	) -> Result<(), WebsocketError> {
+		if !packet.supported_versions().contains(&self.protocol_version) {
+			return Err(WebsocketError::UnsupportedPacket {
+				protocol_version: self.protocol_version,
+			});
+		}
+		Ok(())
+	}
+}
+
+/// How a client started its connection
+#[derive(Debug)]
+pub(super) enum Greeting {
+	Hello(Session),
+	Resume { session_id: Uuid, last_seq: u64 },
+}
+
+#[derive(Debug, thiserror::Error)]
+enum HandshakeError {
+	#[error("The connection closed during the handshake")]
+	Disconnected,
+	#[error("Timed out waiting for a Hello packet")]
+	TimedOut,
+	#[error("Expected a Hello or Resume packet")]
+	ExpectedHello,
+	#[error("Protocol version {0} is no longer supported, please update")]
+	UnsupportedProtocol(u32),
+}
+
+impl HandshakeError {
+	fn close_frame(&self) -> CloseFrame {
+		let code = match self {
+			Self::UnsupportedProtocol(_) => CLOSE_CODE_UNSUPPORTED_PROTOCOL,
+			_ => CLOSE_CODE_HANDSHAKE_REQUIRED,
+		};
+		CloseFrame {
+			code,
+			reason: self.to_string().into(),
+		}
+	}
+}
+
+/// Waits for the client's [ServerBoundPacket::Hello] or
+/// [ServerBoundPacket::Resume], returning it along with its request ID. If
+/// anything else arrives first, the connection is closed and [None] is
+/// returned.
+pub(super) async fn handshake(
+	socket: &mut WebSocket,
+	codec: Codec,
+) -> Option<(Greeting, Option<RequestId>)> {
+	let result = tokio::time::timeout(HANDSHAKE_TIMEOUT, receive_greeting(socket, codec))
+		.await
+		.unwrap_or(Err(HandshakeError::TimedOut));
+
+	match result {
+		Ok(handshake) => Some(handshake),
+		Err(HandshakeError::Disconnected) => None,
+		Err(e) => {
+			let _ = socket.send(Message::Close(Some(e.close_frame()))).await;
+			None
+		}
+	}
+}
+
+async fn receive_greeting(
+	socket: &mut WebSocket,
+	codec: Codec,
+) -> Result<(Greeting, Option<RequestId>), HandshakeError> {
+	loop {
+		let Some(Ok(msg)) = socket.recv().await else {
+			return Err(HandshakeError::Disconnected);
+		};
+		match msg {
+			Message::Ping(_) | Message::Pong(_) => continue,
+			Message::Close(_) => return Err(HandshakeError::Disconnected),
+			Message::Text(_) | Message::Binary(_) => {}
+		}
+
+		let Ok(ServerBoundMessage { request_id, packet }) = codec.decode(msg) else {
+			return Err(HandshakeError::ExpectedHello);
+		};
+		let greeting = match packet {
+			ServerBoundPacket::Hello {
+				protocol_version,
+				mod_version,
+				capabilities,
+			} => {
+				if protocol_version < MIN_PROTOCOL_VERSION {
+					return Err(HandshakeError::UnsupportedProtocol(protocol_version));
+				}
+
+				debug!(protocol_version, %mod_version, "Handshake completed");
+				Greeting::Hello(Session::negotiate(codec, protocol_version, capabilities))
+			}
+			ServerBoundPacket::Resume {
+				session_id,
+				last_seq,
+			} => Greeting::Resume {
+				session_id,
+				last_seq,
+			},
+			_ => return Err(HandshakeError::ExpectedHello),
+		};
+		return Ok((greeting, request_id));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use uuid::Uuid;
+
+	use super::Session;
+	use crate::api::websocket::{
+		codec::Codec,
+		structs::{Capability, ClientBoundPacket, PROTOCOL_VERSION, ServerBoundPacket},
+	};
+
+	#[test]
+	fn negotiates_supported_capabilities_and_version() {
+		let session = Session::negotiate(
+			Codec::Json,
+			PROTOCOL_VERSION + 1,
+			vec![Capability::Unknown, Capability::Presence],
+		);
+
+		assert_eq!(session.protocol_version, PROTOCOL_VERSION);
+		assert_eq!(
+			session.capabilities.into_iter().collect::<Vec<_>>(),
+			vec![Capability::Presence]
+		);
+	}
+
+	#[test]
+	fn filters_packets_by_capability() {
+		let session = Session::negotiate(Codec::Json, PROTOCOL_VERSION, Vec::new());
+		let player = Uuid::nil();
+
+		assert!(!session.wants(&ClientBoundPacket::PlayerPresence {
+			player,
+			online: true,
+		}));
+		assert!(session.wants(&ClientBoundPacket::PlayerEmoteStopped { player }));
+	}
+
+	#[test]
+	fn parses_hello_without_capabilities() {
+		let packet: ServerBoundPacket = serde_json::from_str(
+			r#"{"type":"Hello","protocol_version":1,"mod_version":"1.0.0"}"#,
+		)
+		.expect("packet should parse");
+
+		let session = Session::negotiate(Codec::Json, PROTOCOL_VERSION, Vec::new());
+		assert!(session.check_supported(&packet).is_ok());
+		assert!(matches!(
+			packet,
+			ServerBoundPacket::Hello { capabilities, .. } if capabilities.is_empty()
+		));
+	}
+
+	#[test]
+	fn rejects_packets_newer_than_the_session() {
+		let packet = ServerBoundPacket::UnsubscribeServer;
+		let old = Session::negotiate(Codec::Json, 1, Vec::new());
+		let current = Session::negotiate(Codec::Json, PROTOCOL_VERSION, Vec::new());
+
+		assert!(old.check_supported(&packet).is_err());
+		assert!(current.check_supported(&packet).is_ok());
+	}
+}
@@ -2,7 +2,14 @@ use aide::axum::ApiRouter;
 
 use crate::api::ApiState;
 
+mod codec;
+mod emotes;
 mod endpoint;
+mod handshake;
+mod outfits;
+pub(super) mod queue;
+pub(super) mod resume;
+mod servers;
 pub mod structs;
 
 pub(super) async fn setup_router() -> ApiRouter<ApiState> {
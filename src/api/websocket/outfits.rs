@@ -0,0 +1,166 @@
+//! Lets players switch to one of their saved outfits at once with
+//! [ServerBoundPacket::ApplyOutfit], instead of setting each slot with
+//! [ServerBoundPacket::SetEquippedCosmetic].
+
+use std::collections::HashMap;
+
+use entities::{
+	outfit, player_equipped_cosmetic, prelude::*, sea_orm_active_enums::BodySlot, user,
+};
+use sea_orm::{
+	ActiveValue, ColumnTrait as _, DatabaseConnection, DbErr, EntityTrait as _,
+	QueryFilter as _, Set, TransactionTrait as _,
+	sea_query::{Expr, OnConflict},
+};
+
+use crate::api::{
+	ApiState,
+	outfits::outfit_equipment,
+	realtime::RealtimeEvent,
+	websocket::{
+		endpoint::{load_equipped, validate_cosmetic},
+		structs::WebsocketError,
+	},
+};
+
+/// Equips a player's outfit, clearing the slots it leaves empty, and sets its
+/// particle color
+pub(super) async fn apply_outfit(
+	state: &ApiState,
+	player: &entities::user::Model,
+	outfit_id: i32,
+) -> Result<(), WebsocketError> {
+	let Some(outfit) = Outfit::find_by_id(outfit_id)
+		.filter(outfit::Column::PlayerId.eq(player.id))
+		.one(&state.database)
+		.await?
+	else {
+		return Err(WebsocketError::UnknownOutfit(outfit_id));
+	};
+
+	// Cosmetics may have been revoked since the outfit was saved
+	let equipped = outfit_equipment(&outfit);
+	for (slot, cosmetic_id) in &equipped {
+		validate_cosmetic(state, player.id, slot, *cosmetic_id).await?;
+	}
+
+	let runtime = state
+		.realtime
+		.player_runtime
+		.read()
+		.await
+		.get(&player.minecraft_uuid)
+		.map(|runtime| runtime.equipped.clone());
+	let current = match runtime {
+		Some(current) => current,
+		None => load_equipped(state, player.id).await?,
+	};
+	let changes = equipment_changes(&current, &equipped);
+	persist_outfit(&state.database, player.id, &changes, outfit.particle_color).await?;
+
+	state
+		.realtime
+		.publish(RealtimeEvent::OutfitApplied {
+			player: player.minecraft_uuid,
+			changes,
+			particle_color: outfit.particle_color,
+		})
+		.await;
+	Ok(())
+}
+
+/// Writes an outfit's changes in one transaction, so that the database never
+/// holds half of an outfit
+async fn persist_outfit(
+	db: &DatabaseConnection,
+	player_id: i32,
+	changes: &HashMap<BodySlot, Option<i32>>,
+	particle_color: Option<i32>,
+) -> Result<(), DbErr> {
+	let txn = db.begin().await?;
+	for (slot, cosmetic_id) in changes {
+		match cosmetic_id {
+			Some(cosmetic_id) => {
+				PlayerEquippedCosmetic::insert(player_equipped_cosmetic::ActiveModel {
+					player_id: Set(player_id),
+					slot: Set(slot.clone()),
+					cosmetic_id: Set(*cosmetic_id),
+					updated_at: ActiveValue::NotSet,
+				})
+				.on_conflict(
+					OnConflict::columns([
+						player_equipped_cosmetic::Column::PlayerId,
+						player_equipped_cosmetic::Column::Slot,
+					])
+					.update_column(player_equipped_cosmetic::Column::CosmeticId)
+					.to_owned(),
+				)
+				.exec(&txn)
+				.await?;
+			}
+			None => {
+				PlayerEquippedCosmetic::delete_many()
+					.filter(player_equipped_cosmetic::Column::PlayerId.eq(player_id))
+					.filter(player_equipped_cosmetic::Column::Slot.eq(slot.clone()))
+					.exec(&txn)
+					.await?;
+			}
+		}
+	}
+	User::update_many()
+		.col_expr(user::Column::ParticleColor, Expr::value(particle_color))
+		.filter(user::Column::Id.eq(player_id))
+		.exec(&txn)
+		.await?;
+	txn.commit().await
+}
+
+/// The slots that change when going from the current equipment to an
+/// outfit's, with what they change to
+fn equipment_changes(
+	current: &HashMap<BodySlot, i32>,
+	outfit: &HashMap<BodySlot, i32>,
+) -> HashMap<BodySlot, Option<i32>> {
+	let cleared = current
+		.keys()
+		.filter(|slot| !outfit.contains_key(*slot))
+		.map(|slot| (slot.clone(), None));
+	let equipped = outfit
+		.iter()
+		.filter(|(slot, cosmetic_id)| current.get(*slot) != Some(*cosmetic_id))
+		.map(|(slot, cosmetic_id)| (slot.clone(), Some(*cosmetic_id)));
+	cleared.chain(equipped).collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::HashMap;
+
+	use entities::sea_orm_active_enums::BodySlot;
+
+	use super::equipment_changes;
+
+	#[test]
+	fn only_changed_slots_are_sent() {
+		let current = HashMap::from([
+			(BodySlot::Cape, 1),
+			(BodySlot::Hat, 2),
+			(BodySlot::Wings, 3),
+		]);
+		let outfit = HashMap::from([
+			(BodySlot::Cape, 1),
+			(BodySlot::Hat, 4),
+			(BodySlot::Boots, 5),
+		]);
+
+		assert_eq!(
+			equipment_changes(&current, &outfit),
+			HashMap::from([
+				(BodySlot::Hat, Some(4)),
+				(BodySlot::Boots, Some(5)),
+				(BodySlot::Wings, None),
+			])
+		);
+		assert!(equipment_changes(&outfit, &outfit).is_empty());
+	}
+}
@@ -0,0 +1,259 @@
+//! The bounded queues holding packets on their way to each websocket
+//! connection. When a client can't keep up and its queue fills, packets made
+//! outdated by newer ones are dropped, or the client is disconnected,
+//! depending on the [SlowConsumerPolicy].
+
+use std::{
+	collections::{HashSet, VecDeque},
+	str::FromStr,
+	sync::{Arc, Mutex},
+};
+
+use entities::sea_orm_active_enums::BodySlot;
+use tokio::sync::Notify;
+use uuid::Uuid;
+
+use crate::api::{
+	metrics::Metrics,
+	websocket::structs::{ClientBoundMessage, ClientBoundPacket},
+};
+
+/// What to do when a connection's outbound queue is full
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SlowConsumerPolicy {
+	/// Drop queued packets that a newer packet about the same player (and
+	/// slot) makes outdated, and only disconnect if that frees up nothing
+	DropSuperseded,
+	/// Disconnect the client straight away
+	Disconnect,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("The slow consumer policy must be either drop_superseded or disconnect")]
+pub(crate) struct InvalidSlowConsumerPolicy;
+
+impl FromStr for SlowConsumerPolicy {
+	type Err = InvalidSlowConsumerPolicy;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"drop_superseded" => Ok(Self::DropSuperseded),
+			"disconnect" => Ok(Self::Disconnect),
+			_ => Err(InvalidSlowConsumerPolicy),
+		}
+	}
+}
+
+/// The piece of a player's state that a packet sets. A packet makes queued
+/// packets with the same key outdated.
+#[derive(Debug, PartialEq, Eq, Hash)]
+enum StateKey {
+	Equipped(Uuid, BodySlot),
+	ParticleColor(Uuid),
+	Emote(Uuid),
+	Presence(Uuid),
+	FriendPresence(Uuid),
+}
+
+impl StateKey {
+	fn of(packet: &ClientBoundPacket) -> Option<Self> {
+		match packet {
+			ClientBoundPacket::PlayerCosmeticEquipped { player, slot, .. } => {
+				Some(Self::Equipped(*player, slot.clone()))
+			}
+			ClientBoundPacket::PlayerParticleColorChanged { player, .. } => {
+				Some(Self::ParticleColor(*player))
+			}
+			ClientBoundPacket::PlayerEmoteStarted { player, .. }
+			| ClientBoundPacket::PlayerEmoteStopped { player } => Some(Self::Emote(*player)),
+			ClientBoundPacket::PlayerPresence { player, .. } => {
+				Some(Self::Presence(*player))
+			}
+			ClientBoundPacket::FriendPresence { player, .. } => {
+				Some(Self::FriendPresence(*player))
+			}
+			_ => None,
+		}
+	}
+}
+
+#[derive(Debug, Default)]
+struct Queued {
+	packets: VecDeque<Arc<ClientBoundMessage>>,
+	/// Set once the client was disconnected for being too slow
+	evicted: bool,
+}
+
+#[derive(Debug)]
+pub(crate) struct OutboundQueue {
+	queued: Mutex<Queued>,
+	notify: Notify,
+	capacity: usize,
+	policy: SlowConsumerPolicy,
+	metrics: Arc<Metrics>,
+}
+
+impl OutboundQueue {
+	pub(super) fn new(
+		capacity: usize,
+		policy: SlowConsumerPolicy,
+		metrics: Arc<Metrics>,
+	) -> Self {
+		Self {
+			queued: Mutex::default(),
+			notify: Notify::new(),
+			capacity,
+			policy,
+			metrics,
+		}
+	}
+
+	fn lock(&self) -> std::sync::MutexGuard<'_, Queued> {
+		// Nothing in here can leave the queue in a broken state
+		self.queued
+			.lock()
+			.unwrap_or_else(std::sync::PoisonError::into_inner)
+	}
+
+	/// Queues a packet without waiting, applying the [SlowConsumerPolicy] if
+	/// the queue is full
+	pub(crate) fn push(&self, message: Arc<ClientBoundMessage>) {
+		let mut queued = self.lock();
+		if queued.evicted {
+			return;
+		}
+
+		if queued.packets.len() >= self.capacity {
+			let dropped = match self.policy {
+				SlowConsumerPolicy::DropSuperseded => {
+					drop_superseded(&mut queued.packets, &message)
+				}
+				SlowConsumerPolicy::Disconnect => 0,
+			};
+			if dropped == 0 {
+				self.metrics.record_dequeued(queued.packets.len());
+				self.metrics.record_eviction();
+				queued.packets.clear();
+				queued.evicted = true;
+				self.notify.notify_one();
+				return;
+			}
+			self.metrics.record_dequeued(dropped);
+			self.metrics.record_superseded(dropped);
+		}
+
+		queued.packets.push_back(message);
+		self.metrics.record_queued();
+		self.notify.notify_one();
+	}
+
+	/// Waits for the next packet, or returns [None] once the client was
+	/// evicted for being too slow
+	pub(super) async fn recv(&self) -> Option<Arc<ClientBoundMessage>> {
+		loop {
+			{
+				let mut queued = self.lock();
+				if queued.evicted {
+					return None;
+				}
+				if let Some(message) = queued.packets.pop_front() {
+					self.metrics.record_dequeued(1);
+					return Some(message);
+				}
+			}
+			self.notify.notified().await;
+		}
+	}
+}
+
+impl Drop for OutboundQueue {
+	fn drop(&mut self) {
+		self.metrics.record_dequeued(self.lock().packets.len());
+	}
+}
+
+/// Drops every packet that `next` or a later queued packet makes outdated,
+/// returning how many were dropped
+fn drop_superseded(
+	packets: &mut VecDeque<Arc<ClientBoundMessage>>,
+	next: &ClientBoundMessage,
+) -> usize {
+	let mut seen = HashSet::new();
+	seen.extend(StateKey::of(&next.packet));
+
+	let before = packets.len();
+	let mut kept = VecDeque::with_capacity(before);
+	for message in packets.drain(..).rev() {
+		let superseded =
+			StateKey::of(&message.packet).is_some_and(|key| !seen.insert(key));
+		if !superseded {
+			kept.push_front(message);
+		}
+	}
+	*packets = kept;
+
+	before - packets.len()
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+
+	use entities::sea_orm_active_enums::BodySlot;
+	use uuid::Uuid;
+
+	use super::{OutboundQueue, SlowConsumerPolicy};
+	use crate::api::{metrics::Metrics, websocket::structs::ClientBoundPacket};
+
+	fn equip(player: Uuid, slot: BodySlot, cosmetic_id: i32) -> ClientBoundPacket {
+		ClientBoundPacket::PlayerCosmeticEquipped {
+			player,
+			slot,
+			cosmetic_id: Some(cosmetic_id),
+		}
+	}
+
+	fn queued(queue: &OutboundQueue) -> Vec<serde_json::Value> {
+		queue
+			.lock()
+			.packets
+			.iter()
+			.map(|message| {
+				serde_json::to_value(&**message).expect("packet should serialize")
+			})
+			.collect()
+	}
+
+	#[test]
+	fn drops_superseded_packets_when_full() {
+		let metrics = Arc::new(Metrics::default());
+		let queue = OutboundQueue::new(2, SlowConsumerPolicy::DropSuperseded, metrics);
+		let player = Uuid::nil();
+
+		queue.push(Arc::new(equip(player, BodySlot::Cape, 1).into()));
+		queue.push(Arc::new(equip(player, BodySlot::Hat, 2).into()));
+		queue.push(Arc::new(equip(player, BodySlot::Cape, 3).into()));
+
+		let queued = queued(&queue);
+		assert_eq!(queued.len(), 2);
+		assert_eq!(queued[0]["slot"], "hat");
+		assert_eq!(queued[1]["cosmetic_id"], 3);
+	}
+
+	#[test]
+	fn evicts_when_nothing_can_be_dropped() {
+		for policy in [
+			SlowConsumerPolicy::DropSuperseded,
+			SlowConsumerPolicy::Disconnect,
+		] {
+			let metrics = Arc::new(Metrics::default());
+			let queue = OutboundQueue::new(1, policy, metrics);
+
+			queue.push(Arc::new(ClientBoundPacket::Ack.into()));
+			queue.push(Arc::new(ClientBoundPacket::Ack.into()));
+
+			assert!(queue.lock().evicted);
+			assert!(queued(&queue).is_empty());
+		}
+	}
+}
@@ -0,0 +1,222 @@
+//! Lets connections that negotiated [Capability::Resume] pick up where they
+//! left off after dropping. Packets sent to them are numbered and kept in a
+//! bounded buffer, and when they drop they are kept registered for
+//! [RESUME_GRACE], so that watchers don't see their presence flap.
+
+use std::{
+	collections::{BTreeSet, VecDeque},
+	sync::Arc,
+	time::Duration,
+};
+
+use axum::extract::ws::CloseFrame;
+use tokio::{sync::mpsc, time::Instant};
+use uuid::Uuid;
+
+use crate::api::{
+	ApiState,
+	state::ConnectionId,
+	websocket::{
+		codec::Codec,
+		endpoint::release_connection,
+		handshake::Session,
+		queue::OutboundQueue,
+		structs::{Capability, ClientBoundMessage, ClientBoundPacket},
+	},
+};
+
+/// How long a dropped connection can be resumed for
+const RESUME_GRACE: Duration = Duration::from_secs(30);
+/// How many packets are kept for replay per connection. Sessions that miss
+/// more than this can't be resumed.
+const REPLAY_BUFFER_SIZE: usize = 256;
+
+/// What is kept of a resumable connection
+#[derive(Debug)]
+pub(crate) struct Resumable {
+	protocol_version: u32,
+	capabilities: BTreeSet<Capability>,
+	/// When the connection dropped, if it is waiting to be resumed
+	detached_at: Option<Instant>,
+	next_seq: u64,
+	replay: VecDeque<Arc<ClientBoundMessage>>,
+}
+
+impl Resumable {
+	pub(super) fn new(session: &Session) -> Self {
+		Self {
+			protocol_version: session.protocol_version,
+			capabilities: session.capabilities.clone(),
+			detached_at: None,
+			next_seq: 1,
+			replay: VecDeque::with_capacity(REPLAY_BUFFER_SIZE),
+		}
+	}
+
+	pub(crate) fn is_detached(&self) -> bool {
+		self.detached_at.is_some()
+	}
+
+	/// Numbers a packet, and keeps it around in case it has to be replayed
+	pub(crate) fn sequence(
+		&mut self,
+		packet: ClientBoundPacket,
+	) -> Arc<ClientBoundMessage> {
+		let message = Arc::new(ClientBoundMessage {
+			request_id: None,
+			seq: Some(self.next_seq),
+			packet,
+		});
+		self.next_seq += 1;
+
+		if self.replay.len() == REPLAY_BUFFER_SIZE {
+			self.replay.pop_front();
+		}
+		self.replay.push_back(message.clone());
+		message
+	}
+
+	/// The packets sent after `last_seq`, or [None] if some of them were
+	/// already dropped from the buffer
+	fn replay_since(&self, last_seq: u64) -> Option<Vec<Arc<ClientBoundMessage>>> {
+		let first_kept = self.next_seq - self.replay.len() as u64;
+		if last_seq >= self.next_seq || last_seq + 1 < first_kept {
+			return None;
+		}
+
+		Some(
+			self.replay
+				.iter()
+				.filter(|message| message.seq.is_some_and(|seq| seq > last_seq))
+				.cloned()
+				.collect(),
+		)
+	}
+}
+
+/// Keeps a dropped connection registered for [RESUME_GRACE], after which it
+/// is unregistered unless it was resumed
+pub(super) async fn detach_connection(state: &ApiState, connection_id: ConnectionId) {
+	let detached_at = Instant::now();
+	{
+		let mut connections = state.realtime.connections.write().await;
+		let Some(resumable) = connections
+			.get_mut(&connection_id)
+			.and_then(|connection| connection.resumable.as_mut())
+		else {
+			return;
+		};
+		resumable.detached_at = Some(detached_at);
+	}
+
+	let state = state.clone();
+	tokio::spawn(async move {
+		tokio::time::sleep(RESUME_GRACE).await;
+
+		let connection = {
+			let mut connections = state.realtime.connections.write().await;
+			let expired = connections
+				.get(&connection_id)
+				.and_then(|connection| connection.resumable.as_ref())
+				.is_some_and(|resumable| resumable.detached_at == Some(detached_at));
+			if !expired {
+				return;
+			}
+			connections.remove(&connection_id)
+		};
+		if let Some(connection) = connection {
+			release_connection(&state, connection_id, connection).await;
+		}
+	});
+}
+
+/// Attaches a new socket to a dropped connection of the same player, queueing
+/// every packet after `last_seq` on `queue`. Returns the connection's session, or
+/// [None] if it can't be resumed.
+pub(super) async fn resume_connection(
+	state: &ApiState,
+	owner: Uuid,
+	codec: Codec,
+	connection_id: ConnectionId,
+	last_seq: u64,
+	queue: Arc<OutboundQueue>,
+	close_tx: mpsc::Sender<CloseFrame>,
+) -> Option<Session> {
+	let mut connections = state.realtime.connections.write().await;
+	let connection = connections
+		.get_mut(&connection_id)
+		.filter(|connection| connection.owner == owner)?;
+	let resumable = connection
+		.resumable
+		.as_mut()
+		.filter(|resumable| resumable.is_detached())?;
+
+	for message in resumable.replay_since(last_seq)? {
+		queue.push(message);
+	}
+	resumable.detached_at = None;
+	let session = Session {
+		codec,
+		protocol_version: resumable.protocol_version,
+		capabilities: resumable.capabilities.clone(),
+	};
+	connection.queue = queue;
+	connection.close_tx = close_tx;
+
+	Some(session)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::BTreeSet;
+
+	use super::{REPLAY_BUFFER_SIZE, Resumable};
+	use crate::api::websocket::{
+		codec::Codec, handshake::Session, structs::ClientBoundPacket,
+	};
+
+	fn resumable() -> Resumable {
+		Resumable::new(&Session {
+			codec: Codec::Json,
+			protocol_version: 1,
+			capabilities: BTreeSet::new(),
+		})
+	}
+
+	fn replayed_seqs(resumable: &Resumable, last_seq: u64) -> Option<Vec<u64>> {
+		resumable.replay_since(last_seq).map(|messages| {
+			messages
+				.iter()
+				.map(|message| message.seq.expect("replayed packets are numbered"))
+				.collect()
+		})
+	}
+
+	#[test]
+	fn replays_packets_after_the_last_seen() {
+		let mut resumable = resumable();
+		assert_eq!(replayed_seqs(&resumable, 0), Some(Vec::new()));
+
+		for _ in 0..3 {
+			resumable.sequence(ClientBoundPacket::Ack);
+		}
+		assert_eq!(replayed_seqs(&resumable, 0), Some(vec![1, 2, 3]));
+		assert_eq!(replayed_seqs(&resumable, 2), Some(vec![3]));
+		assert_eq!(replayed_seqs(&resumable, 3), Some(Vec::new()));
+		assert_eq!(replayed_seqs(&resumable, 4), None);
+	}
+
+	#[test]
+	fn refuses_to_replay_dropped_packets() {
+		let mut resumable = resumable();
+		for _ in 0..REPLAY_BUFFER_SIZE + 2 {
+			resumable.sequence(ClientBoundPacket::Ack);
+		}
+
+		assert_eq!(replayed_seqs(&resumable, 1), None);
+		assert_eq!(
+			replayed_seqs(&resumable, 2).map(|seqs| seqs.len()),
+			Some(REPLAY_BUFFER_SIZE)
+		);
+	}
+}
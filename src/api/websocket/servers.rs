@@ -0,0 +1,167 @@
+//! Lets players report the Minecraft server they're on with
+//! [ServerBoundPacket::SetServer], and watch every PolyPlus user on a server
+//! with [ServerBoundPacket::SubscribeServer].
+
+use uuid::Uuid;
+
+use crate::api::{
+	ApiState,
+	presence::set_presence_server,
+	realtime::RealtimeEvent,
+	state::ConnectionId,
+	websocket::{
+		endpoint::subscription_snapshot,
+		structs::{
+			ClientBoundPacket, MAX_PLAYER_SUBSCRIPTIONS, MAX_SERVER_LENGTH,
+			WebsocketError,
+		},
+	},
+};
+
+/// Lowercases a server address and drops the default port, so that the ways
+/// of writing the same address match
+fn normalize_server(server: &str) -> Result<String, WebsocketError> {
+	let server = server.trim().to_ascii_lowercase();
+	let server = match server.strip_suffix(":25565") {
+		Some(address) => address.to_owned(),
+		None => server,
+	};
+	if server.is_empty() || server.len() > MAX_SERVER_LENGTH {
+		return Err(WebsocketError::InvalidServer {
+			max_length: MAX_SERVER_LENGTH,
+		});
+	}
+	Ok(server)
+}
+
+/// Records the server a player is on, on every instance
+pub(super) async fn set_server(
+	state: &ApiState,
+	player: Uuid,
+	server: Option<String>,
+) -> Result<(), WebsocketError> {
+	let server = server.as_deref().map(normalize_server).transpose()?;
+	let Some(mut runtime) = state
+		.realtime
+		.player_runtime
+		.read()
+		.await
+		.get(&player)
+		.cloned()
+	else {
+		// Every connected player has runtime state, so the report can't be kept
+		return Err(WebsocketError::MissingPlayerState);
+	};
+	set_presence_server(
+		&state.database,
+		state.realtime.instance_id,
+		player,
+		server.clone(),
+	)
+	.await?;
+	runtime.server = server;
+
+	state
+		.realtime
+		.publish(RealtimeEvent::ServerChanged { player, runtime })
+		.await;
+	Ok(())
+}
+
+/// Subscribes a connection to the players on a server, replacing its previous
+/// server subscription, and returns the snapshot of those players
+pub(super) async fn subscribe_server(
+	state: &ApiState,
+	connection_id: ConnectionId,
+	server: String,
+) -> Result<ClientBoundPacket, WebsocketError> {
+	let server = normalize_server(&server)?;
+	unsubscribe_server(state, connection_id).await;
+
+	let on_server = {
+		let mut servers = state.realtime.servers.write().await;
+		servers.watch(server.clone(), connection_id);
+		servers.players_on(&server).collect::<Vec<_>>()
+	};
+
+	let newly_subscribed = {
+		let mut connections = state.realtime.connections.write().await;
+		let Some(connection) = connections.get_mut(&connection_id) else {
+			drop(connections);
+			state
+				.realtime
+				.servers
+				.write()
+				.await
+				.unwatch(&server, connection_id);
+			return subscription_snapshot(state, connection_id, Vec::new()).await;
+		};
+
+		let room =
+			MAX_PLAYER_SUBSCRIPTIONS.saturating_sub(connection.subscriptions.len());
+		let players = on_server
+			.into_iter()
+			.filter(|player| {
+				*player != connection.owner && !connection.subscriptions.contains(player)
+			})
+			.take(room)
+			.collect::<Vec<_>>();
+		connection.subscriptions.extend(&players);
+		connection.server_players.extend(&players);
+		connection.watched_server = Some(server);
+		players
+	};
+
+	subscription_snapshot(state, connection_id, newly_subscribed).await
+}
+
+/// Ends a connection's server subscription, unsubscribing it from the players
+/// it added
+pub(super) async fn unsubscribe_server(state: &ApiState, connection_id: ConnectionId) {
+	let (server, players) = {
+		let mut connections = state.realtime.connections.write().await;
+		let Some(connection) = connections.get_mut(&connection_id) else {
+			return;
+		};
+		let Some(server) = connection.watched_server.take() else {
+			return;
+		};
+
+		let players = std::mem::take(&mut connection.server_players);
+		for player in &players {
+			connection.subscriptions.remove(player);
+		}
+		(server, players)
+	};
+
+	state
+		.realtime
+		.servers
+		.write()
+		.await
+		.unwatch(&server, connection_id);
+	state.realtime.remove_watchers(connection_id, players).await;
+}
+
+#[cfg(test)]
+mod tests {
+	use super::normalize_server;
+	use crate::api::websocket::structs::MAX_SERVER_LENGTH;
+
+	#[test]
+	fn normalizes_server_addresses() {
+		for (server, expected) in [
+			("mc.hypixel.net", "mc.hypixel.net"),
+			(" MC.Hypixel.net:25565 ", "mc.hypixel.net"),
+			("play.example.com:25566", "play.example.com:25566"),
+		] {
+			assert_eq!(
+				normalize_server(server).expect("server should be valid"),
+				expected
+			);
+		}
+
+		assert!(normalize_server("  ").is_err());
+		assert!(normalize_server(&"a".repeat(MAX_SERVER_LENGTH + 1)).is_err());
+	}
+}
@@ -1,10 +1,41 @@
-use std::{borrow::Cow, collections::HashMap};
+use std::{
+	borrow::Cow,
+	collections::{BTreeSet, HashMap},
+	ops::RangeInclusive,
+};
 
-use entities::sea_orm_active_enums::BodySlot;
+use entities::sea_orm_active_enums::{AnnouncementLevel, BodySlot};
 use schemars::{JsonSchema, json_schema};
 use serde::{Deserialize, Serialize, ser::SerializeStruct as _};
 use uuid::Uuid;
 
+/// Close code sent when a player is disconnected because they were banned
+pub const CLOSE_CODE_BANNED: u16 = 4003;
+/// Close code sent when a player is disconnected because their account was
+/// deleted
+pub const CLOSE_CODE_ACCOUNT_DELETED: u16 = 4004;
+/// Close code sent when a client's protocol version is older than
+/// [MIN_PROTOCOL_VERSION], and it needs to update
+pub const CLOSE_CODE_UNSUPPORTED_PROTOCOL: u16 = 4005;
+/// Close code sent when a client doesn't start the connection with
+/// [ServerBoundPacket::Hello] in time
+pub const CLOSE_CODE_HANDSHAKE_REQUIRED: u16 = 4006;
+/// Close code sent when a client can't keep up with the packets sent to it
+pub const CLOSE_CODE_SLOW_CONSUMER: u16 = 4007;
+
+/// The newest websocket protocol version this server speaks. Bump this when
+/// packets are added, changed or deprecated.
+pub const PROTOCOL_VERSION: u32 = 4;
+/// The oldest websocket protocol version clients may still connect with
+pub const MIN_PROTOCOL_VERSION: u32 = 1;
+
+/// Max UUIDs in a single `SubscribePlayers` or `GetActiveCosmetics` message.
+pub const MAX_PLAYERS_PER_REQUEST: usize = 64;
+/// Max distinct players a connection may subscribe to at once (render distance).
+pub const MAX_PLAYER_SUBSCRIPTIONS: usize = 128;
+/// Max length of a server address or hash in `SetServer` and `SubscribeServer`
+pub const MAX_SERVER_LENGTH: usize = 255;
+
 #[derive(Debug, thiserror::Error)]
 pub enum WebsocketError {
 	#[error("A fatal websocket connection error")]
@@ -15,6 +46,10 @@ pub enum WebsocketError {
 	Serialization(#[from] serde_json::Error),
 	#[error("Unable to parse request: {0}")]
 	Deserialization(serde_json::Error),
+	#[error("Unable to serialize response: {0}")]
+	BinarySerialization(#[from] rmp_serde::encode::Error),
+	#[error("Unable to parse request: {0}")]
+	BinaryDeserialization(rmp_serde::decode::Error),
 	#[error("Player does not own cosmetic {0}")]
 	UnownedCosmetic(i32),
 	#[error("Cosmetic {cosmetic_id} is not allowed in slot {slot:?}")]
@@ -25,21 +60,60 @@ pub enum WebsocketError {
 	TooManyPlayersInRequest { limit: usize },
 	#[error("Too many player subscriptions (max {limit})")]
 	SubscriptionLimitExceeded { limit: usize },
+	#[error("The device code is invalid, expired or was already confirmed")]
+	InvalidDeviceCode,
+	#[error("Too many packets, try again in {retry_after_ms}ms")]
+	RateLimited { retry_after_ms: u64 },
+	#[error("The handshake was already completed")]
+	DuplicateHandshake,
+	#[error("The session can't be resumed, start a new one with Hello")]
+	ResumeFailed,
+	#[error("This packet is not supported in protocol version {protocol_version}")]
+	UnsupportedPacket { protocol_version: u32 },
+	#[error("The server must be between 1 and {max_length} characters long")]
+	InvalidServer { max_length: usize },
+	#[error("Emote invites can only be sent to other players that are online")]
+	InvalidEmoteTarget,
+	#[error("The emote invite is invalid, expired or was already answered")]
+	InvalidEmoteInvite,
+	#[error("Player has no outfit {0}")]
+	UnknownOutfit(i32),
+	#[error("The player's connection state is missing, reconnect to restore it")]
+	MissingPlayerState,
 }
 
 impl WebsocketError {
-	const ERROR_CODES: &[&str] =
-		&["fatal", "internal_server_error", "bad_request", "not_owned"];
+	const ERROR_CODES: &[&str] = &[
+		"fatal",
+		"internal_server_error",
+		"bad_request",
+		"not_owned",
+		"rate_limited",
+		"resume_failed",
+	];
 
 	pub fn error_code(&self) -> &'static str {
 		match self {
 			Self::Fatal(_) => Self::ERROR_CODES[0],
-			Self::DatabaseQuery(_) | Self::Serialization(_) => Self::ERROR_CODES[1],
+			Self::DatabaseQuery(_)
+			| Self::Serialization(_)
+			| Self::BinarySerialization(_)
+			| Self::MissingPlayerState => Self::ERROR_CODES[1],
 			Self::Deserialization(_)
+			| Self::BinaryDeserialization(_)
 			| Self::InvalidSlot { .. }
 			| Self::TooManyPlayersInRequest { .. }
-			| Self::SubscriptionLimitExceeded { .. } => Self::ERROR_CODES[2],
+			| Self::SubscriptionLimitExceeded { .. }
+			| Self::InvalidDeviceCode
+			| Self::DuplicateHandshake
+			| Self::UnsupportedPacket { .. }
+			| Self::InvalidServer { .. }
+			| Self::InvalidEmoteTarget
+			| Self::InvalidEmoteInvite
+			| Self::UnknownOutfit(_) => Self::ERROR_CODES[2],
 			Self::UnownedCosmetic(_) | Self::UnownedEmote(_) => Self::ERROR_CODES[3],
+			Self::RateLimited { .. } => Self::ERROR_CODES[4],
+			Self::ResumeFailed => Self::ERROR_CODES[5],
 		}
 	}
 }
@@ -79,10 +153,39 @@ impl JsonSchema for WebsocketError {
 	}
 }
 
-/// A JSON object that a client can send in the websocket connection
-#[derive(Debug, Deserialize, JsonSchema)]
+/// A packet that a client can send in the websocket connection, encoded as
+/// negotiated by the connection's subprotocol
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "PascalCase", tag = "type")]
 pub enum ServerBoundPacket {
+	/// Must be the first packet of every connection, nothing else is handled
+	/// until the server replies with [ClientBoundPacket::Welcome]
+	Hello {
+		/// The websocket protocol version the client speaks. Clients older than
+		/// the server's minimum are disconnected with close code 4005.
+		protocol_version: u32,
+		/// The version of the mod, for diagnostics
+		mod_version: String,
+		/// Optional features the client wants to use. Unknown capabilities
+		/// are ignored.
+		#[serde(default)]
+		capabilities: Vec<Capability>,
+	},
+	/// Can be sent instead of [ServerBoundPacket::Hello] to pick a dropped
+	/// session back up, if it negotiated the `resume` capability. Sessions are
+	/// kept for 30 seconds after dropping, on the server instance they were
+	/// connected to.
+	///
+	/// On success, the server replies with [ClientBoundPacket::Welcome] and
+	/// then sends every packet the client missed, with subscriptions intact.
+	/// Otherwise it replies with a `resume_failed` error, and waits for a
+	/// Hello.
+	Resume {
+		/// The `session_id` from the session's Welcome packet
+		session_id: Uuid,
+		/// The highest `seq` the client received, or 0 if none
+		last_seq: u64,
+	},
 	/// Fetches active cosmetics for a list of players in bulk
 	GetActiveCosmetics {
 		/// An array of player UUIDs to include in the bulk lookup
@@ -107,13 +210,80 @@ pub enum ServerBoundPacket {
 		emote_id: i32,
 	},
 	StopEmote,
+	/// Logs the device showing `user_code` in as this player, see the
+	/// `/account/device` endpoints
+	ConfirmDeviceCode {
+		user_code: String,
+	},
+	/// Reports the Minecraft server the player is on, so that others on it can
+	/// find them with [ServerBoundPacket::SubscribeServer]
+	SetServer {
+		/// The server's address, or a hash of it for clients that don't want to
+		/// share it. Addresses are compared case-insensitively and without the
+		/// default port. `null` when the player leaves the server.
+		server: Option<String>,
+	},
+	/// Subscribe to every PolyPlus user on a server, including ones that join
+	/// it later, as far as the subscription limit allows. Replaces any previous
+	/// server subscription.
+	///
+	/// Replies with a [ClientBoundPacket::SubscriptionSnapshot] of the users
+	/// already on the server. Users that join it later are sent in their own
+	/// snapshots, and users that leave it are unsubscribed with
+	/// [ClientBoundPacket::ServerPlayerLeft].
+	SubscribeServer {
+		/// The server's address or hash, as sent in `SetServer`
+		server: String,
+	},
+	/// Ends the server subscription, unsubscribing every player it added
+	UnsubscribeServer,
+	/// Invites another online player to do a paired emote, like a handshake,
+	/// with the player, who must own the emote. The invite expires after 30
+	/// seconds, and replaces the player's previous invite if it is still
+	/// pending.
+	///
+	/// Replies with [ClientBoundPacket::EmoteInviteSent]. The other player
+	/// is sent [ClientBoundPacket::EmoteInviteReceived] if they negotiated the
+	/// `paired_emotes` capability.
+	InviteEmote {
+		target: Uuid,
+		emote_id: i32,
+	},
+	/// Accepts an emote invite, starting the emote for both players with
+	/// [ClientBoundPacket::PairedEmoteStarted]
+	AcceptEmoteInvite {
+		invite_id: Uuid,
+	},
+	DeclineEmoteInvite {
+		invite_id: Uuid,
+	},
+	/// Switches to one of the player's saved outfits, equipping its cosmetics,
+	/// clearing the slots it leaves empty and setting its particle color. The
+	/// cosmetics are checked like [ServerBoundPacket::SetEquippedCosmetic]'s,
+	/// and watchers are sent the slots that changed.
+	ApplyOutfit {
+		outfit_id: i32,
+	},
 }
 
-/// A JSON object that the server will send to the client in the websocket
-/// connection
-#[derive(Debug, Serialize, JsonSchema)]
+/// A packet that the server will send to the client in the websocket
+/// connection, encoded as negotiated by the connection's subprotocol
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "PascalCase", tag = "type")]
 pub enum ClientBoundPacket {
+	/// The reply to [ServerBoundPacket::Hello], after which the connection is
+	/// ready to use
+	Welcome {
+		server_version: String,
+		/// Identifies this connection
+		session_id: Uuid,
+		/// The protocol version used for this connection, the lower of the
+		/// client's and the server's
+		protocol_version: u32,
+		/// The requested capabilities that this server supports
+		capabilities: BTreeSet<Capability>,
+		limits: ConnectionLimits,
+	},
 	/// Information on player UUIDs and what cosmetics they own, sent in
 	/// response to [ServerBoundPacket::GetActiveCosmetics]
 	CosmeticsInfo {
@@ -162,13 +332,255 @@ pub enum ClientBoundPacket {
 		/// are we revoking stuff
 		revoked: bool,
 	},
+	/// Sent in response to [ServerBoundPacket::ConfirmDeviceCode] once the
+	/// device can log in
+	DeviceCodeConfirmed {
+		user_code: String,
+	},
+	/// The player's friends and pending friend requests, sent after
+	/// [ClientBoundPacket::Welcome] to clients with the `friends` capability
+	FriendsSnapshot {
+		friends: Vec<Uuid>,
+		/// The subset of friends that currently have a live PolyPlus session
+		/// connected
+		online_friends: Vec<Uuid>,
+		/// Players that sent the player a friend request
+		incoming_requests: Vec<Uuid>,
+		/// Players the player sent a friend request
+		outgoing_requests: Vec<Uuid>,
+	},
+	/// A friend's PolyPlus session came online or went offline
+	FriendPresence {
+		player: Uuid,
+		online: bool,
+	},
+	/// Another player sent the player a friend request
+	FriendRequestReceived {
+		player: Uuid,
+	},
+	/// The player became friends with another player, by either of them
+	/// accepting a friend request
+	FriendAdded {
+		player: Uuid,
+		online: bool,
+	},
+	/// The player is no longer friends with another player
+	FriendRemoved {
+		player: Uuid,
+	},
+	/// A player that was subscribed to through
+	/// [ServerBoundPacket::SubscribeServer] left the server, and is no longer
+	/// subscribed to
+	ServerPlayerLeft {
+		player: Uuid,
+	},
+	/// The reply to [ServerBoundPacket::InviteEmote]
+	EmoteInviteSent {
+		invite_id: Uuid,
+		target: Uuid,
+		/// When the invite expires, as a Unix timestamp in milliseconds
+		expires_at: i64,
+	},
+	/// Another player invited the player to do a paired emote with them, which
+	/// can be answered with [ServerBoundPacket::AcceptEmoteInvite] or
+	/// [ServerBoundPacket::DeclineEmoteInvite] until it expires
+	EmoteInviteReceived {
+		invite_id: Uuid,
+		player: Uuid,
+		emote_id: i32,
+		/// When the invite expires, as a Unix timestamp in milliseconds
+		expires_at: i64,
+	},
+	/// A player declined the player's emote invite
+	EmoteInviteDeclined {
+		invite_id: Uuid,
+		player: Uuid,
+	},
+	/// Two players started a paired emote. Sent once to every connection that
+	/// watches either of them, and to both players themselves.
+	PairedEmoteStarted {
+		emote_id: i32,
+		/// The player that sent the invite
+		initiator: Uuid,
+		/// The player that accepted it
+		partner: Uuid,
+		/// When both players start the emote, as a Unix timestamp in
+		/// milliseconds. Slightly in the future, so that clients can start it
+		/// in sync.
+		starts_at: i64,
+	},
+	/// A notice from the PolyPlus team, like upcoming maintenance, to show to
+	/// the player. Sent when it is made, and after [ClientBoundPacket::Welcome]
+	/// for as long as it is active, so clients should remember which IDs they
+	/// already showed.
+	Announcement {
+		#[serde(flatten)]
+		announcement: AnnouncementInfo,
+	},
+	/// Catalog entries were created, changed or disabled, and should be
+	/// re-fetched. Missed changes can be caught up on with the
+	/// `/cosmetics/changes` endpoint.
+	CatalogChanged {
+		cosmetic_ids: Vec<i32>,
+		group_ids: Vec<i32>,
+		bundle_ids: Vec<i32>,
+		/// The catalog version after the change
+		catalog_version: i64,
+	},
+	/// Sent in reply to packets that have no other reply, if they had a
+	/// `request_id`
+	Ack,
 	/// An error response from the server
+	#[serde(skip_deserializing)]
 	Error {
 		#[serde(flatten)]
 		error: WebsocketError,
 	},
 }
 
+/// Chosen by the client to match replies up with the packets that caused them
+pub type RequestId = u32;
+
+/// A [ServerBoundPacket] as it is sent over the websocket connection
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct ServerBoundMessage {
+	/// Echoed back on the reply to this packet, whether it succeeds or fails
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub request_id: Option<RequestId>,
+	#[serde(flatten)]
+	pub packet: ServerBoundPacket,
+}
+
+/// A [ClientBoundPacket] as it is sent over the websocket connection
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct ClientBoundMessage {
+	/// The `request_id` of the packet this replies to, if it had one
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub request_id: Option<RequestId>,
+	/// Numbers the packets that aren't replies on sessions that negotiated the
+	/// `resume` capability. Always increasing, but may skip numbers.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub seq: Option<u64>,
+	#[serde(flatten)]
+	pub packet: ClientBoundPacket,
+}
+
+impl From<ClientBoundPacket> for ClientBoundMessage {
+	fn from(packet: ClientBoundPacket) -> Self {
+		Self {
+			request_id: None,
+			seq: None,
+			packet,
+		}
+	}
+}
+
+impl ServerBoundPacket {
+	/// The protocol versions that a packet may be sent in. Deprecated packets
+	/// get an upper bound, so that only older clients can still use them.
+	pub fn supported_versions(&self) -> RangeInclusive<u32> {
+		match self {
+			Self::Hello { .. }
+			| Self::Resume { .. }
+			| Self::GetActiveCosmetics { .. }
+			| Self::SubscribePlayers { .. }
+			| Self::UnsubscribePlayers { .. }
+			| Self::SetEquippedCosmetic { .. }
+			| Self::SetParticleColor { .. }
+			| Self::PlayEmote { .. }
+			| Self::StopEmote
+			| Self::ConfirmDeviceCode { .. } => 1..=PROTOCOL_VERSION,
+			Self::SetServer { .. }
+			| Self::SubscribeServer { .. }
+			| Self::UnsubscribeServer => 2..=PROTOCOL_VERSION,
+			Self::InviteEmote { .. }
+			| Self::AcceptEmoteInvite { .. }
+			| Self::DeclineEmoteInvite { .. } => 3..=PROTOCOL_VERSION,
+			Self::ApplyOutfit { .. } => 4..=PROTOCOL_VERSION,
+		}
+	}
+}
+
+impl ClientBoundPacket {
+	/// The capability a client must have negotiated to be sent this packet
+	pub fn required_capability(&self) -> Option<Capability> {
+		match self {
+			Self::PlayerPresence { .. } => Some(Capability::Presence),
+			Self::FriendsSnapshot { .. }
+			| Self::FriendPresence { .. }
+			| Self::FriendRequestReceived { .. }
+			| Self::FriendAdded { .. }
+			| Self::FriendRemoved { .. } => Some(Capability::Friends),
+			Self::EmoteInviteReceived { .. }
+			| Self::EmoteInviteDeclined { .. }
+			| Self::PairedEmoteStarted { .. } => Some(Capability::PairedEmotes),
+			Self::Announcement { .. } => Some(Capability::Announcements),
+			Self::CatalogChanged { .. } => Some(Capability::CatalogChanges),
+			_ => None,
+		}
+	}
+}
+
+/// An optional feature that is negotiated in the handshake
+#[derive(
+	Debug,
+	Clone,
+	Copy,
+	PartialEq,
+	Eq,
+	PartialOrd,
+	Ord,
+	Hash,
+	Serialize,
+	Deserialize,
+	JsonSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Capability {
+	/// Receive [ClientBoundPacket::PlayerPresence] for subscribed players
+	Presence,
+	/// Number packets and keep sessions around after they drop, so they can be
+	/// picked back up with [ServerBoundPacket::Resume]
+	Resume,
+	/// Receive the friends list after [ClientBoundPacket::Welcome], and packets
+	/// about friends and friend requests
+	Friends,
+	/// Receive emote invites from other players, and the paired emotes of
+	/// subscribed players
+	PairedEmotes,
+	/// Receive [ClientBoundPacket::Announcement]
+	Announcements,
+	/// Receive [ClientBoundPacket::CatalogChanged]
+	CatalogChanges,
+	/// A capability this server doesn't know about
+	#[serde(other)]
+	#[schemars(skip)]
+	Unknown,
+}
+
+/// An announcement, as it is sent to clients
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct AnnouncementInfo {
+	pub id: i32,
+	pub level: AnnouncementLevel,
+	pub title: String,
+	pub body: String,
+	/// Where the player can read more
+	pub link: Option<String>,
+	/// When the announcement is no longer relevant, as a Unix timestamp in
+	/// milliseconds
+	pub expires_at: i64,
+}
+
+/// Limits that apply to a websocket connection
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct ConnectionLimits {
+	/// Max player UUIDs in a single request
+	pub max_players_per_request: usize,
+	/// Max players a connection may be subscribed to at once
+	pub max_player_subscriptions: usize,
+}
+
 #[cfg(test)]
 mod tests {
 	use std::collections::HashMap;
@@ -0,0 +1,38 @@
+use bpaf::Bpaf;
+
+use crate::keyring::PasetoKeyring;
+
+#[derive(Clone, Debug, Bpaf)]
+pub(crate) enum KeysCommand {
+	/// Generates a new PASETO key and prints it as a PASERK string
+	#[bpaf(command("generate"))]
+	Generate,
+	/// Generates a new PASETO key in front of the existing keys and prints the
+	/// resulting PASETO_KEYS value. Tokens encrypted with retired keys stop
+	/// being accepted once the new value is deployed.
+	#[bpaf(command("rotate"))]
+	Rotate {
+		/// The currently deployed PASETO keys, newest first
+		#[bpaf(long("paseto-keys"), env("PASETO_KEYS"), argument("KEYS"))]
+		paseto_keys: PasetoKeyring,
+		/// How many keys to keep after rotating, including the new key. Keep at
+		/// least 2 so tokens issued right before the rotation stay valid.
+		#[bpaf(long("keep"), argument("COUNT"), fallback(2))]
+		keep: usize,
+	},
+}
+
+impl KeysCommand {
+	pub(crate) fn run(self) {
+		let keyring = match self {
+			Self::Generate => PasetoKeyring::generate(),
+			Self::Rotate { paseto_keys, keep } => paseto_keys.rotate(keep),
+		}
+		.expect("Unable to generate PASETO key");
+
+		for key_id in keyring.key_ids() {
+			eprintln!("{key_id}");
+		}
+		println!("{}", keyring.to_paserk_list());
+	}
+}
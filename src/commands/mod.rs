@@ -4,6 +4,19 @@ use axum_client_ip::ClientIpSource;
 use bpaf::Bpaf;
 use http::{HeaderValue, header::InvalidHeaderValue};
 
+use crate::{
+	api::{
+		MOJANG_PROFILE_API_URL, MOJANG_SESSIONSERVER_URL, RateLimit, RealtimeBusKind,
+		SlowConsumerPolicy,
+	},
+	keyring::PasetoKeyring,
+};
+
+mod keys;
+
+pub(crate) use keys::KeysCommand;
+use keys::keys_command;
+
 #[derive(Clone, Debug, Bpaf)]
 #[bpaf(options, version)]
 pub(crate) struct BackendArgs {
@@ -11,10 +24,15 @@ pub(crate) struct BackendArgs {
 	pub(crate) command: Subcommand,
 }
 
+// Parsed once on startup, so the size difference is irrelevant
+#[allow(clippy::large_enum_variant)]
 #[derive(Clone, Debug, Bpaf)]
 pub(crate) enum Subcommand {
 	#[bpaf(command("serve"))]
 	Serve(#[bpaf(external(serve_args))] ServeArgs),
+	/// Manage the PASETO keys used to encrypt authentication tokens
+	#[bpaf(command("keys"))]
+	Keys(#[bpaf(external(keys_command))] KeysCommand),
 }
 
 #[derive(Clone, Debug, Bpaf)]
@@ -65,9 +83,39 @@ pub(crate) struct ServeArgs {
 	/// The endpoint of the s3 bucket to use
 	#[bpaf(long("s3-bucket-endpoint"), env("S3_BUCKET_ENDPOINT"))]
 	pub(crate) s3_bucket_endpoint: String,
-	/// Password for admin operations
-	#[bpaf(long("admin-password"), env("ADMIN_PASSWORD"))]
-	pub(crate) admin_password: String,
+	/// The PASETO keys used to encrypt authentication tokens, as comma
+	/// separated `k4.local.*` PASERK strings ordered newest first. New tokens
+	/// are encrypted with the first key, while the rest are only used to
+	/// decrypt tokens issued before the last rotation. Use the `keys` command
+	/// to generate and rotate these. When unset, an ephemeral key is generated
+	/// and every token is invalidated on restart.
+	#[bpaf(long("paseto-keys"), env("PASETO_KEYS"), argument("KEYS"), optional)]
+	pub(crate) paseto_keys: Option<PasetoKeyring>,
+	/// The base URL of the Mojang session server used to verify logins. Can
+	/// point to any server implementing the same `hasJoined` API.
+	#[bpaf(
+		long("sessionserver-url"),
+		env("SESSIONSERVER_URL"),
+		fallback(MOJANG_SESSIONSERVER_URL.to_owned())
+	)]
+	pub(crate) sessionserver_url: String,
+	/// The base URL of the Mojang profile API used to resolve usernames and
+	/// UUIDs. Can point to any server implementing the same profile lookups.
+	#[bpaf(
+		long("profile-api-url"),
+		env("PROFILE_API_URL"),
+		fallback(MOJANG_PROFILE_API_URL.to_owned())
+	)]
+	pub(crate) profile_api_url: String,
+	/// Accept every login as whichever player the client claims to be,
+	/// without contacting the session server. NEVER enable this in production,
+	/// it is only meant for local development and integration tests.
+	#[bpaf(long("dev-auth"), env("DEV_AUTH"), switch)]
+	pub(crate) dev_auth: bool,
+	/// Salt for the hashes identifying unique visitors of tracked links.
+	/// Changing it makes every returning visitor count as unique again.
+	#[bpaf(long("visitor-hash-salt"), env("VISITOR_HASH_SALT"))]
+	pub(crate) visitor_hash_salt: String,
 	#[bpaf(
 		long("render-service-url"),
 		env("RENDER_SERVICE_URL"),
@@ -84,6 +132,108 @@ pub(crate) struct ServeArgs {
 		fallback_with(default_cors_origins)
 	)]
 	pub(crate) cors_origins: Vec<HeaderValue>,
+	/// How often a client may log in or request a device code, as
+	/// `<requests>/<seconds>`. Clients are told apart by their access token
+	/// if they send one, and by their IP otherwise.
+	#[bpaf(
+		long("login-rate-limit"),
+		env("LOGIN_RATE_LIMIT"),
+		argument("LIMIT"),
+		fallback(RateLimit::from_str("10/60").expect("This str is always a valid RateLimit"))
+	)]
+	pub(crate) login_rate_limit: RateLimit,
+	/// How often a client may create checkout sessions, as
+	/// `<requests>/<seconds>`
+	#[bpaf(
+		long("checkout-rate-limit"),
+		env("CHECKOUT_RATE_LIMIT"),
+		argument("LIMIT"),
+		fallback(RateLimit::from_str("5/60").expect("This str is always a valid RateLimit"))
+	)]
+	pub(crate) checkout_rate_limit: RateLimit,
+	/// How often a client may follow tracked links, as `<requests>/<seconds>`
+	#[bpaf(
+		long("link-rate-limit"),
+		env("LINK_RATE_LIMIT"),
+		argument("LIMIT"),
+		fallback(RateLimit::from_str("30/60").expect("This str is always a valid RateLimit"))
+	)]
+	pub(crate) link_rate_limit: RateLimit,
+	/// How often a client may poll for the session of a device code, as
+	/// `<requests>/<seconds>`. Devices are told to poll every 5 seconds.
+	#[bpaf(
+		long("device-poll-rate-limit"),
+		env("DEVICE_POLL_RATE_LIMIT"),
+		argument("LIMIT"),
+		fallback(RateLimit::from_str("20/60").expect("This str is always a valid RateLimit"))
+	)]
+	pub(crate) device_poll_rate_limit: RateLimit,
+	/// How often a client may look up players, which queries the Mojang profile
+	/// API, as `<requests>/<seconds>`
+	#[bpaf(
+		long("lookup-rate-limit"),
+		env("LOOKUP_RATE_LIMIT"),
+		argument("LIMIT"),
+		fallback(RateLimit::from_str("30/60").expect("This str is always a valid RateLimit"))
+	)]
+	pub(crate) lookup_rate_limit: RateLimit,
+	/// How many packets each websocket connection may send, as
+	/// `<requests>/<seconds>`. Packets over the limit are rejected with an
+	/// error packet.
+	#[bpaf(
+		long("websocket-packet-rate-limit"),
+		env("WEBSOCKET_PACKET_RATE_LIMIT"),
+		argument("LIMIT"),
+		fallback(RateLimit::from_str("20/1").expect("This str is always a valid RateLimit"))
+	)]
+	pub(crate) websocket_packet_rate_limit: RateLimit,
+	/// How realtime events like equips, emotes and presence reach players
+	/// connected to other instances. `in_process` only supports a single
+	/// instance, while `postgres` sends them through the database with
+	/// `NOTIFY`, so any number of instances can share it.
+	#[bpaf(
+		long("realtime-bus"),
+		env("REALTIME_BUS"),
+		argument("BUS"),
+		fallback(RealtimeBusKind::InProcess)
+	)]
+	pub(crate) realtime_bus: RealtimeBusKind,
+	/// How many packets may wait to be sent to each websocket connection
+	#[bpaf(
+		long("websocket-queue-size"),
+		env("WEBSOCKET_QUEUE_SIZE"),
+		argument("PACKETS"),
+		fallback(256)
+	)]
+	pub(crate) websocket_queue_size: usize,
+	/// What to do when a websocket connection's queue is full.
+	/// `drop_superseded` drops queued updates that newer ones replace, and
+	/// only disconnects the client if that doesn't free up space, while
+	/// `disconnect` disconnects it straight away.
+	#[bpaf(
+		long("slow-consumer-policy"),
+		env("SLOW_CONSUMER_POLICY"),
+		argument("POLICY"),
+		fallback(SlowConsumerPolicy::DropSuperseded)
+	)]
+	pub(crate) slow_consumer_policy: SlowConsumerPolicy,
+	/// How often idle websocket connections are pinged, in seconds
+	#[bpaf(
+		long("websocket-ping-interval"),
+		env("WEBSOCKET_PING_INTERVAL"),
+		argument("SECONDS"),
+		fallback(15)
+	)]
+	pub(crate) websocket_ping_interval: u64,
+	/// How long a websocket connection may go without sending anything,
+	/// pongs included, before it is considered dropped, in seconds
+	#[bpaf(
+		long("websocket-idle-timeout"),
+		env("WEBSOCKET_IDLE_TIMEOUT"),
+		argument("SECONDS"),
+		fallback(45)
+	)]
+	pub(crate) websocket_idle_timeout: u64,
 }
 
 fn parse_cors_origins(value: String) -> Result<Vec<HeaderValue>, InvalidHeaderValue> {
@@ -96,5 +246,7 @@ fn parse_cors_origins(value: String) -> Result<Vec<HeaderValue>, InvalidHeaderVa
 }
 
 fn default_cors_origins() -> Result<Vec<HeaderValue>, InvalidHeaderValue> {
-	parse_cors_origins("https://plus-admin.polyfrost.org,http://localhost:3000".to_owned())
+	parse_cors_origins(
+		"https://plus-admin.polyfrost.org,http://localhost:3000".to_owned(),
+	)
 }
@@ -1,12 +1,12 @@
 use chrono::{DateTime, Datelike, Days, NaiveDate, Utc};
 use entities::{
-	daily_playtime, monthly_active_login,
+	daily_playtime, monthly_active_login, player_ban,
 	prelude::*,
 	sea_orm_active_enums::{TransactionProvider, TransactionStatus},
-	transaction, user,
+	session, transaction, user,
 };
 use sea_orm::{
-	ActiveValue, DbErr, EntityTrait, QueryFilter, Set,
+	ActiveValue, Condition, DbErr, EntityTrait, QueryFilter, Set,
 	prelude::*,
 	sea_query::{Expr, OnConflict},
 };
@@ -129,6 +129,64 @@ pub(crate) async fn record_monthly_active_login(
 	Ok(())
 }
 
+/// Revokes every active session of a player, so none of their refresh or
+/// access tokens are accepted anymore. Returns how many sessions were revoked.
+pub(crate) async fn revoke_sessions(
+	db: &impl ConnectionTrait,
+	player_id: i32,
+) -> Result<u64, DbErr> {
+	Ok(Session::update_many()
+		.col_expr(session::Column::RevokedAt, Expr::current_timestamp().into())
+		.filter(session::Column::PlayerId.eq(player_id))
+		.filter(session::Column::RevokedAt.is_null())
+		.exec(db)
+		.await?
+		.rows_affected)
+}
+
+/// Filters bans down to the ones currently in effect
+pub(crate) fn active_ban_condition() -> Condition {
+	Condition::all()
+		.add(player_ban::Column::LiftedAt.is_null())
+		.add(
+			Condition::any()
+				.add(player_ban::Column::ExpiresAt.is_null())
+				.add(player_ban::Column::ExpiresAt.gt(Utc::now())),
+		)
+}
+
+/// Checks whether a player is currently banned. `user.blacklisted` is kept as
+/// a cheap flag for banned players, and is cleared here once every ban of a
+/// player has expired. Players flagged without any ban on record (from before
+/// bans were tracked) stay banned until unbanned.
+pub(crate) async fn is_banned(
+	db: &impl ConnectionTrait,
+	player: &user::Model,
+) -> Result<bool, DbErr> {
+	if !player.blacklisted {
+		return Ok(false);
+	}
+
+	let bans = PlayerBan::find()
+		.filter(player_ban::Column::PlayerId.eq(player.id))
+		.all(db)
+		.await?;
+	let now = Utc::now();
+	if bans.is_empty()
+		|| bans.iter().any(|ban| {
+			ban.lifted_at.is_none() && ban.expires_at.is_none_or(|expiry| expiry > now)
+		}) {
+		return Ok(true);
+	}
+
+	User::update_many()
+		.col_expr(user::Column::Blacklisted, Expr::value(false))
+		.filter(user::Column::Id.eq(player.id))
+		.exec(db)
+		.await?;
+	Ok(false)
+}
+
 pub(crate) async fn accrue_playtime(
 	db: &impl ConnectionTrait,
 	player_id: i32,
@@ -0,0 +1,232 @@
+use std::{str::FromStr, sync::Arc};
+
+use pasetors::{
+	footer::Footer,
+	keys::{Generate, SymmetricKey},
+	paserk::{FormatAsPaserk, Id},
+	version4::V4,
+};
+
+/// A single PASETO key, along with its PASERK key id (`k4.lid.*`).
+#[derive(Clone)]
+struct PasetoKey {
+	lid: Id,
+	id: String,
+	key: SymmetricKey<V4>,
+}
+
+/// The set of PASETO keys the backend accepts, ordered newest first.
+///
+/// New tokens are always encrypted with the newest key, and carry its key id
+/// in the token footer. Older keys are only used to decrypt tokens that were
+/// issued before a rotation, until they are removed from the keyring.
+#[derive(Clone)]
+pub(crate) struct PasetoKeyring {
+	keys: Arc<[PasetoKey]>,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub(crate) enum KeyringError {
+	#[error("At least one PASETO key is required")]
+	Empty,
+	#[error("Key {index} is not a valid k4.local PASERK string")]
+	InvalidKey { index: usize },
+	#[error("Key {index} is listed more than once")]
+	DuplicateKey { index: usize },
+	#[error("Unable to generate PASETO key: {0}")]
+	Generation(#[from] pasetors::errors::Error),
+}
+
+// Only key ids are printed, never key material
+impl std::fmt::Debug for PasetoKeyring {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("PasetoKeyring")
+			.field("key_ids", &self.key_ids().collect::<Vec<_>>())
+			.finish_non_exhaustive()
+	}
+}
+
+impl PasetoKey {
+	fn new(key: SymmetricKey<V4>) -> Self {
+		let lid = Id::from(&key);
+		Self {
+			id: paserk_string(&lid),
+			lid,
+			key,
+		}
+	}
+}
+
+fn paserk_string(value: &impl FormatAsPaserk) -> String {
+	let mut out = String::new();
+	value
+		.fmt(&mut out)
+		.expect("Formatting PASERK into a String is infallible");
+	out
+}
+
+impl PasetoKeyring {
+	/// Creates a keyring holding a single freshly generated key.
+	pub(crate) fn generate() -> Result<Self, KeyringError> {
+		Ok(Self {
+			keys: Arc::new([PasetoKey::new(SymmetricKey::generate()?)]),
+		})
+	}
+
+	/// Returns a new keyring with a freshly generated key in front, keeping at
+	/// most `keep` keys in total (the oldest keys are retired first).
+	pub(crate) fn rotate(&self, keep: usize) -> Result<Self, KeyringError> {
+		let mut keys = vec![PasetoKey::new(SymmetricKey::generate()?)];
+		keys.extend(self.keys.iter().take(keep.max(1) - 1).cloned());
+
+		Ok(Self { keys: keys.into() })
+	}
+
+	/// The key used to encrypt new tokens, and a footer identifying it.
+	pub(crate) fn signing_key(&self) -> (&SymmetricKey<V4>, Footer) {
+		let newest = &self.keys[0];
+		let mut footer = Footer::new();
+		footer.key_id(&newest.lid);
+		(&newest.key, footer)
+	}
+
+	/// Finds the key referenced by the `kid` claim of an untrusted token
+	/// footer. Tokens without a footer, or referencing a retired key, have no
+	/// verifying key.
+	pub(crate) fn verifying_key(&self, footer: &[u8]) -> Option<&SymmetricKey<V4>> {
+		let mut parsed = Footer::new();
+		parsed.parse_bytes(footer).ok()?;
+		let kid = parsed.get_claim("kid")?.as_str()?;
+
+		self.keys
+			.iter()
+			.find(|key| key.id == kid)
+			.map(|key| &key.key)
+	}
+
+	/// The PASERK key ids in this keyring, newest first.
+	pub(crate) fn key_ids(&self) -> impl Iterator<Item = &str> {
+		self.keys.iter().map(|key| key.id.as_str())
+	}
+
+	/// Serializes every key as a comma separated list of PASERK strings, in
+	/// the same format accepted by [`FromStr`]. This exposes secret key
+	/// material, and is only meant for the key management commands.
+	pub(crate) fn to_paserk_list(&self) -> String {
+		self.keys
+			.iter()
+			.map(|key| paserk_string(&key.key))
+			.collect::<Vec<_>>()
+			.join(",")
+	}
+}
+
+impl FromStr for PasetoKeyring {
+	type Err = KeyringError;
+
+	fn from_str(value: &str) -> Result<Self, Self::Err> {
+		let mut keys: Vec<PasetoKey> = Vec::new();
+		for (index, entry) in value
+			.split(',')
+			.map(str::trim)
+			.filter(|entry| !entry.is_empty())
+			.enumerate()
+		{
+			let key = PasetoKey::new(
+				SymmetricKey::<V4>::try_from(entry)
+					.map_err(|_| KeyringError::InvalidKey { index })?,
+			);
+			if keys.iter().any(|existing| existing.id == key.id) {
+				return Err(KeyringError::DuplicateKey { index });
+			}
+			keys.push(key);
+		}
+
+		if keys.is_empty() {
+			return Err(KeyringError::Empty);
+		}
+
+		Ok(Self { keys: keys.into() })
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{KeyringError, PasetoKeyring};
+
+	fn keyring(value: &str) -> PasetoKeyring {
+		value.parse().expect("keyring should parse")
+	}
+
+	#[test]
+	fn round_trips_through_paserk_list() {
+		let generated = PasetoKeyring::generate()
+			.expect("key generation should succeed")
+			.rotate(3)
+			.expect("key generation should succeed");
+		let parsed = keyring(&generated.to_paserk_list());
+
+		assert_eq!(
+			parsed.key_ids().collect::<Vec<_>>(),
+			generated.key_ids().collect::<Vec<_>>()
+		);
+	}
+
+	#[test]
+	fn rejects_empty_invalid_and_duplicate_keys() {
+		let key = PasetoKeyring::generate()
+			.expect("key generation should succeed")
+			.to_paserk_list();
+
+		assert!(matches!(
+			" , ".parse::<PasetoKeyring>(),
+			Err(KeyringError::Empty)
+		));
+		assert!(matches!(
+			format!("{key},k4.local.nope").parse::<PasetoKeyring>(),
+			Err(KeyringError::InvalidKey { index: 1 })
+		));
+		assert!(matches!(
+			format!("{key},{key}").parse::<PasetoKeyring>(),
+			Err(KeyringError::DuplicateKey { index: 1 })
+		));
+	}
+
+	#[test]
+	fn rotation_signs_with_new_key_and_retires_oldest() {
+		let original = PasetoKeyring::generate()
+			.expect("key generation should succeed")
+			.rotate(2)
+			.expect("key generation should succeed");
+		let rotated = original.rotate(2).expect("key generation should succeed");
+
+		let original_ids = original.key_ids().collect::<Vec<_>>();
+		let rotated_ids = rotated.key_ids().collect::<Vec<_>>();
+		assert_eq!(rotated_ids.len(), 2);
+		assert!(!original_ids.contains(&rotated_ids[0]));
+		assert_eq!(rotated_ids[1], original_ids[0]);
+	}
+
+	#[test]
+	fn footer_selects_matching_key() {
+		let original = PasetoKeyring::generate().expect("key generation should succeed");
+		let rotated = original.rotate(2).expect("key generation should succeed");
+
+		let (old_key, old_footer) = original.signing_key();
+		let old_footer = old_footer.to_string().expect("footer should serialize");
+		assert_eq!(
+			rotated.verifying_key(old_footer.as_bytes()),
+			Some(old_key),
+			"tokens signed before a rotation must still verify"
+		);
+
+		let retired = rotated.rotate(1).expect("key generation should succeed");
+		assert!(retired.verifying_key(old_footer.as_bytes()).is_none());
+		assert!(retired.verifying_key(b"").is_none());
+		assert!(
+			retired
+				.verifying_key(br#"{"kid":"k4.lid.unknown"}"#)
+				.is_none()
+		);
+	}
+}
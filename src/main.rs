@@ -8,6 +8,8 @@ use crate::commands::backend_args;
 mod api;
 mod commands;
 mod database;
+mod keyring;
+mod secret;
 
 #[tokio::main]
 async fn main() {
@@ -25,5 +27,6 @@ async fn main() {
 
 	match args.command {
 		commands::Subcommand::Serve(args) => api::start(args).await,
+		commands::Subcommand::Keys(command) => command.run(),
 	}
 }
@@ -0,0 +1,24 @@
+//! Helpers for the random bearer secrets handed out to clients, like refresh
+//! tokens and API keys. Only a hash of each secret is ever stored.
+
+use base64::Engine as _;
+use rand::RngCore as _;
+use sha2::{Digest, Sha256};
+
+/// Generates a new URL safe secret, returning it along with its hash.
+pub(crate) fn generate() -> (String, String) {
+	let mut bytes = [0u8; 32];
+	rand::rng().fill_bytes(&mut bytes);
+	let secret = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(bytes);
+	let hash = hash(&secret);
+	(secret, hash)
+}
+
+/// Hashes a secret for storage. Secrets are random and high entropy, so an
+/// unsalted hash is enough.
+pub(crate) fn hash(secret: &str) -> String {
+	Sha256::digest(secret.as_bytes())
+		.iter()
+		.map(|byte| format!("{byte:02x}"))
+		.collect()
+}